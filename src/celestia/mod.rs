@@ -333,3 +333,6 @@ pub const SPEED_OF_LIGHT_KMS: f64 = 299_792.458;
 
 /// Astronomical unit, in kilometers, according to the [IAU](https://www.iau.org/public/themes/measuring/).
 pub const AU: f64 = 149_597_870.700;
+
+/// Rotation rate of the Earth (rad/s)
+pub const EARTH_ROTATION_RATE: f64 = 7.292_115_146_706_4e-5;
@@ -5,17 +5,22 @@ extern crate serde;
 use self::approx::{abs_diff_eq, relative_eq};
 use self::serde::ser::SerializeStruct;
 use self::serde::{Serialize, Serializer};
-use super::na::{Matrix3, Matrix6, Vector3, Vector6};
+use super::na::{Matrix3, Matrix4, Matrix6, Vector3, Vector4, Vector6, VectorN, U10, U4};
 use super::Frame;
+use crate::dynamics::propulsion::{MountedThruster, Tank, MAX_TANKS, MAX_THRUSTERS};
 use crate::dynamics::thrustctrl::Thruster;
+use crate::errors::NyxError;
 use crate::time::{Duration, Epoch, TimeUnit};
-use crate::utils::{between_0_360, between_pm_180, perpv, r1, r3};
+use crate::utils::{between_0_360, between_pm_180, perpv, r1, r3, stumpff_c2, stumpff_c3};
 use crate::TimeTagged;
 use std::f64::consts::PI;
 use std::f64::EPSILON;
 use std::fmt;
 use std::ops::{Add, Neg, Sub};
 
+/// Maximum number of Newton iterations to solve Kepler's problem with the universal variable in `Orbit::at_epoch`
+const KEPLER_MAX_ITER: usize = 100;
+
 /// If an orbit has an eccentricity below the following value, it is considered circular (only affects warning messages)
 pub const ECC_EPSILON: f64 = 1e-11;
 
@@ -482,6 +487,101 @@ impl Orbit {
         }
     }
 
+    /// Returns this orbit propagated with two body dynamics until the provided epoch, which may be before the epoch
+    /// of this orbit. Uses the universal variable formulation of Kepler's problem (Vallado, 4th Ed., Algorithm 8), and
+    /// is valid for all conic sections. The STM is not propagated.
+    ///
+    /// Returns an error if the frame of this orbit has no GM, or if Kepler's problem does not converge.
+    pub fn at_epoch(&self, epoch: Epoch) -> Result<Self, NyxError> {
+        let gm = match self.frame {
+            Frame::Geoid { gm, .. } | Frame::Celestial { gm, .. } => gm,
+            _ => {
+                return Err(NyxError::CustomError(
+                    "Keplerian propagation not defined in this frame".to_string(),
+                ))
+            }
+        };
+        let mut me = *self;
+        me.dt = epoch;
+        me.stm = None;
+        let mut delta_t = (epoch - self.dt).in_seconds();
+        if delta_t.abs() < EPSILON {
+            return Ok(me);
+        }
+
+        let r0 = self.radius();
+        let v0 = self.velocity();
+        let r0_norm = r0.norm();
+        let rdotv = r0.dot(&v0);
+        let sqrt_gm = gm.sqrt();
+        let alpha = -v0.norm_squared() / gm + 2.0 / r0_norm;
+
+        // Initial guess of the universal variable
+        let mut chi = if alpha > 1e-6 {
+            // Ellipse: remove the full revolutions
+            let period = 2.0 * PI / (alpha.powi(3) * gm).sqrt();
+            delta_t %= period;
+            sqrt_gm * delta_t * alpha
+        } else if alpha.abs() <= 1e-6 {
+            // Parabola
+            let h = r0.cross(&v0);
+            let p = h.norm_squared() / gm;
+            let s = 0.5 * (1.0 / (3.0 * (gm / p.powi(3)).sqrt() * delta_t)).atan();
+            let w = s.tan().cbrt().atan();
+            p.sqrt() * 2.0 / (2.0 * w).tan()
+        } else {
+            // Hyperbola
+            let a = 1.0 / alpha;
+            delta_t.signum()
+                * (-a).sqrt()
+                * ((-2.0 * gm * alpha * delta_t)
+                    / (rdotv + delta_t.signum() * (-gm * a).sqrt() * (1.0 - r0_norm * alpha)))
+                    .ln()
+        };
+
+        let mut converged = false;
+        for _ in 0..KEPLER_MAX_ITER {
+            let psi = chi.powi(2) * alpha;
+            let c2 = stumpff_c2(psi);
+            let c3 = stumpff_c3(psi);
+            let r_norm = chi.powi(2) * c2
+                + rdotv / sqrt_gm * chi * (1.0 - psi * c3)
+                + r0_norm * (1.0 - psi * c2);
+            let delta_chi = (sqrt_gm * delta_t
+                - chi.powi(3) * c3
+                - rdotv / sqrt_gm * chi.powi(2) * c2
+                - r0_norm * chi * (1.0 - psi * c3))
+                / r_norm;
+            chi += delta_chi;
+            if delta_chi.abs() < 1e-12 * chi.abs().max(1.0) {
+                converged = true;
+                break;
+            }
+        }
+        if !converged {
+            return Err(NyxError::MaxIterReached(KEPLER_MAX_ITER));
+        }
+
+        let psi = chi.powi(2) * alpha;
+        let c2 = stumpff_c2(psi);
+        let c3 = stumpff_c3(psi);
+        let f = 1.0 - chi.powi(2) / r0_norm * c2;
+        let g = delta_t - chi.powi(3) / sqrt_gm * c3;
+        let r = f * r0 + g * v0;
+        let r_norm = r.norm();
+        let g_dot = 1.0 - chi.powi(2) / r_norm * c2;
+        let f_dot = sqrt_gm / (r_norm * r0_norm) * chi * (psi * c3 - 1.0);
+        let v = f_dot * r0 + g_dot * v0;
+
+        me.x = r[0];
+        me.y = r[1];
+        me.z = r[2];
+        me.vx = v[0];
+        me.vy = v[1];
+        me.vz = v[2];
+        Ok(me)
+    }
+
     /// Returns the eccentricity vector (no unit)
     pub fn evec(&self) -> Vector3<f64> {
         match self.frame {
@@ -768,6 +868,31 @@ impl Orbit {
         }
     }
 
+    /// Returns the f modified equinoctial element, i.e. e cos(aop + raan)
+    ///
+    /// The modified equinoctial elements are f = e cos(aop + raan), g = e sin(aop + raan), h = tan(inc/2) cos(raan)
+    /// and k = tan(inc/2) sin(raan), which are not singular for circular and equatorial orbits (but are singular for
+    /// retrograde equatorial orbits). This convention (Walker et al., 1985) is used throughout, e.g. by `Achieve` and by
+    /// the semi-analytic propagator.
+    pub fn equinoctial_f(&self) -> f64 {
+        self.ecc() * (self.aop() + self.raan()).to_radians().cos()
+    }
+
+    /// Returns the g modified equinoctial element, i.e. e sin(aop + raan) (cf. `equinoctial_f`)
+    pub fn equinoctial_g(&self) -> f64 {
+        self.ecc() * (self.aop() + self.raan()).to_radians().sin()
+    }
+
+    /// Returns the h modified equinoctial element, i.e. tan(inc/2) cos(raan) (cf. `equinoctial_f`)
+    pub fn equinoctial_h(&self) -> f64 {
+        (self.inc().to_radians() / 2.0).tan() * self.raan().to_radians().cos()
+    }
+
+    /// Returns the k modified equinoctial element, i.e. tan(inc/2) sin(raan) (cf. `equinoctial_f`)
+    pub fn equinoctial_k(&self) -> f64 {
+        (self.inc().to_radians() / 2.0).tan() * self.raan().to_radians().sin()
+    }
+
     /// Returns the eccentric anomaly in degrees
     ///
     /// This is a conversion from GMAT's StateConversionUtil::TrueToEccentricAnomaly
@@ -969,6 +1094,25 @@ impl Orbit {
         self.vz = new_v[2];
     }
 
+    /// Returns the state of `other` relative to this orbit, expressed in the rotating RIC frame of this orbit
+    /// (radial, in-track, cross-track), as [km, km, km, km/s, km/s, km/s].
+    ///
+    /// The relative velocity is the velocity as seen from the rotating frame, i.e. it accounts for the rotation
+    /// of the local frame at the rate h/r².
+    /// Will **panic** if the frames are different.
+    pub fn ric_difference(&self, other: &Orbit) -> Vector6<f64> {
+        assert_eq!(
+            self.frame, other.frame,
+            "cannot compute the relative state between two states in different frames"
+        );
+        // The RCN frame is Radial, Cross (i.e. in-track), Normal
+        let dcm = self.dcm_to_inertial(Frame::RCN).transpose();
+        let omega = Vector3::new(0.0, 0.0, self.hmag() / self.rmag().powi(2));
+        let rho = dcm * (other.radius() - self.radius());
+        let rho_dot = dcm * (other.velocity() - self.velocity()) - omega.cross(&rho);
+        Vector6::new(rho[0], rho[1], rho[2], rho_dot[0], rho_dot[1], rho_dot[2])
+    }
+
     /// Sets the STM of this state of identity, which enables computation of the STM
     pub fn enable_stm(&mut self) {
         self.stm = Some(Matrix6::identity());
@@ -1211,6 +1355,9 @@ pub enum GuidanceMode {
 }
 
 /// A spacecraft state
+///
+/// The spacecraft either has a single `thruster` and its fuel mass, or several `thrusters` each fed by one of its `tanks`.
+/// In the latter case, the fuel mass is the sum of the propellant in all of the tanks.
 #[derive(Clone, Copy, Debug)]
 pub struct SpacecraftState {
     pub orbit: Orbit,
@@ -1218,6 +1365,8 @@ pub struct SpacecraftState {
     pub fuel_mass_kg: f64,
     pub thruster: Option<Thruster>,
     pub mode: GuidanceMode,
+    pub tanks: [Option<Tank>; MAX_TANKS],
+    pub thrusters: [Option<MountedThruster>; MAX_THRUSTERS],
 }
 
 impl SpacecraftState {
@@ -1228,6 +1377,8 @@ impl SpacecraftState {
             fuel_mass_kg,
             thruster: None,
             mode: GuidanceMode::Coast,
+            tanks: [None; MAX_TANKS],
+            thrusters: [None; MAX_THRUSTERS],
         }
     }
 
@@ -1244,8 +1395,52 @@ impl SpacecraftState {
             fuel_mass_kg,
             thruster: Some(thruster),
             mode: init_mode,
+            tanks: [None; MAX_TANKS],
+            thrusters: [None; MAX_THRUSTERS],
         }
     }
+
+    /// Initializes a spacecraft with several tanks and several thrusters, each fed by one of the tanks.
+    /// Will **panic** if there are too many tanks or thrusters, or if a thruster is fed by a tank which does not exist.
+    pub fn with_propulsion(
+        orbit: Orbit,
+        dry_mass_kg: f64,
+        tanks: &[Tank],
+        thrusters: &[MountedThruster],
+        init_mode: GuidanceMode,
+    ) -> Self {
+        assert!(
+            !tanks.is_empty() && tanks.len() <= MAX_TANKS,
+            "a spacecraft must have between 1 and {} tanks",
+            MAX_TANKS
+        );
+        assert!(
+            thrusters.len() <= MAX_THRUSTERS,
+            "a spacecraft may have at most {} thrusters",
+            MAX_THRUSTERS
+        );
+        let mut me = Self::new(orbit, dry_mass_kg, 0.0);
+        me.mode = init_mode;
+        for (i, tank) in tanks.iter().enumerate() {
+            me.tanks[i] = Some(*tank);
+            me.fuel_mass_kg += tank.fuel_mass_kg;
+        }
+        for (i, thruster) in thrusters.iter().enumerate() {
+            assert!(
+                thruster.tank < tanks.len(),
+                "thruster #{} is fed by tank #{} which does not exist",
+                i,
+                thruster.tank
+            );
+            me.thrusters[i] = Some(*thruster);
+        }
+        me
+    }
+
+    /// Returns whether this spacecraft has several tanks and thrusters
+    pub fn has_tanks(&self) -> bool {
+        self.tanks[0].is_some()
+    }
 }
 
 impl PartialEq for SpacecraftState {
@@ -1278,3 +1473,292 @@ impl fmt::LowerExp for SpacecraftState {
         )
     }
 }
+
+/// Maximum number of spacecraft which can be propagated together in a `FormationState`: the state vector of a
+/// formation is statically sized to hold this many members.
+pub const MAX_FORMATION_SIZE: usize = 8;
+
+/// Number of components of each member in the state vector of a formation: position, velocity and fuel mass.
+pub const FORMATION_MEMBER_SIZE: usize = 7;
+
+/// A formation of spacecraft propagated together with a shared integration step.
+///
+/// All of the members share the same epoch. Only the first `count` members are propagated, and a formation has at
+/// most `MAX_FORMATION_SIZE` members.
+/// NOTE: the STM of the members is not propagated in a formation.
+#[derive(Clone, Copy, Debug)]
+pub struct FormationState {
+    pub members: [SpacecraftState; MAX_FORMATION_SIZE],
+    pub count: usize,
+}
+
+impl FormationState {
+    /// Initializes a new formation from the provided spacecraft states, which must all be defined at the same epoch.
+    ///
+    /// Returns an error if there are no members, more than `MAX_FORMATION_SIZE` members, or if their epochs differ.
+    pub fn new(members: &[SpacecraftState]) -> Result<Self, NyxError> {
+        if members.is_empty() || members.len() > MAX_FORMATION_SIZE {
+            return Err(NyxError::CustomError(format!(
+                "a formation must have between 1 and {} members, got {}",
+                MAX_FORMATION_SIZE,
+                members.len()
+            )));
+        }
+        let epoch = members[0].orbit.dt;
+        let mut sc_members = [members[0]; MAX_FORMATION_SIZE];
+        for (i, sc) in members.iter().enumerate() {
+            if sc.orbit.dt != epoch {
+                return Err(NyxError::CustomError(format!(
+                    "formation member #{} is defined at {} but the formation is at {}",
+                    i, sc.orbit.dt, epoch
+                )));
+            }
+            sc_members[i] = *sc;
+            if sc.orbit.stm.is_some() {
+                warn!("STM of formation member #{} will not be propagated", i);
+                sc_members[i].orbit.stm = None;
+            }
+        }
+        Ok(Self {
+            members: sc_members,
+            count: members.len(),
+        })
+    }
+
+    /// Returns the spacecraft state of the requested member of this formation
+    pub fn member(&self, idx: usize) -> SpacecraftState {
+        assert!(idx < self.count, "no member #{} in formation", idx);
+        self.members[idx]
+    }
+
+    /// Returns the state of the deputy minus the state of the chief, in the integration frame.
+    pub fn relative(&self, chief: usize, deputy: usize) -> Orbit {
+        self.member(deputy).orbit - self.member(chief).orbit
+    }
+
+    /// Returns the state of the deputy in the rotating RIC frame of the chief, cf. `Orbit::ric_difference`.
+    pub fn relative_ric(&self, chief: usize, deputy: usize) -> Vector6<f64> {
+        self.member(chief)
+            .orbit
+            .ric_difference(&self.member(deputy).orbit)
+    }
+
+    /// Returns the distance in kilometers between the chief and the deputy.
+    pub fn separation(&self, chief: usize, deputy: usize) -> f64 {
+        self.member(chief)
+            .orbit
+            .distance_to(&self.member(deputy).orbit)
+    }
+}
+
+impl PartialEq for FormationState {
+    fn eq(&self, other: &FormationState) -> bool {
+        self.count == other.count
+            && self
+                .members
+                .iter()
+                .zip(other.members.iter())
+                .take(self.count)
+                .all(|(mine, theirs)| mine == theirs)
+    }
+}
+
+impl fmt::Display for FormationState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, sc) in self.members.iter().take(self.count).enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "#{}\t{}", i, sc)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::LowerExp for FormationState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, sc) in self.members.iter().take(self.count).enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "#{}\t{:e}", i, sc)?;
+        }
+        Ok(())
+    }
+}
+
+/// An orbit propagated with Encke's method, i.e. as its deviation from a reference Keplerian orbit.
+///
+/// The reference orbit is the osculating orbit at the last rectification: it is propagated analytically, so that only
+/// the small deviation due to the perturbations is integrated.
+/// NOTE: the STM is not propagated with Encke's method.
+#[derive(Clone, Copy, Debug)]
+pub struct EnckeState {
+    /// Osculating orbit at the last rectification
+    pub reference: Orbit,
+    /// Position (km) and velocity (km/s) deviation from the reference orbit at the epoch of this state
+    pub deviation: Vector6<f64>,
+    pub dt: Epoch,
+}
+
+impl EnckeState {
+    /// Initializes an Encke state whose reference is the provided orbit
+    pub fn new(orbit: &Orbit) -> Self {
+        let mut reference = *orbit;
+        reference.stm = None;
+        Self {
+            reference,
+            deviation: Vector6::zeros(),
+            dt: orbit.dt,
+        }
+    }
+
+    /// Returns the osculating orbit of this state
+    pub fn orbit(&self) -> Result<Orbit, NyxError> {
+        Ok(self.reference.at_epoch(self.dt)? + self.deviation)
+    }
+
+    /// Returns this state where the reference is the current osculating orbit
+    pub fn rectified(&self) -> Result<Self, NyxError> {
+        Ok(Self::new(&self.orbit()?))
+    }
+}
+
+impl PartialEq for EnckeState {
+    fn eq(&self, other: &EnckeState) -> bool {
+        match (self.orbit(), other.orbit()) {
+            (Ok(mine), Ok(theirs)) => mine == theirs,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for EnckeState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.orbit() {
+            Ok(orbit) => write!(f, "{}", orbit),
+            Err(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl fmt::LowerExp for EnckeState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.orbit() {
+            Ok(orbit) => write!(f, "{:e}", orbit),
+            Err(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// An orbit in Kustaanheimo-Stiefel (KS) coordinates, with the Sundman transformation dt = r ds of the time.
+///
+/// The elements are the KS position u (km^1/2), its derivative with respect to the fictitious time s, the negative of
+/// the Keplerian energy h (km^2/s^2), and the physical time elapsed since the reference epoch (s). The regularized
+/// equations of motion are linear in u for the two body problem, and the fictitious time slows down the integration
+/// near periapsis, which allows for much larger steps on eccentric orbits and close flybys.
+///
+/// The epoch of this state is the fictitious clock of the propagation, which advances by the scale times the
+/// fictitious time, so that it is close to the physical epoch for near circular orbits: use `orbit` or
+/// `physical_epoch` to get the physical quantities.
+/// NOTE: the STM is not propagated in KS coordinates.
+#[derive(Clone, Copy, Debug)]
+pub struct KsState {
+    pub clock: Epoch,
+    /// Epoch at which the physical time elapsed is zero
+    pub ref_epoch: Epoch,
+    /// u (4), du/ds (4), h, and the physical time elapsed since the reference epoch
+    pub elements: VectorN<f64, U10>,
+    /// Ratio of the fictitious clock to the fictitious time s (km), i.e. the initial radius
+    pub scale: f64,
+    pub frame: Frame,
+}
+
+impl KsState {
+    /// Converts this orbit to KS coordinates, where the fictitious clock starts at the epoch of the orbit
+    pub fn from_orbit(orbit: &Orbit) -> Self {
+        let rmag = orbit.rmag();
+        // Choose the solution of x = L(u) u which avoids dividing by a small number
+        let u = if orbit.x >= 0.0 {
+            let u1 = (0.5 * (rmag + orbit.x)).sqrt();
+            Vector4::new(u1, orbit.y / (2.0 * u1), orbit.z / (2.0 * u1), 0.0)
+        } else {
+            let u2 = (0.5 * (rmag - orbit.x)).sqrt();
+            Vector4::new(orbit.y / (2.0 * u2), u2, 0.0, orbit.z / (2.0 * u2))
+        };
+        let velocity = Vector4::new(orbit.vx, orbit.vy, orbit.vz, 0.0);
+        let du = 0.5 * Self::ks_matrix(&u).transpose() * velocity;
+        let h = orbit.frame.gm() / rmag - 0.5 * orbit.vmag().powi(2);
+
+        let mut elements = VectorN::<f64, U10>::zeros();
+        elements.fixed_rows_mut::<U4>(0).copy_from(&u);
+        elements.fixed_rows_mut::<U4>(4).copy_from(&du);
+        elements[8] = h;
+        Self {
+            clock: orbit.dt,
+            ref_epoch: orbit.dt,
+            elements,
+            scale: rmag,
+            frame: orbit.frame,
+        }
+    }
+
+    /// Returns the KS matrix L(u), such that the position is the first three components of L(u) u
+    pub fn ks_matrix(u: &Vector4<f64>) -> Matrix4<f64> {
+        Matrix4::new(
+            u[0], -u[1], -u[2], u[3], u[1], u[0], -u[3], -u[2], u[2], u[3], u[0], u[1], u[3],
+            -u[2], u[1], -u[0],
+        )
+    }
+
+    /// Returns the KS position u
+    pub fn u(&self) -> Vector4<f64> {
+        self.elements.fixed_rows::<U4>(0).into_owned()
+    }
+
+    /// Returns the derivative of the KS position with respect to the fictitious time
+    pub fn du(&self) -> Vector4<f64> {
+        self.elements.fixed_rows::<U4>(4).into_owned()
+    }
+
+    /// Returns the physical epoch of this state
+    pub fn physical_epoch(&self) -> Epoch {
+        self.ref_epoch + self.elements[9] * TimeUnit::Second
+    }
+
+    /// Returns the osculating orbit of this state, at its physical epoch
+    pub fn orbit(&self) -> Orbit {
+        let u = self.u();
+        let l_u = Self::ks_matrix(&u);
+        let radius = l_u * u;
+        let velocity = (2.0 / u.norm_squared()) * l_u * self.du();
+        Orbit::cartesian(
+            radius[0],
+            radius[1],
+            radius[2],
+            velocity[0],
+            velocity[1],
+            velocity[2],
+            self.physical_epoch(),
+            self.frame,
+        )
+    }
+}
+
+impl PartialEq for KsState {
+    fn eq(&self, other: &KsState) -> bool {
+        self.clock == other.clock && self.orbit() == other.orbit()
+    }
+}
+
+impl fmt::Display for KsState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.orbit())
+    }
+}
+
+impl fmt::LowerExp for KsState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:e}", self.orbit())
+    }
+}
@@ -1,8 +1,53 @@
 use crate::celestia::{Frame, Orbit};
 use crate::dimensions::Vector3;
+use crate::time::Epoch;
 
 pub use super::thrustctrl::Mnvr;
 
+/// An impulsive maneuver, i.e. an instantaneous change in velocity.
+#[derive(Copy, Clone, Debug)]
+pub struct Impulse {
+    /// Change in velocity in km/s, expressed in `frame`
+    pub dv: Vector3<f64>,
+    /// Frame of the change in velocity: either the inertial frame or a local frame (VNC, RCN or RIC)
+    pub frame: Frame,
+    /// Isp (in seconds) used to compute the fuel usage. If unset, the isp of the thruster of the spacecraft is used.
+    pub isp: Option<f64>,
+}
+
+impl Impulse {
+    /// Initializes a new impulse in the provided frame, using the isp of the spacecraft thruster
+    pub fn new(dv: Vector3<f64>, frame: Frame) -> Self {
+        assert!(
+            matches!(
+                frame,
+                Frame::Inertial | Frame::VNC | Frame::RCN | Frame::RIC
+            ),
+            "Impulses must be either in the inertial frame or in a local frame"
+        );
+        Self {
+            dv,
+            frame,
+            isp: None,
+        }
+    }
+
+    /// Initializes a new impulse in the provided frame with the provided isp
+    pub fn with_isp(dv: Vector3<f64>, frame: Frame, isp: f64) -> Self {
+        let mut me = Self::new(dv, frame);
+        me.isp = Some(isp);
+        me
+    }
+
+    /// Returns the change in velocity in the inertial frame of the provided orbit
+    pub fn dv_inertial(&self, orbit: &Orbit) -> Vector3<f64> {
+        match self.frame {
+            Frame::Inertial => self.dv,
+            _ => orbit.dcm_to_inertial(self.frame) * self.dv,
+        }
+    }
+}
+
 /// The `DeltaVctrl` trait handles control laws, optimizations, and other such methods for
 /// controlling the change in velocity of a point mass during a mission arc (`MissionArc`).
 pub trait DeltaVctrl
@@ -28,6 +73,14 @@ impl InstantBurns {
     pub fn from_mnvrs(mnvrs: Vec<Mnvr>) -> Self {
         Self { mnvrs, mnvr_no: 0 }
     }
+
+    /// Returns the impulse and its epoch for each of the maneuvers, where the vector of each maneuver is a delta-v in the VNC frame.
+    pub fn impulses(&self) -> Vec<(Epoch, Impulse)> {
+        self.mnvrs
+            .iter()
+            .map(|mnvr| (mnvr.start, Impulse::new(mnvr.vector, Frame::VNC)))
+            .collect()
+    }
 }
 
 impl DeltaVctrl for InstantBurns {
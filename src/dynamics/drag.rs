@@ -1,16 +1,29 @@
 use super::hyperdual::Hyperdual;
 use super::ForceModel;
-use crate::celestia::{Cosm, Frame, SpacecraftState};
+use crate::celestia::{Cosm, Frame, Orbit, SpacecraftState};
 use crate::dimensions::{Matrix3, Vector3, U7};
 use crate::errors::NyxError;
+use crate::time::TimeUnit;
 use std::sync::Arc;
 
 /// Density in kg/m^3 and altitudes in meters, not kilometers!
 #[derive(Clone, Copy, Debug)]
 pub enum AtmDensity {
     Constant(f64),
-    Exponential { rho0: f64, r0: f64, ref_alt_m: f64 },
-    StdAtm { max_alt_m: f64 },
+    Exponential {
+        rho0: f64,
+        r0: f64,
+        ref_alt_m: f64,
+    },
+    StdAtm {
+        max_alt_m: f64,
+    },
+    /// Exponential model whose scale height depends on the solar flux (F10.7 in sfu) and the geomagnetic index (Ap),
+    /// valid between 180 and 500 km (IPS Radio and Space Services, "Satellite Orbital Decay Calculations", 1999)
+    SolarFlux {
+        f107: f64,
+        ap: f64,
+    },
 }
 
 /// `ConstantDrag` implements a constant drag model as defined in Vallado, 4th ed., page 551, with an important caveat.
@@ -91,16 +104,59 @@ impl Drag {
             cosm,
         })
     }
+
+    /// Drag model whose density depends on the solar activity, for orbit lifetime estimations
+    pub fn earth_solar_flux(
+        sc_area: f64,
+        cd: f64,
+        f107: f64,
+        ap: f64,
+        cosm: Arc<Cosm>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            density: AtmDensity::SolarFlux { f107, ap },
+            sc_area,
+            cd,
+            drag_frame: cosm.frame("IAU Earth"),
+            cosm,
+        })
+    }
+
+    /// Returns the density (kg/m^3) at this geodetic altitude (km) of the solar flux model
+    pub fn solar_flux_density(altitude_km: f64, f107: f64, ap: f64) -> f64 {
+        let temperature = 900.0 + 2.5 * (f107 - 70.0) + 1.5 * ap;
+        let molecular_mass = 27.0 - 0.012 * (altitude_km - 200.0);
+        let scale_height_km = temperature / molecular_mass;
+        6e-10 * (-(altitude_km - 175.0) / scale_height_km).exp()
+    }
+
+    /// Returns the velocity (km/s) of this orbit relative to the atmosphere, which is at rest in the drag frame,
+    /// expressed in the frame of the orbit
+    fn relative_velocity(&self, orbit: &Orbit) -> Result<Vector3<f64>, NyxError> {
+        let dcm_at = |dt| {
+            self.cosm
+                .try_frame_chg_dcm_from_to(&orbit.frame, &self.drag_frame, dt)
+        };
+        let dcm = dcm_at(orbit.dt)?;
+        // Rate of change of the rotation to the drag frame, by central differences over one second
+        let dcm_dot =
+            dcm_at(orbit.dt + 0.5 * TimeUnit::Second)? - dcm_at(orbit.dt - 0.5 * TimeUnit::Second)?;
+        Ok(dcm.transpose() * (dcm * orbit.velocity() + dcm_dot * orbit.radius()))
+    }
+
+    /// Returns the drag force (kg*km/s^2) for the provided density (kg/m^3) and relative velocity (km/s)
+    fn force(&self, rho: f64, velocity: &Vector3<f64>) -> Vector3<f64> {
+        // Note the 1e3 is the 1e6 to convert the velocity squared to m^2/s^2 times the 1e-3 to convert the force in N
+        // to kg*km/s^2
+        -0.5 * 1e3 * rho * self.cd * self.sc_area * velocity.norm() * velocity
+    }
 }
 
 impl ForceModel for Drag {
     fn eom(&self, ctx: &SpacecraftState) -> Result<Vector3<f64>, NyxError> {
         let osc = self.cosm.frame_chg(&ctx.orbit, self.drag_frame);
         match self.density {
-            AtmDensity::Constant(rho) => {
-                let velocity = osc.velocity();
-                Ok(-0.5 * rho * self.cd * self.sc_area * velocity.norm() * velocity)
-            }
+            AtmDensity::Constant(rho) => Ok(self.force(rho, &self.relative_velocity(&ctx.orbit)?)),
             AtmDensity::Exponential {
                 rho0,
                 r0,
@@ -110,13 +166,7 @@ impl ForceModel for Drag {
                     * (-(osc.rmag() - (r0 + self.drag_frame.equatorial_radius())) / ref_alt_m)
                         .exp();
 
-                let velocity_eme2k = self
-                    .cosm
-                    .frame_chg(&osc, self.cosm.frame("EME2000"))
-                    .velocity();
-
-                let velocity = velocity_eme2k - osc.velocity();
-                Ok(-0.5 * rho * self.cd * self.sc_area * velocity.norm() * velocity)
+                Ok(self.force(rho, &self.relative_velocity(&ctx.orbit)?))
             }
             AtmDensity::StdAtm { max_alt_m } => {
                 let altitude_km = osc.rmag() - self.drag_frame.equatorial_radius();
@@ -138,13 +188,11 @@ impl ForceModel for Drag {
                     10.0_f64.powf(logdensity)
                 };
 
-                let velocity_eme2k = self
-                    .cosm
-                    .frame_chg(&osc, self.cosm.frame("EME2000"))
-                    .velocity();
-
-                let velocity = velocity_eme2k - osc.velocity();
-                Ok(-0.5 * rho * self.cd * self.sc_area * velocity.norm() * velocity)
+                Ok(self.force(rho, &self.relative_velocity(&ctx.orbit)?))
+            }
+            AtmDensity::SolarFlux { f107, ap } => {
+                let rho = Self::solar_flux_density(osc.geodetic_height(), f107, ap);
+                Ok(self.force(rho, &self.relative_velocity(&ctx.orbit)?))
             }
         }
     }
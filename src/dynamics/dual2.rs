@@ -0,0 +1,139 @@
+use crate::dimensions::{Matrix3, Vector3};
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// A second order dual number (i.e. a hyper-hyperdual number truncated to the second order) in the three components of
+/// the position: it carries the value of a function, its gradient and its Hessian with respect to the position. This
+/// allows computing the second partials of the accelerations, needed to propagate the second order state transition
+/// tensors.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Dual2 {
+    pub real: f64,
+    pub grad: Vector3<f64>,
+    pub hess: Matrix3<f64>,
+}
+
+impl Dual2 {
+    /// Initializes a constant, whose partials are zero
+    pub fn from_real(real: f64) -> Self {
+        Self {
+            real,
+            grad: Vector3::zeros(),
+            hess: Matrix3::zeros(),
+        }
+    }
+
+    /// Initializes the provided component of the position
+    pub fn variable(real: f64, idx: usize) -> Self {
+        let mut me = Self::from_real(real);
+        me.grad[idx] = 1.0;
+        me
+    }
+
+    /// Initializes the three components of the position as variables
+    pub fn from_position(radius: &Vector3<f64>) -> [Self; 3] {
+        [
+            Self::variable(radius[0], 0),
+            Self::variable(radius[1], 1),
+            Self::variable(radius[2], 2),
+        ]
+    }
+
+    /// Applies the function whose value, first and second derivatives at the real part of this number are provided
+    fn chain(self, f: f64, df: f64, d2f: f64) -> Self {
+        Self {
+            real: f,
+            grad: df * self.grad,
+            hess: df * self.hess + d2f * self.grad * self.grad.transpose(),
+        }
+    }
+
+    pub fn recip(self) -> Self {
+        let inv = 1.0 / self.real;
+        self.chain(inv, -inv * inv, 2.0 * inv * inv * inv)
+    }
+
+    pub fn sqrt(self) -> Self {
+        let sqrt = self.real.sqrt();
+        self.chain(sqrt, 0.5 / sqrt, -0.25 / (sqrt * self.real))
+    }
+
+    pub fn powi(self, n: i32) -> Self {
+        let nf = f64::from(n);
+        self.chain(
+            self.real.powi(n),
+            nf * self.real.powi(n - 1),
+            nf * (nf - 1.0) * self.real.powi(n - 2),
+        )
+    }
+}
+
+/// Returns the norm of the provided vector of second order dual numbers
+pub fn norm(vec: &[Dual2; 3]) -> Dual2 {
+    (vec[0] * vec[0] + vec[1] * vec[1] + vec[2] * vec[2]).sqrt()
+}
+
+impl Add for Dual2 {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            real: self.real + rhs.real,
+            grad: self.grad + rhs.grad,
+            hess: self.hess + rhs.hess,
+        }
+    }
+}
+
+impl Sub for Dual2 {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        self + (-rhs)
+    }
+}
+
+impl Neg for Dual2 {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self {
+            real: -self.real,
+            grad: -self.grad,
+            hess: -self.hess,
+        }
+    }
+}
+
+impl Mul for Dual2 {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        let cross = self.grad * rhs.grad.transpose();
+        Self {
+            real: self.real * rhs.real,
+            grad: self.grad * rhs.real + rhs.grad * self.real,
+            hess: self.hess * rhs.real + rhs.hess * self.real + cross + cross.transpose(),
+        }
+    }
+}
+
+impl Mul<f64> for Dual2 {
+    type Output = Self;
+
+    fn mul(self, rhs: f64) -> Self {
+        Self {
+            real: self.real * rhs,
+            grad: self.grad * rhs,
+            hess: self.hess * rhs,
+        }
+    }
+}
+
+impl Div for Dual2 {
+    type Output = Self;
+
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn div(self, rhs: Self) -> Self {
+        self * rhs.recip()
+    }
+}
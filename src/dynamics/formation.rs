@@ -0,0 +1,94 @@
+use super::spacecraft::Spacecraft;
+use super::{Dynamics, NyxError};
+use crate::celestia::{FormationState, FORMATION_MEMBER_SIZE};
+use crate::dimensions::{MatrixN, VectorN, U46, U56, U57};
+use crate::dynamics::Hyperdual;
+use std::sync::Arc;
+
+/// `Formation` propagates several spacecraft, each with their own dynamics, in a single integration with a shared step.
+///
+/// The i-th dynamics apply to the i-th member of the `FormationState`. Note that the error controllers which only
+/// look at the first six components of a state (e.g. `RSSStepPV`) will only control the error of the first member:
+/// use `RSSState` or `LargestError` to account for all of the members.
+/// Like the `FormationState`, a formation has at most `MAX_FORMATION_SIZE` members.
+#[derive(Clone)]
+pub struct Formation<'a> {
+    pub members: Vec<Arc<Spacecraft<'a>>>,
+}
+
+impl<'a> Formation<'a> {
+    /// Initialize a formation from the dynamics of each of its members.
+    pub fn new(members: Vec<Arc<Spacecraft<'a>>>) -> Arc<Self> {
+        Arc::new(Self { members })
+    }
+
+    /// Initialize a formation of `count` spacecraft all subjected to the same dynamics.
+    pub fn identical(dynamics: Arc<Spacecraft<'a>>, count: usize) -> Arc<Self> {
+        let mut members = Vec::with_capacity(count);
+        for _ in 0..count {
+            members.push(dynamics.clone());
+        }
+        Self::new(members)
+    }
+}
+
+impl<'a> Dynamics for Formation<'a> {
+    type HyperdualSize = U57;
+    type StateType = FormationState;
+
+    fn finally(&self, next_state: Self::StateType) -> Result<Self::StateType, NyxError> {
+        let mut state = next_state;
+        for (i, sc_dyn) in self.members.iter().enumerate() {
+            state.members[i] = sc_dyn.finally(state.members[i])?;
+        }
+        Ok(state)
+    }
+
+    fn eom(
+        &self,
+        delta_t: f64,
+        state: &VectorN<f64, U56>,
+        ctx: &FormationState,
+    ) -> Result<VectorN<f64, U56>, NyxError> {
+        if ctx.count != self.members.len() {
+            return Err(NyxError::CustomError(format!(
+                "formation has {} members but {} dynamics are defined",
+                ctx.count,
+                self.members.len()
+            )));
+        }
+
+        let mut d_x = VectorN::<f64, U56>::zeros();
+        for (i, sc_dyn) in self.members.iter().enumerate() {
+            let offset = i * FORMATION_MEMBER_SIZE;
+            if ctx.members[i].has_tanks() {
+                return Err(NyxError::CustomError(format!(
+                    "formation member #{} has several tanks: only a single fuel mass is supported",
+                    i
+                )));
+            }
+            // Rebuild the spacecraft state vector (with a zero STM) to reuse the spacecraft dynamics.
+            let mut sc_vec = VectorN::<f64, U46>::zeros();
+            for j in 0..6 {
+                sc_vec[j] = state[offset + j];
+            }
+            sc_vec[42] = state[offset + 6];
+            let sc_d_x = sc_dyn.eom(delta_t, &sc_vec, &ctx.members[i])?;
+            for j in 0..6 {
+                d_x[offset + j] = sc_d_x[j];
+            }
+            d_x[offset + 6] = sc_d_x[42];
+        }
+
+        Ok(d_x)
+    }
+
+    fn dual_eom(
+        &self,
+        _delta_t_s: f64,
+        _state_vec: &VectorN<Hyperdual<f64, Self::HyperdualSize>, U56>,
+        _ctx: &Self::StateType,
+    ) -> Result<(VectorN<f64, U56>, MatrixN<f64, U56>), NyxError> {
+        Err(NyxError::PartialsUndefined)
+    }
+}
@@ -27,12 +27,29 @@ pub use self::orbital::*;
 pub mod spacecraft;
 pub use self::spacecraft::*;
 
+/// The formation module allows propagating several spacecraft in a single integration.
+pub mod formation;
+pub use self::formation::*;
+
+/// The regularized module provides Encke's method and the Kustaanheimo-Stiefel regularization of the orbital dynamics,
+/// for highly eccentric orbits and close flybys.
+pub mod regularized;
+pub use self::regularized::*;
+
+/// Defines the propellant tanks and the thrusters mounted on a spacecraft.
+pub mod propulsion;
+pub use self::propulsion::*;
+
 /// Defines a few examples of thrust controllers.
 pub mod thrustctrl;
 
 /// Defines some velocity change controllers.
 pub mod deltavctrl;
 
+/// Defines solar electric propulsion models, where the thrust depends on the power available
+pub mod sep;
+pub use self::sep::*;
+
 /// Defines solar radiation pressure models
 pub mod solarpressure;
 pub use self::solarpressure::*;
@@ -45,6 +62,9 @@ pub use self::drag::*;
 pub mod sph_harmonics;
 pub use self::sph_harmonics::*;
 
+/// Defines the second order dual numbers, used to compute the second partials of the accelerations.
+pub mod dual2;
+
 /// The `Dynamics` trait handles and stores any equation of motion *and* the state is integrated.
 ///
 /// Its design is such that several of the provided dynamics can be combined fairly easily. However,
@@ -167,4 +187,46 @@ pub trait AccelModel: Send + Sync {
         radius: &Vector3<Hyperdual<f64, U7>>,
         osc_ctx: &Orbit,
     ) -> Result<(Vector3<f64>, Matrix3<f64>), NyxError>;
+
+    /// Returns the potential (km^2/s^2) of this force model, whose gradient is the acceleration returned by `eom`.
+    /// Only conservative models have a potential, which is used to monitor the conservation of energy.
+    fn potential(&self, _osc: &Orbit) -> Result<f64, NyxError> {
+        Err(NyxError::CustomError(
+            "this acceleration model does not derive from a potential".to_string(),
+        ))
+    }
+
+    /// Returns the second partials of the acceleration with respect to the position, where `hessian[i][(j, k)]` is
+    /// the partial of the i-th component of the acceleration with respect to the j-th and k-th components of the
+    /// position. These are only called if the propagation requires the second order state transition tensor.
+    ///
+    /// By default, these are computed by central differences of the partials of `dual_eom`.
+    fn hessian(&self, osc: &Orbit) -> Result<[Matrix3<f64>; 3], NyxError> {
+        let step = 1e-6 * osc.rmag().max(1.0);
+        let mut hessian = [Matrix3::zeros(); 3];
+        for k in 0..3 {
+            let mut grads = Vec::with_capacity(2);
+            for sign in &[1.0, -1.0] {
+                let mut radius = osc.radius();
+                radius[k] += sign * step;
+                let mut pert = *osc;
+                pert.x = radius[0];
+                pert.y = radius[1];
+                pert.z = radius[2];
+                let radius_d: Vector3<Hyperdual<f64, U7>> = hyperspace_from_vector(&radius);
+                grads.push(self.dual_eom(&radius_d, &pert)?.1);
+            }
+            let diff = (grads[0] - grads[1]) / (2.0 * step);
+            for (i, hess_i) in hessian.iter_mut().enumerate() {
+                for j in 0..3 {
+                    hess_i[(j, k)] = diff[(i, j)];
+                }
+            }
+        }
+        // The differences are not exactly symmetric
+        for hess_i in hessian.iter_mut() {
+            *hess_i = 0.5 * (*hess_i + hess_i.transpose());
+        }
+        Ok(hessian)
+    }
 }
@@ -1,3 +1,4 @@
+use super::dual2::{norm as norm2, Dual2};
 use super::hyperdual::linalg::norm;
 use super::hyperdual::{extract_jacobian_and_result, hyperspace_from_vector, Float, Hyperdual};
 use super::{AccelModel, Dynamics, NyxError};
@@ -51,6 +52,43 @@ impl<'a> OrbitalDynamics<'a> {
     pub fn add_model(&mut self, accel_model: Arc<dyn AccelModel + Sync + 'a>) {
         self.accel_models.push(accel_model);
     }
+
+    /// Returns the specific mechanical energy (km^2/s^2) of this orbit in these dynamics, i.e. including the potential
+    /// of each acceleration model. This will fail if any of the acceleration models is not conservative.
+    pub fn energy(&self, osc: &Orbit) -> Result<f64, NyxError> {
+        let mut energy = osc.energy();
+        for model in &self.accel_models {
+            energy -= model.potential(osc)?;
+        }
+        Ok(energy)
+    }
+
+    /// Returns the Jacobi integral (km^2/s^2) of this orbit, i.e. its energy in the frame rotating at the provided
+    /// angular velocity (rad/s, expressed in the frame of the orbit). This is conserved when the potentials are
+    /// stationary in that rotating frame, e.g. the harmonics of a body spinning at that rate.
+    pub fn jacobi(&self, osc: &Orbit, angular_velocity: &Vector3<f64>) -> Result<f64, NyxError> {
+        Ok(self.energy(osc)? - angular_velocity.dot(&osc.hvec()))
+    }
+
+    /// Returns the second partials of the acceleration with respect to the position in these dynamics, where
+    /// `hessian[i][(j, k)]` is the partial of the i-th component of the acceleration with respect to the j-th and k-th
+    /// components of the position. The acceleration does not depend on the velocity, so these are the only non zero
+    /// second partials of the equations of motion.
+    pub fn hessian(&self, osc: &Orbit) -> Result<[Matrix3<f64>; 3], NyxError> {
+        let radius = Dual2::from_position(&osc.radius());
+        let factor = Dual2::from_real(-osc.frame.gm()) / norm2(&radius).powi(3);
+        let mut hessian = [Matrix3::zeros(); 3];
+        for (hess_i, r_i) in hessian.iter_mut().zip(&radius) {
+            *hess_i = (*r_i * factor).hess;
+        }
+        for model in &self.accel_models {
+            let model_hess = model.hessian(osc)?;
+            for i in 0..3 {
+                hessian[i] += model_hess[i];
+            }
+        }
+        Ok(hessian)
+    }
 }
 
 impl<'a> Dynamics for OrbitalDynamics<'a> {
@@ -293,4 +331,46 @@ impl AccelModel for PointMasses {
 
         Ok((fx, grad))
     }
+
+    /// The disturbing potential of the third bodies, whose gradient is the acceleration relative to the primary body
+    fn potential(&self, osc: &Orbit) -> Result<f64, NyxError> {
+        let mut pot = 0.0;
+        for third_body in &self.bodies {
+            // Orbit of j-th body as seen from primary body
+            let st_ij = self.cosm.try_celestial_state(
+                &third_body.ephem,
+                osc.dt,
+                self.frame,
+                self.correction,
+            )?;
+
+            let r_ij = st_ij.radius();
+            let r_j = osc.radius() - r_ij; // sc as seen from 3rd body
+            pot +=
+                third_body.gm * (1.0 / r_j.norm() - osc.radius().dot(&r_ij) / st_ij.rmag().powi(3));
+        }
+        Ok(pot)
+    }
+
+    /// The acceleration of the primary body towards the third bodies does not depend on the position
+    fn hessian(&self, osc: &Orbit) -> Result<[Matrix3<f64>; 3], NyxError> {
+        let mut hessian = [Matrix3::zeros(); 3];
+        for third_body in &self.bodies {
+            // Orbit of j-th body as seen from primary body
+            let st_ij = self.cosm.try_celestial_state(
+                &third_body.ephem,
+                osc.dt,
+                self.frame,
+                self.correction,
+            )?;
+
+            // The partials with respect to the position from the third body are the same
+            let r_j = Dual2::from_position(&(osc.radius() - st_ij.radius()));
+            let factor = Dual2::from_real(-third_body.gm) / norm2(&r_j).powi(3);
+            for (hess_i, r_j_i) in hessian.iter_mut().zip(&r_j) {
+                *hess_i += (*r_j_i * factor).hess;
+            }
+        }
+        Ok(hessian)
+    }
 }
@@ -0,0 +1,162 @@
+use super::spacecraft::STD_GRAVITY;
+use super::thrustctrl::Thruster;
+use crate::dimensions::{Matrix3, Vector3};
+
+/// Maximum number of propellant tanks on a spacecraft
+pub const MAX_TANKS: usize = 4;
+/// Maximum number of thrusters on a spacecraft
+pub const MAX_THRUSTERS: usize = 8;
+
+/// A propellant tank. If the ullage volume is zero, the tank is considered pressure regulated,
+/// otherwise the pressurant expands isothermally as the propellant is consumed (blowdown).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Tank {
+    /// Propellant mass, in kg
+    pub fuel_mass_kg: f64,
+    /// Pressure when the tank holds `ref_fuel_mass_kg` of propellant, in kPa
+    pub ref_pressure_kpa: f64,
+    /// Ullage volume when the tank holds `ref_fuel_mass_kg` of propellant, in m^3
+    pub ref_ullage_m3: f64,
+    /// Propellant mass at the reference pressure, in kg
+    pub ref_fuel_mass_kg: f64,
+    /// Density of the propellant, in kg/m^3
+    pub fuel_density_kg_m3: f64,
+}
+
+impl Tank {
+    /// Initializes a pressure regulated tank
+    pub fn regulated(fuel_mass_kg: f64) -> Self {
+        Self {
+            fuel_mass_kg,
+            ref_pressure_kpa: 0.0,
+            ref_ullage_m3: 0.0,
+            ref_fuel_mass_kg: fuel_mass_kg,
+            fuel_density_kg_m3: 1.0,
+        }
+    }
+
+    /// Initializes a blowdown tank from its current propellant mass, pressure (in kPa), ullage volume (in m^3),
+    /// and propellant density (in kg/m^3). For example, the density of hydrazine is about 1004 kg/m^3.
+    pub fn blowdown(
+        fuel_mass_kg: f64,
+        pressure_kpa: f64,
+        ullage_m3: f64,
+        fuel_density_kg_m3: f64,
+    ) -> Self {
+        assert!(ullage_m3 > 0.0, "ullage volume must be positive");
+        assert!(fuel_density_kg_m3 > 0.0, "fuel density must be positive");
+        Self {
+            fuel_mass_kg,
+            ref_pressure_kpa: pressure_kpa,
+            ref_ullage_m3: ullage_m3,
+            ref_fuel_mass_kg: fuel_mass_kg,
+            fuel_density_kg_m3,
+        }
+    }
+
+    /// Returns whether this tank is in blowdown mode
+    pub fn is_blowdown(&self) -> bool {
+        self.ref_ullage_m3 > 0.0
+    }
+
+    /// Returns the pressure of this tank, in kPa
+    pub fn pressure_kpa(&self) -> f64 {
+        self.ref_pressure_kpa * self.blowdown_ratio()
+    }
+
+    /// Returns the ratio of the current pressure to the reference pressure (always 1.0 for a regulated tank).
+    pub fn blowdown_ratio(&self) -> f64 {
+        if self.is_blowdown() {
+            let ullage = self.ref_ullage_m3
+                + (self.ref_fuel_mass_kg - self.fuel_mass_kg) / self.fuel_density_kg_m3;
+            self.ref_ullage_m3 / ullage
+        } else {
+            1.0
+        }
+    }
+}
+
+/// A thruster mounted on the spacecraft and fed by one of its tanks.
+///
+/// The thrust of the thruster is its thrust at the reference pressure of its tank: in blowdown mode, the thrust
+/// decreases linearly with the pressure of the tank, and the isp is assumed constant.
+#[derive(Copy, Clone, Debug)]
+pub struct MountedThruster {
+    pub thruster: Thruster,
+    /// Unit vector of the direction of the thrust in the body frame
+    pub direction: Vector3<f64>,
+    /// Index of the tank feeding this thruster
+    pub tank: usize,
+}
+
+impl MountedThruster {
+    /// Initializes a new mounted thruster, the direction is normalized.
+    pub fn new(thruster: Thruster, direction: Vector3<f64>, tank: usize) -> Self {
+        assert!(
+            tank < MAX_TANKS,
+            "tank index must be less than {}",
+            MAX_TANKS
+        );
+        Self {
+            thruster,
+            direction: direction / direction.norm(),
+            tank,
+        }
+    }
+
+    /// Returns the thrust (in N) and the mass flow (in kg/s) of this thruster when fed by the provided tank
+    pub fn performance(&self, tank: &Tank) -> (f64, f64) {
+        let thrust = self.thruster.thrust * tank.blowdown_ratio();
+        (thrust, thrust / (self.thruster.isp * STD_GRAVITY))
+    }
+}
+
+/// Returns the rotation from the body frame to the inertial frame such that the body +X axis is along the
+/// provided (unit) direction. The roll about this direction is the smallest rotation from the inertial X axis.
+pub fn body_to_inertial(direction: &Vector3<f64>) -> Matrix3<f64> {
+    let x_hat = Vector3::x();
+    let axis = x_hat.cross(direction);
+    let sin_angle = axis.norm();
+    let cos_angle = x_hat.dot(direction);
+    if sin_angle < 1e-12 {
+        if cos_angle > 0.0 {
+            Matrix3::identity()
+        } else {
+            // Rotation of 180 degrees about the Z axis
+            Matrix3::new(-1.0, 0.0, 0.0, 0.0, -1.0, 0.0, 0.0, 0.0, 1.0)
+        }
+    } else {
+        let skew = Matrix3::new(
+            0.0, -axis[2], axis[1], axis[2], 0.0, -axis[0], -axis[1], axis[0], 0.0,
+        );
+        Matrix3::identity() + skew + skew * skew * ((1.0 - cos_angle) / sin_angle.powi(2))
+    }
+}
+
+#[test]
+fn test_blowdown() {
+    let mut tank = Tank::blowdown(100.0, 2400.0, 0.02, 1004.0);
+    assert!((tank.pressure_kpa() - 2400.0).abs() < std::f64::EPSILON);
+    // Use half of the propellant
+    tank.fuel_mass_kg = 50.0;
+    let ullage = 0.02 + 50.0 / 1004.0;
+    assert!((tank.pressure_kpa() - 2400.0 * 0.02 / ullage).abs() < 1e-9);
+
+    let regulated = Tank::regulated(10.0);
+    assert!((regulated.blowdown_ratio() - 1.0).abs() < std::f64::EPSILON);
+}
+
+#[test]
+fn test_body_to_inertial() {
+    for dir in &[
+        Vector3::new(1.0, 0.0, 0.0),
+        Vector3::new(-1.0, 0.0, 0.0),
+        Vector3::new(0.0, 1.0, 0.0),
+        Vector3::new(1.0, -2.0, 3.0) / 14.0_f64.sqrt(),
+    ] {
+        let dcm = body_to_inertial(dir);
+        assert!((dcm * Vector3::x() - dir).norm() < 1e-12);
+        assert!((dcm.determinant() - 1.0).abs() < 1e-12);
+        assert!((dcm * dcm.transpose() - Matrix3::identity()).norm() < 1e-12);
+    }
+}
@@ -0,0 +1,188 @@
+use super::hyperdual::Hyperdual;
+use super::orbital::OrbitalDynamics;
+use super::{Dynamics, NyxError};
+use crate::celestia::{EnckeState, KsState};
+use crate::dimensions::{MatrixN, Vector3, Vector4, Vector6, VectorN, U10, U11, U3, U4, U6, U7};
+use crate::propagators::error_ctrl::ErrorCtrl;
+use crate::propagators::PropInstance;
+use crate::time::{Epoch, TimeUnit};
+use std::sync::Arc;
+
+/// Maximum number of corrections of the fictitious duration to reach a physical epoch
+const MAX_EPOCH_CORRECTIONS: usize = 20;
+/// Tolerance on the physical epoch reached by a KS propagation (s)
+const EPOCH_TOLERANCE_S: f64 = 1e-5;
+
+/// `Encke` propagates the deviation of an orbit from a reference Keplerian orbit, subjected to the acceleration
+/// models of the orbital dynamics. The reference is rectified when the deviation becomes large.
+///
+/// As the deviation is small and smooth, the integration allows larger steps than Cowell's formulation for
+/// weakly perturbed orbits, e.g. highly eccentric orbits.
+///
+/// Reference: Battin, "An Introduction to the Mathematics and Methods of Astrodynamics", Revised Ed., section 10.2.
+#[derive(Clone)]
+pub struct Encke<'a> {
+    pub orbital_dyn: Arc<OrbitalDynamics<'a>>,
+    /// Ratio of the position deviation to the reference radius above which the reference is rectified
+    pub rectify_ratio: f64,
+}
+
+impl<'a> Encke<'a> {
+    /// Initializes Encke's method with a rectification when the deviation reaches 1% of the radius
+    pub fn new(orbital_dyn: Arc<OrbitalDynamics<'a>>) -> Arc<Self> {
+        Self::with_rectification(orbital_dyn, 1e-2)
+    }
+
+    /// Initializes Encke's method with the provided rectification ratio
+    pub fn with_rectification(
+        orbital_dyn: Arc<OrbitalDynamics<'a>>,
+        rectify_ratio: f64,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            orbital_dyn,
+            rectify_ratio,
+        })
+    }
+}
+
+impl<'a> Dynamics for Encke<'a> {
+    type HyperdualSize = U7;
+    type StateType = EnckeState;
+
+    /// Rectifies the reference orbit if the deviation is too large
+    fn finally(&self, next_state: Self::StateType) -> Result<Self::StateType, NyxError> {
+        let ref_rmag = next_state.reference.at_epoch(next_state.dt)?.rmag();
+        if next_state.deviation.fixed_rows::<U3>(0).norm() > self.rectify_ratio * ref_rmag {
+            debug!("rectifying Encke reference at {}", next_state.dt);
+            next_state.rectified()
+        } else {
+            Ok(next_state)
+        }
+    }
+
+    fn eom(
+        &self,
+        delta_t_s: f64,
+        state: &Vector6<f64>,
+        ctx: &EnckeState,
+    ) -> Result<Vector6<f64>, NyxError> {
+        let reference = ctx
+            .reference
+            .at_epoch(ctx.dt + delta_t_s * TimeUnit::Second)?;
+        let osc = reference + *state;
+        let rho = reference.radius();
+        let radius = osc.radius();
+        let delta_r: Vector3<f64> = state.fixed_rows::<U3>(0).into_owned();
+        // 1 - (rho/r)^3 computed without the loss of precision of the difference of close numbers (Battin eq. 8.61)
+        let q = delta_r.dot(&(delta_r - 2.0 * radius)) / radius.norm_squared();
+        let f_q = -q * (3.0 + 3.0 * q + q.powi(2)) / (1.0 + (1.0 + q).powf(1.5));
+        let mut accel = osc.frame.gm() / rho.norm().powi(3) * (f_q * radius - delta_r);
+        for model in &self.orbital_dyn.accel_models {
+            accel += model.eom(&osc)?;
+        }
+
+        let mut d_x = Vector6::zeros();
+        for i in 0..3 {
+            d_x[i] = state[i + 3];
+            d_x[i + 3] = accel[i];
+        }
+        Ok(d_x)
+    }
+
+    fn dual_eom(
+        &self,
+        _delta_t_s: f64,
+        _state_vec: &VectorN<Hyperdual<f64, Self::HyperdualSize>, U6>,
+        _ctx: &Self::StateType,
+    ) -> Result<(Vector6<f64>, MatrixN<f64, U6>), NyxError> {
+        Err(NyxError::PartialsUndefined)
+    }
+}
+
+/// `KustaanheimoStiefel` propagates an orbit in KS coordinates with the Sundman transformation of the time, subjected
+/// to the acceleration models of the orbital dynamics (all of which are treated as perturbations).
+///
+/// The propagation is in the fictitious clock of the `KsState`: use `PropInstance::until_physical_epoch` to stop at a
+/// physical epoch. Note that `RSSStepPV` only controls the error of the first six components of the state, and that
+/// the physical time dominates the norm of the state in `RSSState`: prefer `LargestError` with these dynamics.
+///
+/// Reference: Stiefel and Scheifele, "Linear and Regular Celestial Mechanics", 1971, chapter 9.
+#[derive(Clone)]
+pub struct KustaanheimoStiefel<'a> {
+    pub orbital_dyn: Arc<OrbitalDynamics<'a>>,
+}
+
+impl<'a> KustaanheimoStiefel<'a> {
+    pub fn new(orbital_dyn: Arc<OrbitalDynamics<'a>>) -> Arc<Self> {
+        Arc::new(Self { orbital_dyn })
+    }
+}
+
+impl<'a> Dynamics for KustaanheimoStiefel<'a> {
+    type HyperdualSize = U11;
+    type StateType = KsState;
+
+    fn eom(
+        &self,
+        _delta_t_s: f64,
+        state: &VectorN<f64, U10>,
+        ctx: &KsState,
+    ) -> Result<VectorN<f64, U10>, NyxError> {
+        let mut osc_ctx = *ctx;
+        osc_ctx.elements = *state;
+        let osc = osc_ctx.orbit();
+
+        let mut accel = Vector3::zeros();
+        for model in &self.orbital_dyn.accel_models {
+            accel += model.eom(&osc)?;
+        }
+
+        let u = osc_ctx.u();
+        let du = osc_ctx.du();
+        let h = state[8];
+        let rmag = u.norm_squared();
+        let l_t_p =
+            KsState::ks_matrix(&u).transpose() * Vector4::new(accel[0], accel[1], accel[2], 0.0);
+        let ddu = -0.5 * h * u + 0.5 * rmag * l_t_p;
+
+        // Derivatives with respect to the fictitious time s, scaled to the fictitious clock
+        let mut d_x = VectorN::<f64, U10>::zeros();
+        d_x.fixed_rows_mut::<U4>(0).copy_from(&du);
+        d_x.fixed_rows_mut::<U4>(4).copy_from(&ddu);
+        d_x[8] = -2.0 * du.dot(&l_t_p);
+        d_x[9] = rmag;
+        Ok(d_x / ctx.scale)
+    }
+
+    fn dual_eom(
+        &self,
+        _delta_t_s: f64,
+        _state_vec: &VectorN<Hyperdual<f64, Self::HyperdualSize>, U10>,
+        _ctx: &Self::StateType,
+    ) -> Result<(VectorN<f64, U10>, MatrixN<f64, U10>), NyxError> {
+        Err(NyxError::PartialsUndefined)
+    }
+}
+
+impl<'a, 'b, E: ErrorCtrl> PropInstance<'a, KustaanheimoStiefel<'b>, E> {
+    /// Propagates until the provided physical epoch, by correcting the fictitious duration with the rate of the
+    /// physical time (Newton's method) until the epoch is reached within ten microseconds.
+    pub fn until_physical_epoch(&mut self, epoch: Epoch) -> Result<KsState, NyxError> {
+        for iter in 0..MAX_EPOCH_CORRECTIONS {
+            let remaining_s = (epoch - self.state.physical_epoch()).in_seconds();
+            if remaining_s.abs() < EPOCH_TOLERANCE_S {
+                return Ok(self.state);
+            }
+            // The physical time advances by r / scale per unit of the fictitious clock, i.e. by a / scale on average
+            // over a revolution of an elliptical orbit, which is a better first guess for long durations
+            let h = self.state.elements[8];
+            let rate = if iter == 0 && h > 0.0 {
+                self.state.frame.gm() / (2.0 * h * self.state.scale)
+            } else {
+                self.state.u().norm_squared() / self.state.scale
+            };
+            self.for_duration((remaining_s / rate) * TimeUnit::Second)?;
+        }
+        Err(NyxError::MaxIterReached(MAX_EPOCH_CORRECTIONS))
+    }
+}
@@ -0,0 +1,166 @@
+use super::spacecraft::STD_GRAVITY;
+use super::thrustctrl::{Thruster, ThrusterModel};
+use crate::celestia::eclipse::{EclipseLocator, EclipseState};
+use crate::celestia::{Cosm, Frame, LTCorr, Orbit, SpacecraftState, AU};
+use crate::errors::NyxError;
+use crate::time::{Epoch, SECONDS_PER_DAY};
+use std::sync::Arc;
+
+/// A solar array whose power depends on the distance to the Sun, its degradation and the eclipse state.
+#[derive(Clone)]
+pub struct SolarArray {
+    /// Power generated at 1 AU and beginning of life, in kW
+    pub power_1au_kw: f64,
+    /// Yearly degradation of the array, e.g. 0.02 for 2% per year
+    pub degradation: f64,
+    /// Beginning of life of the array
+    pub bol: Epoch,
+    /// Power consumed by the spacecraft bus and not available for propulsion, in kW
+    pub bus_power_kw: f64,
+    pub e_loc: EclipseLocator,
+}
+
+impl SolarArray {
+    /// Initializes a new solar array without degradation, using the Sun J2000 as the light source.
+    pub fn new(
+        power_1au_kw: f64,
+        bus_power_kw: f64,
+        bol: Epoch,
+        shadow_bodies: Vec<Frame>,
+        cosm: Arc<Cosm>,
+    ) -> Self {
+        let e_loc = EclipseLocator {
+            light_source: cosm.frame("Sun J2000"),
+            shadow_bodies,
+            cosm,
+            correction: LTCorr::None,
+        };
+        Self {
+            power_1au_kw,
+            degradation: 0.0,
+            bol,
+            bus_power_kw,
+            e_loc,
+        }
+    }
+
+    /// Returns the power generated by the array at this orbit, in kW
+    pub fn generated_kw(&self, osc: &Orbit) -> f64 {
+        // Compute the shadowing factor
+        let k = match self.e_loc.compute(osc) {
+            EclipseState::Umbra => return 0.0,
+            EclipseState::Visibilis => 1.0,
+            EclipseState::Penumbra(val) => val,
+        };
+
+        let r_sun_au = self
+            .e_loc
+            .cosm
+            .frame_chg(osc, self.e_loc.light_source)
+            .rmag()
+            / AU;
+
+        let years = (osc.dt - self.bol).in_seconds() / (365.25 * SECONDS_PER_DAY);
+        let aging = (1.0 - self.degradation).powf(years.max(0.0));
+
+        k * aging * self.power_1au_kw / r_sun_au.powi(2)
+    }
+
+    /// Returns the power available to the propulsion system at this orbit, in kW
+    pub fn available_kw(&self, osc: &Orbit) -> f64 {
+        (self.generated_kw(osc) - self.bus_power_kw).max(0.0)
+    }
+}
+
+/// A throttle table of an electric thruster, modeled as polynomials of the input power.
+/// The coefficients are in increasing order, i.e. the first one is the constant term.
+#[derive(Clone, Debug)]
+pub struct ThrottleTable {
+    /// Polynomial of the thrust (in mN) as a function of the input power (in kW)
+    pub thrust_mn: Vec<f64>,
+    /// Polynomial of the mass flow (in mg/s) as a function of the input power (in kW)
+    pub mass_flow_mg_s: Vec<f64>,
+    /// Minimum input power of the thruster, in kW; the thruster is off below this power
+    pub min_power_kw: f64,
+    /// Maximum input power of the thruster, in kW
+    pub max_power_kw: f64,
+}
+
+impl ThrottleTable {
+    /// Linear fit of the NSTAR throttle table (Deep Space 1 and Dawn)
+    pub fn nstar() -> Self {
+        Self {
+            thrust_mn: vec![-1.28, 40.56],
+            mass_flow_mg_s: vec![0.462, 1.117],
+            min_power_kw: 0.5,
+            max_power_kw: 2.3,
+        }
+    }
+
+    /// Linear fit of the NEXT throttle table
+    pub fn next() -> Self {
+        Self {
+            thrust_mn: vec![7.08, 33.18],
+            mass_flow_mg_s: vec![1.488, 0.6168],
+            min_power_kw: 0.54,
+            max_power_kw: 6.9,
+        }
+    }
+
+    /// Returns the thruster for this input power (in kW). The thrust is zero if the power is below the minimum.
+    pub fn thruster(&self, power_kw: f64) -> Thruster {
+        if power_kw < self.min_power_kw {
+            return Thruster {
+                thrust: 0.0,
+                isp: 0.0,
+            };
+        }
+        let power_kw = power_kw.min(self.max_power_kw);
+        let thrust = polyval(&self.thrust_mn, power_kw) * 1e-3;
+        let mass_flow = polyval(&self.mass_flow_mg_s, power_kw) * 1e-6;
+        Thruster {
+            thrust,
+            isp: thrust / (mass_flow * STD_GRAVITY),
+        }
+    }
+}
+
+/// A solar electric propulsion system: the thrust and isp depend on the power generated by the solar array.
+#[derive(Clone)]
+pub struct SEPThruster {
+    pub array: SolarArray,
+    pub table: ThrottleTable,
+}
+
+impl SEPThruster {
+    pub fn new(array: SolarArray, table: ThrottleTable) -> Arc<Self> {
+        Arc::new(Self { array, table })
+    }
+}
+
+impl ThrusterModel for SEPThruster {
+    fn thruster(&self, state: &SpacecraftState) -> Result<Thruster, NyxError> {
+        Ok(self.table.thruster(self.array.available_kw(&state.orbit)))
+    }
+}
+
+/// Evaluates a polynomial whose coefficients are in increasing order
+fn polyval(coeffs: &[f64], x: f64) -> f64 {
+    coeffs.iter().rev().fold(0.0, |acc, c| acc * x + c)
+}
+
+#[test]
+fn test_throttle_tables() {
+    let nstar = ThrottleTable::nstar();
+    let full = nstar.thruster(2.5);
+    assert!((full.thrust - 0.092).abs() < 1e-3);
+    assert!((full.isp - 3100.0).abs() < 50.0);
+    let off = nstar.thruster(0.4);
+    assert!(off.thrust.abs() < std::f64::EPSILON);
+
+    let next = ThrottleTable::next();
+    let full = next.thruster(6.9);
+    assert!((full.thrust - 0.236).abs() < 1e-3);
+    assert!((full.isp - 4190.0).abs() < 50.0);
+    assert!(next.thruster(3.0).thrust < full.thrust);
+}
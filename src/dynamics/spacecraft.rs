@@ -1,7 +1,8 @@
 use super::orbital::OrbitalDynamics;
-use super::thrustctrl::ThrustControl;
+use super::propulsion::{body_to_inertial, MAX_TANKS};
+use super::thrustctrl::{ThrustControl, ThrusterModel};
 use super::{Dynamics, ForceModel};
-use crate::dimensions::{DimName, MatrixN, Vector1, Vector3, VectorN, U3, U4, U42, U43, U6, U7};
+use crate::dimensions::{DimName, MatrixN, Vector1, Vector3, VectorN, U3, U4, U42, U46, U6, U7};
 use crate::dynamics::Hyperdual;
 // use crate::od::Estimable;
 use crate::celestia::SpacecraftState;
@@ -14,13 +15,22 @@ use std::sync::Arc;
 pub use super::solarpressure::SolarPressure;
 
 const NORM_ERR: f64 = 1e-12;
-const STD_GRAVITY: f64 = 9.80665; // From NIST special publication 330, 2008 edition
+pub(crate) const STD_GRAVITY: f64 = 9.80665; // From NIST special publication 330, 2008 edition
+
+/// Returns the fuel mass (in kg) used by an impulse of this delta-v (in km/s) with this isp (in s), for a spacecraft of
+/// this total mass (in kg), using the rocket equation.
+pub(crate) fn rocket_fuel_usage(total_mass_kg: f64, dv_km_s: f64, isp_s: f64) -> f64 {
+    // The delta-v is converted from km/s to m/s
+    total_mass_kg * (1.0 - (-dv_km_s * 1e3 / (isp_s * STD_GRAVITY)).exp())
+}
 
 #[derive(Clone)]
 pub struct Spacecraft<'a> {
     pub orbital_dyn: Arc<OrbitalDynamics<'a>>,
     pub force_models: Vec<Arc<dyn ForceModel + 'a>>,
     pub ctrl: Option<Arc<dyn ThrustControl + 'a>>,
+    /// If set, the thruster is computed from this model instead of using the thruster of the state
+    pub thruster_model: Option<Arc<dyn ThrusterModel + 'a>>,
     pub decrement_mass: bool,
 }
 
@@ -34,6 +44,24 @@ impl<'a> Spacecraft<'a> {
         Arc::new(Self {
             orbital_dyn,
             ctrl: Some(ctrl),
+            thruster_model: None,
+            force_models: Vec::new(),
+            decrement_mass: true,
+        })
+    }
+
+    /// Initialize a Spacecraft with a set of orbital dynamics, a propulsion subsystem and a thruster model,
+    /// e.g. a solar electric propulsion system whose performance depends on the available power.
+    /// By default, the mass of the vehicle will be decremented as propellant is consummed.
+    pub fn with_ctrl_and_thruster(
+        orbital_dyn: Arc<OrbitalDynamics<'a>>,
+        ctrl: Arc<dyn ThrustControl + 'a>,
+        thruster_model: Arc<dyn ThrusterModel + 'a>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            orbital_dyn,
+            ctrl: Some(ctrl),
+            thruster_model: Some(thruster_model),
             force_models: Vec::new(),
             decrement_mass: true,
         })
@@ -48,6 +76,7 @@ impl<'a> Spacecraft<'a> {
         Arc::new(Self {
             orbital_dyn,
             ctrl: Some(ctrl),
+            thruster_model: None,
             force_models: Vec::new(),
             decrement_mass: false,
         })
@@ -64,6 +93,7 @@ impl<'a> Spacecraft<'a> {
         Self {
             orbital_dyn,
             ctrl: None,
+            thruster_model: None,
             force_models: Vec::new(),
             decrement_mass: true,
         }
@@ -93,6 +123,83 @@ impl<'a> Spacecraft<'a> {
         self.force_models.push(force_model);
     }
 
+    /// Returns the thrust force in the inertial frame (in N) and the fuel rate of each tank (in kg/s).
+    /// If the spacecraft has several thrusters, it is oriented such that the net thrust of its mounted thrusters is
+    /// along the direction of the control, and the thruster model (if any) sets the performance of each of them.
+    fn thrust(
+        &self,
+        ctrl: &dyn ThrustControl,
+        osc_sc: &SpacecraftState,
+    ) -> Result<(Vector3<f64>, [f64; MAX_TANKS]), NyxError> {
+        let mut fuel_rates = [0.0; MAX_TANKS];
+        if osc_sc.has_tanks() {
+            if osc_sc.thrusters[0].is_none() {
+                return Err(NyxError::CtrlExistsButNoThrusterAvail);
+            }
+            let thrust_power = ctrl.throttle(osc_sc);
+            if !(0.0..=1.0).contains(&thrust_power) {
+                return Err(NyxError::CtrlThrottleRangeErr(thrust_power));
+            } else if thrust_power > 0.0 {
+                let thrust_inertial = ctrl.direction(osc_sc);
+                if (thrust_inertial.norm() - 1.0).abs() > NORM_ERR {
+                    return Err(NyxError::CtrlNotAUnitVector(thrust_inertial.norm()));
+                }
+                let model_thruster = match &self.thruster_model {
+                    Some(model) => Some(model.thruster(osc_sc)?),
+                    None => None,
+                };
+                let mut thrust_body = Vector3::zeros();
+                for mounted in osc_sc.thrusters.iter().flatten() {
+                    let tank = match osc_sc.tanks[mounted.tank] {
+                        Some(tank) => tank,
+                        None => return Err(NyxError::CtrlExistsButNoThrusterAvail),
+                    };
+                    let mut mounted = *mounted;
+                    if let Some(thruster) = model_thruster {
+                        mounted.thruster = thruster;
+                    }
+                    let (thrust, mass_flow) = mounted.performance(&tank);
+                    thrust_body += thrust_power * thrust * mounted.direction;
+                    if self.decrement_mass {
+                        fuel_rates[mounted.tank] -= thrust_power * mass_flow;
+                    }
+                }
+                let thrust_mag = thrust_body.norm();
+                if thrust_mag > 0.0 {
+                    // Rotate the net thrust axis in the body frame onto the direction of the control
+                    let dcm = body_to_inertial(&thrust_inertial)
+                        * body_to_inertial(&(thrust_body / thrust_mag)).transpose();
+                    return Ok((dcm * thrust_body, fuel_rates));
+                }
+            }
+            return Ok((Vector3::zeros(), fuel_rates));
+        }
+
+        let thruster = match &self.thruster_model {
+            Some(model) => model.thruster(osc_sc)?,
+            None => match osc_sc.thruster {
+                Some(thruster) => thruster,
+                None => return Err(NyxError::CtrlExistsButNoThrusterAvail),
+            },
+        };
+        let thrust_power = ctrl.throttle(osc_sc);
+        if !(0.0..=1.0).contains(&thrust_power) {
+            Err(NyxError::CtrlThrottleRangeErr(thrust_power))
+        } else if thrust_power > 0.0 && thruster.thrust > 0.0 {
+            // Thrust arc
+            let thrust_inertial = ctrl.direction(osc_sc);
+            if (thrust_inertial.norm() - 1.0).abs() > NORM_ERR {
+                return Err(NyxError::CtrlNotAUnitVector(thrust_inertial.norm()));
+            }
+            if self.decrement_mass {
+                fuel_rates[0] = -thrust_power * thruster.thrust / (thruster.isp * STD_GRAVITY);
+            }
+            Ok((thrust_inertial * thrust_power * thruster.thrust, fuel_rates))
+        } else {
+            Ok((Vector3::zeros(), fuel_rates))
+        }
+    }
+
     /// A shortcut to spacecraft.ctrl if the control is defined
     pub fn ctrl_achieved(&self, state: &SpacecraftState) -> Result<bool, NyxError> {
         match &self.ctrl {
@@ -112,6 +219,19 @@ impl<'a> Dynamics for Spacecraft<'a> {
             return Err(NyxError::FuelExhausted);
         }
 
+        for (i, tank) in next_state.tanks.iter().enumerate() {
+            if let Some(tank) = tank {
+                if tank.fuel_mass_kg < 0.0 {
+                    error!(
+                        "negative fuel mass in tank #{} at {}",
+                        i,
+                        next_state.epoch()
+                    );
+                    return Err(NyxError::TankExhausted(i));
+                }
+            }
+        }
+
         if let Some(ctrl) = &self.ctrl {
             let mut state = next_state;
             // Update the control mode
@@ -125,21 +245,19 @@ impl<'a> Dynamics for Spacecraft<'a> {
     fn eom(
         &self,
         delta_t: f64,
-        state: &VectorN<f64, U43>,
+        state: &VectorN<f64, U46>,
         ctx: &SpacecraftState,
-    ) -> Result<VectorN<f64, U43>, NyxError> {
+    ) -> Result<VectorN<f64, U46>, NyxError> {
         // Compute the orbital dynamics
         let orbital_dyn_vec = state.fixed_rows::<U42>(0).into_owned();
         let d_x_orbital_dyn = self
             .orbital_dyn
             .eom(delta_t, &orbital_dyn_vec, &ctx.orbit)?;
-        // Note: 0.0 is the current fuel usage at this point.
-        let mut d_x = VectorN::<f64, U43>::from_iterator(
-            d_x_orbital_dyn
-                .iter()
-                .chain(Vector1::new(0.0).iter())
-                .cloned(),
-        );
+        // Note: the fuel usage of each tank is zero at this point.
+        let mut d_x = VectorN::<f64, U46>::zeros();
+        for (i, val) in d_x_orbital_dyn.iter().enumerate() {
+            d_x[i] = *val;
+        }
 
         let mut total_mass = ctx.dry_mass_kg;
 
@@ -148,42 +266,19 @@ impl<'a> Dynamics for Spacecraft<'a> {
 
         // Now include the control as needed.
         if let Some(ctrl) = &self.ctrl {
-            let (thrust_force, fuel_rate) = {
-                if osc_sc.thruster.is_none() {
-                    return Err(NyxError::CtrlExistsButNoThrusterAvail);
-                }
-                let thruster = osc_sc.thruster.unwrap();
-                let thrust_power = ctrl.throttle(&osc_sc);
-                if !(0.0..=1.0).contains(&thrust_power) {
-                    return Err(NyxError::CtrlThrottleRangeErr(thrust_power));
-                } else if thrust_power > 0.0 {
-                    // Thrust arc
-                    let thrust_inertial = ctrl.direction(&osc_sc);
-                    if (thrust_inertial.norm() - 1.0).abs() > NORM_ERR {
-                        return Err(NyxError::CtrlNotAUnitVector(thrust_inertial.norm()));
-                    }
-                    // Compute the thrust in Newtons and Isp
-                    let total_thrust = (thrust_power * thruster.thrust) * 1e-3; // Convert m/s^-2 to km/s^-2
-                    (
-                        thrust_inertial * total_thrust,
-                        if self.decrement_mass {
-                            let fuel_usage =
-                                thrust_power * thruster.thrust / (thruster.isp * STD_GRAVITY);
-                            -fuel_usage
-                        } else {
-                            0.0
-                        },
-                    )
-                } else {
-                    (Vector3::zeros(), 0.0)
-                }
-            };
+            let (thrust_force, fuel_rates) = self.thrust(ctrl.as_ref(), &osc_sc)?;
+            let fuel_rate: f64 = fuel_rates.iter().sum();
+            // The fuel mass is the sum of the fuel in each tank
+            let fuel_mass: f64 = state.fixed_rows::<U4>(U42::dim()).sum();
             // Add the fuel mass to the total mass, minus the change in fuel
             total_mass += ctx.fuel_mass_kg + fuel_rate;
             for i in 0..3 {
-                d_x[i + 3] += thrust_force[i] / (ctx.dry_mass_kg + state[U43::dim() - 1]);
+                // Convert m/s^-2 to km/s^-2
+                d_x[i + 3] += thrust_force[i] * 1e-3 / (ctx.dry_mass_kg + fuel_mass);
+            }
+            for (i, tank_rate) in fuel_rates.iter().enumerate() {
+                d_x[U42::dim() + i] += tank_rate;
             }
-            d_x[U43::dim() - 1] += fuel_rate;
         }
 
         // Compute additional force models as needed.
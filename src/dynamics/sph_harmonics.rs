@@ -146,23 +146,10 @@ where
             vr11_h,
         })
     }
-}
-
-impl<S: GravityPotentialStor + Send> AccelModel for Harmonics<S> {
-    fn eom(&self, osc: &Orbit) -> Result<Vector3<f64>, NyxError> {
-        // Get the DCM to convert from the integration state to the computation frame of the harmonics
-        let dcm = self
-            .cosm
-            .try_frame_chg_dcm_from_to(&osc.frame, &self.compute_frame, osc.dt)?;
-        // Convert to the computation frame
-        let mut state = *osc;
-        state.apply_dcm(dcm);
 
-        // Using the GMAT notation, with extra character for ease of highlight
-        let r_ = state.rmag();
-        let s_ = state.x / r_;
-        let t_ = state.y / r_;
-        let u_ = state.z / r_;
+    /// Computes the normalized associated Legendre polynomials A_nm and the r_m and i_m terms (real and imaginary
+    /// parts of (s + i t)^m) from the direction cosines of the position in the computation frame.
+    fn legendre(&self, s_: f64, t_: f64, u_: f64) -> (DMatrix<f64>, Vec<f64>, Vec<f64>) {
         let max_degree = self.stor.max_degree_n() as usize; // In GMAT, the order is NN
         let max_order = self.stor.max_order_m() as usize; // In GMAT, the order is MM
 
@@ -197,6 +184,30 @@ impl<S: GravityPotentialStor + Send> AccelModel for Harmonics<S> {
             i_m.push(s_ * i_m[m - 1] + t_ * r_m[m - 1]);
         }
 
+        (a_nm, r_m, i_m)
+    }
+}
+
+impl<S: GravityPotentialStor + Send> AccelModel for Harmonics<S> {
+    fn eom(&self, osc: &Orbit) -> Result<Vector3<f64>, NyxError> {
+        // Get the DCM to convert from the integration state to the computation frame of the harmonics
+        let dcm = self
+            .cosm
+            .try_frame_chg_dcm_from_to(&osc.frame, &self.compute_frame, osc.dt)?;
+        // Convert to the computation frame
+        let mut state = *osc;
+        state.apply_dcm(dcm);
+
+        // Using the GMAT notation, with extra character for ease of highlight
+        let r_ = state.rmag();
+        let s_ = state.x / r_;
+        let t_ = state.y / r_;
+        let u_ = state.z / r_;
+        let max_degree = self.stor.max_degree_n() as usize; // In GMAT, the order is NN
+        let max_order = self.stor.max_order_m() as usize; // In GMAT, the order is MM
+
+        let (a_nm, r_m, i_m) = self.legendre(s_, t_, u_);
+
         let rho = self.compute_frame.equatorial_radius() / r_;
         let mut a0 = 0.0;
         let mut a1 = 0.0;
@@ -371,4 +382,30 @@ impl<S: GravityPotentialStor + Send> AccelModel for Harmonics<S> {
         }
         Ok((fx, grad))
     }
+
+    /// The potential of the harmonics, without the central (degree zero) term, using the same Pines formulation
+    fn potential(&self, osc: &Orbit) -> Result<f64, NyxError> {
+        let dcm = self
+            .cosm
+            .try_frame_chg_dcm_from_to(&osc.frame, &self.compute_frame, osc.dt)?;
+        let mut state = *osc;
+        state.apply_dcm(dcm);
+
+        let r_ = state.rmag();
+        let max_degree = self.stor.max_degree_n() as usize;
+        let max_order = self.stor.max_order_m() as usize;
+        let (a_nm, r_m, i_m) = self.legendre(state.x / r_, state.y / r_, state.z / r_);
+
+        let rho = self.compute_frame.equatorial_radius() / r_;
+        let mut pot = 0.0;
+        for n in 1..max_degree {
+            let mut sum = 0.0;
+            for m in 0..=min(n, max_order) {
+                let (c_val, s_val) = self.stor.cs_nm(n, m);
+                sum += a_nm[(n, m)] * (c_val * r_m[m] + s_val * i_m[m]);
+            }
+            pot += rho.powi(n as i32) * sum;
+        }
+        Ok(self.compute_frame.gm() / r_ * pot)
+    }
 }
@@ -0,0 +1,141 @@
+use super::{GuidanceMode, NyxError, SpacecraftState, ThrustControl, Vector3};
+use crate::celestia::eclipse::{EclipseLocator, EclipseState};
+use crate::time::{Duration, TimeUnit};
+use crate::TimeTagged;
+use std::fmt;
+use std::sync::Arc;
+
+/// EclipseCoasting wraps another thrust control and coasts whenever the spacecraft is in eclipse,
+/// e.g. for an electric propulsion spacecraft which cannot thrust without sunlight.
+pub struct EclipseCoasting<'a> {
+    pub ctrl: Arc<dyn ThrustControl + 'a>,
+    pub e_loc: EclipseLocator,
+    /// Thrusting is allowed in penumbra if the fraction of light is at least this value (1.0 coasts in any penumbra)
+    pub min_light: f64,
+}
+
+impl<'a> EclipseCoasting<'a> {
+    /// Wraps the provided control to coast in umbra and penumbra, as an Arc
+    /// Note: this returns an Arc so it can be plugged into the Spacecraft dynamics directly.
+    pub fn new(ctrl: Arc<dyn ThrustControl + 'a>, e_loc: EclipseLocator) -> Arc<Self> {
+        Self::with_min_light(ctrl, e_loc, 1.0)
+    }
+
+    /// Wraps the provided control to coast in umbra, and in penumbra when the fraction of light is below `min_light`
+    pub fn with_min_light(
+        ctrl: Arc<dyn ThrustControl + 'a>,
+        e_loc: EclipseLocator,
+        min_light: f64,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            ctrl,
+            e_loc,
+            min_light,
+        })
+    }
+
+    /// Returns whether the spacecraft is in eclipse, i.e. must coast
+    pub fn in_eclipse(&self, state: &SpacecraftState) -> bool {
+        match self.e_loc.compute(&state.orbit) {
+            EclipseState::Umbra => true,
+            EclipseState::Penumbra(light) => light < self.min_light,
+            EclipseState::Visibilis => false,
+        }
+    }
+}
+
+impl<'a> ThrustControl for EclipseCoasting<'a> {
+    fn direction(&self, state: &SpacecraftState) -> Vector3<f64> {
+        self.ctrl.direction(state)
+    }
+
+    fn throttle(&self, state: &SpacecraftState) -> f64 {
+        if self.in_eclipse(state) {
+            0.0
+        } else {
+            self.ctrl.throttle(state)
+        }
+    }
+
+    /// Coasts in eclipse, and otherwise uses the guidance mode of the wrapped control
+    fn next(&self, state: &SpacecraftState) -> GuidanceMode {
+        if self.in_eclipse(state) {
+            if state.mode == GuidanceMode::Thrust {
+                info!("entering eclipse, disabling control: {:o}", state.orbit);
+            }
+            GuidanceMode::Coast
+        } else {
+            self.ctrl.next(state)
+        }
+    }
+
+    fn achieved(&self, state: &SpacecraftState) -> Result<bool, NyxError> {
+        self.ctrl.achieved(state)
+    }
+}
+
+/// The duty cycle of a thrust control: the time spent thrusting over the total time.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DutyCycle {
+    /// Time spent thrusting, weighted by the throttle (i.e. the equivalent time at full thrust), in seconds
+    pub thrust_on_s: f64,
+    /// Total time, in seconds
+    pub elapsed_s: f64,
+}
+
+impl DutyCycle {
+    /// Computes the duty cycle of the provided control over a sequence of states, e.g. as published by a propagator
+    /// on its output channel. The time between two states is weighted by the throttle of the control at the first one.
+    pub fn from_states<'b, I>(ctrl: &dyn ThrustControl, states: I) -> Self
+    where
+        I: IntoIterator<Item = &'b SpacecraftState>,
+    {
+        let mut me = Self::default();
+        let mut prev: Option<&SpacecraftState> = None;
+        for state in states {
+            if let Some(prev) = prev {
+                me.add(ctrl, prev, state);
+            }
+            prev = Some(state);
+        }
+        me
+    }
+
+    /// Accounts for the time between these two consecutive states, weighted by the throttle of the control
+    pub fn add(
+        &mut self,
+        ctrl: &dyn ThrustControl,
+        prev: &SpacecraftState,
+        next: &SpacecraftState,
+    ) {
+        let delta_s = (next.epoch() - prev.epoch()).in_seconds();
+        self.elapsed_s += delta_s;
+        self.thrust_on_s += ctrl.throttle(prev) * delta_s;
+    }
+
+    /// Returns the total time spent thrusting
+    pub fn thrust_on(&self) -> Duration {
+        self.thrust_on_s * TimeUnit::Second
+    }
+
+    /// Returns the ratio of the time spent thrusting over the total time, between 0 and 1
+    pub fn ratio(&self) -> f64 {
+        if self.elapsed_s > 0.0 {
+            self.thrust_on_s / self.elapsed_s
+        } else {
+            0.0
+        }
+    }
+}
+
+impl fmt::Display for DutyCycle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "thrust on for {} over {} (duty cycle of {:.2}%)",
+            self.thrust_on(),
+            self.elapsed_s * TimeUnit::Second,
+            self.ratio() * 100.0
+        )
+    }
+}
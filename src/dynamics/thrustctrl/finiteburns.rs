@@ -1,11 +1,61 @@
 use super::ThrustControl;
 use crate::celestia::{Frame, GuidanceMode, SpacecraftState};
 use crate::dimensions::Vector3;
+use crate::dynamics::deltavctrl::Impulse;
+use crate::dynamics::spacecraft::{rocket_fuel_usage, STD_GRAVITY};
+use crate::errors::NyxError;
 use crate::state::TimeTagged;
-use crate::time::{Epoch, TimeUnit};
+use crate::time::{Duration, Epoch, TimeUnit};
 use std::sync::Arc;
 
-/// Mnvr defined a single maneuver. Direction MUST be in the VNC frame (Velocity / Normal / Cross).
+/// Steering law of the thrust direction during a maneuver, as angles in the frame of the maneuver.
+///
+/// The angle alpha is measured from the first axis towards the second axis, and the angle beta is measured from the
+/// plane of the first two axes towards the third axis, i.e. the direction is
+/// (cos(alpha) cos(beta), sin(alpha) cos(beta), sin(beta)). Hence, depending on the frame:
+/// + Inertial: alpha is the right ascension and beta is the declination of the thrust;
+/// + RCN and RIC: alpha is the in-plane angle from the radial direction towards the along-track direction, and beta
+/// is the out-of-plane angle towards the orbit normal;
+/// + VNC: alpha is the out-of-plane angle from the velocity towards the orbit normal, and beta is the angle towards the
+/// co-normal (V x N, i.e. the outward radial direction of a circular orbit), so alpha = beta = 0 is along the velocity.
+///
+/// The angles are in radians and the polynomials are evaluated with the time since the start of the maneuver in
+/// seconds, the first coefficient being the constant term.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Steering {
+    /// The thrust direction is the constant vector of the maneuver
+    Constant,
+    /// The angles vary linearly with time
+    Linear { alpha: [f64; 2], beta: [f64; 2] },
+    /// The angles vary quadratically with time
+    Quadratic { alpha: [f64; 3], beta: [f64; 3] },
+}
+
+impl Steering {
+    /// Returns the unit vector of the thrust direction, or None if the steering is constant
+    pub fn direction(&self, delta_t_s: f64) -> Option<Vector3<f64>> {
+        let (alpha, beta) = match self {
+            Steering::Constant => return None,
+            Steering::Linear { alpha, beta } => (
+                alpha[0] + alpha[1] * delta_t_s,
+                beta[0] + beta[1] * delta_t_s,
+            ),
+            Steering::Quadratic { alpha, beta } => (
+                alpha[0] + (alpha[1] + alpha[2] * delta_t_s) * delta_t_s,
+                beta[0] + (beta[1] + beta[2] * delta_t_s) * delta_t_s,
+            ),
+        };
+        let (sin_alpha, cos_alpha) = alpha.sin_cos();
+        let (sin_beta, cos_beta) = beta.sin_cos();
+        Some(Vector3::new(
+            cos_alpha * cos_beta,
+            sin_alpha * cos_beta,
+            sin_beta,
+        ))
+    }
+}
+
+/// Mnvr defined a single maneuver, whose direction is expressed in the frame of the `FiniteBurns`.
 /// It may be used with a maneuver scheduler.
 #[derive(Copy, Clone, Debug)]
 pub struct Mnvr {
@@ -15,8 +65,10 @@ pub struct Mnvr {
     pub end: Epoch,
     /// Thrust level, if 1.0 use all thruster available at full power
     pub thrust_lvl: f64,
-    /// Direction of the thrust in the VNC frame
+    /// Direction of the thrust, used if the steering is constant
     pub vector: Vector3<f64>,
+    /// Steering law of the thrust direction during the maneuver
+    pub steering: Steering,
 }
 
 impl Mnvr {
@@ -27,8 +79,72 @@ impl Mnvr {
             end: dt + TimeUnit::Microsecond,
             thrust_lvl: 1.0,
             vector,
+            steering: Steering::Constant,
+        }
+    }
+
+    /// Creates a maneuver whose thrust direction follows the provided steering law.
+    pub fn with_steering(start: Epoch, end: Epoch, thrust_lvl: f64, steering: Steering) -> Self {
+        Self {
+            start,
+            end,
+            thrust_lvl,
+            vector: steering.direction(0.0).unwrap_or_else(Vector3::zeros),
+            steering,
         }
     }
+
+    /// Converts an impulse applied to this spacecraft state into an equivalent finite burn at full thrust, centered on
+    /// the epoch of the state, whose direction is expressed in the provided frame (inertial, VNC, RCN or RIC).
+    /// The duration is such that the burn uses the fuel of the impulse, as computed with the rocket equation.
+    pub fn from_impulse(
+        sc: &SpacecraftState,
+        impulse: &Impulse,
+        frame: Frame,
+    ) -> Result<Self, NyxError> {
+        let thruster = match (sc.thrusters[0], sc.thruster) {
+            (Some(mounted), _) => mounted.thruster,
+            (None, Some(thruster)) => thruster,
+            (None, None) => return Err(NyxError::CtrlExistsButNoThrusterAvail),
+        };
+        let isp = impulse.isp.unwrap_or(thruster.isp);
+        let dv = impulse.dv_inertial(&sc.orbit);
+        let dv_norm = dv.norm();
+        if dv_norm <= 0.0 {
+            return Err(NyxError::CtrlNotAUnitVector(dv_norm));
+        }
+        if thruster.thrust <= 0.0 {
+            return Err(NyxError::CustomError(format!(
+                "cannot convert an impulse into a finite burn with a thrust of {} N",
+                thruster.thrust
+            )));
+        }
+        let fuel_usage = rocket_fuel_usage(sc.dry_mass_kg + sc.fuel_mass_kg, dv_norm, isp);
+        let duration_s = fuel_usage * isp * STD_GRAVITY / thruster.thrust;
+        let vector = match frame {
+            Frame::Inertial => dv / dv_norm,
+            _ => sc.orbit.dcm_to_inertial(frame).transpose() * dv / dv_norm,
+        };
+        Ok(Self {
+            start: sc.epoch() - 0.5 * duration_s * TimeUnit::Second,
+            end: sc.epoch() + 0.5 * duration_s * TimeUnit::Second,
+            thrust_lvl: 1.0,
+            vector,
+            steering: Steering::Constant,
+        })
+    }
+
+    /// Returns the duration of this maneuver
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+
+    /// Returns the thrust direction at the provided epoch, in the frame of the maneuver
+    pub fn direction(&self, epoch: Epoch) -> Vector3<f64> {
+        self.steering
+            .direction((epoch - self.start).in_seconds())
+            .unwrap_or(self.vector)
+    }
 }
 
 /// A controller for a set of pre-determined maneuvers.
@@ -42,10 +158,14 @@ pub struct FiniteBurns {
 
 impl FiniteBurns {
     /// Builds a schedule from the vector of maneuvers, must be provided in chronological order.
+    /// The maneuvers are either in the inertial frame, or in a local frame (VNC, RCN or RIC) which follows the orbit.
     pub fn from_mnvrs(mnvrs: Vec<Mnvr>, frame: Frame) -> Arc<Self> {
         assert!(
-            matches!(frame, Frame::Inertial | Frame::VNC),
-            "Maneuvers must be either in the inertial frame or in a body frame"
+            matches!(
+                frame,
+                Frame::Inertial | Frame::VNC | Frame::RCN | Frame::RIC
+            ),
+            "Maneuvers must be either in the inertial frame or in a local frame"
         );
         Arc::new(Self { mnvrs, frame })
     }
@@ -56,32 +176,28 @@ impl ThrustControl for FiniteBurns {
         // NOTE: We do not increment the mnvr number here. The power function is called first,
         // so we let that function handle starting and stopping of the maneuver.
         match osc.mode {
-            GuidanceMode::Custom(mnvr_no) => {
-                let next_mnvr = self.mnvrs[mnvr_no as usize];
-                if next_mnvr.start <= osc.epoch() {
+            GuidanceMode::Custom(mnvr_no) => match self.mnvrs.get(mnvr_no as usize) {
+                Some(next_mnvr) if next_mnvr.start <= osc.epoch() => {
+                    let vector = next_mnvr.direction(osc.epoch());
                     if matches!(self.frame, Frame::Inertial) {
-                        next_mnvr.vector
+                        vector
                     } else {
-                        osc.orbit.dcm_to_inertial(self.frame) * next_mnvr.vector
+                        osc.orbit.dcm_to_inertial(self.frame) * vector
                     }
-                } else {
-                    Vector3::zeros()
                 }
-            }
+                // Either before the maneuver or after the last one
+                _ => Vector3::zeros(),
+            },
             _ => Vector3::zeros(),
         }
     }
 
     fn throttle(&self, osc: &SpacecraftState) -> f64 {
         match osc.mode {
-            GuidanceMode::Custom(mnvr_no) => {
-                let next_mnvr = self.mnvrs[mnvr_no as usize];
-                if next_mnvr.start <= osc.epoch() {
-                    next_mnvr.thrust_lvl
-                } else {
-                    0.0
-                }
-            }
+            GuidanceMode::Custom(mnvr_no) => match self.mnvrs.get(mnvr_no as usize) {
+                Some(next_mnvr) if next_mnvr.start <= osc.epoch() => next_mnvr.thrust_lvl,
+                _ => 0.0,
+            },
             _ => {
                 // We aren't in maneuver mode, so return 0% throttle
                 0.0
@@ -108,8 +224,11 @@ impl ThrustControl for FiniteBurns {
             }
             _ => {
                 // If we haven't started the maneuvers yet, let's get ready to do so by switching to the mode
-                // which will start the first maneuver
-                GuidanceMode::Custom(0)
+                // which will start the next maneuver, and keep coasting once all of them are done
+                match self.mnvrs.iter().position(|mnvr| sc.epoch() < mnvr.end) {
+                    Some(mnvr_no) => GuidanceMode::Custom(mnvr_no as u8),
+                    None => GuidanceMode::Coast,
+                }
             }
         }
     }
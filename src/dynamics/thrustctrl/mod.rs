@@ -1,9 +1,19 @@
 use crate::celestia::{Frame, GuidanceMode, Orbit, SpacecraftState};
 use crate::dimensions::Vector3;
 use crate::errors::NyxError;
+use crate::utils::between_pm_180;
 
 mod finiteburns;
-pub use finiteburns::{FiniteBurns, Mnvr};
+pub use finiteburns::{FiniteBurns, Mnvr, Steering};
+
+mod profile;
+pub use profile::ThrustProfile;
+
+mod coasting;
+pub use coasting::{DutyCycle, EclipseCoasting};
+
+mod qlaw;
+pub use qlaw::{QLaw, QLawOptions};
 
 mod ruggiero;
 pub use ruggiero::Ruggiero;
@@ -15,6 +25,19 @@ pub struct Thruster {
     pub isp: f64,
 }
 
+/// The `ThrusterModel` trait computes the thruster performance available at a given state, e.g. as a function of the
+/// power available to an electric propulsion system. If set on a `Spacecraft`, it supersedes the thruster of the state.
+pub trait ThrusterModel: Send + Sync {
+    /// Returns the thruster available at this state: the maximum thrust (in N) and the isp (in s).
+    fn thruster(&self, state: &SpacecraftState) -> Result<Thruster, NyxError>;
+}
+
+impl ThrusterModel for Thruster {
+    fn thruster(&self, _state: &SpacecraftState) -> Result<Thruster, NyxError> {
+        Ok(*self)
+    }
+}
+
 /// The `ThrustControl` trait handles control laws, optimizations, and other such methods for
 /// controlling the overall thrust direction when tied to a `Spacecraft`. For delta V control,
 /// tie the DeltaVctrl to a MissionArc.
@@ -36,25 +59,245 @@ pub trait ThrustControl: Send + Sync {
 }
 
 /// Goals used for sub-optimal controls
+///
+/// Angles are in degrees, and the periapsis, apoapsis and geodetic altitude are in km. The true anomaly, mean anomaly
+/// and argument of latitude objectives are phasing objectives: they are achieved by drifting, i.e. by lowering the
+/// semi-major axis to catch up with the target and by raising it to fall behind.
 #[derive(Copy, Clone, Debug)]
 pub enum Achieve {
-    Sma { target: f64, tol: f64 },
-    Ecc { target: f64, tol: f64 },
-    Inc { target: f64, tol: f64 },
-    Raan { target: f64, tol: f64 },
-    Aop { target: f64, tol: f64 },
+    Sma {
+        target: f64,
+        tol: f64,
+    },
+    Ecc {
+        target: f64,
+        tol: f64,
+    },
+    Inc {
+        target: f64,
+        tol: f64,
+    },
+    Raan {
+        target: f64,
+        tol: f64,
+    },
+    Aop {
+        target: f64,
+        tol: f64,
+    },
+    /// Radius of periapsis
+    Periapsis {
+        target: f64,
+        tol: f64,
+    },
+    /// Radius of apoapsis
+    Apoapsis {
+        target: f64,
+        tol: f64,
+    },
+    TrueAnomaly {
+        target: f64,
+        tol: f64,
+    },
+    MeanAnomaly {
+        target: f64,
+        tol: f64,
+    },
+    /// Argument of latitude
+    Aol {
+        target: f64,
+        tol: f64,
+    },
+    /// Modified equinoctial element f (cf. `Orbit::equinoctial_f`)
+    EquinoctialF {
+        target: f64,
+        tol: f64,
+    },
+    /// Modified equinoctial element g (cf. `Orbit::equinoctial_g`)
+    EquinoctialG {
+        target: f64,
+        tol: f64,
+    },
+    /// Modified equinoctial element h (cf. `Orbit::equinoctial_h`)
+    EquinoctialH {
+        target: f64,
+        tol: f64,
+    },
+    /// Modified equinoctial element k (cf. `Orbit::equinoctial_k`)
+    EquinoctialK {
+        target: f64,
+        tol: f64,
+    },
+    /// Geodetic altitude, only defined for orbits around a geoid
+    GeodeticAlt {
+        target: f64,
+        tol: f64,
+    },
 }
 
 impl Achieve {
     pub fn achieved(&self, state: &Orbit) -> bool {
+        self.error(state).abs() < self.tol()
+    }
+
+    /// Returns the current value of the targeted quantity
+    pub fn value(&self, state: &Orbit) -> f64 {
+        match *self {
+            Achieve::Sma { .. } => state.sma(),
+            Achieve::Ecc { .. } => state.ecc(),
+            Achieve::Inc { .. } => state.inc(),
+            Achieve::Raan { .. } => state.raan(),
+            Achieve::Aop { .. } => state.aop(),
+            Achieve::Periapsis { .. } => state.periapsis(),
+            Achieve::Apoapsis { .. } => state.apoapsis(),
+            Achieve::TrueAnomaly { .. } => state.ta(),
+            Achieve::MeanAnomaly { .. } => state.ma(),
+            Achieve::Aol { .. } => state.aol(),
+            Achieve::EquinoctialF { .. } => state.equinoctial_f(),
+            Achieve::EquinoctialG { .. } => state.equinoctial_g(),
+            Achieve::EquinoctialH { .. } => state.equinoctial_h(),
+            Achieve::EquinoctialK { .. } => state.equinoctial_k(),
+            Achieve::GeodeticAlt { .. } => state.geodetic_height(),
+        }
+    }
+
+    /// Returns the targeted value
+    pub fn target(&self) -> f64 {
         match *self {
-            Achieve::Sma { target, tol } => (state.sma() - target).abs() < tol,
-            Achieve::Ecc { target, tol } => (state.ecc() - target).abs() < tol,
-            Achieve::Inc { target, tol } => (state.inc() - target).abs() < tol,
-            Achieve::Raan { target, tol } => (state.raan() - target).abs() < tol,
-            Achieve::Aop { target, tol } => (state.aop() - target).abs() < tol,
+            Achieve::Sma { target, .. }
+            | Achieve::Ecc { target, .. }
+            | Achieve::Inc { target, .. }
+            | Achieve::Raan { target, .. }
+            | Achieve::Aop { target, .. }
+            | Achieve::Periapsis { target, .. }
+            | Achieve::Apoapsis { target, .. }
+            | Achieve::TrueAnomaly { target, .. }
+            | Achieve::MeanAnomaly { target, .. }
+            | Achieve::Aol { target, .. }
+            | Achieve::EquinoctialF { target, .. }
+            | Achieve::EquinoctialG { target, .. }
+            | Achieve::EquinoctialH { target, .. }
+            | Achieve::EquinoctialK { target, .. }
+            | Achieve::GeodeticAlt { target, .. } => target,
         }
     }
+
+    /// Returns the tolerance on the targeted value
+    pub fn tol(&self) -> f64 {
+        match *self {
+            Achieve::Sma { tol, .. }
+            | Achieve::Ecc { tol, .. }
+            | Achieve::Inc { tol, .. }
+            | Achieve::Raan { tol, .. }
+            | Achieve::Aop { tol, .. }
+            | Achieve::Periapsis { tol, .. }
+            | Achieve::Apoapsis { tol, .. }
+            | Achieve::TrueAnomaly { tol, .. }
+            | Achieve::MeanAnomaly { tol, .. }
+            | Achieve::Aol { tol, .. }
+            | Achieve::EquinoctialF { tol, .. }
+            | Achieve::EquinoctialG { tol, .. }
+            | Achieve::EquinoctialH { tol, .. }
+            | Achieve::EquinoctialK { tol, .. }
+            | Achieve::GeodeticAlt { tol, .. } => tol,
+        }
+    }
+
+    /// Returns the difference between the current and the targeted values, between -180 and 180 degrees for the
+    /// phasing objectives
+    pub fn error(&self, state: &Orbit) -> f64 {
+        let delta = self.value(state) - self.target();
+        if self.is_phasing() {
+            between_pm_180(delta)
+        } else {
+            delta
+        }
+    }
+
+    /// Returns whether this is a phasing objective, i.e. achieved by drifting along the orbit
+    pub fn is_phasing(&self) -> bool {
+        matches!(
+            self,
+            Achieve::TrueAnomaly { .. } | Achieve::MeanAnomaly { .. } | Achieve::Aol { .. }
+        )
+    }
+
+    /// Returns the value of this objective from the sma (in km), ecc, inc, raan and aop (in radians), or None if
+    /// this objective also depends on the position along the orbit
+    fn element_value(&self, oe: &[f64; 5]) -> Option<f64> {
+        let [sma, ecc, inc, raan, aop] = *oe;
+        match *self {
+            Achieve::Sma { .. } => Some(sma),
+            Achieve::Ecc { .. } => Some(ecc),
+            Achieve::Inc { .. } => Some(inc.to_degrees()),
+            Achieve::Raan { .. } => Some(raan.to_degrees()),
+            Achieve::Aop { .. } => Some(aop.to_degrees()),
+            Achieve::Periapsis { .. } => Some(sma * (1.0 - ecc)),
+            Achieve::Apoapsis { .. } => Some(sma * (1.0 + ecc)),
+            Achieve::EquinoctialF { .. } => Some(ecc * (aop + raan).cos()),
+            Achieve::EquinoctialG { .. } => Some(ecc * (aop + raan).sin()),
+            Achieve::EquinoctialH { .. } => Some((inc / 2.0).tan() * raan.cos()),
+            Achieve::EquinoctialK { .. } => Some((inc / 2.0).tan() * raan.sin()),
+            Achieve::TrueAnomaly { .. }
+            | Achieve::MeanAnomaly { .. }
+            | Achieve::Aol { .. }
+            | Achieve::GeodeticAlt { .. } => None,
+        }
+    }
+
+    /// Returns the rate of change of this objective for a unit acceleration in the RCN frame (cf. `gauss_rate`).
+    /// The phasing objectives and the geodetic altitude are controlled through the semi-major axis.
+    fn rate(&self, gm: f64, oe: &[f64; 5], ta: f64) -> Vector3<f64> {
+        let [sma, ecc, inc, raan, aop] = *oe;
+        match *self {
+            Achieve::Sma { .. } | Achieve::GeodeticAlt { .. } => gauss_rate(gm, oe, ta, 0),
+            Achieve::Ecc { .. } => gauss_rate(gm, oe, ta, 1),
+            Achieve::Inc { .. } => gauss_rate(gm, oe, ta, 2),
+            Achieve::Raan { .. } => gauss_rate(gm, oe, ta, 3),
+            Achieve::Aop { .. } => gauss_rate(gm, oe, ta, 4),
+            Achieve::Periapsis { .. } => {
+                (1.0 - ecc) * gauss_rate(gm, oe, ta, 0) - sma * gauss_rate(gm, oe, ta, 1)
+            }
+            Achieve::Apoapsis { .. } => {
+                (1.0 + ecc) * gauss_rate(gm, oe, ta, 0) + sma * gauss_rate(gm, oe, ta, 1)
+            }
+            // Lowering the semi-major axis increases the mean motion
+            Achieve::TrueAnomaly { .. } | Achieve::MeanAnomaly { .. } | Achieve::Aol { .. } => {
+                -gauss_rate(gm, oe, ta, 0)
+            }
+            Achieve::EquinoctialF { .. }
+            | Achieve::EquinoctialG { .. }
+            | Achieve::EquinoctialH { .. }
+            | Achieve::EquinoctialK { .. } => {
+                let tan_half_inc = (inc / 2.0).tan();
+                let eq = [
+                    sma,
+                    ecc * (aop + raan).cos(),
+                    ecc * (aop + raan).sin(),
+                    tan_half_inc * raan.cos(),
+                    tan_half_inc * raan.sin(),
+                ];
+                let element = match *self {
+                    Achieve::EquinoctialF { .. } => 1,
+                    Achieve::EquinoctialG { .. } => 2,
+                    Achieve::EquinoctialH { .. } => 3,
+                    _ => 4,
+                };
+                equinoctial_gauss_rate(gm, &eq, raan + aop + ta, element)
+            }
+        }
+    }
+}
+
+/// Returns the sma (in km), ecc, inc, raan and aop (in radians) of this orbit, as used by `gauss_rate`
+fn osc_elements(osc: &Orbit) -> [f64; 5] {
+    [
+        osc.sma(),
+        osc.ecc(),
+        osc.inc().to_radians(),
+        osc.raan().to_radians(),
+        osc.aop().to_radians(),
+    ]
 }
 
 fn unit_vector_from_angles(alpha: f64, beta: f64) -> Vector3<f64> {
@@ -64,3 +307,215 @@ fn unit_vector_from_angles(alpha: f64, beta: f64) -> Vector3<f64> {
         beta.sin(),
     )
 }
+
+/// Returns the rate of change of an osculating element for a unit acceleration in the RCN frame, from the Gauss
+/// variational equations. The elements are the sma (in km), ecc, inc, raan and aop (in radians), in this order, and
+/// the true anomaly is in radians.
+fn gauss_rate(gm: f64, oe: &[f64; 5], ta: f64, element: usize) -> Vector3<f64> {
+    let [sma, ecc, inc, _, aop] = *oe;
+    let p = sma * (1.0 - ecc.powi(2));
+    let h = (gm * p).sqrt();
+    let (sin_ta, cos_ta) = ta.sin_cos();
+    let r = p / (1.0 + ecc * cos_ta);
+    let (sin_aol, cos_aol) = (aop + ta).sin_cos();
+    match element {
+        0 => 2.0 * sma.powi(2) / h * Vector3::new(ecc * sin_ta, p / r, 0.0),
+        1 => Vector3::new(p * sin_ta, (p + r) * cos_ta + r * ecc, 0.0) / h,
+        2 => Vector3::new(0.0, 0.0, r * cos_aol / h),
+        3 => Vector3::new(0.0, 0.0, r * sin_aol / (h * inc.sin())),
+        4 => Vector3::new(
+            -p * cos_ta / (h * ecc),
+            (p + r) * sin_ta / (h * ecc),
+            -r * sin_aol * inc.cos() / (h * inc.sin()),
+        ),
+        _ => panic!("element #{} does not exist", element),
+    }
+}
+
+/// Returns the rate of change of an osculating element for a unit acceleration in the RCN frame, from the Gauss
+/// variational equations in equinoctial elements (Walker et al., 1985), which are not singular on circular and
+/// equatorial orbits. The elements are the sma (in km), f, g, h, k (cf. `Orbit::equinoctial_f`) and the mean longitude
+/// (in radians), in this order, and the true longitude is in radians.
+pub(crate) fn equinoctial_gauss_rate(
+    gm: f64,
+    eq: &[f64; 5],
+    true_long: f64,
+    element: usize,
+) -> Vector3<f64> {
+    let [sma, f, g, h, k] = *eq;
+    let ecc_sq = f.powi(2) + g.powi(2);
+    let p = sma * (1.0 - ecc_sq);
+    let sqrt_p_gm = (p / gm).sqrt();
+    let (sin_l, cos_l) = true_long.sin_cos();
+    // Ratio of the semi-latus rectum to the radius
+    let w = 1.0 + f * cos_l + g * sin_l;
+    let s2 = 1.0 + h.powi(2) + k.powi(2);
+    // tan(inc/2) sin(aol)
+    let h_sin_aol = h * sin_l - k * cos_l;
+    let (e_cos_ta, e_sin_ta) = (f * cos_l + g * sin_l, f * sin_l - g * cos_l);
+    match element {
+        0 => 2.0 * sma.powi(2) / (gm * p).sqrt() * Vector3::new(e_sin_ta, w, 0.0),
+        1 => sqrt_p_gm * Vector3::new(sin_l, ((w + 1.0) * cos_l + f) / w, -h_sin_aol * g / w),
+        2 => sqrt_p_gm * Vector3::new(-cos_l, ((w + 1.0) * sin_l + g) / w, h_sin_aol * f / w),
+        3 => sqrt_p_gm * Vector3::new(0.0, 0.0, s2 * cos_l / (2.0 * w)),
+        4 => sqrt_p_gm * Vector3::new(0.0, 0.0, s2 * sin_l / (2.0 * w)),
+        5 => {
+            // Sum of the rates of the mean anomaly (without the mean motion), the aop and the raan
+            let beta = (1.0 - ecc_sq).sqrt();
+            let na = (gm / sma).sqrt();
+            Vector3::new(
+                -(2.0 / w * (1.0 - ecc_sq) + beta * e_cos_ta / (1.0 + beta)) / na,
+                beta * e_sin_ta * (1.0 + 1.0 / w) / ((1.0 + beta) * na),
+                sqrt_p_gm * h_sin_aol / w,
+            )
+        }
+        _ => panic!("element #{} does not exist", element),
+    }
+}
+
+#[test]
+fn achieve_rates() {
+    use crate::celestia::Cosm;
+    use crate::time::Epoch;
+    let cosm = Cosm::de438();
+    let eme2k = cosm.frame("EME2000");
+    let start_time = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+    let orbit = Orbit::keplerian(8000.0, 0.1, 30.0, 40.0, 50.0, 60.0, start_time, eme2k);
+    let gm = orbit.frame.gm();
+    let oe = osc_elements(&orbit);
+    let dcm = orbit.dcm_to_inertial(Frame::RCN);
+
+    let objectives = [
+        Achieve::Sma {
+            target: 0.0,
+            tol: 0.0,
+        },
+        Achieve::Ecc {
+            target: 0.0,
+            tol: 0.0,
+        },
+        Achieve::Inc {
+            target: 0.0,
+            tol: 0.0,
+        },
+        Achieve::Raan {
+            target: 0.0,
+            tol: 0.0,
+        },
+        Achieve::Aop {
+            target: 0.0,
+            tol: 0.0,
+        },
+        Achieve::Periapsis {
+            target: 0.0,
+            tol: 0.0,
+        },
+        Achieve::Apoapsis {
+            target: 0.0,
+            tol: 0.0,
+        },
+        Achieve::EquinoctialF {
+            target: 0.0,
+            tol: 0.0,
+        },
+        Achieve::EquinoctialG {
+            target: 0.0,
+            tol: 0.0,
+        },
+        Achieve::EquinoctialH {
+            target: 0.0,
+            tol: 0.0,
+        },
+        Achieve::EquinoctialK {
+            target: 0.0,
+            tol: 0.0,
+        },
+    ];
+
+    // The rate of change for a unit acceleration is the change for a small impulse, computed by central differences
+    let dv = 1e-5;
+    for obj in &objectives {
+        let mut rate = obj.rate(gm, &oe, orbit.ta().to_radians());
+        if let Achieve::Inc { .. } | Achieve::Raan { .. } | Achieve::Aop { .. } = obj {
+            rate = rate.map(|val| val.to_degrees());
+        }
+        for i in 0..3 {
+            let mut dv_rcn = Vector3::zeros();
+            dv_rcn[i] = dv;
+            let dv_inertial = dcm * dv_rcn;
+            let mut plus = orbit;
+            let mut minus = orbit;
+            plus.vx += dv_inertial[0];
+            plus.vy += dv_inertial[1];
+            plus.vz += dv_inertial[2];
+            minus.vx -= dv_inertial[0];
+            minus.vy -= dv_inertial[1];
+            minus.vz -= dv_inertial[2];
+            let finite_diff = (obj.value(&plus) - obj.value(&minus)) / (2.0 * dv);
+            assert!(
+                (finite_diff - rate[i]).abs() < 1e-4 * rate.norm(),
+                "{:?}: rate #{} is {} but expected {}",
+                obj,
+                i,
+                rate[i],
+                finite_diff
+            );
+        }
+    }
+}
+
+#[test]
+fn equinoctial_rates() {
+    use crate::celestia::Cosm;
+    use crate::time::Epoch;
+    let cosm = Cosm::de438();
+    let eme2k = cosm.frame("EME2000");
+    let start_time = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+    let orbit = Orbit::keplerian(8000.0, 0.1, 30.0, 40.0, 50.0, 60.0, start_time, eme2k);
+    let gm = orbit.frame.gm();
+    let dcm = orbit.dcm_to_inertial(Frame::RCN);
+    let eq = [
+        orbit.sma(),
+        orbit.equinoctial_f(),
+        orbit.equinoctial_g(),
+        orbit.equinoctial_h(),
+        orbit.equinoctial_k(),
+    ];
+    let true_long = (orbit.raan() + orbit.aop() + orbit.ta()).to_radians();
+    let mean_long = |orbit: &Orbit| orbit.raan() + orbit.aop() + orbit.ma();
+
+    // The sma and the mean longitude are checked here, the other elements are checked by the Achieve rates
+    let dv = 1e-5;
+    for i in 0..3 {
+        let mut dv_rcn = Vector3::zeros();
+        dv_rcn[i] = dv;
+        let dv_inertial = dcm * dv_rcn;
+        let mut plus = orbit;
+        let mut minus = orbit;
+        plus.vx += dv_inertial[0];
+        plus.vy += dv_inertial[1];
+        plus.vz += dv_inertial[2];
+        minus.vx -= dv_inertial[0];
+        minus.vy -= dv_inertial[1];
+        minus.vz -= dv_inertial[2];
+        let sma_rate = equinoctial_gauss_rate(gm, &eq, true_long, 0);
+        let finite_diff = (plus.sma() - minus.sma()) / (2.0 * dv);
+        assert!(
+            (finite_diff - sma_rate[i]).abs() < 1e-4 * sma_rate.norm(),
+            "sma rate #{} is {} but expected {}",
+            i,
+            sma_rate[i],
+            finite_diff
+        );
+        let long_rate = equinoctial_gauss_rate(gm, &eq, true_long, 5);
+        let finite_diff =
+            between_pm_180(mean_long(&plus) - mean_long(&minus)).to_radians() / (2.0 * dv);
+        assert!(
+            (finite_diff - long_rate[i]).abs() < 1e-4 * long_rate.norm(),
+            "mean longitude rate #{} is {} but expected {}",
+            i,
+            long_rate[i],
+            finite_diff
+        );
+    }
+}
@@ -0,0 +1,191 @@
+extern crate csv;
+
+use super::{Frame, GuidanceMode, NyxError, SpacecraftState, ThrustControl, Vector3};
+use crate::state::TimeTagged;
+use crate::time::Epoch;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// A tabulated thrust profile, e.g. replayed from an operations maneuver file.
+///
+/// Each entry of the profile defines the thrust direction and throttle from its epoch onward: the direction is linearly
+/// interpolated between consecutive entries (and normalized), whereas the throttle is held until the next entry.
+/// The spacecraft coasts before the first entry, and the last entry is held indefinitely: end the profile with an
+/// entry whose throttle is zero to stop thrusting.
+#[derive(Clone, Debug)]
+pub struct ThrustProfile {
+    /// Epochs of the entries, in chronological order
+    pub epochs: Vec<Epoch>,
+    /// Direction of the thrust at each epoch, as unit vectors in `frame`
+    pub directions: Vec<Vector3<f64>>,
+    /// Throttle from each epoch until the next one, between 0 and 1
+    pub throttles: Vec<f64>,
+    /// The frame in which the directions are defined: either the inertial frame or a local frame (VNC, RCN or RIC)
+    pub frame: Frame,
+}
+
+impl ThrustProfile {
+    /// Builds a thrust profile from the entries (epoch, direction, throttle), which must be in chronological order.
+    /// Returns an error if the frame is neither the inertial frame nor a local frame.
+    pub fn new(
+        entries: Vec<(Epoch, Vector3<f64>, f64)>,
+        frame: Frame,
+    ) -> Result<Arc<Self>, NyxError> {
+        if !matches!(
+            frame,
+            Frame::Inertial | Frame::VNC | Frame::RCN | Frame::RIC
+        ) {
+            return Err(NyxError::CustomError(format!(
+                "Thrust profiles must be either in the inertial frame or in a local frame, got {:?}",
+                frame
+            )));
+        }
+        let mut epochs = Vec::with_capacity(entries.len());
+        let mut directions = Vec::with_capacity(entries.len());
+        let mut throttles = Vec::with_capacity(entries.len());
+        for (epoch, direction, throttle) in entries {
+            if let Some(prev) = epochs.last() {
+                if epoch <= *prev {
+                    return Err(NyxError::InvalidInterpolationData(format!(
+                        "thrust profile epochs are not in chronological order at {}",
+                        epoch
+                    )));
+                }
+            }
+            if !(0.0..=1.0).contains(&throttle) {
+                return Err(NyxError::CtrlThrottleRangeErr(throttle));
+            }
+            let norm = direction.norm();
+            if norm <= 0.0 {
+                return Err(NyxError::CtrlNotAUnitVector(norm));
+            }
+            epochs.push(epoch);
+            directions.push(direction / norm);
+            throttles.push(throttle);
+        }
+        Ok(Arc::new(Self {
+            epochs,
+            directions,
+            throttles,
+            frame,
+        }))
+    }
+
+    /// Loads a thrust profile from a CSV file with a header and the columns `epoch, x, y, z, throttle`, e.g.
+    /// `2020-01-01T00:00:00 TAI, 1.0, 0.0, 0.0, 1.0`. The direction does not need to be normalized.
+    pub fn from_csv(path: &str, frame: Frame) -> Result<Arc<Self>, NyxError> {
+        let mut rdr = match csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_path(path)
+        {
+            Ok(rdr) => rdr,
+            Err(e) => return Err(NyxError::LoadingError(format!("{}: {}", path, e))),
+        };
+        let mut entries = Vec::new();
+        for (line, record) in rdr.records().enumerate() {
+            let record = match record {
+                Ok(record) => record,
+                Err(e) => return Err(NyxError::LoadingError(format!("{}: {}", path, e))),
+            };
+            if record.len() != 5 {
+                return Err(NyxError::LoadingError(format!(
+                    "{}: expected 5 columns on row {} but found {}",
+                    path,
+                    line + 1,
+                    record.len()
+                )));
+            }
+            let epoch = match Epoch::from_str(&record[0]) {
+                Ok(epoch) => epoch,
+                Err(e) => {
+                    return Err(NyxError::LoadingError(format!(
+                        "{}: invalid epoch on row {}: {:?}",
+                        path,
+                        line + 1,
+                        e
+                    )))
+                }
+            };
+            let mut values = [0.0; 4];
+            for (i, value) in values.iter_mut().enumerate() {
+                *value = match record[i + 1].parse::<f64>() {
+                    Ok(value) => value,
+                    Err(e) => {
+                        return Err(NyxError::LoadingError(format!(
+                            "{}: invalid value on row {}: {}",
+                            path,
+                            line + 1,
+                            e
+                        )))
+                    }
+                };
+            }
+            entries.push((
+                epoch,
+                Vector3::new(values[0], values[1], values[2]),
+                values[3],
+            ));
+        }
+        Self::new(entries, frame)
+    }
+
+    /// Returns the index of the entry in effect at this epoch, if any
+    fn entry(&self, epoch: Epoch) -> Option<usize> {
+        if self.epochs.is_empty() || epoch < self.epochs[0] {
+            return None;
+        }
+        match self
+            .epochs
+            .binary_search_by(|probe| probe.partial_cmp(&epoch).unwrap())
+        {
+            Ok(idx) => Some(idx),
+            Err(idx) => Some(idx - 1),
+        }
+    }
+}
+
+impl ThrustControl for ThrustProfile {
+    fn direction(&self, osc: &SpacecraftState) -> Vector3<f64> {
+        match self.entry(osc.epoch()) {
+            Some(idx) => {
+                let vector = if idx + 1 < self.epochs.len() {
+                    // Linear interpolation between this entry and the next one
+                    let span = (self.epochs[idx + 1] - self.epochs[idx]).in_seconds();
+                    let frac = (osc.epoch() - self.epochs[idx]).in_seconds() / span;
+                    let vector =
+                        self.directions[idx] * (1.0 - frac) + self.directions[idx + 1] * frac;
+                    if vector.norm() > 0.0 {
+                        vector / vector.norm()
+                    } else {
+                        // Opposite directions: keep the current one until the next entry
+                        self.directions[idx]
+                    }
+                } else {
+                    // The last entry is held
+                    self.directions[idx]
+                };
+                if matches!(self.frame, Frame::Inertial) {
+                    vector
+                } else {
+                    osc.orbit.dcm_to_inertial(self.frame) * vector
+                }
+            }
+            None => Vector3::zeros(),
+        }
+    }
+
+    fn throttle(&self, osc: &SpacecraftState) -> f64 {
+        match self.entry(osc.epoch()) {
+            Some(idx) => self.throttles[idx],
+            None => 0.0,
+        }
+    }
+
+    fn next(&self, sc: &SpacecraftState) -> GuidanceMode {
+        if self.throttle(sc) > 0.0 {
+            GuidanceMode::Thrust
+        } else {
+            GuidanceMode::Coast
+        }
+    }
+}
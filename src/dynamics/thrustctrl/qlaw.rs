@@ -0,0 +1,432 @@
+use super::{
+    gauss_rate, osc_elements, Achieve, Frame, GuidanceMode, NyxError, Orbit, SpacecraftState,
+    ThrustControl, Vector3,
+};
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+/// Scaling of the semi-major axis term, from Petropoulos (m, n, r)
+const SMA_SCALING: (f64, f64, f64) = (3.0, 4.0, 2.0);
+/// Steepness of the minimum periapsis penalty
+const PENALTY_K: f64 = 100.0;
+/// Weight of the out of plane rate in the maximum rate of change of the argument of periapsis
+const AOP_B: f64 = 0.01;
+/// Number of true anomalies used to compute the effectivity of the thrust on the osculating orbit
+const EFFECTIVITY_SAMPLES: usize = 72;
+
+/// Options of the Q-law: effectivity thresholds and minimum periapsis penalty.
+#[derive(Copy, Clone, Debug)]
+pub struct QLawOptions {
+    /// Coast when the relative effectivity is below this threshold (between 0 and 1, 0 disables it)
+    pub eta_rel: f64,
+    /// Coast when the absolute effectivity is below this threshold (between 0 and 1, 0 disables it)
+    pub eta_abs: f64,
+    /// Minimum radius of periapsis in km, enforced through a penalty (0 disables it)
+    pub rp_min_km: f64,
+    /// Weight of the minimum periapsis penalty
+    pub penalty_weight: f64,
+}
+
+impl Default for QLawOptions {
+    fn default() -> Self {
+        Self {
+            eta_rel: 0.0,
+            eta_abs: 0.0,
+            rp_min_km: 0.0,
+            penalty_weight: 1.0,
+        }
+    }
+}
+
+/// QLaw defines the Lyapunov feedback control law from Petropoulos (AAS 2004-5089 and AIAA 2005-5305).
+///
+/// The proximity quotient Q estimates the time to go to the targeted elements, and the thrust direction is that
+/// which decreases Q the fastest. When the thrust is not effective enough at the current location on the orbit,
+/// the spacecraft coasts (if the effectivity thresholds are set).
+#[derive(Clone, Debug)]
+pub struct QLaw {
+    /// Stores the objectives
+    objectives: Vec<Achieve>,
+    /// Stores the weight of each objective
+    weights: Vec<f64>,
+    opts: QLawOptions,
+}
+
+/// WARNING: Objectives must be in degrees! The phasing objectives and the geodetic altitude are not supported.
+impl QLaw {
+    /// Creates a new Q-law with unit weights, without coasting and without periapsis penalty, as an Arc
+    /// Note: this returns an Arc so it can be plugged into the Spacecraft dynamics directly.
+    pub fn new(objectives: Vec<Achieve>) -> Result<Arc<Self>, NyxError> {
+        Self::with_options(
+            objectives.into_iter().map(|obj| (obj, 1.0)).collect(),
+            QLawOptions::default(),
+        )
+    }
+
+    /// Creates a new Q-law from the weighted objectives and the provided options, as an Arc.
+    /// Returns an error if an objective is not supported or if a weight is negative.
+    pub fn with_options(
+        objectives: Vec<(Achieve, f64)>,
+        opts: QLawOptions,
+    ) -> Result<Arc<Self>, NyxError> {
+        for (obj, weight) in &objectives {
+            if obj.element_value(&[1.0; 5]).is_none() {
+                return Err(NyxError::CustomError(format!(
+                    "the Q-law does not support {:?}, which depends on the position along the orbit",
+                    obj
+                )));
+            }
+            if *weight < 0.0 {
+                return Err(NyxError::CustomError(format!(
+                    "the weight of {:?} must be positive, got {}",
+                    obj, weight
+                )));
+            }
+        }
+        let (objectives, weights) = objectives.into_iter().unzip();
+        Ok(Arc::new(Self {
+            objectives,
+            weights,
+            opts,
+        }))
+    }
+
+    /// Returns the proximity quotient of these elements (sma in km, angles in radians) for a unit acceleration,
+    /// only accounting for the objectives which are not yet achieved.
+    fn quotient(&self, gm: f64, oe: &[f64; 5], active: &[bool]) -> f64 {
+        let [sma, ecc, inc, raan, aop] = *oe;
+        let p = sma * (1.0 - ecc.powi(2));
+        let h = (gm * p).sqrt();
+        let mut q = 0.0;
+        for (i, obj) in self.objectives.iter().enumerate() {
+            if !active[i] {
+                continue;
+            }
+            let (delta, max_rate, scaling) = match *obj {
+                Achieve::Sma { target, .. } => {
+                    let (m, n, r) = SMA_SCALING;
+                    let max_rate = 2.0 * (sma.powi(3) * (1.0 + ecc) / (gm * (1.0 - ecc))).sqrt();
+                    let scaling =
+                        (1.0 + ((sma - target).abs() / (m * target)).powf(n)).powf(1.0 / r);
+                    (sma - target, max_rate, scaling)
+                }
+                Achieve::Ecc { target, .. } => (ecc - target, 2.0 * p / h, 1.0),
+                Achieve::Inc { target, .. } => {
+                    let (sin_aop, cos_aop) = aop.sin_cos();
+                    let max_rate =
+                        p / (h * ((1.0 - (ecc * sin_aop).powi(2)).sqrt() - ecc * cos_aop.abs()));
+                    (inc - target.to_radians(), max_rate, 1.0)
+                }
+                Achieve::Raan { target, .. } => {
+                    let (sin_aop, cos_aop) = aop.sin_cos();
+                    let max_rate = p
+                        / (h * inc.sin()
+                            * ((1.0 - (ecc * cos_aop).powi(2)).sqrt() - ecc * sin_aop.abs()));
+                    (angle_diff(raan, target.to_radians()), max_rate, 1.0)
+                }
+                Achieve::Aop { target, .. } => {
+                    let oe2 = 1.0 - ecc.powi(2);
+                    let e3 = ecc.powi(3);
+                    let sqrt_val = (0.25 * (oe2 / e3).powi(2) + 1.0 / 27.0).sqrt();
+                    let cos_ta = (oe2 / (2.0 * e3) + sqrt_val).cbrt()
+                        - (-oe2 / (2.0 * e3) + sqrt_val).cbrt()
+                        - 1.0 / ecc;
+                    let r = p / (1.0 + ecc * cos_ta);
+                    let in_plane =
+                        ((p * cos_ta).powi(2) + ((p + r).powi(2)) * (1.0 - cos_ta.powi(2))).sqrt()
+                            / (ecc * h);
+                    let (sin_aop, cos_aop) = aop.sin_cos();
+                    let raan_rate = p
+                        / (h * inc.sin()
+                            * ((1.0 - (ecc * cos_aop).powi(2)).sqrt() - ecc * sin_aop.abs()));
+                    let out_of_plane = raan_rate * inc.cos().abs();
+                    let max_rate = (in_plane + AOP_B * out_of_plane) / (1.0 + AOP_B);
+                    (angle_diff(aop, target.to_radians()), max_rate, 1.0)
+                }
+                obj => {
+                    // Compute the maximum rate of change of this objective on the osculating orbit
+                    let mut max_rate: f64 = 0.0;
+                    for k in 0..EFFECTIVITY_SAMPLES {
+                        let ta = 2.0 * PI * (k as f64) / (EFFECTIVITY_SAMPLES as f64);
+                        max_rate = max_rate.max(obj.rate(gm, oe, ta).norm());
+                    }
+                    (obj.element_value(oe).unwrap() - obj.target(), max_rate, 1.0)
+                }
+            };
+            q += self.weights[i] * scaling * (delta / max_rate).powi(2);
+        }
+        let penalty = if self.opts.rp_min_km > 0.0 {
+            self.opts.penalty_weight
+                * (PENALTY_K * (1.0 - sma * (1.0 - ecc) / self.opts.rp_min_km)).exp()
+        } else {
+            0.0
+        };
+        (1.0 + penalty) * q
+    }
+
+    /// Returns the partial derivatives of the proximity quotient with respect to the elements, by central differences
+    fn quotient_partials(&self, gm: f64, oe: &[f64; 5], active: &[bool]) -> [f64; 5] {
+        let mut partials = [0.0; 5];
+        for i in 0..5 {
+            let step = 1e-6 * oe[i].abs().max(1.0);
+            let mut hi = *oe;
+            hi[i] += step;
+            let mut lo = *oe;
+            if i != 1 || oe[i] > step {
+                lo[i] -= step;
+            }
+            partials[i] =
+                (self.quotient(gm, &hi, active) - self.quotient(gm, &lo, active)) / (hi[i] - lo[i]);
+        }
+        partials
+    }
+
+    /// Returns the unit acceleration (in the RCN frame) which decreases the proximity quotient the fastest at this
+    /// true anomaly (in radians), scaled by the rate of change of the proximity quotient.
+    fn steepest_descent(gm: f64, oe: &[f64; 5], ta: f64, partials: &[f64; 5]) -> Vector3<f64> {
+        let mut rate = Vector3::zeros();
+        for (i, partial) in partials.iter().enumerate() {
+            // Skip the elements which do not change Q, e.g. the argument of periapsis is singular on circular orbits
+            if partial.abs() > 0.0 {
+                rate += *partial * gauss_rate(gm, oe, ta, i);
+            }
+        }
+        -rate
+    }
+
+    /// Returns the thrust direction in the RCN frame and whether thrusting is effective enough at this state.
+    /// The direction is zero if all of the objectives are achieved.
+    fn control(&self, osc: &Orbit) -> (Vector3<f64>, bool) {
+        let active: Vec<bool> = self
+            .objectives
+            .iter()
+            .zip(&self.weights)
+            .map(|(obj, weight)| *weight > 0.0 && !obj.achieved(osc))
+            .collect();
+        if !active.iter().any(|&a| a) {
+            return (Vector3::zeros(), false);
+        }
+
+        let gm = osc.frame.gm();
+        let oe = osc_elements(osc);
+        let partials = self.quotient_partials(gm, &oe, &active);
+        let ctrl = Self::steepest_descent(gm, &oe, osc.ta().to_radians(), &partials);
+        if ctrl.norm() <= 0.0 {
+            return (ctrl, false);
+        }
+
+        if self.opts.eta_rel <= 0.0 && self.opts.eta_abs <= 0.0 {
+            return (ctrl / ctrl.norm(), true);
+        }
+
+        // Compute the best and worst rates of change of Q on the osculating orbit
+        let mut best = std::f64::MIN;
+        let mut worst = std::f64::MAX;
+        for k in 0..EFFECTIVITY_SAMPLES {
+            let ta = 2.0 * PI * (k as f64) / (EFFECTIVITY_SAMPLES as f64);
+            let rate = Self::steepest_descent(gm, &oe, ta, &partials).norm();
+            best = best.max(rate);
+            worst = worst.min(rate);
+        }
+        let rate = ctrl.norm();
+        let eta_abs = rate / best;
+        let eta_rel = if best > worst {
+            (rate - worst) / (best - worst)
+        } else {
+            1.0
+        };
+        let effective = eta_abs >= self.opts.eta_abs && eta_rel >= self.opts.eta_rel;
+        (ctrl / rate, effective)
+    }
+}
+
+impl ThrustControl for QLaw {
+    /// Returns whether the control law has achieved all goals
+    fn achieved(&self, state: &SpacecraftState) -> Result<bool, NyxError> {
+        for obj in &self.objectives {
+            if !obj.achieved(&state.orbit) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn direction(&self, sc: &SpacecraftState) -> Vector3<f64> {
+        if sc.mode == GuidanceMode::Coast {
+            Vector3::zeros()
+        } else if sc.mode == GuidanceMode::Thrust {
+            let (ctrl, _) = self.control(&sc.orbit);
+            // Convert to inertial -- this whole control is computed in the RCN frame
+            sc.orbit.dcm_to_inertial(Frame::RCN) * ctrl
+        } else {
+            panic!("Unsupported guidance mode {:?}", sc.mode);
+        }
+    }
+
+    // Either thrust full power or not at all
+    fn throttle(&self, sc: &SpacecraftState) -> f64 {
+        if sc.mode == GuidanceMode::Coast {
+            0.0
+        } else if sc.mode == GuidanceMode::Thrust {
+            if self.control(&sc.orbit).1 {
+                1.0
+            } else {
+                0.0
+            }
+        } else {
+            panic!("Unsupported guidance mode {:?}", sc.mode);
+        }
+    }
+
+    /// Update the state for the next iteration: coast when the objectives are achieved or the thrust is not effective
+    fn next(&self, sc: &SpacecraftState) -> GuidanceMode {
+        if self.control(&sc.orbit).1 {
+            if sc.mode == GuidanceMode::Coast {
+                info!("enabling control: {:o}", sc.orbit);
+            }
+            GuidanceMode::Thrust
+        } else {
+            if sc.mode == GuidanceMode::Thrust {
+                info!("disabling control: {:o}", sc.orbit);
+            }
+            GuidanceMode::Coast
+        }
+    }
+}
+
+/// Returns the difference between two angles in radians, between -pi and pi
+fn angle_diff(angle: f64, target: f64) -> f64 {
+    let delta = angle - target;
+    delta.sin().atan2(delta.cos())
+}
+
+#[test]
+fn qlaw_direction() {
+    use crate::celestia::Cosm;
+    use crate::time::Epoch;
+    let mut cosm = Cosm::de438_raw();
+    cosm.frame_mut_gm("EME2000", 398_600.433);
+    let eme2k = cosm.frame("EME2000");
+    let start_time = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+    let orbit = Orbit::keplerian(7000.0, 0.0, 10.0, 0.0, 0.0, 30.0, start_time, eme2k);
+    let mut osc_sc = SpacecraftState::new(orbit, 1.0, 0.0);
+    osc_sc.mode = GuidanceMode::Thrust;
+
+    // Raising the sma of a circular orbit is mostly done by thrusting along the velocity. Note that the eccentricity
+    // is not targeted, so the control also increases it since that increases the maximum rate of change of the sma.
+    let raise = QLaw::new(vec![Achieve::Sma {
+        target: 42164.0,
+        tol: 1.0,
+    }])
+    .unwrap();
+    let along = orbit.velocity() / orbit.vmag();
+    let got = raise.direction(&osc_sc);
+    assert!((got.norm() - 1.0).abs() < 1e-12);
+    assert!(
+        got.dot(&along) > 0.9,
+        "incorrect direction computed: {}",
+        got
+    );
+    assert!((raise.throttle(&osc_sc) - 1.0).abs() < std::f64::EPSILON);
+
+    // Lowering it is done by thrusting against the velocity
+    let lower = QLaw::new(vec![Achieve::Sma {
+        target: 6800.0,
+        tol: 1.0,
+    }])
+    .unwrap();
+    assert!(lower.direction(&osc_sc).dot(&along) < -0.9);
+
+    // Changing the inclination is mostly done out of plane
+    let plane = QLaw::new(vec![Achieve::Inc {
+        target: 12.0,
+        tol: 0.01,
+    }])
+    .unwrap();
+    let h_hat = orbit.hvec() / orbit.hmag();
+    let got = plane.direction(&osc_sc);
+    assert!(
+        got.dot(&h_hat).abs() > 0.9,
+        "incorrect direction computed: {}",
+        got
+    );
+
+    // And no thrust once achieved
+    let done = QLaw::new(vec![Achieve::Sma {
+        target: 7000.0,
+        tol: 1.0,
+    }])
+    .unwrap();
+    assert!(done.throttle(&osc_sc).abs() < std::f64::EPSILON);
+    assert_eq!(done.next(&osc_sc), GuidanceMode::Coast);
+
+    // Any number of objectives is supported, but not those which depend on the position along the orbit
+    let elements = vec![
+        Achieve::Sma {
+            target: 42164.0,
+            tol: 1.0,
+        },
+        Achieve::Ecc {
+            target: 0.01,
+            tol: 5e-5,
+        },
+        Achieve::Inc {
+            target: 0.05,
+            tol: 5e-3,
+        },
+        Achieve::Raan {
+            target: 0.0,
+            tol: 5e-3,
+        },
+        Achieve::Aop {
+            target: 0.0,
+            tol: 5e-3,
+        },
+        Achieve::EquinoctialF {
+            target: 0.0,
+            tol: 5e-5,
+        },
+    ];
+    assert!(QLaw::new(elements).is_ok());
+    assert!(QLaw::new(vec![Achieve::GeodeticAlt {
+        target: 400.0,
+        tol: 1.0,
+    }])
+    .is_err());
+}
+
+#[test]
+fn qlaw_effectivity() {
+    use crate::celestia::Cosm;
+    use crate::time::Epoch;
+    let mut cosm = Cosm::de438_raw();
+    cosm.frame_mut_gm("EME2000", 398_600.433);
+    let eme2k = cosm.frame("EME2000");
+    let start_time = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+
+    // Raising the sma of an eccentric orbit is most effective at periapsis
+    let ctrl = QLaw::with_options(
+        vec![(
+            Achieve::Sma {
+                target: 42164.0,
+                tol: 1.0,
+            },
+            1.0,
+        )],
+        QLawOptions {
+            eta_rel: 0.5,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let periapsis = Orbit::keplerian(24505.9, 0.725, 7.05, 0.0, 0.0, 0.0, start_time, eme2k);
+    let apoapsis = Orbit::keplerian(24505.9, 0.725, 7.05, 0.0, 0.0, 180.0, start_time, eme2k);
+    assert_eq!(
+        ctrl.next(&SpacecraftState::new(apoapsis, 1.0, 0.0)),
+        GuidanceMode::Coast
+    );
+    assert_eq!(
+        ctrl.next(&SpacecraftState::new(periapsis, 1.0, 0.0)),
+        GuidanceMode::Thrust
+    );
+}
@@ -1,16 +1,21 @@
 use super::{
-    unit_vector_from_angles, Achieve, Frame, GuidanceMode, NyxError, Orbit, SpacecraftState,
-    ThrustControl, Vector3,
+    osc_elements, unit_vector_from_angles, Achieve, Frame, GuidanceMode, NyxError, Orbit,
+    SpacecraftState, ThrustControl, Vector3,
 };
-use std::f64::consts::FRAC_PI_2 as half_pi;
+use std::f64::consts::{FRAC_PI_2 as half_pi, PI};
 use std::sync::Arc;
 
+/// Number of true anomalies used to compute the efficiency of the thrust on the osculating orbit
+const EFFICIENCY_SAMPLES: usize = 72;
+
 /// Ruggiero defines the closed loop control law from IEPC 2011-102
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
 pub struct Ruggiero {
     /// Stores the objectives
-    objectives: [Option<Achieve>; 5],
+    objectives: Vec<Achieve>,
     init_state: Orbit,
+    /// Minimum efficiency to thrust for an objective (0 means always thrust)
+    eta_threshold: f64,
 }
 
 /// The Ruggiero is a locally optimal control of a state for specific osculating elements.
@@ -19,16 +24,79 @@ impl Ruggiero {
     /// Creates a new Ruggiero locally optimal control as an Arc
     /// Note: this returns an Arc so it can be plugged into the Spacecraft dynamics directly.
     pub fn new(objectives: Vec<Achieve>, initial: Orbit) -> Arc<Self> {
-        let mut objs: [Option<Achieve>; 5] = [None, None, None, None, None];
-        for i in 0..objectives.len() {
-            objs[i] = Some(objectives[i]);
-        }
         Arc::new(Self {
-            objectives: objs,
+            objectives,
             init_state: initial,
+            eta_threshold: 0.0,
         })
     }
 
+    /// Creates a new Ruggiero locally optimal control which only thrusts for an objective when its efficiency is
+    /// at least `eta_threshold` (between 0 and 1). The efficiency is the rate of change of the element at the current
+    /// location over its best rate of change on the osculating orbit.
+    pub fn with_eta(
+        objectives: Vec<Achieve>,
+        initial: Orbit,
+        eta_threshold: f64,
+    ) -> Result<Arc<Self>, NyxError> {
+        if !(0.0..=1.0).contains(&eta_threshold) {
+            return Err(NyxError::CustomError(format!(
+                "efficiency threshold must be between 0 and 1, got {}",
+                eta_threshold
+            )));
+        }
+        Ok(Arc::new(Self {
+            objectives,
+            init_state: initial,
+            eta_threshold,
+        }))
+    }
+
+    /// Returns the weight of this objective at this orbit, i.e. zero if it's achieved
+    fn obj_weight(&self, obj: &Achieve, osc: &Orbit) -> f64 {
+        if obj.is_phasing() {
+            // The initial phasing is meaningless since the anomaly changes along the orbit
+            if obj.achieved(osc) {
+                0.0
+            } else {
+                -obj.error(osc) / 180.0
+            }
+        } else {
+            Self::weighting(
+                obj.value(&self.init_state),
+                obj.target(),
+                obj.value(osc),
+                obj.tol(),
+            )
+        }
+    }
+
+    /// Returns whether thrusting for this objective is efficient enough at this orbit
+    fn efficient(&self, obj: &Achieve, osc: &Orbit) -> bool {
+        if self.eta_threshold <= 0.0 {
+            return true;
+        }
+        let gm = osc.frame.gm();
+        let oe = osc_elements(osc);
+        let rate = obj.rate(gm, &oe, osc.ta().to_radians()).norm();
+        let mut best_rate = rate;
+        for k in 0..EFFICIENCY_SAMPLES {
+            let ta = 2.0 * PI * (k as f64) / (EFFICIENCY_SAMPLES as f64);
+            best_rate = best_rate.max(obj.rate(gm, &oe, ta).norm());
+        }
+        rate >= self.eta_threshold * best_rate
+    }
+
+    /// Returns whether any objective must be thrusted for at this orbit
+    fn thrust_needed(&self, osc: &Orbit) -> bool {
+        for obj in &self.objectives {
+            if self.obj_weight(obj, osc).abs() > 0.0 && self.efficient(obj, osc) {
+                return true;
+            }
+        }
+        false
+    }
+
     fn weighting(init: f64, target: f64, osc: f64, tol: f64) -> f64 {
         if (osc - target).abs() < tol {
             0.0
@@ -49,11 +117,9 @@ impl Ruggiero {
 impl ThrustControl for Ruggiero {
     /// Returns whether the control law has achieved all goals
     fn achieved(&self, state: &SpacecraftState) -> Result<bool, NyxError> {
-        for maybe_obj in &self.objectives {
-            if let Some(obj) = maybe_obj {
-                if !obj.achieved(&state.orbit) {
-                    return Ok(false);
-                }
+        for obj in &self.objectives {
+            if !obj.achieved(&state.orbit) {
+                return Ok(false);
             }
         }
         Ok(true)
@@ -65,79 +131,84 @@ impl ThrustControl for Ruggiero {
         } else if sc.mode == GuidanceMode::Thrust {
             let osc = sc.orbit;
             let mut ctrl = Vector3::zeros();
-            for maybe_obj in &self.objectives {
-                if let Some(obj) = maybe_obj {
-                    match *obj {
-                        Achieve::Sma { target, tol } => {
-                            let weight =
-                                Self::weighting(self.init_state.sma(), target, osc.sma(), tol);
-                            if weight.abs() > 0.0 {
-                                let num = osc.ecc() * osc.ta().to_radians().sin();
-                                let denom = 1.0 + osc.ecc() * osc.ta().to_radians().cos();
-                                let alpha = num.atan2(denom);
-                                ctrl += unit_vector_from_angles(alpha, 0.0) * weight;
-                            }
+            for obj in &self.objectives {
+                if !self.efficient(obj, &osc) {
+                    continue;
+                }
+                match *obj {
+                    Achieve::Sma { target, tol } => {
+                        let weight = Self::weighting(self.init_state.sma(), target, osc.sma(), tol);
+                        if weight.abs() > 0.0 {
+                            let num = osc.ecc() * osc.ta().to_radians().sin();
+                            let denom = 1.0 + osc.ecc() * osc.ta().to_radians().cos();
+                            let alpha = num.atan2(denom);
+                            ctrl += unit_vector_from_angles(alpha, 0.0) * weight;
                         }
-                        Achieve::Ecc { target, tol } => {
-                            let weight =
-                                Self::weighting(self.init_state.ecc(), target, osc.ecc(), tol);
-                            if weight.abs() > 0.0 {
-                                let num = osc.ta().to_radians().sin();
-                                let denom =
-                                    osc.ta().to_radians().cos() + osc.ea().to_radians().cos();
-                                let alpha = num.atan2(denom);
-                                ctrl += unit_vector_from_angles(alpha, 0.0) * weight;
-                            }
+                    }
+                    Achieve::Ecc { target, tol } => {
+                        let weight = Self::weighting(self.init_state.ecc(), target, osc.ecc(), tol);
+                        if weight.abs() > 0.0 {
+                            let num = osc.ta().to_radians().sin();
+                            let denom = osc.ta().to_radians().cos() + osc.ea().to_radians().cos();
+                            let alpha = num.atan2(denom);
+                            ctrl += unit_vector_from_angles(alpha, 0.0) * weight;
                         }
-                        Achieve::Inc { target, tol } => {
-                            let weight =
-                                Self::weighting(self.init_state.inc(), target, osc.inc(), tol);
-                            if weight.abs() > 0.0 {
-                                let beta =
-                                    half_pi.copysign(((osc.ta() + osc.aop()).to_radians()).cos());
-                                ctrl += unit_vector_from_angles(0.0, beta) * weight;
-                            }
+                    }
+                    Achieve::Inc { target, tol } => {
+                        let weight = Self::weighting(self.init_state.inc(), target, osc.inc(), tol);
+                        if weight.abs() > 0.0 {
+                            let beta =
+                                half_pi.copysign(((osc.ta() + osc.aop()).to_radians()).cos());
+                            ctrl += unit_vector_from_angles(0.0, beta) * weight;
                         }
-                        Achieve::Raan { target, tol } => {
-                            // BUG: https://gitlab.com/chrisrabotin/nyx/issues/83
-                            let weight =
-                                Self::weighting(self.init_state.raan(), target, osc.raan(), tol);
-                            if weight.abs() > 0.0 {
-                                let beta =
-                                    half_pi.copysign(((osc.ta() + osc.aop()).to_radians()).sin());
-                                ctrl += unit_vector_from_angles(0.0, beta) * weight;
-                            }
+                    }
+                    Achieve::Raan { target, tol } => {
+                        // BUG: https://gitlab.com/chrisrabotin/nyx/issues/83
+                        let weight =
+                            Self::weighting(self.init_state.raan(), target, osc.raan(), tol);
+                        if weight.abs() > 0.0 {
+                            let beta =
+                                half_pi.copysign(((osc.ta() + osc.aop()).to_radians()).sin());
+                            ctrl += unit_vector_from_angles(0.0, beta) * weight;
                         }
-                        Achieve::Aop { target, tol } => {
-                            let weight =
-                                Self::weighting(self.init_state.aop(), target, osc.aop(), tol);
-                            let oe2 = 1.0 - osc.ecc().powi(2);
-                            let e3 = osc.ecc().powi(3);
-                            // Compute the optimal true anomaly for in-plane thrusting
-                            let sqrt_val = (0.25 * (oe2 / e3).powi(2) + 1.0 / 27.0).sqrt();
-                            let opti_ta_alpha = ((oe2 / (2.0 * e3) + sqrt_val).powf(1.0 / 3.0)
-                                - (-oe2 / (2.0 * e3) + sqrt_val).powf(1.0 / 3.0)
-                                - 1.0 / osc.ecc())
-                            .acos();
-                            // Compute the optimal true anomaly for out of plane thrusting
-                            let opti_ta_beta = (-osc.ecc() * osc.aop().to_radians().cos()).acos()
-                                - osc.aop().to_radians();
-                            // And choose whether to do an in-plane or out of plane thrust
-                            if (osc.ta().to_radians() - opti_ta_alpha).abs()
-                                < (osc.ta().to_radians() - opti_ta_beta).abs()
-                            {
-                                // In plane
-                                let p = osc.semi_parameter();
-                                let (sin_ta, cos_ta) = osc.ta().to_radians().sin_cos();
-                                let alpha = (-p * cos_ta).atan2((p + osc.rmag()) * sin_ta);
-                                ctrl += unit_vector_from_angles(alpha, 0.0) * weight;
-                            } else {
-                                // Out of plane
-                                let beta = half_pi.copysign(
-                                    -(osc.ta().to_radians() + osc.aop().to_radians()).sin(),
-                                ) * osc.inc().to_radians().cos();
-                                ctrl += unit_vector_from_angles(0.0, beta) * weight;
-                            };
+                    }
+                    Achieve::Aop { target, tol } => {
+                        let weight = Self::weighting(self.init_state.aop(), target, osc.aop(), tol);
+                        let oe2 = 1.0 - osc.ecc().powi(2);
+                        let e3 = osc.ecc().powi(3);
+                        // Compute the optimal true anomaly for in-plane thrusting
+                        let sqrt_val = (0.25 * (oe2 / e3).powi(2) + 1.0 / 27.0).sqrt();
+                        let opti_ta_alpha = ((oe2 / (2.0 * e3) + sqrt_val).powf(1.0 / 3.0)
+                            - (-oe2 / (2.0 * e3) + sqrt_val).powf(1.0 / 3.0)
+                            - 1.0 / osc.ecc())
+                        .acos();
+                        // Compute the optimal true anomaly for out of plane thrusting
+                        let opti_ta_beta = (-osc.ecc() * osc.aop().to_radians().cos()).acos()
+                            - osc.aop().to_radians();
+                        // And choose whether to do an in-plane or out of plane thrust
+                        if (osc.ta().to_radians() - opti_ta_alpha).abs()
+                            < (osc.ta().to_radians() - opti_ta_beta).abs()
+                        {
+                            // In plane
+                            let p = osc.semi_parameter();
+                            let (sin_ta, cos_ta) = osc.ta().to_radians().sin_cos();
+                            let alpha = (-p * cos_ta).atan2((p + osc.rmag()) * sin_ta);
+                            ctrl += unit_vector_from_angles(alpha, 0.0) * weight;
+                        } else {
+                            // Out of plane
+                            let beta = half_pi
+                                .copysign(-(osc.ta().to_radians() + osc.aop().to_radians()).sin())
+                                * osc.inc().to_radians().cos();
+                            ctrl += unit_vector_from_angles(0.0, beta) * weight;
+                        };
+                    }
+                    _ => {
+                        // Thrust along the direction of the best rate of change of this objective
+                        let weight = self.obj_weight(obj, &osc);
+                        if weight.abs() > 0.0 {
+                            let oe = osc_elements(&osc);
+                            let rate = obj.rate(osc.frame.gm(), &oe, osc.ta().to_radians());
+                            ctrl += rate / rate.norm() * weight;
                         }
                     }
                 }
@@ -160,49 +231,11 @@ impl ThrustControl for Ruggiero {
         if sc.mode == GuidanceMode::Coast {
             0.0
         } else if sc.mode == GuidanceMode::Thrust {
-            let osc = sc.orbit;
-            for maybe_obj in &self.objectives {
-                if let Some(obj) = maybe_obj {
-                    match *obj {
-                        Achieve::Sma { target, tol } => {
-                            let weight =
-                                Self::weighting(self.init_state.sma(), target, osc.sma(), tol);
-                            if weight.abs() > 0.0 {
-                                return 1.0;
-                            }
-                        }
-                        Achieve::Ecc { target, tol } => {
-                            let weight =
-                                Self::weighting(self.init_state.ecc(), target, osc.ecc(), tol);
-                            if weight.abs() > 0.0 {
-                                return 1.0;
-                            }
-                        }
-                        Achieve::Inc { target, tol } => {
-                            let weight =
-                                Self::weighting(self.init_state.inc(), target, osc.inc(), tol);
-                            if weight.abs() > 0.0 {
-                                return 1.0;
-                            }
-                        }
-                        Achieve::Raan { target, tol } => {
-                            let weight =
-                                Self::weighting(self.init_state.raan(), target, osc.raan(), tol);
-                            if weight.abs() > 0.0 {
-                                return 1.0;
-                            }
-                        }
-                        Achieve::Aop { target, tol } => {
-                            let weight =
-                                Self::weighting(self.init_state.aop(), target, osc.aop(), tol);
-                            if weight.abs() > 0.0 {
-                                return 1.0;
-                            }
-                        }
-                    }
-                }
+            if self.thrust_needed(&sc.orbit) {
+                1.0
+            } else {
+                0.0
             }
-            0.0
         } else {
             panic!("Unsupported guidance mode {:?}", sc.mode);
         }
@@ -210,7 +243,8 @@ impl ThrustControl for Ruggiero {
 
     /// Update the state for the next iteration
     fn next(&self, sc: &SpacecraftState) -> GuidanceMode {
-        if self.throttle(sc) > 0.0 {
+        // This does not depend on the current mode so that the control resumes after coasting
+        if self.thrust_needed(&sc.orbit) {
             if sc.mode == GuidanceMode::Coast {
                 info!("enabling control: {:o}", sc.orbit);
             }
@@ -276,3 +310,47 @@ fn ruggiero_weight() {
         "incorrect direction computed"
     );
 }
+
+#[test]
+fn ruggiero_with_eta() {
+    use crate::celestia::Cosm;
+    use crate::time::Epoch;
+    let cosm = Cosm::de438();
+    let eme2k = cosm.frame("EME2000");
+    let start_time = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+    let orbit = Orbit::keplerian(7378.1363, 0.01, 0.05, 0.0, 0.0, 1.0, start_time, eme2k);
+
+    // Any number of objectives is supported
+    let objectives = vec![
+        Achieve::Sma {
+            target: 42164.0,
+            tol: 1.0,
+        },
+        Achieve::Ecc {
+            target: 0.01,
+            tol: 5e-5,
+        },
+        Achieve::Inc {
+            target: 0.05,
+            tol: 5e-3,
+        },
+        Achieve::Raan {
+            target: 0.0,
+            tol: 5e-3,
+        },
+        Achieve::Aop {
+            target: 0.0,
+            tol: 5e-3,
+        },
+        Achieve::EquinoctialF {
+            target: 0.01,
+            tol: 5e-5,
+        },
+    ];
+    let ruggiero = Ruggiero::with_eta(objectives.clone(), orbit, 0.5).unwrap();
+    assert_eq!(ruggiero.objectives.len(), 6);
+
+    // The efficiency threshold must be between 0 and 1
+    assert!(Ruggiero::with_eta(objectives.clone(), orbit, -0.1).is_err());
+    assert!(Ruggiero::with_eta(objectives, orbit, 1.5).is_err());
+}
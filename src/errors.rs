@@ -7,6 +7,8 @@ pub enum NyxError {
     SingularStateTransitionMatrix,
     /// Fuel exhausted error, try running without fuel depletion, and then adding it.
     FuelExhausted,
+    /// The propellant of the tank at this index is exhausted, try running without fuel depletion, and then adding it.
+    TankExhausted(usize),
     /// Propagation event not triggered withinin the max propagation time
     ConditionNeverTriggered,
     /// Propagation event not hit enough times (requested, found).
@@ -43,6 +45,10 @@ pub enum NyxError {
     CtrlThrottleRangeErr(f64),
     /// An objective based analysis or control was attempted, but no objective was defined.
     NoObjectiveDefined,
+    /// The targeting problem is ill-defined, e.g. a maneuver before the initial state, or a non-scalar objective
+    InvalidTargetingProblem(String),
+    /// The orbit design is unfeasible, e.g. there is no sun-synchronous inclination at this altitude
+    InvalidOrbitDesign(String),
     /// Some custom error for new dynamics
     CustomError(String),
 }
@@ -73,6 +79,11 @@ impl fmt::Display for NyxError {
                 f,
                 "Spacecraft fuel exhausted, disable fuel depletion and place maneuvers"
             ),
+            Self::TankExhausted(tank) => write!(
+                f,
+                "Spacecraft fuel of tank #{} exhausted, disable fuel depletion and place maneuvers",
+                tank
+            ),
             Self::ConditionNeverTriggered => write!(
                 f,
                 "Try increasing the search space, i.e. increase the maximum propagation time"
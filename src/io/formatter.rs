@@ -115,6 +115,106 @@ pub enum StateHeader {
     VZ { frame: Option<String> },
 }
 
+impl StateHeader {
+    /// Returns the frame of this header, if any
+    pub fn frame(&self) -> Option<&String> {
+        match self {
+            StateHeader::Epoch(_) => None,
+            StateHeader::AoL { frame }
+            | StateHeader::AoP { frame }
+            | StateHeader::apoapsis { frame }
+            | StateHeader::EA { frame }
+            | StateHeader::ECC { frame }
+            | StateHeader::energy { frame }
+            | StateHeader::evec { frame }
+            | StateHeader::geodetic_height { frame }
+            | StateHeader::geodetic_latitude { frame }
+            | StateHeader::geodetic_longitude { frame }
+            | StateHeader::hmag { frame }
+            | StateHeader::hvec { frame }
+            | StateHeader::HX { frame }
+            | StateHeader::HY { frame }
+            | StateHeader::HZ { frame }
+            | StateHeader::INC { frame }
+            | StateHeader::MA { frame }
+            | StateHeader::periapsis { frame }
+            | StateHeader::period { frame }
+            | StateHeader::RAAN { frame }
+            | StateHeader::radius { frame }
+            | StateHeader::rmag { frame }
+            | StateHeader::semi_parameter { frame }
+            | StateHeader::SMA { frame }
+            | StateHeader::TA { frame }
+            | StateHeader::TLong { frame }
+            | StateHeader::velocity { frame }
+            | StateHeader::vmag { frame }
+            | StateHeader::X { frame }
+            | StateHeader::Y { frame }
+            | StateHeader::Z { frame }
+            | StateHeader::VX { frame }
+            | StateHeader::VY { frame }
+            | StateHeader::VZ { frame } => frame.as_ref(),
+        }
+    }
+
+    /// Returns the value of this quantity for the provided state, which must already be in the frame of the header.
+    /// Returns None for the epoch and the vector quantities (e.g. `radius` or `evec`), which are not scalars.
+    pub fn value(&self, state: &Orbit) -> Option<f64> {
+        match self {
+            StateHeader::AoL { .. } => Some(state.aol()),
+            StateHeader::AoP { .. } => Some(state.aop()),
+            StateHeader::apoapsis { .. } => Some(state.apoapsis()),
+            StateHeader::EA { .. } => Some(state.ea()),
+            StateHeader::ECC { .. } => Some(state.ecc()),
+            StateHeader::energy { .. } => Some(state.energy()),
+            StateHeader::geodetic_height { .. } => Some(state.geodetic_height()),
+            StateHeader::geodetic_latitude { .. } => Some(state.geodetic_latitude()),
+            StateHeader::geodetic_longitude { .. } => Some(state.geodetic_longitude()),
+            StateHeader::hmag { .. } => Some(state.hmag()),
+            StateHeader::HX { .. } => Some(state.hx()),
+            StateHeader::HY { .. } => Some(state.hy()),
+            StateHeader::HZ { .. } => Some(state.hz()),
+            StateHeader::INC { .. } => Some(state.inc()),
+            StateHeader::MA { .. } => Some(state.ma()),
+            StateHeader::periapsis { .. } => Some(state.periapsis()),
+            StateHeader::period { .. } => Some(state.period().in_seconds()),
+            StateHeader::RAAN { .. } => Some(state.raan()),
+            StateHeader::rmag { .. } => Some(state.rmag()),
+            StateHeader::semi_parameter { .. } => Some(state.semi_parameter()),
+            StateHeader::SMA { .. } => Some(state.sma()),
+            StateHeader::TA { .. } => Some(state.ta()),
+            StateHeader::TLong { .. } => Some(state.tlong()),
+            StateHeader::vmag { .. } => Some(state.vmag()),
+            StateHeader::X { .. } => Some(state.x),
+            StateHeader::Y { .. } => Some(state.y),
+            StateHeader::Z { .. } => Some(state.z),
+            StateHeader::VX { .. } => Some(state.vx),
+            StateHeader::VY { .. } => Some(state.vy),
+            StateHeader::VZ { .. } => Some(state.vz),
+            StateHeader::Epoch(_)
+            | StateHeader::evec { .. }
+            | StateHeader::hvec { .. }
+            | StateHeader::radius { .. }
+            | StateHeader::velocity { .. } => None,
+        }
+    }
+
+    /// Returns whether this quantity is an angle in degrees, i.e. whether differences must be wrapped
+    pub fn is_angle(&self) -> bool {
+        matches!(
+            self,
+            StateHeader::AoL { .. }
+                | StateHeader::AoP { .. }
+                | StateHeader::EA { .. }
+                | StateHeader::geodetic_longitude { .. }
+                | StateHeader::MA { .. }
+                | StateHeader::RAAN { .. }
+                | StateHeader::TA { .. }
+                | StateHeader::TLong { .. }
+        )
+    }
+}
+
 impl fmt::Display for StateHeader {
     // Prints the Keplerian orbital elements with units
     fn fmt(&self, fh: &mut fmt::Formatter) -> fmt::Result {
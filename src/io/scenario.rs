@@ -321,7 +321,7 @@ pub struct ForceModel {
     pub srp: HashMap<String, SolarPressureSerde>,
 }
 
-#[derive(Deserialize)]
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum PropagatorKind {
     Dormand45,
@@ -331,6 +331,10 @@ pub enum PropagatorKind {
     Rk89,
     Rk4,
     Verner56,
+    /// Eighth order Adams-Bashforth-Moulton multistep, started with an RK89
+    AdamsBashforthMoulton,
+    /// Eighth order Gauss-Jackson multistep, started with an RK89
+    GaussJackson,
 }
 
 #[derive(Deserialize)]
@@ -252,4 +252,4 @@ pub mod dimensions {
 /// Re-export some useful things
 pub mod state;
 pub use self::state::{State, TimeTagged};
-pub use celestia::{Orbit, SpacecraftState};
+pub use celestia::{EnckeState, FormationState, KsState, Orbit, SpacecraftState};
@@ -8,6 +8,9 @@ pub mod ui;
 
 pub mod trajectory;
 
+/// Differential corrector to target finite burns
+pub mod targeter;
+
 pub type ScTraj = trajectory::Traj<SpacecraftState>;
 pub type Ephemeris = trajectory::Traj<Orbit>;
 
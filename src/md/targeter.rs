@@ -0,0 +1,498 @@
+use crate::celestia::{Cosm, Frame, GuidanceMode, Orbit, SpacecraftState};
+use crate::dimensions::{DMatrix, DVector, Matrix6, Vector3};
+use crate::dynamics::deltavctrl::Impulse;
+use crate::dynamics::spacecraft::Spacecraft;
+use crate::dynamics::thrustctrl::{FiniteBurns, Mnvr, Steering, ThrustControl};
+use crate::errors::NyxError;
+use crate::io::formatter::StateHeader;
+use crate::propagators::error_ctrl::ErrorCtrl;
+use crate::propagators::{PropOpts, Propagator};
+use crate::time::{Epoch, TimeUnit};
+use crate::TimeTagged;
+use std::fmt;
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+
+/// Perturbation of the start epoch and of the duration of the burn used to compute the partials, in seconds
+const PERT_TIME_S: f64 = 0.1;
+/// Perturbation of the thrust angles used to compute the partials, in radians
+const PERT_ANGLE_RAD: f64 = 1e-5;
+/// Perturbation of the final position used to compute the partials of the objectives, in km
+const PERT_POS_KM: f64 = 1e-4;
+/// Perturbation of the final velocity used to compute the partials of the objectives, in km/s
+const PERT_VEL_KM_S: f64 = 1e-7;
+
+/// The parameters of the finite burn which the targeter may vary.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Vary {
+    /// Start epoch of the burn, the duration being held constant
+    StartEpoch,
+    /// Duration of the burn, the start epoch being held constant
+    Duration,
+    /// In-plane angle of the thrust direction, i.e. alpha of a `Steering`, in the frame of the targeter
+    InPlaneAngle,
+    /// Out-of-plane angle of the thrust direction, i.e. beta of a `Steering`, in the frame of the targeter
+    OutOfPlaneAngle,
+}
+
+impl Vary {
+    /// Returns the index of this variable in the parameters of the corrector and the size of its perturbation
+    fn index_and_perturbation(self) -> (usize, f64) {
+        match self {
+            Vary::StartEpoch => (0, PERT_TIME_S),
+            Vary::Duration => (1, PERT_TIME_S),
+            Vary::InPlaneAngle => (2, PERT_ANGLE_RAD),
+            Vary::OutOfPlaneAngle => (3, PERT_ANGLE_RAD),
+        }
+    }
+}
+
+/// The kind of constraint of an objective
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Constraint {
+    /// The quantity must be equal to the value, within the tolerance
+    Equal,
+    /// The quantity must be less than the value (plus the tolerance)
+    LessThan,
+    /// The quantity must be greater than the value (minus the tolerance)
+    GreaterThan,
+}
+
+/// An objective of the targeter: a constraint on any scalar `StateHeader` quantity at the target epoch.
+/// If the header specifies a frame, the quantity is computed in that frame.
+#[derive(Clone, Debug)]
+pub struct Objective {
+    pub parameter: StateHeader,
+    pub constraint: Constraint,
+    /// Desired value of an equality constraint, or bound of an inequality constraint, in the unit of the header
+    pub value: f64,
+    pub tolerance: f64,
+}
+
+impl Objective {
+    /// Initializes an equality constraint
+    pub fn equal(parameter: StateHeader, value: f64, tolerance: f64) -> Self {
+        Self {
+            parameter,
+            constraint: Constraint::Equal,
+            value,
+            tolerance,
+        }
+    }
+
+    /// Initializes an inequality constraint where the quantity must be less than the bound
+    pub fn less_than(parameter: StateHeader, bound: f64, tolerance: f64) -> Self {
+        Self {
+            parameter,
+            constraint: Constraint::LessThan,
+            value: bound,
+            tolerance,
+        }
+    }
+
+    /// Initializes an inequality constraint where the quantity must be greater than the bound
+    pub fn greater_than(parameter: StateHeader, bound: f64, tolerance: f64) -> Self {
+        Self {
+            parameter,
+            constraint: Constraint::GreaterThan,
+            value: bound,
+            tolerance,
+        }
+    }
+
+    /// Returns the equality constraints to achieve the Cartesian state of the target orbit, in its frame
+    pub fn from_state(target: &Orbit, pos_tol_km: f64, vel_tol_km_s: f64) -> Vec<Self> {
+        vec![
+            Self::equal(StateHeader::X { frame: None }, target.x, pos_tol_km),
+            Self::equal(StateHeader::Y { frame: None }, target.y, pos_tol_km),
+            Self::equal(StateHeader::Z { frame: None }, target.z, pos_tol_km),
+            Self::equal(StateHeader::VX { frame: None }, target.vx, vel_tol_km_s),
+            Self::equal(StateHeader::VY { frame: None }, target.vy, vel_tol_km_s),
+            Self::equal(StateHeader::VZ { frame: None }, target.vz, vel_tol_km_s),
+        ]
+    }
+
+    /// Returns the difference between two values of the quantity, wrapped between -180 and 180 degrees for angles
+    fn delta(&self, value: f64, other: f64) -> f64 {
+        let delta = value - other;
+        if self.parameter.is_angle() {
+            (delta + 180.0).rem_euclid(360.0) - 180.0
+        } else {
+            delta
+        }
+    }
+
+    /// Returns the error of this value with respect to the desired value (or the bound)
+    pub fn error(&self, value: f64) -> f64 {
+        self.delta(value, self.value)
+    }
+
+    /// Returns whether this value satisfies the constraint
+    pub fn satisfied(&self, value: f64) -> bool {
+        let error = self.error(value);
+        match self.constraint {
+            Constraint::Equal => error.abs() <= self.tolerance,
+            Constraint::LessThan => error <= self.tolerance,
+            Constraint::GreaterThan => error >= -self.tolerance,
+        }
+    }
+}
+
+impl fmt::Display for Objective {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let op = match self.constraint {
+            Constraint::Equal => "=",
+            Constraint::LessThan => "<",
+            Constraint::GreaterThan => ">",
+        };
+        write!(
+            f,
+            "{} {} {} (tol. {})",
+            self.parameter, op, self.value, self.tolerance
+        )
+    }
+}
+
+/// The solution of the targeter
+#[derive(Clone, Debug)]
+pub struct TargeterSolution {
+    /// The finite burn which achieves the objectives
+    pub mnvr: Mnvr,
+    /// The state at the target epoch
+    pub achieved: SpacecraftState,
+    pub objectives: Vec<Objective>,
+    /// Value of each objective at the target epoch
+    pub achieved_values: Vec<f64>,
+    /// Number of corrections applied to the initial guess
+    pub iterations: usize,
+}
+
+impl fmt::Display for TargeterSolution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Targeter converged in {} iterations: burn from {} for {} with direction {} and throttle {}",
+            self.iterations,
+            self.mnvr.start,
+            self.mnvr.duration(),
+            self.mnvr.vector,
+            self.mnvr.thrust_lvl
+        )?;
+        for (obj, value) in self.objectives.iter().zip(&self.achieved_values) {
+            writeln!(f, "\t{}: achieved {}", obj, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// A differential corrector which solves for the start epoch, the duration and the direction of a single finite burn
+/// such that the spacecraft achieves the objectives at the target epoch.
+///
+/// The partials of the state at the end of the burn with respect to the burn parameters are computed with finite
+/// differences, and mapped to the target epoch with the STM of the coast. The STM only accounts for the orbital
+/// dynamics (not for the additional force models of the spacecraft), so convergence may be slower with such models.
+/// Inequality constraints are only included in the correction when they are violated.
+pub struct FiniteBurnTargeter<'a, E: ErrorCtrl> {
+    /// The spacecraft dynamics, whose thrust control is replaced by the burn being targeted
+    pub spacecraft: Arc<Spacecraft<'a>>,
+    pub opts: PropOpts<E>,
+    pub objectives: Vec<Objective>,
+    pub variables: Vec<Vary>,
+    /// Frame of the thrust direction: either the inertial frame or a local frame (VNC, RCN or RIC)
+    pub frame: Frame,
+    /// Used to compute the objectives defined in another frame than the one of the state
+    pub cosm: Arc<Cosm>,
+    pub max_iter: usize,
+}
+
+impl<'a, E: ErrorCtrl> FiniteBurnTargeter<'a, E> {
+    /// Initializes a targeter which varies the provided variables to achieve the objectives, with at most 50 iterations.
+    /// Returns an error if the frame is neither the inertial frame nor a local frame.
+    pub fn new(
+        spacecraft: Arc<Spacecraft<'a>>,
+        opts: PropOpts<E>,
+        objectives: Vec<Objective>,
+        variables: Vec<Vary>,
+        frame: Frame,
+        cosm: Arc<Cosm>,
+    ) -> Result<Self, NyxError> {
+        if !matches!(
+            frame,
+            Frame::Inertial | Frame::VNC | Frame::RCN | Frame::RIC
+        ) {
+            return Err(NyxError::InvalidTargetingProblem(format!(
+                "targeted burns must be either in the inertial frame or in a local frame, got {:?}",
+                frame
+            )));
+        }
+        Ok(Self {
+            spacecraft,
+            opts,
+            objectives,
+            variables,
+            frame,
+            cosm,
+            max_iter: 50,
+        })
+    }
+
+    /// Solves for the finite burn, starting from the initial guess, which achieves the objectives at the target epoch.
+    /// The initial state must be prior to the start of the burn, and the guess must have a constant steering.
+    pub fn try_achieve(
+        &self,
+        initial: SpacecraftState,
+        guess: Mnvr,
+        target_epoch: Epoch,
+    ) -> Result<TargeterSolution, NyxError> {
+        if self.objectives.is_empty() {
+            return Err(NyxError::NoObjectiveDefined);
+        }
+        if self.variables.is_empty() {
+            return Err(NyxError::InvalidTargetingProblem(
+                "no variable to vary".to_string(),
+            ));
+        }
+        if guess.steering != Steering::Constant {
+            return Err(NyxError::InvalidTargetingProblem(
+                "the initial guess must have a constant steering".to_string(),
+            ));
+        }
+        if guess.start < initial.epoch() {
+            return Err(NyxError::InvalidTargetingProblem(format!(
+                "burn starts at {} before the initial state at {}",
+                guess.start,
+                initial.epoch()
+            )));
+        }
+
+        // The parameters are the offset of the start epoch from the guess (s), the duration (s), and the angles (rad)
+        let unit = guess.vector / guess.vector.norm();
+        let mut params = [
+            0.0,
+            guess.duration().in_seconds(),
+            unit[1].atan2(unit[0]),
+            unit[2].asin(),
+        ];
+
+        for iter in 0..=self.max_iter {
+            let mnvr = Self::mnvr(&guess, &params);
+            // All of the perturbed burns end before this epoch, after which the STM maps the partials to the target
+            let junction = mnvr.end + PERT_TIME_S * TimeUnit::Second;
+            if target_epoch < junction {
+                return Err(NyxError::InvalidTargetingProblem(format!(
+                    "target epoch {} is before the end of the burn at {}",
+                    target_epoch, mnvr.end
+                )));
+            }
+
+            let nominal = self.burn_arc(initial, mnvr, junction)?;
+            let (achieved, stm) = self.coast_with_stm(nominal, target_epoch)?;
+
+            let mut achieved_values = Vec::with_capacity(self.objectives.len());
+            for obj in &self.objectives {
+                achieved_values.push(self.evaluate(obj, &achieved.orbit)?);
+            }
+
+            // Only the equalities and the violated inequalities are corrected
+            let active: Vec<usize> = self
+                .objectives
+                .iter()
+                .zip(&achieved_values)
+                .enumerate()
+                .filter(|(_, (obj, value))| {
+                    obj.constraint == Constraint::Equal || !obj.satisfied(**value)
+                })
+                .map(|(i, _)| i)
+                .collect();
+
+            let converged = self
+                .objectives
+                .iter()
+                .zip(&achieved_values)
+                .all(|(obj, value)| obj.satisfied(*value));
+
+            if converged {
+                info!("Targeter converged in {} iterations", iter);
+                return Ok(TargeterSolution {
+                    mnvr,
+                    achieved,
+                    objectives: self.objectives.clone(),
+                    achieved_values,
+                    iterations: iter,
+                });
+            } else if iter == self.max_iter {
+                break;
+            }
+
+            // Partials of the state at the junction epoch with respect to the variables
+            let nominal_vec = nominal.orbit.to_cartesian_vec();
+            let mut dx_dp = DMatrix::<f64>::zeros(6, self.variables.len());
+            for (j, var) in self.variables.iter().enumerate() {
+                let (idx, pert) = var.index_and_perturbation();
+                let mut perturbed = params;
+                perturbed[idx] += pert;
+                let state = self.burn_arc(initial, Self::mnvr(&guess, &perturbed), junction)?;
+                let delta = (state.orbit.to_cartesian_vec() - nominal_vec) / pert;
+                for i in 0..6 {
+                    dx_dp[(i, j)] = delta[i];
+                }
+            }
+
+            // Partials of the active objectives with respect to the state at the target epoch
+            let mut dq_dx = DMatrix::<f64>::zeros(active.len(), 6);
+            let mut errors = DVector::<f64>::zeros(active.len());
+            for (row, i) in active.iter().enumerate() {
+                let obj = &self.objectives[*i];
+                errors[row] = obj.error(achieved_values[*i]);
+                for k in 0..6 {
+                    let pert = if k < 3 { PERT_POS_KM } else { PERT_VEL_KM_S };
+                    let plus = self.evaluate(obj, &Self::perturbed(&achieved.orbit, k, pert))?;
+                    let minus = self.evaluate(obj, &Self::perturbed(&achieved.orbit, k, -pert))?;
+                    dq_dx[(row, k)] = obj.delta(plus, minus) / (2.0 * pert);
+                }
+            }
+
+            let phi = DMatrix::<f64>::from_iterator(6, 6, stm.iter().cloned());
+            let jac = dq_dx * phi * dx_dp;
+            // Minimum norm correction if under-determined, least squares correction if over-determined
+            let jac_pinv = match jac.pseudo_inverse(1e-12) {
+                Ok(pinv) => pinv,
+                Err(e) => return Err(NyxError::InvalidTargetingProblem(e.to_string())),
+            };
+            let correction = -jac_pinv * errors;
+
+            for (j, var) in self.variables.iter().enumerate() {
+                let (idx, _) = var.index_and_perturbation();
+                params[idx] += correction[j];
+            }
+            // The burn cannot start before the initial state, and must have a positive duration
+            params[0] = params[0].max((initial.epoch() - guess.start).in_seconds());
+            params[1] = params[1].max(PERT_TIME_S);
+            debug!(
+                "Targeter iteration #{}: {} active constraints, correction {}",
+                iter,
+                active.len(),
+                correction
+            );
+        }
+
+        Err(NyxError::MaxIterReached(self.max_iter))
+    }
+
+    /// Converts an impulse applied at the provided epoch into an equivalent finite burn centered on that epoch,
+    /// and then solves for the finite burn which achieves the objectives at the target epoch.
+    pub fn try_achieve_from_impulse(
+        &self,
+        initial: SpacecraftState,
+        impulse_epoch: Epoch,
+        impulse: Impulse,
+        target_epoch: Epoch,
+    ) -> Result<TargeterSolution, NyxError> {
+        let prop = Propagator::rk89(self.dynamics(None), self.opts);
+        let mut state = initial;
+        state.mode = GuidanceMode::Coast;
+        let at_impulse = prop
+            .with(state)
+            .for_duration(impulse_epoch - initial.epoch())?;
+        let guess = Mnvr::from_impulse(&at_impulse, &impulse, self.frame)?;
+        self.try_achieve(initial, guess, target_epoch)
+    }
+
+    /// Builds the burn from the parameters of the corrector
+    fn mnvr(guess: &Mnvr, params: &[f64; 4]) -> Mnvr {
+        let start = guess.start + params[0] * TimeUnit::Second;
+        let (sin_alpha, cos_alpha) = params[2].sin_cos();
+        let (sin_beta, cos_beta) = params[3].sin_cos();
+        Mnvr {
+            start,
+            end: start + params[1] * TimeUnit::Second,
+            thrust_lvl: guess.thrust_lvl,
+            vector: Vector3::new(cos_alpha * cos_beta, sin_alpha * cos_beta, sin_beta),
+            steering: Steering::Constant,
+        }
+    }
+
+    /// Returns the spacecraft dynamics with the provided burn as its thrust control
+    fn dynamics(&self, ctrl: Option<Arc<FiniteBurns>>) -> Arc<Spacecraft<'a>> {
+        let mut sc_dyn = (*self.spacecraft).clone();
+        sc_dyn.ctrl = ctrl.map(|ctrl| ctrl as Arc<dyn ThrustControl + 'a>);
+        Arc::new(sc_dyn)
+    }
+
+    /// Propagates the initial state through the burn until the provided epoch
+    fn burn_arc(
+        &self,
+        initial: SpacecraftState,
+        mnvr: Mnvr,
+        until: Epoch,
+    ) -> Result<SpacecraftState, NyxError> {
+        let burns = FiniteBurns::from_mnvrs(vec![mnvr], self.frame);
+        let prop = Propagator::rk89(self.dynamics(Some(burns)), self.opts);
+        let mut state = initial;
+        state.mode = GuidanceMode::Custom(0);
+        state.orbit.stm = None;
+        // Stop at the start and at the end of the burn, so that the thrust discontinuities are on step boundaries
+        for epoch in &[mnvr.start, mnvr.end, until] {
+            state = prop.with(state).for_duration(*epoch - state.epoch())?;
+        }
+        Ok(state)
+    }
+
+    /// Coasts until the target epoch and returns the final state and the STM of the coast
+    fn coast_with_stm(
+        &self,
+        state: SpacecraftState,
+        target_epoch: Epoch,
+    ) -> Result<(SpacecraftState, Matrix6<f64>), NyxError> {
+        let prop = Propagator::rk89(self.dynamics(None), self.opts);
+        let mut init = state;
+        init.mode = GuidanceMode::Coast;
+        init.orbit.enable_stm();
+        let (tx, rx) = channel();
+        let mut achieved = prop
+            .with(init)
+            .with_tx(tx)
+            .for_duration(target_epoch - init.epoch())?;
+        // The STM of each state is that of its step, so the STM of the coast is their product
+        let mut stm = Matrix6::identity();
+        for step in rx.try_iter() {
+            if let Some(step_stm) = step.orbit.stm {
+                stm = step_stm * stm;
+            }
+        }
+        achieved.orbit.stm = None;
+        Ok((achieved, stm))
+    }
+
+    /// Computes the quantity of this objective, in the frame of the objective if specified
+    fn evaluate(&self, obj: &Objective, orbit: &Orbit) -> Result<f64, NyxError> {
+        let state = match obj.parameter.frame() {
+            Some(name) => {
+                let frame = self.cosm.try_frame(name)?;
+                self.cosm.try_frame_chg(orbit, frame)?
+            }
+            None => *orbit,
+        };
+        match obj.parameter.value(&state) {
+            Some(value) => Ok(value),
+            None => Err(NyxError::InvalidTargetingProblem(format!(
+                "{} is not a scalar quantity",
+                obj.parameter
+            ))),
+        }
+    }
+
+    /// Returns the orbit where the k-th Cartesian component is perturbed by delta
+    fn perturbed(orbit: &Orbit, k: usize, delta: f64) -> Orbit {
+        let mut me = *orbit;
+        match k {
+            0 => me.x += delta,
+            1 => me.y += delta,
+            2 => me.z += delta,
+            3 => me.vx += delta,
+            4 => me.vy += delta,
+            _ => me.vz += delta,
+        }
+        me
+    }
+}
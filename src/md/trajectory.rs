@@ -4,6 +4,7 @@ use crate::dimensions::allocator::Allocator;
 use crate::dimensions::{DefaultAllocator, DimName, VectorN};
 use crate::errors::NyxError;
 use crate::propagators::events::Event;
+use crate::propagators::StepInterpolant;
 use crate::time::{Duration, Epoch, TimeUnit};
 use crate::State;
 use std::collections::BTreeMap;
@@ -11,6 +12,8 @@ use std::sync::mpsc::Receiver;
 use std::time::Duration as StdDur;
 
 const INTERP_TOLERANCE: f64 = 1e-10;
+/// Number of integration steps per segment of a trajectory built from the continuous extension of the steps
+const STEPS_PER_SEGMENT: usize = 32;
 
 /// Stores a segment of an interpolation
 pub struct Segment<S: State>
@@ -20,6 +23,8 @@ where
     start_epoch: Epoch,
     duration: Duration,
     coefficients: Vec<Vec<f64>>,
+    /// Continuous extensions of the integration steps of this segment, used instead of the coefficients if any
+    steps: Vec<StepInterpolant<S>>,
     end_state: S,
 }
 
@@ -59,6 +64,17 @@ where
             )));
         }
 
+        if !self.steps.is_empty() {
+            // Evaluate the continuous extension of the step which contains this epoch
+            let step = self
+                .steps
+                .iter()
+                .rev()
+                .find(|step| step.start.epoch() <= epoch)
+                .unwrap_or(&self.steps[0]);
+            return step.evaluate(epoch);
+        }
+
         let t_prime = normalize(
             dur_into_window.in_seconds(),
             0.0,
@@ -76,11 +92,84 @@ where
 
         Ok(state)
     }
+
+    /// Builds a segment from the continuous extensions of consecutive integration steps
+    fn from_steps(steps: Vec<StepInterpolant<S>>) -> Result<Self, NyxError> {
+        let start_epoch = steps[0].start.epoch();
+        let last_step = &steps[steps.len() - 1];
+        let end_state = last_step.evaluate(last_step.end_epoch())?;
+        Ok(Self {
+            start_epoch,
+            duration: end_state.epoch() - start_epoch,
+            coefficients: Vec::new(),
+            steps,
+            end_state,
+        })
+    }
+}
+
+impl<S: State> Traj<S>
+where
+    DefaultAllocator: Allocator<f64, S::PropVecSize> + Allocator<f64, S::Size>,
+{
+    /// Creates a new trajectory with the provided starting state from the continuous extensions of the integration
+    /// steps, which must be consecutive and forward in time (cf. `PropInstance::for_duration_with_traj`).
+    pub fn from_steps(state: S, steps: Vec<StepInterpolant<S>>) -> Result<Self, NyxError> {
+        let mut me = Self {
+            segments: BTreeMap::new(),
+            start_state: state,
+            timeout_ms: 100,
+            max_offset: 0,
+        };
+
+        let mut window: Vec<StepInterpolant<S>> = Vec::with_capacity(STEPS_PER_SEGMENT);
+        for step in steps {
+            if step.step_s <= 0.0 {
+                return Err(NyxError::InvalidInterpolationData(format!(
+                    "trajectories can only be built from forward steps, got a step of {} s at {}",
+                    step.step_s,
+                    step.start.epoch()
+                )));
+            }
+            // Segments are indexed by the second they start in, so a new segment must start in a later second
+            if window.len() >= STEPS_PER_SEGMENT
+                && me.offset_of(step.start.epoch()) > me.offset_of(window[0].start.epoch())
+            {
+                let this_wdn =
+                    std::mem::replace(&mut window, Vec::with_capacity(STEPS_PER_SEGMENT));
+                me.append_segment(Segment::from_steps(this_wdn)?);
+            }
+            window.push(step);
+        }
+        if window.is_empty() {
+            return Err(NyxError::NoInterpolationData(
+                "no integration step to build the trajectory".to_string(),
+            ));
+        }
+        me.append_segment(Segment::from_steps(window)?);
+
+        Ok(me)
+    }
+
+    /// Returns the number of seconds since the start of this trajectory, rounded down
+    fn offset_of(&self, epoch: Epoch) -> u32 {
+        ((epoch - self.start_state.epoch()).in_seconds().floor()) as u32
+    }
+
+    fn append_segment(&mut self, segment: Segment<S>) {
+        // Compute the number of seconds since start of trajectory
+        let offset_s = self.offset_of(segment.start_epoch);
+        self.segments.insert(offset_s, segment);
+        if offset_s > self.max_offset {
+            self.max_offset = offset_s;
+        }
+    }
 }
 
 impl<S: State + 'static> Traj<S>
 where
     DefaultAllocator: Allocator<f64, S::PropVecSize> + Allocator<f64, S::Size>,
+    <DefaultAllocator as Allocator<f64, S::PropVecSize>>::Buffer: Send,
 {
     /// Creates a new trajectory with the provided starting state (used as a template) and a receiving channel.
     /// The trajectories are always generated on a separate thread.
@@ -93,9 +182,7 @@ where
             max_offset: 0,
         };
 
-        // Bug? With a spacecraft, we need more interpolation windows than just an orbit.
-        // I've spent 12h trying to understand why, but I can't, so screw it for it.
-        let items_per_segments = if S::PropVecSize::dim() == 43 { 16 } else { 32 };
+        let items_per_segments = S::interp_items_per_segment();
 
         let mut children = vec![];
         let mut window_states: Vec<S> = Vec::with_capacity(items_per_segments);
@@ -130,22 +217,16 @@ where
         Ok(me)
     }
 
-    fn append_segment(&mut self, segment: Segment<S>) {
-        // Compute the number of seconds since start of trajectory
-        let offset_s = ((segment.start_epoch - self.start_state.epoch())
-            .in_seconds()
-            .floor()) as u32;
-        self.segments.insert(offset_s, segment);
-        if offset_s > self.max_offset {
-            self.max_offset = offset_s;
-        }
-    }
-
     /// Evaluate the trajectory at this specific epoch.
     pub fn evaluate(&self, epoch: Epoch) -> Result<S, NyxError> {
         let offset_s = ((epoch - self.start_state.epoch()).in_seconds().floor()) as u32;
-        // Retrieve that segment
-        match self.segments.range(..=offset_s).rev().next() {
+        // Retrieve that segment, i.e. the latest one starting before this epoch (several may start in the same second)
+        match self
+            .segments
+            .range(..=offset_s)
+            .rev()
+            .find(|(_, segment)| segment.start_epoch <= epoch)
+        {
             None => {
                 // Let's see if this corresponds to the max offset value
                 let last_item = self.segments[&self.max_offset].end_state;
@@ -349,6 +430,7 @@ where
         start_epoch: start_win_epoch,
         duration: window_duration,
         coefficients,
+        steps: Vec::new(),
         end_state: this_wdn[this_wdn.len() - 1],
     })
 }
@@ -14,8 +14,11 @@ pub use crate::dynamics::{Dynamics, NyxError};
 use crate::io::formatter::*;
 use crate::io::quantity::ParsingError;
 use crate::io::scenario::ConditionSerde;
-use crate::io::scenario::ScenarioSerde;
-use crate::propagators::Propagator;
+use crate::io::scenario::{PropagatorKind, ScenarioSerde};
+use crate::propagators::error_ctrl::RSSStepPV;
+use crate::propagators::{
+    CashKarp45, Dormand45, Dormand78, Fehlberg45, PropOpts, Propagator, RK4Fixed, Verner56, RK89,
+};
 use crate::time::{Duration, Epoch, SECONDS_PER_DAY};
 use crate::SpacecraftState;
 use std::convert::TryFrom;
@@ -34,6 +37,7 @@ where
     pub prop_time: Option<Duration>,
     pub prop_event: Option<ConditionSerde>,
     pub prop_tol: f64,
+    pub prop_kind: PropagatorKind,
     pub name: String,
 }
 
@@ -283,6 +287,7 @@ where
                         prop_time,
                         prop_event,
                         prop_tol,
+                        prop_kind: prop.kind.unwrap_or(PropagatorKind::Rk89),
                         name: prop_name.clone(),
                     },
                     formatter,
@@ -298,6 +303,28 @@ where
     //     p.with(self.init_state)
     // }
 
+    /// Builds the propagator of the kind and with the tolerance of this process
+    pub fn propagator<'b>(&self) -> Propagator<'b, Spacecraft<'a>, RSSStepPV> {
+        let dynamics = self.sc_dyn.clone();
+        let opts = PropOpts::with_tolerance(self.prop_tol);
+        match self.prop_kind {
+            PropagatorKind::Dormand45 => Propagator::new::<Dormand45>(dynamics, opts),
+            PropagatorKind::Dormand78 => Propagator::new::<Dormand78>(dynamics, opts),
+            PropagatorKind::Fehlberg45 => Propagator::new::<Fehlberg45>(dynamics, opts),
+            PropagatorKind::CashKarp45 => Propagator::new::<CashKarp45>(dynamics, opts),
+            PropagatorKind::Rk89 => Propagator::new::<RK89>(dynamics, opts),
+            // The RK4 has no error estimate, so it is used with the default initial step
+            PropagatorKind::Rk4 => {
+                Propagator::new::<RK4Fixed>(dynamics, PropOpts::with_fixed_step_s(60.0))
+            }
+            PropagatorKind::Verner56 => Propagator::new::<Verner56>(dynamics, opts),
+            PropagatorKind::AdamsBashforthMoulton => {
+                Propagator::adams_bashforth_moulton(dynamics, opts)
+            }
+            PropagatorKind::GaussJackson => Propagator::gauss_jackson(dynamics, opts),
+        }
+    }
+
     pub fn execute(mut self) -> Result<(), NyxError> {
         self.execute_with(vec![])
     }
@@ -312,8 +339,7 @@ where
         let maybe_prop_event = self.prop_event.clone();
 
         // Build the propagator
-        let mut prop_setup = Propagator::default(self.sc_dyn.clone());
-        prop_setup.set_tolerance(self.prop_tol);
+        let prop_setup = self.propagator();
         let mut prop = prop_setup.with(self.init_state);
         // let mut prop = self.propagator();
         // Set up the channels
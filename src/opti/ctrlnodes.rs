@@ -7,9 +7,11 @@ use crate::time::Duration;
 use crate::tools::lambert::{standard, TransferKind};
 use std::sync::mpsc::channel;
 
+/// A node of the trajectory, whose control applies until the next node
 #[derive(Copy, Clone, Debug)]
 pub struct Node {
     pub orbit: Orbit,
+    /// Thrust direction in the inertial frame scaled by the throttle, i.e. its norm is between 0 and 1
     pub ctrl: Vector3<f64>,
 }
 
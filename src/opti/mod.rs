@@ -1 +1,10 @@
 pub mod ctrlnodes;
+
+/// Direct multiple shooting optimizer, seeded by the control nodes
+pub mod multishoot;
+
+/// Sims-Flanagan model of low-thrust legs
+pub mod simsflanagan;
+
+/// Sequential quadratic programming updates of the controls, shared by the optimizers
+pub mod sqp;
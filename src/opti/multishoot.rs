@@ -0,0 +1,627 @@
+use super::ctrlnodes::{Heuristic, Node};
+use super::sqp::{apply_ctrl_step, min_ctrl_step};
+use crate::celestia::{GuidanceMode, Orbit, SpacecraftState};
+use crate::dimensions::{DMatrix, DVector, Matrix6, Matrix6x3, Vector3, Vector6, U3};
+use crate::dynamics::spacecraft::Spacecraft;
+use crate::dynamics::thrustctrl::{Mnvr, Steering, ThrustControl};
+use crate::errors::NyxError;
+use crate::propagators::error_ctrl::ErrorCtrl;
+use crate::propagators::{PropOpts, Propagator};
+use crate::time::{Epoch, TimeUnit};
+use crate::TimeTagged;
+use std::fmt;
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+
+/// Perturbation of the control used to compute the partials of the defects by finite differencing
+const PERT_CTRL: f64 = 1e-4;
+
+/// A constant thrust in the inertial frame, where the norm of the vector is the throttle
+struct NodeControl {
+    vector: Vector3<f64>,
+}
+
+impl ThrustControl for NodeControl {
+    fn direction(&self, _state: &SpacecraftState) -> Vector3<f64> {
+        if self.vector.norm() > 0.0 {
+            self.vector / self.vector.norm()
+        } else {
+            Vector3::x()
+        }
+    }
+
+    fn throttle(&self, _state: &SpacecraftState) -> f64 {
+        // Guard against the rounding of normalized controls
+        self.vector.norm().min(1.0)
+    }
+
+    fn next(&self, _state: &SpacecraftState) -> GuidanceMode {
+        if self.vector.norm() > 0.0 {
+            GuidanceMode::Thrust
+        } else {
+            GuidanceMode::Coast
+        }
+    }
+}
+
+/// The converged transfer of the multiple shooting
+#[derive(Clone, Debug)]
+pub struct ShootingSolution {
+    /// Nodes of the transfer, the control of each node being applied until the next node
+    pub nodes: Vec<Node>,
+    /// Fuel mass at each node
+    pub fuel_mass_kg: Vec<f64>,
+    /// Norm of the position (km) and velocity (km/s) defects of each segment
+    pub defects: Vec<(f64, f64)>,
+    /// Total number of iterations, on all of the meshes
+    pub iterations: usize,
+}
+
+impl ShootingSolution {
+    /// Returns the inertial finite burns which fly this transfer, e.g. to replay it with `FiniteBurns::from_mnvrs`
+    pub fn to_mnvrs(&self) -> Vec<Mnvr> {
+        let mut mnvrs = Vec::new();
+        for (node, next) in self.nodes.iter().zip(self.nodes.iter().skip(1)) {
+            let throttle = node.ctrl.norm();
+            if throttle > 0.0 {
+                mnvrs.push(Mnvr {
+                    start: node.orbit.dt,
+                    end: next.orbit.dt,
+                    thrust_lvl: throttle,
+                    vector: node.ctrl / throttle,
+                    steering: Steering::Constant,
+                });
+            }
+        }
+        mnvrs
+    }
+
+    /// Returns the fuel usage of the transfer in kg
+    pub fn fuel_usage_kg(&self) -> f64 {
+        self.fuel_mass_kg[0] - self.fuel_mass_kg[self.fuel_mass_kg.len() - 1]
+    }
+}
+
+impl fmt::Display for ShootingSolution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Multiple shooting converged in {} iterations with {} nodes, using {:.6} kg of fuel",
+            self.iterations,
+            self.nodes.len(),
+            self.fuel_usage_kg()
+        )?;
+        for (node, fuel) in self.nodes.iter().zip(&self.fuel_mass_kg) {
+            writeln!(
+                f,
+                "\t{}\tthrottle = {:.6}\tdirection = [{:.6}, {:.6}, {:.6}]\tfuel = {:.6} kg",
+                node.orbit.dt,
+                node.ctrl.norm(),
+                node.ctrl[0],
+                node.ctrl[1],
+                node.ctrl[2],
+                fuel
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Direct multiple shooting optimizer.
+///
+/// The transfer is split in segments between nodes. The state of each node and, if `thrusting` is set, the control
+/// of each segment are the variables of the problem. Each segment is propagated from its node with a constant
+/// inertial thrust, and the defect between the end of the segment and the next node is constrained to zero. The
+/// problem minimizes the sum of the squared throttles with a sequential quadratic programming (SQP) solve, which
+/// reduces to a Newton solve of the defects for ballistic transfers. The partials of the defects with respect to
+/// the states are the STMs of the segments, and those with respect to the controls are computed by finite
+/// differencing.
+///
+/// Once converged, the mesh is refined by splitting the segments where the control changes by more than the
+/// refinement tolerance, and the problem is solved again.
+pub struct MultipleShooting<'a, E: ErrorCtrl> {
+    /// Dynamics of the spacecraft, whose thrust control is replaced by the control of each segment
+    pub spacecraft: Arc<Spacecraft<'a>>,
+    pub opts: PropOpts<E>,
+    /// Nodes of the transfer: the first and the last ones are fixed, and the control of the last one is unused
+    pub nodes: Vec<Node>,
+    /// Spacecraft at the first node, used for its masses and its thruster
+    pub init_sc: SpacecraftState,
+    /// Whether the controls are variables of the problem, if not the transfer is ballistic
+    pub thrusting: bool,
+    /// Tolerance on the position defects in km
+    pub pos_tol_km: f64,
+    /// Tolerance on the velocity defects in km/s
+    pub vel_tol_km_s: f64,
+    /// Tolerance on the control step of the SQP, i.e. on the optimality of the controls
+    pub ctrl_tol: f64,
+    /// Maximum number of iterations on each mesh
+    pub max_iter: usize,
+    /// A segment is split in two if its control differs from that of the next segment by more than this amount
+    pub refine_tol: f64,
+    /// Maximum number of nodes of the mesh refinement
+    pub max_nodes: usize,
+}
+
+impl<'a, E: ErrorCtrl> MultipleShooting<'a, E> {
+    /// Initializes a thrusting multiple shooting problem from the provided nodes, where the first node must be the
+    /// orbit of the initial spacecraft. Mesh refinement is disabled, set `refine_tol` and `max_nodes` to enable it.
+    pub fn new(
+        spacecraft: Arc<Spacecraft<'a>>,
+        opts: PropOpts<E>,
+        init_sc: SpacecraftState,
+        nodes: Vec<Node>,
+    ) -> Self {
+        let max_nodes = nodes.len();
+        Self {
+            spacecraft,
+            opts,
+            nodes,
+            init_sc,
+            thrusting: true,
+            pos_tol_km: 1e-5,
+            vel_tol_km_s: 1e-8,
+            ctrl_tol: 1e-6,
+            max_iter: 25,
+            refine_tol: 0.1,
+            max_nodes,
+        }
+    }
+
+    /// Initializes a ballistic multiple shooting problem, e.g. for multi-body transfers
+    pub fn ballistic(
+        spacecraft: Arc<Spacecraft<'a>>,
+        opts: PropOpts<E>,
+        init_sc: SpacecraftState,
+        nodes: Vec<Node>,
+    ) -> Self {
+        let mut me = Self::new(spacecraft, opts, init_sc, nodes);
+        me.thrusting = false;
+        for node in &mut me.nodes {
+            node.ctrl = Vector3::zeros();
+        }
+        me
+    }
+
+    /// Initializes a thrusting multiple shooting problem from the nodes of the heuristic between the initial
+    /// spacecraft and the final orbit. The nodes of the heuristic which are not strictly between the start and the
+    /// end are ignored, and their controls are used as the initial guess.
+    pub fn from_heuristic<H: Heuristic>(
+        spacecraft: Arc<Spacecraft<'a>>,
+        opts: PropOpts<E>,
+        init_sc: SpacecraftState,
+        end: Orbit,
+        heuristic: &H,
+    ) -> Result<Self, NyxError> {
+        let seeded = heuristic.nodes(init_sc.orbit, end)?;
+        let first_ctrl = seeded.first().map_or(Vector3::zeros(), |node| node.ctrl);
+        let mut nodes = vec![Node {
+            orbit: init_sc.orbit,
+            ctrl: first_ctrl,
+        }];
+        nodes.extend(
+            seeded
+                .into_iter()
+                .filter(|node| node.orbit.dt > init_sc.epoch() && node.orbit.dt < end.dt),
+        );
+        nodes.push(Node {
+            orbit: end,
+            ctrl: Vector3::zeros(),
+        });
+        Ok(Self::new(spacecraft, opts, init_sc, nodes))
+    }
+
+    /// Solves the problem and refines the mesh until no segment needs to be split or the maximum number of nodes
+    /// is reached. The nodes of the problem are updated with the solution.
+    pub fn solve(&mut self) -> Result<ShootingSolution, NyxError> {
+        if self.nodes.len() < 2 {
+            return Err(NyxError::InvalidTargetingProblem(
+                "multiple shooting requires at least two nodes".to_string(),
+            ));
+        }
+        if self.nodes[0].orbit.dt != self.init_sc.epoch() {
+            return Err(NyxError::InvalidTargetingProblem(format!(
+                "first node at {} but spacecraft at {}",
+                self.nodes[0].orbit.dt,
+                self.init_sc.epoch()
+            )));
+        }
+        for (node, next) in self.nodes.iter().zip(self.nodes.iter().skip(1)) {
+            if next.orbit.dt <= node.orbit.dt {
+                return Err(NyxError::InvalidTargetingProblem(format!(
+                    "nodes must be in chronological order ({} is not after {})",
+                    next.orbit.dt, node.orbit.dt
+                )));
+            }
+        }
+
+        let mut iterations = self.solve_mesh()?;
+        while self.refine()? {
+            info!("mesh refined to {} nodes", self.nodes.len());
+            iterations += self.solve_mesh()?;
+        }
+
+        let (ends, _, _) = self.segments(false)?;
+        let mut fuel_mass_kg = vec![self.init_sc.fuel_mass_kg];
+        let mut defects = Vec::new();
+        for (i, end) in ends.iter().enumerate() {
+            fuel_mass_kg.push(end.fuel_mass_kg);
+            let defect = self.defect(end, i);
+            defects.push((
+                defect.fixed_rows::<U3>(0).norm(),
+                defect.fixed_rows::<U3>(3).norm(),
+            ));
+        }
+
+        Ok(ShootingSolution {
+            nodes: self.nodes.clone(),
+            fuel_mass_kg,
+            defects,
+            iterations,
+        })
+    }
+
+    /// Solves the problem on the current mesh and returns the number of iterations
+    fn solve_mesh(&mut self) -> Result<usize, NyxError> {
+        let num_segs = self.nodes.len() - 1;
+        // The states of the first and of the last nodes are fixed
+        let num_states = 6 * (num_segs - 1);
+        let num_vars = if self.thrusting {
+            num_states + 3 * num_segs
+        } else {
+            num_states
+        };
+        let num_cstr = 6 * num_segs;
+
+        let mut ctrl_step = std::f64::INFINITY;
+        for it in 0..=self.max_iter {
+            let (ends, stms, ctrl_partials) = self.segments(self.thrusting)?;
+
+            let mut defects = DVector::zeros(num_cstr);
+            let mut converged = true;
+            for (i, end) in ends.iter().enumerate() {
+                let defect = self.defect(end, i);
+                if defect.fixed_rows::<U3>(0).norm() > self.pos_tol_km
+                    || defect.fixed_rows::<U3>(3).norm() > self.vel_tol_km_s
+                {
+                    converged = false;
+                }
+                defects.rows_mut(6 * i, 6).copy_from(&defect);
+            }
+            debug!(
+                "multiple shooting #{}: defects = {:e}, control step = {:e}",
+                it,
+                defects.norm(),
+                ctrl_step
+            );
+            if converged && (!self.thrusting || ctrl_step < self.ctrl_tol) {
+                return Ok(it);
+            }
+            if it == self.max_iter {
+                break;
+            }
+
+            // Jacobian of the defects
+            let mut jac = DMatrix::zeros(num_cstr, num_vars);
+            for (i, stm) in stms.iter().enumerate() {
+                if i > 0 {
+                    jac.slice_mut((6 * i, 6 * (i - 1)), (6, 6)).copy_from(stm);
+                }
+                if i < num_segs - 1 {
+                    jac.slice_mut((6 * i, 6 * i), (6, 6))
+                        .copy_from(&(-Matrix6::identity()));
+                }
+            }
+            for (i, partials) in ctrl_partials.iter().enumerate() {
+                jac.slice_mut((6 * i, num_states + 3 * i), (6, 3))
+                    .copy_from(partials);
+            }
+
+            let step = if self.thrusting {
+                let ctrls: Vec<Vector3<f64>> = self
+                    .nodes
+                    .iter()
+                    .take(num_segs)
+                    .map(|node| node.ctrl)
+                    .collect();
+                min_ctrl_step(&jac, &defects, &ctrls, num_states)?
+            } else {
+                match jac.pseudo_inverse(1e-12) {
+                    Ok(pinv) => -pinv * &defects,
+                    Err(e) => return Err(NyxError::InvalidTargetingProblem(e.to_string())),
+                }
+            };
+
+            // Update the variables
+            for (i, node) in self.nodes.iter_mut().enumerate().take(num_segs).skip(1) {
+                let orbit = &mut node.orbit;
+                let offset = 6 * (i - 1);
+                orbit.x += step[offset];
+                orbit.y += step[offset + 1];
+                orbit.z += step[offset + 2];
+                orbit.vx += step[offset + 3];
+                orbit.vy += step[offset + 4];
+                orbit.vz += step[offset + 5];
+            }
+            if self.thrusting {
+                ctrl_step = apply_ctrl_step(
+                    self.nodes
+                        .iter_mut()
+                        .take(num_segs)
+                        .map(|node| &mut node.ctrl),
+                    &step,
+                    num_states,
+                );
+            }
+        }
+        Err(NyxError::MaxIterReached(self.max_iter))
+    }
+
+    /// Splits the segments whose control differs from that of the next segment by more than the refinement
+    /// tolerance, and returns whether the mesh was refined. The new node is the middle of the propagated segment.
+    fn refine(&mut self) -> Result<bool, NyxError> {
+        if !self.thrusting {
+            return Ok(false);
+        }
+        let num_segs = self.nodes.len() - 1;
+        let mut refined = Vec::with_capacity(self.max_nodes);
+        let mut fuel_mass_kg = self.init_sc.fuel_mass_kg;
+        for i in 0..num_segs {
+            let node = self.nodes[i];
+            let end_epoch = self.nodes[i + 1].orbit.dt;
+            refined.push(node);
+            let split = i < num_segs - 1
+                && (self.nodes[i + 1].ctrl - node.ctrl).norm() > self.refine_tol
+                && self.nodes.len() + refined.len() - i <= self.max_nodes;
+            if split {
+                let mid_epoch = node.orbit.dt
+                    + 0.5 * (end_epoch - node.orbit.dt).in_seconds() * TimeUnit::Second;
+                let (mid, _) = self.propagate(&node, fuel_mass_kg, mid_epoch, node.ctrl, false)?;
+                refined.push(Node {
+                    orbit: mid.orbit,
+                    ctrl: node.ctrl,
+                });
+            }
+            let (end, _) = self.propagate(&node, fuel_mass_kg, end_epoch, node.ctrl, false)?;
+            fuel_mass_kg = end.fuel_mass_kg;
+        }
+        refined.push(self.nodes[num_segs]);
+        let was_refined = refined.len() > self.nodes.len();
+        self.nodes = refined;
+        Ok(was_refined)
+    }
+
+    /// Propagates each segment from its node, with the fuel mass at the end of the previous segment. Returns the
+    /// end of each segment, its STM and, if requested, the partials of the end state with respect to the control.
+    #[allow(clippy::type_complexity)]
+    fn segments(
+        &self,
+        with_ctrl_partials: bool,
+    ) -> Result<(Vec<SpacecraftState>, Vec<Matrix6<f64>>, Vec<Matrix6x3<f64>>), NyxError> {
+        let mut ends = Vec::with_capacity(self.nodes.len() - 1);
+        let mut stms = Vec::with_capacity(self.nodes.len() - 1);
+        let mut ctrl_partials = Vec::with_capacity(self.nodes.len() - 1);
+        let mut fuel_mass_kg = self.init_sc.fuel_mass_kg;
+        for i in 0..self.nodes.len() - 1 {
+            let node = self.nodes[i];
+            let end_epoch = self.nodes[i + 1].orbit.dt;
+            let (end, stm) = self.propagate(&node, fuel_mass_kg, end_epoch, node.ctrl, true)?;
+            if with_ctrl_partials {
+                let nominal = end.orbit.to_cartesian_vec();
+                let mut partials = Matrix6x3::zeros();
+                for k in 0..3 {
+                    // Perturb towards the inside of the unit ball so that the throttle remains valid
+                    let mut pert = node.ctrl;
+                    pert[k] += PERT_CTRL;
+                    let delta = if pert.norm() > 1.0 {
+                        pert[k] -= 2.0 * PERT_CTRL;
+                        -PERT_CTRL
+                    } else {
+                        PERT_CTRL
+                    };
+                    let (pert_end, _) =
+                        self.propagate(&node, fuel_mass_kg, end_epoch, pert, false)?;
+                    let column = (pert_end.orbit.to_cartesian_vec() - nominal) / delta;
+                    partials.set_column(k, &column);
+                }
+                ctrl_partials.push(partials);
+            }
+            fuel_mass_kg = end.fuel_mass_kg;
+            ends.push(end);
+            stms.push(stm);
+        }
+        Ok((ends, stms, ctrl_partials))
+    }
+
+    /// Propagates the node until the provided epoch with a constant control, and returns the final state and, if
+    /// requested, the STM of the orbit (identity otherwise)
+    fn propagate(
+        &self,
+        node: &Node,
+        fuel_mass_kg: f64,
+        until: Epoch,
+        ctrl: Vector3<f64>,
+        with_stm: bool,
+    ) -> Result<(SpacecraftState, Matrix6<f64>), NyxError> {
+        let mut sc_dyn = (*self.spacecraft).clone();
+        sc_dyn.ctrl = if self.thrusting {
+            Some(Arc::new(NodeControl { vector: ctrl }) as Arc<dyn ThrustControl + 'a>)
+        } else {
+            None
+        };
+        let prop = Propagator::rk89(Arc::new(sc_dyn), self.opts);
+
+        let mut init = self.init_sc;
+        init.orbit = node.orbit;
+        init.orbit.stm = None;
+        init.fuel_mass_kg = fuel_mass_kg;
+        init.mode = if self.thrusting && ctrl.norm() > 0.0 {
+            GuidanceMode::Thrust
+        } else {
+            GuidanceMode::Coast
+        };
+        if with_stm {
+            init.orbit.enable_stm();
+        }
+
+        let (tx, rx) = channel();
+        let mut end = prop
+            .with(init)
+            .with_tx(tx)
+            .for_duration(until - init.epoch())?;
+        // The STM of each state is that of its step, so the STM of the segment is their product
+        let mut stm = Matrix6::identity();
+        for step in rx.try_iter() {
+            if let Some(step_stm) = step.orbit.stm {
+                stm = step_stm * stm;
+            }
+        }
+        end.orbit.stm = None;
+        Ok((end, stm))
+    }
+
+    /// Returns the defect between the end of the i-th segment and the next node
+    fn defect(&self, end: &SpacecraftState, i: usize) -> Vector6<f64> {
+        end.orbit.to_cartesian_vec() - self.nodes[i + 1].orbit.to_cartesian_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::celestia::Cosm;
+    use crate::celestia::Frame;
+    use crate::dynamics::orbital::OrbitalDynamics;
+    use crate::dynamics::thrustctrl::{FiniteBurns, Thruster};
+    use crate::propagators::error_ctrl::RSSStepPV;
+
+    fn opts() -> PropOpts<RSSStepPV> {
+        PropOpts::with_adaptive_step_s(1.0, 60.0, 1e-12, RSSStepPV {})
+    }
+
+    /// Returns the states of a ballistic propagation of the orbit every step until the end epoch (included)
+    fn ballistic_nodes(orbit: Orbit, step_min: i64, end: Epoch) -> Vec<Node> {
+        let prop = Propagator::rk89(Spacecraft::new(OrbitalDynamics::two_body()), opts());
+        let mut nodes = vec![Node {
+            orbit,
+            ctrl: Vector3::zeros(),
+        }];
+        let mut state = SpacecraftState::new(orbit, 500.0, 0.0);
+        while state.epoch() < end {
+            state = prop
+                .with(state)
+                .for_duration(step_min * TimeUnit::Minute)
+                .unwrap();
+            nodes.push(Node {
+                orbit: state.orbit,
+                ctrl: Vector3::zeros(),
+            });
+        }
+        nodes
+    }
+
+    #[test]
+    fn multishoot_ballistic() {
+        let cosm = Cosm::de438();
+        let eme2k = cosm.frame("EME2000");
+        let start_time = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+        let start = Orbit::keplerian(7000.0, 0.01, 28.5, 10.0, 20.0, 30.0, start_time, eme2k);
+        let truth = ballistic_nodes(start, 20, start_time + 1 * TimeUnit::Hour);
+        assert_eq!(truth.len(), 4);
+
+        // Offset the interior nodes
+        let mut nodes = truth.clone();
+        for node in nodes.iter_mut().skip(1).take(2) {
+            node.orbit.x += 10.0;
+            node.orbit.vy -= 1e-3;
+        }
+
+        let init_sc = SpacecraftState::new(start, 500.0, 0.0);
+        let mut ms = MultipleShooting::ballistic(
+            Spacecraft::new(OrbitalDynamics::two_body()),
+            opts(),
+            init_sc,
+            nodes,
+        );
+        let sol = ms.solve().unwrap();
+        println!("{}", sol);
+
+        for (pos, vel) in &sol.defects {
+            assert!(*pos < 1e-5 && *vel < 1e-8);
+        }
+        for (node, exp) in sol.nodes.iter().zip(&truth) {
+            assert!((node.orbit.radius() - exp.orbit.radius()).norm() < 1e-3);
+            assert!((node.orbit.velocity() - exp.orbit.velocity()).norm() < 1e-6);
+        }
+        assert!(sol.to_mnvrs().is_empty());
+    }
+
+    #[test]
+    fn multishoot_low_thrust() {
+        let cosm = Cosm::de438();
+        let eme2k = cosm.frame("EME2000");
+        let start_time = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+        let end_time = start_time + 2 * TimeUnit::Hour;
+        let start = Orbit::keplerian(7000.0, 0.01, 28.5, 10.0, 20.0, 30.0, start_time, eme2k);
+        let thruster = Thruster {
+            thrust: 0.5,
+            isp: 2000.0,
+        };
+        let init_sc =
+            SpacecraftState::with_thruster(start, 450.0, 50.0, thruster, GuidanceMode::Thrust);
+
+        // The target is reached with a constant half throttle along the initial velocity
+        let truth_ctrl = 0.5 * start.velocity() / start.velocity().norm();
+        let truth_dyn = Spacecraft::with_ctrl(
+            OrbitalDynamics::two_body(),
+            Arc::new(NodeControl { vector: truth_ctrl }),
+        );
+        let target = Propagator::rk89(truth_dyn, opts())
+            .with(init_sc)
+            .for_duration(end_time - start_time)
+            .unwrap();
+
+        // Seed the nodes with the ballistic trajectory
+        let mut nodes = ballistic_nodes(start, 30, end_time);
+        assert_eq!(nodes.len(), 5);
+        nodes[4].orbit = target.orbit;
+
+        let mut ms = MultipleShooting::new(
+            Spacecraft::new(OrbitalDynamics::two_body()),
+            opts(),
+            init_sc,
+            nodes,
+        );
+        ms.refine_tol = 1e-3;
+        ms.max_nodes = 7;
+        let sol = ms.solve().unwrap();
+        println!("{}", sol);
+
+        assert!(sol.nodes.len() >= 5 && sol.nodes.len() <= 7);
+        for (pos, vel) in &sol.defects {
+            assert!(*pos < 1e-5 && *vel < 1e-8);
+        }
+        for node in &sol.nodes {
+            assert!(node.ctrl.norm() <= 1.0);
+        }
+        // The constant control is feasible, so the optimal transfer uses at most as much fuel
+        assert!(sol.fuel_usage_kg() <= init_sc.fuel_mass_kg - target.fuel_mass_kg + 1e-6);
+
+        // Replay the solution as finite burns, stopping at each node
+        let replay_dyn = Spacecraft::with_ctrl(
+            OrbitalDynamics::two_body(),
+            FiniteBurns::from_mnvrs(sol.to_mnvrs(), Frame::Inertial),
+        );
+        let prop = Propagator::rk89(replay_dyn, opts());
+        let mut state = init_sc;
+        state.mode = GuidanceMode::Custom(0);
+        for node in sol.nodes.iter().skip(1) {
+            state = prop
+                .with(state)
+                .for_duration(node.orbit.dt - state.epoch())
+                .unwrap();
+        }
+        assert!((state.orbit.radius() - target.orbit.radius()).norm() < 1e-2);
+        assert!((state.fuel_mass_kg - sol.fuel_mass_kg[sol.fuel_mass_kg.len() - 1]).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,403 @@
+use super::ctrlnodes::{Heuristic, Node};
+use super::sqp::{apply_ctrl_step, min_ctrl_step};
+use crate::celestia::{GuidanceMode, Orbit, SpacecraftState};
+use crate::dimensions::{DMatrix, DVector, Vector3, Vector6, U3};
+use crate::dynamics::spacecraft::rocket_fuel_usage;
+use crate::dynamics::thrustctrl::Thruster;
+use crate::errors::NyxError;
+use crate::time::{Duration, Epoch, TimeUnit};
+use crate::TimeTagged;
+
+/// Perturbation of the controls used to compute the partials of the mismatch by finite differencing
+const PERT_CTRL: f64 = 1e-6;
+/// Perturbation of the arrival fuel mass used to compute the partials of the mismatch, in kg
+const PERT_MASS_KG: f64 = 1e-3;
+
+/// Sims-Flanagan model of a low-thrust leg.
+///
+/// The leg is split in segments of equal duration. The thrust of each segment is modeled as an impulse at the middle
+/// of the segment, and the spacecraft follows Keplerian arcs between the impulses. The impulse of each segment is
+/// bounded by the deltaV of the thruster at full throttle during the whole segment. The first half of the segments
+/// is propagated forward from the departure, and the second half is propagated backward from the arrival: the
+/// state and the mass mismatch at this match point must be zero for the leg to be feasible.
+///
+/// Reference: Sims, J. A., and Flanagan, S. N., "Preliminary Design of Low-Thrust Interplanetary Missions", 1999.
+#[derive(Clone, Debug)]
+pub struct SimsFlanagan {
+    /// Departure spacecraft, used for its orbit, masses and thruster
+    pub departure: SpacecraftState,
+    /// Arrival orbit, in the frame of the departure
+    pub arrival: Orbit,
+    /// Control of each segment: the inertial direction of the impulse scaled by the throttle
+    pub ctrls: Vec<Vector3<f64>>,
+    /// Fuel mass at arrival, from which the backward segments are propagated
+    pub arrival_fuel_mass_kg: f64,
+    /// Tolerance on the position mismatch in km
+    pub pos_tol_km: f64,
+    /// Tolerance on the velocity mismatch in km/s
+    pub vel_tol_km_s: f64,
+    /// Tolerance on the mass mismatch in kg
+    pub mass_tol_kg: f64,
+    /// Tolerance on the largest change of a control component between two iterations
+    pub ctrl_tol: f64,
+    pub max_iter: usize,
+}
+
+impl SimsFlanagan {
+    /// Initializes a ballistic leg with this number of segments between the departure spacecraft and the arrival
+    /// orbit. The departure spacecraft must have a thruster, either mounted or as its single thruster.
+    pub fn new(
+        departure: SpacecraftState,
+        arrival: Orbit,
+        num_segments: usize,
+    ) -> Result<Self, NyxError> {
+        if departure.thrusters[0].is_none() && departure.thruster.is_none() {
+            return Err(NyxError::CtrlExistsButNoThrusterAvail);
+        }
+        if num_segments == 0 {
+            return Err(NyxError::InvalidTargetingProblem(
+                "Sims-Flanagan leg requires at least one segment".to_string(),
+            ));
+        }
+        if arrival.dt <= departure.epoch() {
+            return Err(NyxError::InvalidTargetingProblem(format!(
+                "arrival at {} is not after departure at {}",
+                arrival.dt,
+                departure.epoch()
+            )));
+        }
+        Ok(Self {
+            departure,
+            arrival,
+            ctrls: vec![Vector3::zeros(); num_segments],
+            arrival_fuel_mass_kg: departure.fuel_mass_kg,
+            pos_tol_km: 1e-3,
+            vel_tol_km_s: 1e-8,
+            mass_tol_kg: 1e-6,
+            ctrl_tol: 1e-6,
+            max_iter: 50,
+        })
+    }
+
+    /// Returns the number of segments
+    pub fn num_segments(&self) -> usize {
+        self.ctrls.len()
+    }
+
+    /// Returns the number of segments propagated forward, the others are propagated backward
+    pub fn num_forward(&self) -> usize {
+        (self.ctrls.len() + 1) / 2
+    }
+
+    /// Returns the duration of each segment
+    pub fn segment_duration(&self) -> Duration {
+        self.segment_duration_s() * TimeUnit::Second
+    }
+
+    fn segment_duration_s(&self) -> f64 {
+        (self.arrival.dt - self.departure.epoch()).in_seconds() / (self.ctrls.len() as f64)
+    }
+
+    /// Returns the epoch after this number of segments, which may be fractional
+    fn epoch_at(&self, num_segments: f64) -> Epoch {
+        self.departure.epoch() + num_segments * self.segment_duration_s() * TimeUnit::Second
+    }
+
+    /// Returns the magnitude of the impulse at full throttle for the provided mass, in km/s
+    fn max_dv_km_s(&self, mass_kg: f64) -> f64 {
+        let thruster = self.thruster();
+        thruster.thrust * 1e-3 / mass_kg * self.segment_duration_s()
+    }
+
+    /// Returns the first mounted thruster of the departure spacecraft, or its single thruster
+    fn thruster(&self) -> Thruster {
+        match self.departure.thrusters[0] {
+            Some(mounted) => mounted.thruster,
+            None => self.departure.thruster.unwrap(),
+        }
+    }
+
+    /// Propagates this number of segments forward from the departure. Returns the final orbit, the final fuel mass
+    /// and the node at the start of each segment.
+    fn forward(&self, num_segments: usize) -> Result<(Orbit, f64, Vec<Node>), NyxError> {
+        let mut orbit = self.departure.orbit;
+        orbit.stm = None;
+        let mut fuel_mass_kg = self.departure.fuel_mass_kg;
+        let mut nodes = Vec::with_capacity(num_segments);
+        for (i, ctrl) in self.ctrls.iter().enumerate().take(num_segments) {
+            nodes.push(Node { orbit, ctrl: *ctrl });
+            orbit = orbit.at_epoch(self.epoch_at(i as f64 + 0.5))?;
+            let mass_kg = self.departure.dry_mass_kg + fuel_mass_kg;
+            let dv = ctrl * self.max_dv_km_s(mass_kg);
+            orbit.vx += dv[0];
+            orbit.vy += dv[1];
+            orbit.vz += dv[2];
+            fuel_mass_kg -= rocket_fuel_usage(mass_kg, dv.norm(), self.thruster().isp);
+            orbit = orbit.at_epoch(self.epoch_at(i as f64 + 1.0))?;
+        }
+        Ok((orbit, fuel_mass_kg, nodes))
+    }
+
+    /// Propagates the last segments backward from the arrival, down to the match point. Returns the orbit and the
+    /// fuel mass at the match point and the node at the start of each segment. The impulses of the backward segments
+    /// are bounded using the mass after the impulse.
+    fn backward(&self) -> Result<(Orbit, f64, Vec<Node>), NyxError> {
+        let mut orbit = self.arrival;
+        orbit.stm = None;
+        let mut fuel_mass_kg = self.arrival_fuel_mass_kg;
+        let mut nodes = Vec::with_capacity(self.ctrls.len() - self.num_forward());
+        for (i, ctrl) in self.ctrls.iter().enumerate().skip(self.num_forward()).rev() {
+            orbit = orbit.at_epoch(self.epoch_at(i as f64 + 0.5))?;
+            let mass_kg = self.departure.dry_mass_kg + fuel_mass_kg;
+            let dv = ctrl * self.max_dv_km_s(mass_kg);
+            orbit.vx -= dv[0];
+            orbit.vy -= dv[1];
+            orbit.vz -= dv[2];
+            // Mass before the impulse, from the fraction of the mass left after it
+            let remaining = 1.0 - rocket_fuel_usage(1.0, dv.norm(), self.thruster().isp);
+            fuel_mass_kg = mass_kg / remaining - self.departure.dry_mass_kg;
+            orbit = orbit.at_epoch(self.epoch_at(i as f64))?;
+            nodes.push(Node { orbit, ctrl: *ctrl });
+        }
+        nodes.reverse();
+        Ok((orbit, fuel_mass_kg, nodes))
+    }
+
+    /// Returns the state mismatch (forward minus backward) and the fuel mass mismatch at the match point
+    pub fn mismatch(&self) -> Result<(Vector6<f64>, f64), NyxError> {
+        let (fwd_orbit, fwd_fuel, _) = self.forward(self.num_forward())?;
+        let (bwd_orbit, bwd_fuel, _) = self.backward()?;
+        Ok((
+            fwd_orbit.to_cartesian_vec() - bwd_orbit.to_cartesian_vec(),
+            fwd_fuel - bwd_fuel,
+        ))
+    }
+
+    /// Returns whether the mismatch is within the tolerances
+    pub fn is_feasible(&self) -> Result<bool, NyxError> {
+        let (state, fuel) = self.mismatch()?;
+        Ok(state.fixed_rows::<U3>(0).norm() <= self.pos_tol_km
+            && state.fixed_rows::<U3>(3).norm() <= self.vel_tol_km_s
+            && fuel.abs() <= self.mass_tol_kg)
+    }
+
+    /// Returns the fuel usage of the leg in kg
+    pub fn fuel_usage_kg(&self) -> f64 {
+        self.departure.fuel_mass_kg - self.arrival_fuel_mass_kg
+    }
+
+    /// Returns the node at the start of each segment, from the forward propagation for the first half of the leg and
+    /// from the backward propagation for the second half. The control of each node is that of its segment, which
+    /// is also a constant thrust control for `MultipleShooting`.
+    pub fn nodes(&self) -> Result<Vec<Node>, NyxError> {
+        let (_, _, mut nodes) = self.forward(self.num_forward())?;
+        let (_, _, bwd_nodes) = self.backward()?;
+        nodes.extend(bwd_nodes);
+        Ok(nodes)
+    }
+
+    fn mismatch_vec(&self) -> Result<DVector<f64>, NyxError> {
+        let (state, fuel) = self.mismatch()?;
+        Ok(DVector::from_iterator(
+            7,
+            state.iter().cloned().chain(std::iter::once(fuel)),
+        ))
+    }
+
+    /// Returns a copy of this leg where the k-th variable (the controls, then the arrival fuel mass) is perturbed
+    fn perturbed(&self, k: usize, delta: f64) -> Self {
+        let mut me = self.clone();
+        if k < 3 * self.ctrls.len() {
+            me.ctrls[k / 3][k % 3] += delta;
+        } else {
+            me.arrival_fuel_mass_kg += delta;
+        }
+        me
+    }
+
+    /// Solves for the controls of minimum norm which make the leg feasible, with a sequential quadratic programming
+    /// solve where the partials are computed by central differences. The throttles are kept between 0 and 1, and
+    /// the controls are updated in place. Returns the number of iterations.
+    pub fn solve(&mut self) -> Result<usize, NyxError> {
+        let num_ctrls = 3 * self.ctrls.len();
+        let num_vars = num_ctrls + 1;
+        let mut ctrl_step = std::f64::INFINITY;
+        for it in 0..=self.max_iter {
+            let mismatch = self.mismatch_vec()?;
+            debug!(
+                "Sims-Flanagan #{}: mismatch = {:e}, control step = {:e}",
+                it,
+                mismatch.norm(),
+                ctrl_step
+            );
+            if self.is_feasible()? && ctrl_step < self.ctrl_tol {
+                if self.arrival_fuel_mass_kg < 0.0 {
+                    return Err(NyxError::FuelExhausted);
+                }
+                return Ok(it);
+            }
+            if it == self.max_iter {
+                break;
+            }
+
+            let mut jac = DMatrix::zeros(7, num_vars);
+            for k in 0..num_vars {
+                let delta = if k < num_ctrls {
+                    PERT_CTRL
+                } else {
+                    PERT_MASS_KG
+                };
+                let column = (self.perturbed(k, delta).mismatch_vec()?
+                    - self.perturbed(k, -delta).mismatch_vec()?)
+                    / (2.0 * delta);
+                jac.set_column(k, &column);
+            }
+
+            let step = min_ctrl_step(&jac, &mismatch, &self.ctrls, 0)?;
+            ctrl_step = apply_ctrl_step(self.ctrls.iter_mut(), &step, 0);
+            self.arrival_fuel_mass_kg += step[num_ctrls];
+        }
+        Err(NyxError::MaxIterReached(self.max_iter))
+    }
+}
+
+/// Seeds the nodes of a low-thrust transfer with a Sims-Flanagan leg, e.g. to initialize `MultipleShooting`.
+pub struct SimsFlanaganHeuristic {
+    pub dry_mass_kg: f64,
+    pub fuel_mass_kg: f64,
+    pub thruster: Thruster,
+    pub num_segments: usize,
+}
+
+impl Heuristic for SimsFlanaganHeuristic {
+    fn nodes(&self, start: Orbit, end: Orbit) -> Result<Vec<Node>, NyxError> {
+        let departure = SpacecraftState::with_thruster(
+            start,
+            self.dry_mass_kg,
+            self.fuel_mass_kg,
+            self.thruster,
+            GuidanceMode::Thrust,
+        );
+        let mut leg = SimsFlanagan::new(departure, end, self.num_segments)?;
+        leg.solve()?;
+        leg.nodes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::celestia::Cosm;
+    use crate::dynamics::orbital::OrbitalDynamics;
+    use crate::dynamics::propulsion::{MountedThruster, Tank};
+    use crate::dynamics::spacecraft::Spacecraft;
+    use crate::opti::multishoot::MultipleShooting;
+    use crate::propagators::error_ctrl::RSSStepPV;
+    use crate::propagators::PropOpts;
+
+    /// Returns a leg from a circular orbit at 1 AU and the arrival of a constant control on 10 segments
+    fn heliocentric_leg() -> (SimsFlanagan, Orbit, f64) {
+        let cosm = Cosm::de438();
+        let sun2k = cosm.frame("Sun J2000");
+        let start_time = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+        let au = 149_597_870.7;
+        let vel = (sun2k.gm() / au).sqrt();
+        let start = Orbit::cartesian(au, 0.0, 0.0, 0.0, vel, 0.0, start_time, sun2k);
+        let departure = SpacecraftState::with_thruster(
+            start,
+            1000.0,
+            300.0,
+            Thruster {
+                thrust: 0.3,
+                isp: 3000.0,
+            },
+            GuidanceMode::Thrust,
+        );
+        let arrival_time = start_time + 200 * TimeUnit::Day;
+
+        let mut truth =
+            SimsFlanagan::new(departure, start.at_epoch(arrival_time).unwrap(), 10).unwrap();
+        for ctrl in &mut truth.ctrls {
+            *ctrl = Vector3::new(0.0, 0.5, 0.1);
+        }
+        let (arrival, arrival_fuel_mass_kg, _) = truth.forward(10).unwrap();
+        let leg = SimsFlanagan::new(departure, arrival, 10).unwrap();
+        (leg, arrival, arrival_fuel_mass_kg)
+    }
+
+    #[test]
+    fn sims_flanagan_leg() {
+        let (mut leg, arrival, truth_fuel) = heliocentric_leg();
+        assert_eq!(leg.num_forward(), 5);
+        assert!((leg.segment_duration().in_seconds() - 20.0 * 86_400.0).abs() < 1e-6);
+        assert!(!leg.is_feasible().unwrap());
+
+        let iterations = leg.solve().unwrap();
+        println!(
+            "converged in {} iterations using {} kg",
+            iterations,
+            leg.fuel_usage_kg()
+        );
+        assert!(leg.is_feasible().unwrap());
+        // The constant control is feasible, so the solution of minimum norm uses less fuel
+        assert!(leg.arrival_fuel_mass_kg > truth_fuel);
+        for ctrl in &leg.ctrls {
+            assert!(ctrl.norm() <= 1.0);
+        }
+
+        let nodes = leg.nodes().unwrap();
+        assert_eq!(nodes.len(), 10);
+        assert_eq!(nodes[0].orbit.dt, leg.departure.epoch());
+        let last_seg_s = (arrival.dt - nodes[9].orbit.dt).in_seconds();
+        assert!((last_seg_s - leg.segment_duration().in_seconds()).abs() < 1e-3);
+    }
+
+    #[test]
+    fn sims_flanagan_thrusters() {
+        let (leg, arrival, _) = heliocentric_leg();
+        let mut ballistic = leg.departure;
+        ballistic.thruster = None;
+        assert!(SimsFlanagan::new(ballistic, arrival, 10).is_err());
+
+        // A mounted thruster is used instead of the single thruster
+        let mounted = SpacecraftState::with_propulsion(
+            leg.departure.orbit,
+            leg.departure.dry_mass_kg,
+            &[Tank::regulated(leg.departure.fuel_mass_kg)],
+            &[MountedThruster::new(leg.thruster(), Vector3::x(), 0)],
+            GuidanceMode::Thrust,
+        );
+        let mounted_leg = SimsFlanagan::new(mounted, arrival, 10).unwrap();
+        assert_eq!(mounted_leg.thruster().thrust, leg.thruster().thrust);
+        assert_eq!(mounted_leg.thruster().isp, leg.thruster().isp);
+    }
+
+    #[test]
+    fn sims_flanagan_multishoot() {
+        let (leg, arrival, _) = heliocentric_leg();
+        let heuristic = SimsFlanaganHeuristic {
+            dry_mass_kg: leg.departure.dry_mass_kg,
+            fuel_mass_kg: leg.departure.fuel_mass_kg,
+            thruster: leg.thruster(),
+            num_segments: 10,
+        };
+
+        // Refine the Sims-Flanagan leg with continuous thrust
+        let mut ms = MultipleShooting::from_heuristic(
+            Spacecraft::new(OrbitalDynamics::two_body()),
+            PropOpts::with_adaptive_step_s(1.0, 86_400.0, 1e-12, RSSStepPV {}),
+            leg.departure,
+            arrival,
+            &heuristic,
+        )
+        .unwrap();
+        assert_eq!(ms.nodes.len(), 11);
+        ms.pos_tol_km = 1e-3;
+        ms.vel_tol_km_s = 1e-8;
+        let sol = ms.solve().unwrap();
+        println!("{}", sol);
+        for (pos, vel) in &sol.defects {
+            assert!(*pos < 1e-3 && *vel < 1e-8);
+        }
+    }
+}
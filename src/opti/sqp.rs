@@ -0,0 +1,52 @@
+use crate::dimensions::{DMatrix, DVector, Vector3, U3};
+use crate::errors::NyxError;
+
+/// Returns the step of the variables which minimizes half the sum of the squared controls, subject to the linearized
+/// constraints `jac * step = -defects`. The controls are the variables starting at `ctrl_offset`, three per control.
+pub fn min_ctrl_step(
+    jac: &DMatrix<f64>,
+    defects: &DVector<f64>,
+    ctrls: &[Vector3<f64>],
+    ctrl_offset: usize,
+) -> Result<DVector<f64>, NyxError> {
+    let (num_cstr, num_vars) = jac.shape();
+    // KKT system of the minimization of half the sum of the squared controls
+    let size = num_vars + num_cstr;
+    let mut kkt = DMatrix::zeros(size, size);
+    let mut rhs = DVector::zeros(size);
+    for (i, ctrl) in ctrls.iter().enumerate() {
+        let offset = ctrl_offset + 3 * i;
+        kkt.slice_mut((offset, offset), (3, 3)).fill_with_identity();
+        rhs.rows_mut(offset, 3).copy_from(&(-ctrl));
+    }
+    kkt.slice_mut((num_vars, 0), (num_cstr, num_vars))
+        .copy_from(jac);
+    kkt.slice_mut((0, num_vars), (num_vars, num_cstr))
+        .copy_from(&jac.transpose());
+    rhs.rows_mut(num_vars, num_cstr).copy_from(&(-defects));
+    match kkt.lu().solve(&rhs) {
+        Some(sol) => Ok(sol.rows(0, num_vars).into_owned()),
+        None => Err(NyxError::InvalidTargetingProblem(
+            "singular KKT matrix".to_string(),
+        )),
+    }
+}
+
+/// Applies the step of the controls, which are the variables starting at `ctrl_offset`, and returns the largest change
+/// of a control component, used as the convergence criterion of the SQP.
+pub fn apply_ctrl_step<'a, I>(ctrls: I, step: &DVector<f64>, ctrl_offset: usize) -> f64
+where
+    I: Iterator<Item = &'a mut Vector3<f64>>,
+{
+    let mut ctrl_step = 0.0_f64;
+    for (i, ctrl) in ctrls.enumerate() {
+        let prev = *ctrl;
+        *ctrl += step.fixed_rows::<U3>(ctrl_offset + 3 * i);
+        // The throttle cannot exceed one
+        if ctrl.norm() > 1.0 {
+            *ctrl /= ctrl.norm();
+        }
+        ctrl_step = ctrl_step.max((*ctrl - prev).amax());
+    }
+    ctrl_step
+}
@@ -0,0 +1,123 @@
+use crate::dimensions::allocator::Allocator;
+use crate::dimensions::{DefaultAllocator, VectorN};
+use crate::errors::NyxError;
+use crate::time::{Epoch, TimeUnit};
+use crate::State;
+
+/// A node of a Hermite interpolation: its time (or normalized time), the propagated vector and its time derivative
+pub type HermiteNode<T, N> = (T, VectorN<f64, N>, VectorN<f64, N>);
+
+/// The continuous extension (dense output) of a single integration step.
+///
+/// The propagated vector is a polynomial of the normalized time θ ∈ [0, 1] of the step, i.e.
+/// y(t_0 + θh) = Σ_p c_p θ^p. It is built either from the stages of a Runge Kutta which provides a continuous
+/// extension (cf. `RK::dense_coeffs`), or by Hermite interpolation on the states and derivatives at the bounds of
+/// the step (and of the previous steps when available).
+#[derive(Clone, Debug)]
+pub struct StepInterpolant<S: State>
+where
+    DefaultAllocator: Allocator<f64, S::Size> + Allocator<f64, S::PropVecSize>,
+{
+    /// State at the start of the step, used as the template of the interpolated states
+    pub start: S,
+    /// Step size in seconds (negative when propagating backward)
+    pub step_s: f64,
+    /// Coefficients of the polynomial, in increasing powers of θ
+    pub coefficients: Vec<VectorN<f64, S::PropVecSize>>,
+}
+
+impl<S: State> StepInterpolant<S>
+where
+    DefaultAllocator: Allocator<f64, S::Size> + Allocator<f64, S::PropVecSize>,
+{
+    /// Builds the continuous extension of a Runge Kutta step from its stages `k` and the dense output coefficients
+    /// of the method (the coefficients of each b_i(θ) in increasing powers of θ, starting with θ).
+    pub fn from_stages(
+        start: S,
+        step_s: f64,
+        k: &[VectorN<f64, S::PropVecSize>],
+        dense_coeffs: &[f64],
+    ) -> Result<Self, NyxError> {
+        let degree = dense_coeffs.len() / k.len();
+        let mut coefficients = Vec::with_capacity(degree + 1);
+        coefficients.push(start.as_vector()?);
+        for p in 0..degree {
+            let mut c_p = VectorN::<f64, S::PropVecSize>::zeros();
+            for (i, ki) in k.iter().enumerate() {
+                c_p += step_s * dense_coeffs[i * degree + p] * ki;
+            }
+            coefficients.push(c_p);
+        }
+        Ok(Self {
+            start,
+            step_s,
+            coefficients,
+        })
+    }
+
+    /// Builds the Hermite interpolant matching the propagated vector and its time derivative at each of the provided
+    /// nodes, given as (θ, vector, derivative). The nodes must be distinct.
+    pub fn hermite(start: S, step_s: f64, nodes: &[HermiteNode<f64, S::PropVecSize>]) -> Self {
+        // Newton divided differences on the doubled nodes, where the derivatives are with respect to θ
+        let mut z = Vec::with_capacity(2 * nodes.len());
+        let mut column = Vec::with_capacity(2 * nodes.len());
+        for (theta, vector, _) in nodes {
+            z.push(*theta);
+            z.push(*theta);
+            column.push(vector.clone());
+            column.push(vector.clone());
+        }
+        let n = z.len();
+        let mut newton = vec![column[0].clone()];
+        for k in 1..n {
+            let mut next = Vec::with_capacity(n - k);
+            for i in 0..n - k {
+                if k == 1 && i % 2 == 0 {
+                    next.push(step_s * &nodes[i / 2].2);
+                } else {
+                    next.push((&column[i + 1] - &column[i]) / (z[i + k] - z[i]));
+                }
+            }
+            column = next;
+            newton.push(column[0].clone());
+        }
+        // Expand the Newton form into the monomial coefficients
+        let mut coefficients = vec![newton[n - 1].clone()];
+        for k in (0..n - 1).rev() {
+            let mut next = vec![VectorN::<f64, S::PropVecSize>::zeros(); coefficients.len() + 1];
+            for (j, c_j) in coefficients.iter().enumerate() {
+                next[j + 1] += c_j;
+                next[j] -= z[k] * c_j;
+            }
+            next[0] += &newton[k];
+            coefficients = next;
+        }
+        Self {
+            start,
+            step_s,
+            coefficients,
+        }
+    }
+
+    /// Returns the epoch at the end of this step
+    pub fn end_epoch(&self) -> Epoch {
+        self.start.epoch() + self.step_s * TimeUnit::Second
+    }
+
+    /// Returns the interpolated vector at the provided normalized time of the step
+    pub fn vector_at(&self, theta: f64) -> VectorN<f64, S::PropVecSize> {
+        let mut vector = self.coefficients[self.coefficients.len() - 1].clone();
+        for c_p in self.coefficients.iter().rev().skip(1) {
+            vector = vector * theta + c_p;
+        }
+        vector
+    }
+
+    /// Evaluates the interpolated state at the provided epoch, which should be within this step.
+    pub fn evaluate(&self, epoch: Epoch) -> Result<S, NyxError> {
+        let theta = (epoch - self.start.epoch()).in_seconds() / self.step_s;
+        let mut state = self.start;
+        state.set(epoch, &self.vector_at(theta))?;
+        Ok(state)
+    }
+}
@@ -1,6 +1,17 @@
 use super::RK;
 
+// Coefficients d_i of the continuous extension of Dormand45, from Hairer, Nørsett & Wanner, "Solving Ordinary
+// Differential Equations I", section II.6 (dense output of DOPRI5).
+const D45_D1: f64 = -12_715_105_075.0 / 11_282_082_432.0;
+const D45_D3: f64 = 87_487_479_700.0 / 32_700_410_799.0;
+const D45_D4: f64 = -10_690_763_975.0 / 1_880_347_072.0;
+const D45_D5: f64 = 701_980_252_875.0 / 199_316_789_632.0;
+const D45_D6: f64 = -1_453_857_185.0 / 822_651_844.0;
+const D45_D7: f64 = 69_997_945.0 / 29_380_423.0;
+
 /// `Dormand45` is a [Dormand-Prince integrator](https://en.wikipedia.org/wiki/Dormand%E2%80%93Prince_method).
+///
+/// It provides a fourth order continuous extension, used to locate events and to build trajectories.
 pub struct Dormand45 {}
 
 impl RK for Dormand45 {
@@ -53,6 +64,39 @@ impl RK for Dormand45 {
             1.0 / 40.0,
         ]
     }
+    fn dense_coeffs() -> &'static [f64] {
+        // b_i(θ) = θ b_i + θ(1-θ)(δ_i1 - b_i) + θ²(1-θ)(2b_i - δ_i1 - δ_i7) + θ²(1-θ)² d_i
+        &[
+            1.0,
+            3.0 * 35.0 / 384.0 - 2.0 + D45_D1,
+            1.0 - 2.0 * 35.0 / 384.0 - 2.0 * D45_D1,
+            D45_D1,
+            0.0,
+            0.0,
+            0.0,
+            0.0,
+            0.0,
+            3.0 * 500.0 / 1_113.0 + D45_D3,
+            -2.0 * 500.0 / 1_113.0 - 2.0 * D45_D3,
+            D45_D3,
+            0.0,
+            3.0 * 125.0 / 192.0 + D45_D4,
+            -2.0 * 125.0 / 192.0 - 2.0 * D45_D4,
+            D45_D4,
+            0.0,
+            -3.0 * 2_187.0 / 6_784.0 + D45_D5,
+            2.0 * 2_187.0 / 6_784.0 - 2.0 * D45_D5,
+            D45_D5,
+            0.0,
+            3.0 * 11.0 / 84.0 + D45_D6,
+            -2.0 * 11.0 / 84.0 - 2.0 * D45_D6,
+            D45_D6,
+            0.0,
+            D45_D7 - 1.0,
+            1.0 - 2.0 * D45_D7,
+            D45_D7,
+        ]
+    }
 }
 
 /// `Dormand78` is a [Dormand-Prince integrator](https://en.wikipedia.org/wiki/Dormand%E2%80%93Prince_method).
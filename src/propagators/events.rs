@@ -1,7 +1,7 @@
 use crate::celestia::{Cosm, Frame, Orbit};
 use crate::time::{Duration, Epoch, TimeUnit};
 use crate::utils::between_pm_180;
-use crate::SpacecraftState;
+use crate::{FormationState, SpacecraftState};
 use std::fmt;
 
 /// A general Event
@@ -235,6 +235,38 @@ impl<'a> Event for SCEvent<'a> {
     }
 }
 
+/// An event between two members of a formation, identified by their index in the `FormationState`.
+#[derive(Debug)]
+pub struct FormationEvent {
+    pub chief: usize,
+    pub deputy: usize,
+    /// Separation distance threshold in kilometers
+    pub distance: f64,
+}
+
+impl FormationEvent {
+    /// Triggers when the distance between the chief and the deputy crosses the provided threshold (in km).
+    pub fn separation(chief: usize, deputy: usize, distance: f64) -> Box<Self> {
+        Box::new(Self {
+            chief,
+            deputy,
+            distance,
+        })
+    }
+}
+
+impl Event for FormationEvent {
+    type StateType = FormationState;
+
+    fn eval(&self, state: &Self::StateType) -> f64 {
+        state.separation(self.chief, self.deputy) - self.distance
+    }
+
+    fn eval_crossing(&self, prev_state: &Self::StateType, next_state: &Self::StateType) -> bool {
+        self.eval(prev_state) * self.eval(next_state) <= 0.0
+    }
+}
+
 /// A condition to stop a propagator.
 /// Note: min_step of propagator options will guide how precise the solution can be!
 #[derive(Debug)]
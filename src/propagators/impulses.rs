@@ -0,0 +1,66 @@
+use super::events::Event;
+use crate::dynamics::deltavctrl::{Impulse, InstantBurns};
+use crate::time::{Duration, Epoch, TimeUnit};
+use std::fmt;
+
+/// Defines when an impulsive maneuver is applied during a propagation.
+pub enum ImpulseTrigger<S: Copy> {
+    /// Apply the impulse at exactly this epoch
+    Epoch(Epoch),
+    /// Apply the impulse when this event is triggered, located to within `epsilon`
+    Event {
+        event: Box<dyn Event<StateType = S>>,
+        epsilon: Duration,
+    },
+}
+
+impl<S: Copy> fmt::Debug for ImpulseTrigger<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ImpulseTrigger::Epoch(epoch) => write!(f, "at {}", epoch),
+            ImpulseTrigger::Event { event, epsilon } => {
+                write!(f, "at {:?} (within {})", event, epsilon)
+            }
+        }
+    }
+}
+
+/// An impulsive maneuver applied by a `PropInstance` when its trigger happens.
+///
+/// The scheduled impulses are applied in order: an impulse triggered by an event will be applied at the first
+/// occurrence of that event after the previous impulse.
+#[derive(Debug)]
+pub struct ScheduledImpulse<S: Copy> {
+    pub trigger: ImpulseTrigger<S>,
+    pub impulse: Impulse,
+}
+
+impl<S: Copy> ScheduledImpulse<S> {
+    /// Schedules this impulse at the provided epoch
+    pub fn at_epoch(epoch: Epoch, impulse: Impulse) -> Self {
+        Self {
+            trigger: ImpulseTrigger::Epoch(epoch),
+            impulse,
+        }
+    }
+
+    /// Schedules this impulse at the next occurrence of the provided event, located to within one millisecond
+    pub fn at_event(event: Box<dyn Event<StateType = S>>, impulse: Impulse) -> Self {
+        Self {
+            trigger: ImpulseTrigger::Event {
+                event,
+                epsilon: 1 * TimeUnit::Millisecond,
+            },
+            impulse,
+        }
+    }
+
+    /// Schedules all of the maneuvers of the instantaneous burns at their start epoch
+    pub fn from_instant_burns(burns: &InstantBurns) -> Vec<Self> {
+        burns
+            .impulses()
+            .into_iter()
+            .map(|(epoch, impulse)| Self::at_epoch(epoch, impulse))
+            .collect()
+    }
+}
@@ -6,6 +6,29 @@ pub use self::error_ctrl::*;
 
 pub mod events;
 
+/// Provides impulsive maneuvers which are applied during a propagation.
+pub mod impulses;
+
+/// Semi-analytic propagation of the mean equinoctial elements.
+pub mod semianalytic;
+
+/// Provides the Adams-Bashforth-Moulton and Gauss-Jackson multistep integration methods.
+pub mod multistep;
+pub use self::multistep::Multistep;
+
+/// Provides the continuous extension of the integration steps, used to locate events and to build trajectories.
+pub mod dense;
+pub use self::dense::StepInterpolant;
+
+/// Provides the fixed step symplectic integrators (Yoshida and Wisdom-Holman) of conservative orbital dynamics.
+pub mod symplectic;
+
+/// Provides the implicit Radau IIA integration step, used to propagate stiff dynamics.
+pub mod radau;
+
+/// Provides the propagation of the second order state transition tensors of orbital dynamics.
+pub mod stt;
+
 // Re-Export
 mod rk;
 pub use self::rk::*;
@@ -40,6 +63,15 @@ where
     /// Returns a pointer to a list of f64 corresponding to the b_i and b^*_i coefficients of the
     /// Butcher table for that RK. `Self.a_coeffs().len()` must be of size (order+1)*2.
     fn b_coeffs() -> &'static [f64];
+
+    /// Returns a pointer to a list of f64 corresponding to the coefficients of the continuous extension of this
+    /// integrator, if any: for each stage i, the coefficients of b_i(θ) in increasing powers of θ starting with θ,
+    /// such that y(t + θh) = y(t) + h \sum_i b_i(θ) k_i. `Self.dense_coeffs().len()` must be a multiple of the
+    /// number of stages. By default there is none (e.g. `RK89`), and the steps are interpolated with Hermite
+    /// polynomials.
+    fn dense_coeffs() -> &'static [f64] {
+        &[]
+    }
 }
 
 /// Stores the details of the previous integration step of a given propagator. Access as `my_prop.clone().latest_details()`.
@@ -0,0 +1,367 @@
+use crate::dimensions::allocator::Allocator;
+use crate::dimensions::{DefaultAllocator, DimName, VectorN};
+use crate::time::Epoch;
+use std::collections::VecDeque;
+
+/// Order of the multistep methods, i.e. the number of derivatives used in each step
+pub const MULTISTEP_ORDER: usize = 8;
+
+/// The multistep integration methods. These are started with the Runge Kutta of the propagator, and each step
+/// then only requires two evaluations of the dynamics (predict, evaluate, correct, evaluate).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Multistep {
+    /// Adams-Bashforth predictor and Adams-Moulton corrector on all of the components of the state
+    AdamsBashforthMoulton,
+    /// Gauss-Jackson (summed Störmer-Cowell) predictor-corrector on the second order components of the state, i.e.
+    /// the positions whose second derivative is the acceleration, and Adams-Bashforth-Moulton on the other components
+    GaussJackson,
+}
+
+/// The coefficients of a multistep method, computed by integration of the Lagrange polynomials interpolating the
+/// derivatives on equally spaced nodes (in units of the step, the most recent node is zero).
+#[derive(Clone, Debug)]
+pub struct MultistepCoeffs {
+    pub method: Multistep,
+    pub order: usize,
+    /// Nodes of the predictor: 0, -1, ..., 1 - order
+    nodes: Vec<f64>,
+    /// Adams-Bashforth weights of f_n, f_{n-1}, ...
+    adams_pred: Vec<f64>,
+    /// Adams-Moulton weights of f_{n+1}, f_n, ...
+    adams_corr: Vec<f64>,
+    /// Störmer weights of the second difference of the position
+    stormer_pred: Vec<f64>,
+    /// Cowell weights of the second difference of the position
+    cowell_corr: Vec<f64>,
+    /// Weights of the first difference of the position at -1, used to initialize the summed form
+    start_weights: Vec<f64>,
+    /// Ratio of the local error of the corrector to the difference between the corrector and the predictor (Milne)
+    adams_milne: f64,
+    cowell_milne: f64,
+}
+
+impl MultistepCoeffs {
+    pub fn new(method: Multistep, order: usize) -> Self {
+        let nodes: Vec<f64> = (0..order).map(|i| -(i as f64)).collect();
+        let corr_nodes: Vec<f64> = (0..order).map(|i| 1.0 - i as f64).collect();
+
+        let (adams_pred, stormer_fwd) = quadrature(&nodes, 1.0);
+        let (_, stormer_bwd) = quadrature(&nodes, -1.0);
+        let (adams_corr, cowell_fwd) = quadrature(&corr_nodes, 1.0);
+        let (_, cowell_bwd) = quadrature(&corr_nodes, -1.0);
+
+        // The local errors are proportional to the integrals of the polynomials which vanish on the nodes
+        let (adams_p, stormer_p) = second_difference(&node_polynomial(&nodes));
+        let (adams_c, cowell_c) = second_difference(&node_polynomial(&corr_nodes));
+
+        Self {
+            method,
+            order,
+            adams_pred,
+            adams_corr,
+            stormer_pred: sum(&stormer_fwd, &stormer_bwd),
+            cowell_corr: sum(&cowell_fwd, &cowell_bwd),
+            start_weights: stormer_bwd,
+            adams_milne: (adams_c / (adams_p - adams_c)).abs(),
+            cowell_milne: (cowell_c / (stormer_p - cowell_c)).abs(),
+            nodes,
+        }
+    }
+
+    /// Returns the weights of the derivatives to compute the first and second integrals from the most recent node to
+    /// the provided fraction of the step (between `1 - order` and zero for an interpolation).
+    pub fn interpolation(&self, s: f64) -> (Vec<f64>, Vec<f64>) {
+        quadrature(&self.nodes, s)
+    }
+}
+
+/// The history of the derivatives of a multistep integration, on equally spaced epochs
+#[derive(Clone, Debug)]
+pub struct MultistepHistory<N: DimName>
+where
+    DefaultAllocator: Allocator<f64, N>,
+{
+    /// Step between the derivatives in seconds, negative when propagating backward
+    pub step_s: f64,
+    /// Epoch of the most recent derivative
+    pub epoch: Epoch,
+    /// State vector at the most recent epoch
+    pub head: VectorN<f64, N>,
+    /// Derivatives, the most recent first
+    pub derivs: VecDeque<VectorN<f64, N>>,
+    /// First difference of the second order components over one step (summed form of Gauss-Jackson)
+    pub delta: VectorN<f64, N>,
+}
+
+impl<N: DimName> MultistepHistory<N>
+where
+    DefaultAllocator: Allocator<f64, N>,
+{
+    pub fn new(epoch: Epoch, step_s: f64, head: VectorN<f64, N>, deriv: VectorN<f64, N>) -> Self {
+        let mut derivs = VecDeque::with_capacity(2 * MULTISTEP_ORDER);
+        derivs.push_front(deriv);
+        Self {
+            step_s,
+            epoch,
+            head,
+            derivs,
+            delta: VectorN::<f64, N>::zeros(),
+        }
+    }
+
+    /// Returns whether there are enough derivatives to take a multistep step
+    pub fn is_complete(&self, coeffs: &MultistepCoeffs) -> bool {
+        self.derivs.len() >= coeffs.order
+    }
+
+    /// Adds a new point at the head of the history, and keeps the points needed to double the step
+    pub fn push(
+        &mut self,
+        epoch: Epoch,
+        head: VectorN<f64, N>,
+        deriv: VectorN<f64, N>,
+        coeffs: &MultistepCoeffs,
+    ) {
+        self.epoch = epoch;
+        self.head = head;
+        self.derivs.push_front(deriv);
+        self.derivs.truncate(2 * coeffs.order - 1);
+    }
+
+    /// Weighted sum of the derivatives, starting with `first` if provided (the corrector), for the provided components
+    fn weighted(&self, weights: &[f64], first: Option<&VectorN<f64, N>>, idx: usize) -> f64 {
+        let mut total = 0.0;
+        let mut derivs = first.into_iter().chain(self.derivs.iter());
+        for w in weights {
+            total += w * derivs.next().unwrap()[idx];
+        }
+        total
+    }
+
+    /// Predicts the state vector one step after the provided state vector of the head
+    pub fn predict(
+        &self,
+        y: &VectorN<f64, N>,
+        pairs: &[(usize, usize)],
+        coeffs: &MultistepCoeffs,
+    ) -> (VectorN<f64, N>, VectorN<f64, N>) {
+        self.integrate(y, None, pairs, &coeffs.adams_pred, &coeffs.stormer_pred)
+    }
+
+    /// Corrects the state vector one step after the provided state vector of the head, with the predicted derivative
+    pub fn correct(
+        &self,
+        y: &VectorN<f64, N>,
+        deriv: &VectorN<f64, N>,
+        pairs: &[(usize, usize)],
+        coeffs: &MultistepCoeffs,
+    ) -> (VectorN<f64, N>, VectorN<f64, N>) {
+        self.integrate(
+            y,
+            Some(deriv),
+            pairs,
+            &coeffs.adams_corr,
+            &coeffs.cowell_corr,
+        )
+    }
+
+    /// Returns the new state vector and the new first differences
+    fn integrate(
+        &self,
+        y: &VectorN<f64, N>,
+        first: Option<&VectorN<f64, N>>,
+        pairs: &[(usize, usize)],
+        adams: &[f64],
+        stormer: &[f64],
+    ) -> (VectorN<f64, N>, VectorN<f64, N>) {
+        let h = self.step_s;
+        let mut next = y.clone();
+        for i in 0..N::dim() {
+            next[i] += h * self.weighted(adams, first, i);
+        }
+        let mut delta = self.delta.clone();
+        for (pos, vel) in pairs {
+            delta[*pos] += h.powi(2) * self.weighted(stormer, first, *vel);
+            next[*pos] = y[*pos] + delta[*pos];
+        }
+        (next, delta)
+    }
+
+    /// Returns the estimate of the local error of the corrected state vector (Milne's device)
+    pub fn error_estimate(
+        &self,
+        predicted: &VectorN<f64, N>,
+        corrected: &VectorN<f64, N>,
+        pairs: &[(usize, usize)],
+        coeffs: &MultistepCoeffs,
+    ) -> VectorN<f64, N> {
+        let mut error_est = coeffs.adams_milne * (corrected - predicted);
+        for (pos, _) in pairs {
+            error_est[*pos] = coeffs.cowell_milne * (corrected[*pos] - predicted[*pos]);
+        }
+        error_est
+    }
+
+    /// Interpolates the state vector at the provided fraction of the step from the head (extrapolates if positive)
+    pub fn interpolate(
+        &self,
+        y: &VectorN<f64, N>,
+        s: f64,
+        pairs: &[(usize, usize)],
+        coeffs: &MultistepCoeffs,
+    ) -> VectorN<f64, N> {
+        let h = self.step_s;
+        let (first, second) = coeffs.interpolation(s);
+        let mut interp = y.clone();
+        for i in 0..N::dim() {
+            interp[i] += h * self.weighted(&first, None, i);
+        }
+        for (pos, vel) in pairs {
+            interp[*pos] =
+                y[*pos] + s * h * y[*vel] + h.powi(2) * self.weighted(&second, None, *vel);
+        }
+        interp
+    }
+
+    /// Initializes the first differences of the second order components from their derivatives
+    pub fn reset_delta(
+        &mut self,
+        y: &VectorN<f64, N>,
+        pairs: &[(usize, usize)],
+        coeffs: &MultistepCoeffs,
+    ) {
+        let h = self.step_s;
+        for (pos, vel) in pairs {
+            self.delta[*pos] =
+                h * y[*vel] - h.powi(2) * self.weighted(&coeffs.start_weights, None, *vel);
+        }
+    }
+
+    /// Halves the step by interpolation of the derivatives
+    pub fn halve(
+        &mut self,
+        y: &VectorN<f64, N>,
+        pairs: &[(usize, usize)],
+        coeffs: &MultistepCoeffs,
+    ) {
+        let mut derivs = VecDeque::with_capacity(2 * coeffs.order);
+        for j in 0..coeffs.order {
+            let values = lagrange_values(&coeffs.nodes, -0.5 * j as f64);
+            let mut deriv = VectorN::<f64, N>::zeros();
+            for (w, f) in values.iter().zip(self.derivs.iter()) {
+                deriv += *w * f;
+            }
+            derivs.push_back(deriv);
+        }
+        self.derivs = derivs;
+        self.step_s *= 0.5;
+        self.reset_delta(y, pairs, coeffs);
+    }
+
+    /// Doubles the step if the history is long enough, and returns whether it was doubled
+    pub fn double(
+        &mut self,
+        y: &VectorN<f64, N>,
+        pairs: &[(usize, usize)],
+        coeffs: &MultistepCoeffs,
+    ) -> bool {
+        if self.derivs.len() < 2 * coeffs.order - 1 {
+            return false;
+        }
+        self.derivs = self.derivs.iter().step_by(2).cloned().collect();
+        self.step_s *= 2.0;
+        self.reset_delta(y, pairs, coeffs);
+        true
+    }
+}
+
+/// Returns the coefficients, in increasing powers, of the Lagrange polynomial which is one on the i-th node
+fn lagrange_polynomial(nodes: &[f64], i: usize) -> Vec<f64> {
+    let mut poly = vec![1.0];
+    for (j, node) in nodes.iter().enumerate() {
+        if j != i {
+            poly = multiply(&poly, -node / (nodes[i] - node), 1.0 / (nodes[i] - node));
+        }
+    }
+    poly
+}
+
+/// Returns the coefficients, in increasing powers, of the polynomial which vanishes on all of the nodes
+fn node_polynomial(nodes: &[f64]) -> Vec<f64> {
+    nodes
+        .iter()
+        .fold(vec![1.0], |poly, node| multiply(&poly, -node, 1.0))
+}
+
+/// Multiplies the polynomial by (c0 + c1 x)
+fn multiply(poly: &[f64], c0: f64, c1: f64) -> Vec<f64> {
+    let mut product = vec![0.0; poly.len() + 1];
+    for (k, c) in poly.iter().enumerate() {
+        product[k] += c * c0;
+        product[k + 1] += c * c1;
+    }
+    product
+}
+
+/// Returns the integrals from zero to s of the polynomial, and of (s - x) times the polynomial
+fn integrals(poly: &[f64], s: f64) -> (f64, f64) {
+    let mut first = 0.0;
+    let mut second = 0.0;
+    for (k, c) in poly.iter().enumerate() {
+        let k = k as i32;
+        first += c * s.powi(k + 1) / f64::from(k + 1);
+        second += c * s.powi(k + 2) / f64::from((k + 1) * (k + 2));
+    }
+    (first, second)
+}
+
+/// Returns the integral over one step, and the second difference over plus and minus one step (Störmer)
+fn second_difference(poly: &[f64]) -> (f64, f64) {
+    let (first, fwd) = integrals(poly, 1.0);
+    let (_, bwd) = integrals(poly, -1.0);
+    (first, fwd + bwd)
+}
+
+/// Returns the quadrature weights of the values on the nodes for the first and second integrals from zero to s
+fn quadrature(nodes: &[f64], s: f64) -> (Vec<f64>, Vec<f64>) {
+    (0..nodes.len())
+        .map(|i| integrals(&lagrange_polynomial(nodes, i), s))
+        .unzip()
+}
+
+/// Returns the values of the Lagrange polynomials of the nodes at x
+fn lagrange_values(nodes: &[f64], x: f64) -> Vec<f64> {
+    (0..nodes.len())
+        .map(|i| {
+            lagrange_polynomial(nodes, i)
+                .iter()
+                .rev()
+                .fold(0.0, |acc, c| acc * x + c)
+        })
+        .collect()
+}
+
+fn sum(a: &[f64], b: &[f64]) -> Vec<f64> {
+    a.iter().zip(b.iter()).map(|(x, y)| x + y).collect()
+}
+
+#[test]
+fn test_multistep_coefficients() {
+    let coeffs = MultistepCoeffs::new(Multistep::AdamsBashforthMoulton, 4);
+    // Classical fourth order Adams-Bashforth and Adams-Moulton
+    let adams_pred = [55.0 / 24.0, -59.0 / 24.0, 37.0 / 24.0, -9.0 / 24.0];
+    let adams_corr = [9.0 / 24.0, 19.0 / 24.0, -5.0 / 24.0, 1.0 / 24.0];
+    for i in 0..4 {
+        assert!((coeffs.adams_pred[i] - adams_pred[i]).abs() < 1e-14);
+        assert!((coeffs.adams_corr[i] - adams_corr[i]).abs() < 1e-14);
+    }
+    // Störmer: 1 + 1/12 ∇² + 1/12 ∇³ and Cowell: 1 - ∇ + 1/12 ∇² in ordinate form
+    let stormer = [7.0 / 6.0, -5.0 / 12.0, 1.0 / 3.0, -1.0 / 12.0];
+    let cowell = [1.0 / 12.0, 5.0 / 6.0, 1.0 / 12.0, 0.0];
+    for i in 0..4 {
+        assert!((coeffs.stormer_pred[i] - stormer[i]).abs() < 1e-14);
+        assert!((coeffs.cowell_corr[i] - cowell[i]).abs() < 1e-14);
+    }
+    // Milne's device: the Adams-Moulton error is 19/270 of the difference with Adams-Bashforth
+    assert!((coeffs.adams_milne - 19.0 / 270.0).abs() < 1e-14);
+}
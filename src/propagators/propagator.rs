@@ -1,17 +1,32 @@
+extern crate hyperdual;
+
+use self::hyperdual::{Hyperdual, Owned};
+use super::dense::{HermiteNode, StepInterpolant};
 use super::error_ctrl::{ErrorCtrl, RSSStepPV};
 use super::events::{EventTrackers, StopCondition};
+use super::impulses::{ImpulseTrigger, ScheduledImpulse};
+use super::multistep::{Multistep, MultistepCoeffs, MultistepHistory, MULTISTEP_ORDER};
+use super::radau::{radau_step, NEWTON_KAPPA, NEWTON_MAX_ITER, RADAU_ERR_ORDER};
 use super::{IntegrationDetails, RK, RK89};
 use crate::dimensions::allocator::Allocator;
-use crate::dimensions::{DefaultAllocator, VectorN};
+use crate::dimensions::{DMatrix, DefaultAllocator, DimName, VectorN};
 use crate::dynamics::Dynamics;
 use crate::errors::NyxError;
-use crate::time::{Duration, TimeUnit};
+use crate::md::trajectory::Traj;
+use crate::time::{Duration, Epoch, TimeUnit};
 use crate::{State, TimeTagged};
 use std::f64;
 use std::f64::EPSILON;
 use std::sync::mpsc::Sender;
 use std::sync::Arc;
 
+/// Maximum number of bisections to locate the event triggering an impulse
+const MAX_BISECTIONS: usize = 100;
+/// Maximum number of Hermite nodes to interpolate the steps of the integrators without a continuous extension
+const DENSE_NODES: usize = 4;
+/// Maximum span of the Hermite nodes of the previous steps, in number of steps of the interpolated step
+const DENSE_SPAN: f64 = 3.0;
+
 /// A Propagator allows propagating a set of dynamics forward or backward in time.
 /// It is an EventTracker, without any event tracking. It includes the options, the integrator
 /// details of the previous step, and the set of coefficients used for the monomorphic instance.
@@ -27,6 +42,10 @@ where
     stages: usize,         // Number of stages, i.e. how many times the derivatives will be called
     a_coeffs: &'a [f64],
     b_coeffs: &'a [f64],
+    dense_coeffs: &'a [f64], // Coefficients of the continuous extension, if any
+    multistep: Option<MultistepCoeffs>, // Multistep method, started with the RK coefficients
+    // Jacobian of the dynamics with respect to the state, if this is the implicit Radau IIA method
+    implicit: Option<StateJacobian<D>>,
 }
 
 /// The `Propagator` trait defines the functions of a propagator and of an event tracker.
@@ -44,6 +63,9 @@ where
             order: T::order(),
             a_coeffs: T::a_coeffs(),
             b_coeffs: T::b_coeffs(),
+            dense_coeffs: T::dense_coeffs(),
+            multistep: None,
+            implicit: None,
         }
     }
 
@@ -62,6 +84,27 @@ where
         Self::new::<RK89>(dynamics, opts)
     }
 
+    /// A multistep propagator of the provided method with custom propagator options, started with an RK89.
+    ///
+    /// With an adaptive step, the step is halved or doubled to keep the error estimate within the tolerance. Note
+    /// that the integrator restarts after each impulse and each change of the state in `Dynamics::finally`, and
+    /// after stopping between two steps if the state has an STM: these methods are best suited to long propagations.
+    pub fn multistep(dynamics: Arc<D>, method: Multistep, opts: PropOpts<E>) -> Self {
+        let mut me = Self::rk89(dynamics, opts);
+        me.multistep = Some(MultistepCoeffs::new(method, MULTISTEP_ORDER));
+        me
+    }
+
+    /// An eighth order Adams-Bashforth-Moulton propagator with custom propagator options.
+    pub fn adams_bashforth_moulton(dynamics: Arc<D>, opts: PropOpts<E>) -> Self {
+        Self::multistep(dynamics, Multistep::AdamsBashforthMoulton, opts)
+    }
+
+    /// An eighth order Gauss-Jackson propagator with custom propagator options.
+    pub fn gauss_jackson(dynamics: Arc<D>, opts: PropOpts<E>) -> Self {
+        Self::multistep(dynamics, Multistep::GaussJackson, opts)
+    }
+
     pub fn with(&'a self, state: D::StateType) -> PropInstance<'a, D, E> {
         // let init_time = state.epoch();
         // let init_state_vec = dynamics.state_vector();
@@ -75,7 +118,8 @@ where
             prop: Arc::new(self),
             tx_chan: None,
             event_trackers: EventTrackers::none(),
-            prevent_tx: false,
+            impulses: Vec::new(),
+            next_impulse: 0,
             details: IntegrationDetails {
                 step: self.opts.init_step,
                 error: 0.0,
@@ -85,7 +129,82 @@ where
             fixed_step: self.opts.fixed_step,
             // init_time,
             k,
+            history: None,
+            ms_state: None,
+            dense: false,
+            steps: Vec::new(),
+            dense_nodes: Vec::new(),
+            stop_trigger: None,
+        }
+    }
+}
+
+impl<'a, D: Dynamics, E: ErrorCtrl> Propagator<'a, D, E>
+where
+    DefaultAllocator: Allocator<f64, <D::StateType as State>::Size>
+        + Allocator<f64, <D::StateType as State>::PropVecSize>
+        + Allocator<f64, <D::StateType as State>::Size, <D::StateType as State>::Size>
+        + Allocator<f64, D::HyperdualSize>
+        + Allocator<Hyperdual<f64, D::HyperdualSize>, <D::StateType as State>::Size>,
+    Owned<f64, D::HyperdualSize>: Copy,
+{
+    /// An implicit Radau IIA propagator (order 5) with custom propagator options, suited to stiff dynamics such as
+    /// reentries through steep density gradients, where the explicit methods are limited to tiny steps.
+    ///
+    /// The collocation equations are solved with a simplified Newton iteration, whose Jacobian is computed once per
+    /// step with `Dynamics::eom_grad`, or by finite differences if the dynamics do not define their partials. The
+    /// adaptive step size uses the embedded error estimate of the method (order 4), and the step is halved when
+    /// the Newton iteration does not converge.
+    pub fn radau(dynamics: Arc<D>, opts: PropOpts<E>) -> Self {
+        let mut me = Self::rk89(dynamics, opts);
+        me.implicit = Some(state_jacobian::<D>);
+        me
+    }
+}
+
+/// Computes the Jacobian of the dynamics with respect to the state from the propagated vector
+type StateJacobian<D> = fn(
+    Arc<D>,
+    VectorN<f64, <<D as Dynamics>::StateType as State>::PropVecSize>,
+    <D as Dynamics>::StateType,
+) -> Result<DMatrix<f64>, NyxError>;
+
+/// Returns the Jacobian of the dynamics with respect to the state (i.e. without the STM) of the provided vector.
+/// If the dynamics do not define their partials, the Jacobian is computed by finite differences of the EOMs.
+fn state_jacobian<D: Dynamics>(
+    dynamics: Arc<D>,
+    state_vec: VectorN<f64, <D::StateType as State>::PropVecSize>,
+    ctx: D::StateType,
+) -> Result<DMatrix<f64>, NyxError>
+where
+    DefaultAllocator: Allocator<f64, <D::StateType as State>::Size>
+        + Allocator<f64, <D::StateType as State>::PropVecSize>
+        + Allocator<f64, <D::StateType as State>::Size, <D::StateType as State>::Size>
+        + Allocator<f64, D::HyperdualSize>
+        + Allocator<Hyperdual<f64, D::HyperdualSize>, <D::StateType as State>::Size>,
+    Owned<f64, D::HyperdualSize>: Copy,
+{
+    let size = <D::StateType as State>::Size::dim();
+    let state = VectorN::<f64, <D::StateType as State>::Size>::from_iterator(
+        state_vec.iter().take(size).cloned(),
+    );
+    match dynamics.eom_grad(0.0, &state, &ctx) {
+        Ok((_, grad)) => Ok(DMatrix::from_iterator(size, size, grad.iter().cloned())),
+        Err(NyxError::PartialsUndefined) => {
+            let deriv = dynamics.eom(0.0, &state_vec, &ctx)?;
+            let mut jacobian = DMatrix::<f64>::zeros(size, size);
+            for j in 0..size {
+                let delta = EPSILON.sqrt() * state_vec[j].abs().max(1.0);
+                let mut perturbed = state_vec.clone();
+                perturbed[j] += delta;
+                let perturbed_deriv = dynamics.eom(0.0, &perturbed, &ctx)?;
+                for i in 0..size {
+                    jacobian[(i, j)] = (perturbed_deriv[i] - deriv[i]) / delta;
+                }
+            }
+            Ok(jacobian)
         }
+        Err(e) => Err(e),
     }
 }
 
@@ -117,22 +236,36 @@ where
     pub tx_chan: Option<Sender<D::StateType>>,
     /// An event tracking instance
     pub event_trackers: EventTrackers<D::StateType>,
+    /// The impulsive maneuvers to apply during the propagation, in chronological order
+    pub impulses: Vec<ScheduledImpulse<D::StateType>>,
     /// Stores the details of the previous integration step
     pub details: IntegrationDetails,
-    prevent_tx: bool, // Allows preventing publishing to channel even if channel is set
+    next_impulse: usize, // Index of the next impulse to apply
     step_size: Duration, // Stores the adapted step for the _next_ call
     fixed_step: bool,
     // init_time: Epoch,
     // init_state_vec: VectorN<f64, <D::StateType as State>::Size>,
     // Allows us to do pre-allocation of the ki vectors
     k: Vec<VectorN<f64, <D::StateType as State>::PropVecSize>>,
+    // History of the multistep integration, if any
+    history: Option<MultistepHistory<<D::StateType as State>::PropVecSize>>,
+    // Latest state computed by the integrator, to detect changes of the state between two multistep steps
+    ms_state: Option<D::StateType>,
+    // Whether to record the continuous extension of each step
+    dense: bool,
+    // Continuous extensions of the recorded steps
+    steps: Vec<StepInterpolant<D::StateType>>,
+    // Hermite nodes (epoch, vector, derivative) of the latest interpolated steps
+    dense_nodes: Vec<HermiteNode<Epoch, <D::StateType as State>::PropVecSize>>,
+    // Number of hits of the first event after which the propagation stops, if any
+    stop_trigger: Option<usize>,
 }
 
 impl<'a, D: Dynamics, E: ErrorCtrl> PropInstance<'a, D, E>
 where
     DefaultAllocator: Allocator<f64, <D::StateType as State>::Size>
         + Allocator<f64, <D::StateType as State>::PropVecSize>
-        + Allocator<f64, <D::StateType as State>::Size>,
+        + Allocator<f64, <D::StateType as State>::Size, <D::StateType as State>::Size>,
 {
     /// Allows setting the step size of the propagator
     pub fn set_step(&mut self, step_size: Duration, fixed: bool) {
@@ -146,6 +279,17 @@ where
         self
     }
 
+    /// Set the impulsive maneuvers to apply during the propagation, must be provided in chronological order.
+    ///
+    /// The propagation stops exactly at each impulse, and the state right after each impulse is published on the
+    /// output channel. Impulses are only applied when propagating forward in time, and the propagation returns an
+    /// error if the epoch of the next impulse is before the current epoch.
+    pub fn with_impulses(mut self, impulses: Vec<ScheduledImpulse<D::StateType>>) -> Self {
+        self.impulses = impulses;
+        self.next_impulse = 0;
+        self
+    }
+
     /// Returns the state of the propagation
     ///
     /// WARNING: Do not use the dynamics to get the state, it will be the initial value!
@@ -162,6 +306,31 @@ where
         let stop_time = self.state.epoch() + duration;
         loop {
             let dt = self.state.epoch();
+            let prev_state = self.state;
+            // Stop exactly at the next impulse if it's scheduled before the next step
+            if let Some(impulse_time) = self.next_impulse_epoch() {
+                if !backprop && impulse_time <= stop_time {
+                    if impulse_time < dt {
+                        return Err(NyxError::CustomError(format!(
+                            "impulse #{} scheduled at {} is before the current epoch {}",
+                            self.next_impulse, impulse_time, dt
+                        )));
+                    } else if impulse_time == dt {
+                        self.apply_impulse()?;
+                        continue;
+                    } else if dt + self.step_size >= impulse_time {
+                        self.state = self.fixed_step_from(self.state, impulse_time - dt)?;
+                        self.record_step(prev_state, self.ms_state.unwrap(), true)?;
+                        self.event_trackers
+                            .eval_and_save(dt, self.state.epoch(), &self.state);
+                        if self.stop_triggered(backprop) {
+                            return Ok(self.state);
+                        }
+                        self.apply_impulse()?;
+                        continue;
+                    }
+                }
+            }
             if (!backprop && dt + self.step_size > stop_time)
                 || (backprop && dt + self.step_size <= stop_time)
             {
@@ -170,24 +339,26 @@ where
                     return Ok(self.state);
                 }
                 // Take one final step of exactly the needed duration until the stop time
-                let prev_step_size = self.step_size;
-                let prev_step_kind = self.fixed_step;
-                self.set_step(stop_time - dt, true);
-                let (t, state_vec) = self.derive()?;
-                self.state.set(self.state.epoch() + t, &state_vec)?;
-                self.state = self.prop.dynamics.finally(self.state)?;
+                self.state = self.fixed_step_from(self.state, stop_time - dt)?;
+                if !backprop && self.impulse_event_crossed(&prev_state) {
+                    self.locate_impulse_event(prev_state)?;
+                    self.record_step(prev_state, self.state, false)?;
+                    self.event_trackers
+                        .eval_and_save(dt, self.state.epoch(), &self.state);
+                    if self.stop_triggered(backprop) {
+                        return Ok(self.state);
+                    }
+                    self.apply_impulse()?;
+                    continue;
+                }
+                self.record_step(prev_state, self.ms_state.unwrap(), true)?;
                 // Evaluate the event trackers
                 self.event_trackers
                     .eval_and_save(dt, self.state.epoch(), &self.state);
-                // Restore the step size for subsequent calls
-                self.set_step(prev_step_size, prev_step_kind);
-                if !self.prevent_tx {
-                    if let Some(ref chan) = self.tx_chan {
-                        if let Err(e) = chan.send(self.state) {
-                            warn!("could not publish to channel: {}", e)
-                        }
-                    }
+                if self.stop_triggered(backprop) {
+                    return Ok(self.state);
                 }
+                self.publish();
                 if backprop {
                     self.step_size = -self.step_size; // Restore to a positive step size
                 }
@@ -196,34 +367,79 @@ where
                 let (t, state_vec) = self.derive()?;
 
                 self.state.set(self.state.epoch() + t, &state_vec)?;
+                self.ms_state = Some(self.state);
                 self.state = self.prop.dynamics.finally(self.state)?;
+                if !backprop && self.impulse_event_crossed(&prev_state) {
+                    self.locate_impulse_event(prev_state)?;
+                    self.record_step(prev_state, self.state, false)?;
+                    self.event_trackers
+                        .eval_and_save(dt, self.state.epoch(), &self.state);
+                    if self.stop_triggered(backprop) {
+                        return Ok(self.state);
+                    }
+                    self.apply_impulse()?;
+                    continue;
+                }
+                self.record_step(prev_state, self.ms_state.unwrap(), true)?;
                 // Evaluate the event trackers
                 self.event_trackers
                     .eval_and_save(dt, self.state.epoch(), &self.state);
-                if !self.prevent_tx {
-                    if let Some(ref chan) = self.tx_chan {
-                        if let Err(e) = chan.send(self.state) {
-                            warn!("could not publish to channel: {}", e)
-                        }
-                    }
+                if self.stop_triggered(backprop) {
+                    return Ok(self.state);
                 }
+                self.publish();
             }
         }
     }
 
+    /// Propagates for the provided duration (which must be positive), and returns the final state and the trajectory
+    /// built from the continuous extension of each integration step.
+    pub fn for_duration_with_traj(
+        &mut self,
+        duration: Duration,
+    ) -> Result<(D::StateType, Traj<D::StateType>), NyxError> {
+        if duration < TimeUnit::Nanosecond {
+            return Err(NyxError::CustomError(
+                "trajectories can only be built when propagating forward".to_string(),
+            ));
+        }
+        let start_state = self.state;
+        self.dense = true;
+        self.steps.clear();
+        self.dense_nodes.clear();
+        let rslt = self.for_duration(duration);
+        self.dense = false;
+        let steps = std::mem::take(&mut self.steps);
+        let end_state = rslt?;
+        Ok((end_state, Traj::from_steps(start_state, steps)?))
+    }
+
+    /// Propagates until the provided stop condition is met, and returns the state at that time.
+    ///
+    /// The propagation stops after the step during which the event is hit for the requested number of times. The
+    /// event is then located with a Brent solver on the continuous extension of that step, and the returned state is
+    /// computed with a single step from the start of that step. The states up to the event are published on the
+    /// output channel, if any.
     pub fn until_event(
         &mut self,
         condition: StopCondition<D::StateType>,
     ) -> Result<D::StateType, NyxError> {
-        let init_state = self.state;
-        self.prevent_tx = true;
         // Rewrite the event tracker
         if !self.event_trackers.events.is_empty() {
             warn!("Rewriting event tracker with the StopCondition");
         }
 
         self.event_trackers = EventTrackers::from_event(condition.event);
-        self.for_duration(condition.max_prop_time)?;
+        self.stop_trigger = Some(condition.trigger);
+        self.dense = true;
+        self.steps.clear();
+        self.dense_nodes.clear();
+        let rslt = self.for_duration(condition.max_prop_time);
+        self.stop_trigger = None;
+        self.dense = false;
+        let step = self.steps.pop();
+        self.steps.clear();
+        rslt?;
         // Check if the event has been triggered
         if self.event_trackers.found_bounds[0].len() < condition.trigger {
             if condition.trigger == 1 {
@@ -237,20 +453,18 @@ where
                 ));
             }
         }
+        // The event was hit during the latest step
+        let step = step.ok_or(NyxError::ConditionNeverTriggered)?;
+        let start_epoch = step.start.epoch();
+        let event = &self.event_trackers.events[0];
+        let eval = |x: f64| -> Result<f64, NyxError> {
+            Ok(event.eval(&step.evaluate(start_epoch + x * TimeUnit::Second)?))
+        };
 
-        let (xa_e, xb_e) = self.event_trackers.found_bounds[0][condition.trigger - 1];
-        let mut xa = xa_e.as_tai_seconds();
-        let mut xb = xb_e.as_tai_seconds();
-        // Reinitialize the dynamics and start the search
-        self.state = init_state;
-        self.event_trackers.reset();
-        let initial_epoch = self.state.epoch();
-        // Compute the initial values of the condition at those bounds.
-        self.for_duration(xa_e - initial_epoch)?;
-        let mut ya = self.event_trackers.events[0].eval(&self.state);
-        // And prop until next bound
-        self.for_duration((xb - xa) * TimeUnit::Second)?;
-        let mut yb = self.event_trackers.events[0].eval(&self.state);
+        let mut xa = 0.0;
+        let mut xb = step.step_s;
+        let mut ya = eval(xa)?;
+        let mut yb = eval(xb)?;
         // The Brent solver, from the roots crate (sadly could not directly integrate it here)
         // Source: https://docs.rs/roots/0.0.5/src/roots/numerical/brent.rs.html#57-131
 
@@ -300,18 +514,14 @@ where
             } else {
                 flag = false;
             }
-            // Propagate until time s
-            self.for_duration(s * TimeUnit::Second)?;
-            let ys = self.event_trackers.events[0].eval(&self.state);
+            // Interpolate at time s
+            let ys = eval(s)?;
             d = c;
             c = xb;
             yc = yb;
             if ya * ys < 0.0 {
                 // Root bracketed between a and s
-                // Propagate until time xa
-                self.for_duration(xa * TimeUnit::Second)?;
-                let ya_p = self.event_trackers.events[0].eval(&self.state);
-                let (_a, _ya, _b, _yb) = arrange(xa, ya_p, s, ys);
+                let (_a, _ya, _b, _yb) = arrange(xa, ya, s, ys);
                 {
                     xa = _a;
                     ya = _ya;
@@ -320,10 +530,7 @@ where
                 }
             } else {
                 // Root bracketed between s and b
-                // Propagate until time xb
-                self.for_duration(xb * TimeUnit::Second)?;
-                let yb_p = self.event_trackers.events[0].eval(&self.state);
-                let (_a, _ya, _b, _yb) = arrange(s, ys, xb, yb_p);
+                let (_a, _ya, _b, _yb) = arrange(s, ys, xb, yb);
                 {
                     xa = _a;
                     ya = _ya;
@@ -337,20 +544,310 @@ where
             }
         }
 
-        // Now that we have the time at which the condition is matched, let's propagate until then
-        self.prevent_tx = false;
-        let elapsed_time = -(closest_t * TimeUnit::Second);
-        self.for_duration(elapsed_time)?;
+        // Now that we have the time at which the condition is matched, let's step from the start of the step until then
+        self.state = self.fixed_step_from(step.start, closest_t * TimeUnit::Second)?;
+        self.publish();
 
         Ok(self.state)
     }
 
+    /// Returns whether the propagation must stop because the first event was hit the requested number of times,
+    /// in which case the step size is restored if propagating backward.
+    fn stop_triggered(&mut self, backprop: bool) -> bool {
+        match self.stop_trigger {
+            Some(trigger) if self.event_trackers.found_bounds[0].len() >= trigger => {
+                if backprop {
+                    self.step_size = -self.step_size; // Restore to a positive step size
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Records the continuous extension of the step from the provided start state to the provided integrated state,
+    /// if the steps are recorded. The stages of the Runge Kutta are used if they are those of this step and if the
+    /// integrator provides a continuous extension, else the step is interpolated with a Hermite polynomial on the
+    /// bounds of this step and of the previous steps.
+    fn record_step(
+        &mut self,
+        start: D::StateType,
+        end: D::StateType,
+        stages_valid: bool,
+    ) -> Result<(), NyxError> {
+        if !self.dense {
+            return Ok(());
+        }
+        let step_s = (end.epoch() - start.epoch()).in_seconds();
+        if step_s.abs() < EPSILON {
+            return Ok(());
+        }
+        let interp = if stages_valid
+            && self.prop.multistep.is_none()
+            && !self.prop.dense_coeffs.is_empty()
+        {
+            self.dense_nodes.clear();
+            StepInterpolant::from_stages(start, step_s, &self.k, self.prop.dense_coeffs)?
+        } else {
+            let y0 = start.as_vector()?;
+            let y1 = end.as_vector()?;
+            let contiguous = match self.dense_nodes.last() {
+                Some((epoch, vector, _)) => *epoch == start.epoch() && *vector == y0,
+                None => false,
+            };
+            let f0 = if contiguous {
+                self.dense_nodes.pop().unwrap().2
+            } else {
+                self.dense_nodes.clear();
+                self.prop.dynamics.eom(0.0, &y0, &start)?
+            };
+            // Only keep the latest nodes of the previous steps, if they are close enough to this step
+            let start_epoch = start.epoch();
+            let theta_of = |epoch: Epoch| (epoch - start_epoch).in_seconds() / step_s;
+            self.dense_nodes
+                .retain(|(epoch, _, _)| (-DENSE_SPAN..0.0).contains(&theta_of(*epoch)));
+            while self.dense_nodes.len() > DENSE_NODES - 2 {
+                self.dense_nodes.remove(0);
+            }
+            let f1 = self.prop.dynamics.eom(step_s, &y1, &start)?;
+            self.dense_nodes.push((start_epoch, y0, f0));
+            self.dense_nodes.push((end.epoch(), y1, f1));
+            let nodes: Vec<_> = self
+                .dense_nodes
+                .iter()
+                .map(|(epoch, vector, deriv)| (theta_of(*epoch), vector.clone(), deriv.clone()))
+                .collect();
+            StepInterpolant::hermite(start, step_s, &nodes)
+        };
+        if self.stop_trigger.is_some() {
+            // Only the latest step is needed to locate the event
+            self.steps.clear();
+        }
+        self.steps.push(interp);
+        Ok(())
+    }
+
+    /// Publishes the current state on the output channel, if any.
+    fn publish(&self) {
+        if let Some(ref chan) = self.tx_chan {
+            if let Err(e) = chan.send(self.state) {
+                warn!("could not publish to channel: {}", e)
+            }
+        }
+    }
+
+    /// Takes a single step of exactly the provided duration from the provided state, and returns the new state.
+    fn fixed_step_from(
+        &mut self,
+        state: D::StateType,
+        step: Duration,
+    ) -> Result<D::StateType, NyxError> {
+        if self.prop.multistep.is_some() {
+            if let Some(next_state) = self.multistep_step_from(state, step)? {
+                return self.prop.dynamics.finally(next_state);
+            }
+            // Not reachable from the history: restart the multistep integration after this step
+            self.history = None;
+        }
+        let prev_step_size = self.step_size;
+        let prev_step_kind = self.fixed_step;
+        self.state = state;
+        self.set_step(step, true);
+        let (t, state_vec) = self.step_derive()?;
+        let mut next_state = self.state;
+        next_state.set(next_state.epoch() + t, &state_vec)?;
+        self.ms_state = Some(next_state);
+        // Restore the step size for subsequent calls
+        self.set_step(prev_step_size, prev_step_kind);
+        self.prop.dynamics.finally(next_state)
+    }
+
+    /// Returns the epoch of the next impulse, if it is triggered by an epoch
+    fn next_impulse_epoch(&self) -> Option<Epoch> {
+        match self.impulses.get(self.next_impulse) {
+            Some(ScheduledImpulse {
+                trigger: ImpulseTrigger::Epoch(epoch),
+                ..
+            }) => Some(*epoch),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the event triggering the next impulse happened between the provided state and the current state
+    fn impulse_event_crossed(&self, prev_state: &D::StateType) -> bool {
+        match self.impulses.get(self.next_impulse) {
+            Some(ScheduledImpulse {
+                trigger: ImpulseTrigger::Event { event, .. },
+                ..
+            }) => event.eval_crossing(prev_state, &self.state),
+            _ => false,
+        }
+    }
+
+    /// Locates the event triggering the next impulse by bisection between the provided state and the current state.
+    /// The current state is set to the first state found after the event.
+    fn locate_impulse_event(&mut self, prev_state: D::StateType) -> Result<(), NyxError> {
+        // Take the impulses to be able to propagate while borrowing the event
+        let impulses = std::mem::take(&mut self.impulses);
+        let (event, epsilon) = match &impulses[self.next_impulse].trigger {
+            ImpulseTrigger::Event { event, epsilon } => (event, *epsilon),
+            ImpulseTrigger::Epoch(_) => unreachable!(),
+        };
+        let mut after_state = self.state;
+        let mut lower = 0.0;
+        let mut upper = (self.state.epoch() - prev_state.epoch()).in_seconds();
+        let mut iter = 0;
+        let rslt = loop {
+            if upper - lower <= epsilon.in_seconds() {
+                break Ok(());
+            }
+            iter += 1;
+            if iter > MAX_BISECTIONS {
+                break Err(NyxError::MaxIterReached(MAX_BISECTIONS));
+            }
+            let mid = 0.5 * (lower + upper);
+            let mid_state = match self.fixed_step_from(prev_state, mid * TimeUnit::Second) {
+                Ok(mid_state) => mid_state,
+                Err(e) => break Err(e),
+            };
+            if event.eval_crossing(&prev_state, &mid_state) {
+                upper = mid;
+                after_state = mid_state;
+            } else {
+                lower = mid;
+            }
+        };
+        self.impulses = impulses;
+        self.state = after_state;
+        rslt
+    }
+
+    /// Applies the next impulse to the current state and publishes the new state.
+    fn apply_impulse(&mut self) -> Result<(), NyxError> {
+        let impulse = self.impulses[self.next_impulse].impulse;
+        self.state.apply_impulse(&impulse)?;
+        info!(
+            "impulse #{} applied at {}: {:?}",
+            self.next_impulse,
+            self.state.epoch(),
+            impulse
+        );
+        self.next_impulse += 1;
+        self.publish();
+        Ok(())
+    }
+
     /// This method integrates whichever function is provided as `d_xdt`. Everything passed to this function is in **seconds**.
     ///
     /// This function returns the step sized used (as a Duration) and the new state as y_{n+1} = y_n + \frac{dy_n}{dt}.
     /// To get the integration details, check `self.latest_details`.
     fn derive(
         &mut self,
+    ) -> Result<(Duration, VectorN<f64, <D::StateType as State>::PropVecSize>), NyxError> {
+        if self.prop.multistep.is_some() {
+            self.multistep_derive()
+        } else {
+            self.step_derive()
+        }
+    }
+
+    /// Takes a single step of the Radau IIA method or of the Runge Kutta, adapting the step size if needed.
+    fn step_derive(
+        &mut self,
+    ) -> Result<(Duration, VectorN<f64, <D::StateType as State>::PropVecSize>), NyxError> {
+        match self.prop.implicit {
+            Some(jacobian) => self.radau_derive(jacobian),
+            None => self.rk_derive(),
+        }
+    }
+
+    /// Takes a single Radau IIA step, adapting the step size if needed.
+    fn radau_derive(
+        &mut self,
+        jacobian: StateJacobian<D>,
+    ) -> Result<(Duration, VectorN<f64, <D::StateType as State>::PropVecSize>), NyxError> {
+        let state = &self.state_vector();
+        let ctx = &self.state;
+        let dynamics = &self.prop.dynamics;
+        self.details.attempts = 1;
+        // The derivative and the Jacobian at the start of the step are shared by all attempts
+        let deriv = dynamics.eom(0.0, state, ctx)?;
+        let jac = jacobian(Arc::clone(dynamics), state.clone(), *ctx)?;
+        let newton_tol = NEWTON_KAPPA * self.prop.opts.tolerance;
+        loop {
+            let step_size = self.step_size.in_seconds();
+            let rslt = radau_step::<_, E, _>(
+                |t, y| dynamics.eom(t, y, ctx),
+                state,
+                &deriv,
+                &jac,
+                step_size,
+                newton_tol,
+            )?;
+            let step = match rslt {
+                Some(step) => step,
+                None => {
+                    if self.fixed_step
+                        || step_size.abs() <= self.prop.opts.min_step.in_seconds()
+                        || self.details.attempts >= self.prop.opts.attempts
+                    {
+                        return Err(NyxError::MaxIterReached(NEWTON_MAX_ITER));
+                    }
+                    // The Newton iteration did not converge, so let's retry with half the step
+                    self.details.attempts += 1;
+                    let proposed_step = (0.5 * step_size.abs())
+                        .max(self.prop.opts.min_step.in_seconds())
+                        * step_size.signum();
+                    self.step_size = proposed_step * TimeUnit::Second;
+                    continue;
+                }
+            };
+
+            if self.fixed_step {
+                self.details.step = self.step_size;
+                return Ok((self.details.step, step.next));
+            }
+
+            self.details.error = E::estimate(&step.error_est, &step.next, state);
+            if self.details.error <= self.prop.opts.tolerance
+                || step_size.abs() <= self.prop.opts.min_step.in_seconds()
+                || self.details.attempts >= self.prop.opts.attempts
+            {
+                if self.details.attempts >= self.prop.opts.attempts {
+                    warn!(
+                        "maximum number of attempts reached ({})",
+                        self.details.attempts
+                    );
+                }
+
+                self.details.step = step_size * TimeUnit::Second;
+                if self.details.error < self.prop.opts.tolerance {
+                    // Let's increase the step size for the next iteration.
+                    let proposed_step = 0.9
+                        * step_size.abs()
+                        * (self.prop.opts.tolerance / self.details.error)
+                            .powf(1.0 / f64::from(RADAU_ERR_ORDER));
+                    self.step_size = proposed_step.min(self.prop.opts.max_step.in_seconds())
+                        * step_size.signum()
+                        * TimeUnit::Second;
+                }
+                return Ok((self.details.step, step.next));
+            } else {
+                self.details.attempts += 1;
+                let proposed_step = 0.9
+                    * step_size.abs()
+                    * (self.prop.opts.tolerance / self.details.error)
+                        .powf(1.0 / f64::from(RADAU_ERR_ORDER));
+                self.step_size = proposed_step.max(self.prop.opts.min_step.in_seconds())
+                    * step_size.signum()
+                    * TimeUnit::Second;
+            }
+        }
+    }
+
+    /// Takes a single Runge Kutta step, adapting the step size if needed.
+    fn rk_derive(
+        &mut self,
     ) -> Result<(Duration, VectorN<f64, <D::StateType as State>::PropVecSize>), NyxError> {
         let state = &self.state_vector();
         let ctx = &self.state;
@@ -448,6 +945,191 @@ where
         }
     }
 
+    /// Returns whether the state is the latest state computed by the propagator with a multistep history, i.e.
+    /// whether the history can be used to continue the integration.
+    fn history_matches_state(&self) -> bool {
+        match (&self.history, &self.ms_state) {
+            (Some(_), Some(ms_state)) => {
+                *ms_state == self.state && ms_state.as_vector().ok() == self.state.as_vector().ok()
+            }
+            _ => false,
+        }
+    }
+
+    /// Restarts the multistep history from the current state, with the current step size.
+    fn restart_history(&mut self) -> Result<(), NyxError> {
+        let state_vec = self.state_vector();
+        let deriv = self.prop.dynamics.eom(0.0, &state_vec, &self.state)?;
+        debug!("starting multistep integration at {}", self.state.epoch());
+        self.history = Some(MultistepHistory::new(
+            self.state.epoch(),
+            self.step_size.in_seconds(),
+            state_vec,
+            deriv,
+        ));
+        self.ms_state = Some(self.state);
+        Ok(())
+    }
+
+    /// Takes a single multistep step (predict, evaluate, correct, evaluate), or a step of the Runge Kutta starter if
+    /// the history is incomplete. With an adaptive step, the step is halved until the error estimate is within the
+    /// tolerance, and doubled for the next step if the error is much smaller than the tolerance.
+    fn multistep_derive(
+        &mut self,
+    ) -> Result<(Duration, VectorN<f64, <D::StateType as State>::PropVecSize>), NyxError> {
+        let prop = Arc::clone(&self.prop);
+        let coeffs = prop.multistep.as_ref().unwrap();
+        let epoch = self.state.epoch();
+        let restart = match &self.history {
+            Some(hist)
+                if self.history_matches_state()
+                    && hist.step_s * self.step_size.in_seconds() > 0.0 =>
+            {
+                // An interpolated state can only catch up with the history if it has no STM
+                hist.epoch != epoch && (!hist.is_complete(coeffs) || self.state.stm().is_ok())
+            }
+            _ => true,
+        };
+        if restart {
+            self.restart_history()?;
+        }
+        self.details.attempts = 1;
+
+        let (head_epoch, complete, step_s) = {
+            let hist = self.history.as_ref().unwrap();
+            (hist.epoch, hist.is_complete(coeffs), hist.step_s)
+        };
+        if head_epoch != epoch {
+            // Catch up with the most recent step of the history from an interpolated state
+            self.details.step = head_epoch - epoch;
+            return Ok((
+                self.details.step,
+                self.history.as_ref().unwrap().head.clone(),
+            ));
+        }
+
+        let pairs = match coeffs.method {
+            Multistep::GaussJackson => self.state.second_order_pairs(),
+            Multistep::AdamsBashforthMoulton => Vec::new(),
+        };
+
+        if !complete {
+            // Start with steps of the Runge Kutta
+            self.step_size = step_s * TimeUnit::Second;
+            let (step, state_vec) = self.rk_derive()?;
+            if (step.in_seconds() - step_s).abs() > 1e-9 * step_s.abs() {
+                // The Runge Kutta reduced the step, so restart from the new state with that step
+                self.history = None;
+            } else {
+                let deriv = prop.dynamics.eom(step_s, &state_vec, &self.state)?;
+                let hist = self.history.as_mut().unwrap();
+                hist.push(epoch + step, state_vec.clone(), deriv, coeffs);
+                if hist.is_complete(coeffs) {
+                    hist.reset_delta(&state_vec, &pairs, coeffs);
+                }
+            }
+            self.step_size = step;
+            return Ok((step, state_vec));
+        }
+
+        let state_vec = self.state.as_vector()?;
+        let hist = self.history.as_mut().unwrap();
+        loop {
+            let step_s = hist.step_s;
+            let (predicted, _) = hist.predict(&state_vec, &pairs, coeffs);
+            let predicted_deriv = prop.dynamics.eom(step_s, &predicted, &self.state)?;
+            let (corrected, delta) = hist.correct(&state_vec, &predicted_deriv, &pairs, coeffs);
+            if !self.fixed_step {
+                let error_est = hist.error_estimate(&predicted, &corrected, &pairs, coeffs);
+                self.details.error = E::estimate(&error_est, &corrected, &state_vec);
+                if self.details.error > prop.opts.tolerance
+                    && 0.5 * step_s.abs() >= prop.opts.min_step.in_seconds()
+                    && self.details.attempts < prop.opts.attempts
+                {
+                    self.details.attempts += 1;
+                    hist.halve(&state_vec, &pairs, coeffs);
+                    continue;
+                }
+            }
+
+            let step = step_s * TimeUnit::Second;
+            let deriv = prop.dynamics.eom(step_s, &corrected, &self.state)?;
+            hist.push(epoch + step, corrected.clone(), deriv, coeffs);
+            hist.delta = delta;
+            if !self.fixed_step
+                && self.details.error * 2.0_f64.powi(coeffs.order as i32 + 2) < prop.opts.tolerance
+                && 2.0 * step_s.abs() <= prop.opts.max_step.in_seconds()
+            {
+                hist.double(&corrected, &pairs, coeffs);
+            }
+            self.step_size = hist.step_s * TimeUnit::Second;
+            self.details.step = step;
+            return Ok((step, corrected));
+        }
+    }
+
+    /// Takes a step of exactly the provided duration from the provided state with the multistep history: the
+    /// history is advanced past the new epoch if needed, and the new state is interpolated. If the state has an STM,
+    /// the new state is instead extrapolated from the history, such that its STM is the one of this step.
+    /// Returns None if the history cannot be used from the provided state.
+    fn multistep_step_from(
+        &mut self,
+        state: D::StateType,
+        step: Duration,
+    ) -> Result<Option<D::StateType>, NyxError> {
+        let prop = Arc::clone(&self.prop);
+        let coeffs = prop.multistep.as_ref().unwrap();
+        self.state = state;
+        let target = state.epoch() + step;
+        let has_stm = state.stm().is_ok();
+        match &self.history {
+            Some(hist) if hist.is_complete(coeffs) && self.history_matches_state() => {
+                let ahead = (target - hist.epoch).in_seconds() * hist.step_s > 0.0;
+                if ahead
+                    && (hist.step_s * self.step_size.in_seconds() <= 0.0
+                        || (has_stm && hist.epoch != state.epoch()))
+                {
+                    return Ok(None);
+                }
+            }
+            _ => return Ok(None),
+        }
+
+        if !has_stm {
+            // Advance the history until it passes the new epoch
+            while (target - self.history.as_ref().unwrap().epoch).in_seconds()
+                * self.history.as_ref().unwrap().step_s
+                > 0.0
+            {
+                let (t, state_vec) = self.multistep_derive()?;
+                self.state.set(self.state.epoch() + t, &state_vec)?;
+                self.ms_state = Some(self.state);
+            }
+        }
+
+        let hist = self.history.as_ref().unwrap();
+        let s = (target - hist.epoch).in_seconds() / hist.step_s;
+        let at_head = self.state.epoch() == hist.epoch;
+        if s < 1.0 - coeffs.order as f64 || s > 1.0 || (has_stm && !at_head) {
+            return Ok(None);
+        }
+        let pairs = match coeffs.method {
+            Multistep::GaussJackson => self.state.second_order_pairs(),
+            Multistep::AdamsBashforthMoulton => Vec::new(),
+        };
+        let head = if at_head {
+            self.state.as_vector()?
+        } else {
+            hist.head.clone()
+        };
+        let state_vec = hist.interpolate(&head, s, &pairs, coeffs);
+        let mut next_state = self.state;
+        next_state.set(target, &state_vec)?;
+        self.ms_state = Some(next_state);
+        self.details.step = step;
+        Ok(Some(next_state))
+    }
+
     /// Borrow the details of the latest integration step.
     pub fn latest_details(&self) -> &IntegrationDetails {
         &self.details
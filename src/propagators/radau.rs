@@ -0,0 +1,161 @@
+use super::error_ctrl::ErrorCtrl;
+use crate::dimensions::allocator::Allocator;
+use crate::dimensions::{DMatrix, DVector, DefaultAllocator, DimName, VectorN};
+use crate::errors::NyxError;
+
+/// Nodes of the three stage Radau IIA method, i.e. the roots of the right Radau polynomial
+pub const RADAU_C: [f64; 3] = [0.155_051_025_721_682_22, 0.644_948_974_278_317_8, 1.0];
+/// Butcher table of the three stage Radau IIA method, whose last row are the weights (stiffly accurate method)
+pub const RADAU_A: [[f64; 3]; 3] = [
+    [
+        0.196_815_477_223_660_44,
+        -0.065_535_425_850_198_38,
+        0.023_770_974_348_220_15,
+    ],
+    [
+        0.394_424_314_739_087_3,
+        0.292_073_411_665_228_43,
+        -0.041_548_752_125_997_92,
+    ],
+    [
+        0.376_403_062_700_467_25,
+        0.512_485_826_188_421_6,
+        0.111_111_111_111_111_1,
+    ],
+];
+/// Coefficients of the stages in the embedded error estimate
+const RADAU_ERR: [f64; 3] = [
+    -10.048_809_399_827_414,
+    1.382_142_733_160_748,
+    -0.333_333_333_333_333_3,
+];
+/// Inverse of the real eigenvalue of the inverse of the Butcher table
+const RADAU_GAMMA0: f64 = 0.274_888_829_595_677_34;
+/// Order of the local error estimate, used to adapt the step size
+pub const RADAU_ERR_ORDER: u8 = 4;
+/// Maximum number of simplified Newton iterations to solve the collocation equations
+pub const NEWTON_MAX_ITER: usize = 7;
+/// Ratio of the tolerance of the propagator at which the Newton iteration is converged
+pub const NEWTON_KAPPA: f64 = 0.03;
+
+/// A Radau IIA step, whose collocation equations converged
+#[derive(Clone, Debug)]
+pub struct RadauStep<N: DimName>
+where
+    DefaultAllocator: Allocator<f64, N>,
+{
+    /// Propagated vector at the end of the step
+    pub next: VectorN<f64, N>,
+    /// Embedded error estimate
+    pub error_est: VectorN<f64, N>,
+    /// Number of Newton iterations
+    pub iterations: usize,
+}
+
+/// Takes a step of the three stage Radau IIA method (order 5), solving the collocation equations with a simplified
+/// Newton iteration.
+///
+/// The Jacobian is only provided with respect to the first components of the vector (the state), the other components
+/// (e.g. the STM) are assumed to not change the dynamics, and are solved by fixed point. The iteration is converged when
+/// the `ErrorCtrl` estimate of the update of each stage is below `newton_tol`. Returns None if the iteration diverges
+/// or does not converge in `NEWTON_MAX_ITER` iterations, and an error if the Newton matrix is singular.
+///
+/// Reference: Hairer and Wanner, "Solving Ordinary Differential Equations II", section IV.8, 1996.
+pub fn radau_step<N, E, F>(
+    eom: F,
+    y0: &VectorN<f64, N>,
+    f0: &VectorN<f64, N>,
+    jacobian: &DMatrix<f64>,
+    step_s: f64,
+    newton_tol: f64,
+) -> Result<Option<RadauStep<N>>, NyxError>
+where
+    N: DimName,
+    E: ErrorCtrl,
+    F: Fn(f64, &VectorN<f64, N>) -> Result<VectorN<f64, N>, NyxError>,
+    DefaultAllocator: Allocator<f64, N>,
+{
+    let size = jacobian.nrows();
+    // Newton matrix I - h A ⊗ J of the state components of the three stages
+    let mut newton = DMatrix::<f64>::identity(3 * size, 3 * size);
+    for i in 0..3 {
+        for j in 0..3 {
+            for p in 0..size {
+                for q in 0..size {
+                    newton[(i * size + p, j * size + q)] -=
+                        step_s * RADAU_A[i][j] * jacobian[(p, q)];
+                }
+            }
+        }
+    }
+
+    // Increments of the stages from the initial vector
+    let mut z = vec![VectorN::<f64, N>::zeros(); 3];
+    let mut prev_norm: Option<f64> = None;
+    let mut iterations = 0;
+    loop {
+        iterations += 1;
+        let mut derivs = Vec::with_capacity(3);
+        for (c_i, z_i) in RADAU_C.iter().zip(&z) {
+            derivs.push(eom(c_i * step_s, &(y0 + z_i))?);
+        }
+        // Residuals of the collocation equations
+        let mut residuals = Vec::with_capacity(3);
+        let mut rhs = DVector::<f64>::zeros(3 * size);
+        for i in 0..3 {
+            let mut residual = -&z[i];
+            for (a_ij, deriv) in RADAU_A[i].iter().zip(&derivs) {
+                residual += step_s * a_ij * deriv;
+            }
+            for p in 0..size {
+                rhs[i * size + p] = residual[p];
+            }
+            residuals.push(residual);
+        }
+        let delta = dsolve(&newton, &rhs).ok_or_else(|| {
+            NyxError::CustomError("Radau IIA Newton matrix is singular".to_string())
+        })?;
+        let mut norm = 0.0_f64;
+        for (i, mut update) in residuals.into_iter().enumerate() {
+            for p in 0..size {
+                update[p] = delta[i * size + p];
+            }
+            z[i] += &update;
+            norm = norm.max(E::estimate(&update, &(y0 + &z[i]), y0));
+        }
+        if norm <= newton_tol {
+            break;
+        }
+        match prev_norm {
+            Some(prev) if norm >= prev => return Ok(None),
+            _ if iterations >= NEWTON_MAX_ITER => return Ok(None),
+            _ => prev_norm = Some(norm),
+        }
+    }
+
+    // Error estimate, filtered by (I - γ0 h J)^-1 to remain bounded for stiff components
+    let mut error_est = f0 * step_s;
+    for (e_i, z_i) in RADAU_ERR.iter().zip(&z) {
+        error_est += *e_i * z_i;
+    }
+    error_est *= RADAU_GAMMA0;
+    let filter = DMatrix::<f64>::identity(size, size) - RADAU_GAMMA0 * step_s * jacobian;
+    let rhs = DVector::<f64>::from_iterator(size, error_est.iter().take(size).cloned());
+    let filtered = dsolve(&filter, &rhs).ok_or_else(|| {
+        NyxError::CustomError("Radau IIA error filter matrix is singular".to_string())
+    })?;
+    for p in 0..size {
+        error_est[p] = filtered[p];
+    }
+
+    Ok(Some(RadauStep {
+        next: y0 + &z[2],
+        error_est,
+        iterations,
+    }))
+}
+
+/// Solves the dynamically sized linear system `m x = b`, or returns None if `m` is singular
+fn dsolve(m: &DMatrix<f64>, b: &DVector<f64>) -> Option<DVector<f64>> {
+    m.clone().lu().solve(b)
+}
@@ -108,6 +108,10 @@ const SQRT6: f64 = 2.449_489_742_783_178;
 /// `RK89` is a Runge Kutta 8-9 integrator.
 ///
 /// Coefficients taken from GMAT `src/base/propagator/RungeKutta89.cpp`.
+///
+/// It does not provide a continuous extension: Verner's interpolants of this pair require additional stages which
+/// are not implemented. Its steps are instead interpolated with a Hermite polynomial on the bounds of the step and of
+/// the previous steps (see `PropInstance::until_event` and `Traj`).
 pub struct RK89 {}
 
 impl RK for RK89 {
@@ -0,0 +1,423 @@
+use crate::celestia::{Frame, Orbit, SpacecraftState};
+use crate::dimensions::{Vector3, Vector6};
+use crate::dynamics::spacecraft::Spacecraft;
+use crate::dynamics::thrustctrl::equinoctial_gauss_rate;
+use crate::dynamics::AccelModel;
+use crate::errors::NyxError;
+use crate::time::{Duration, Epoch, TimeUnit};
+use std::f64::consts::PI;
+use std::fmt;
+use std::sync::Arc;
+
+/// Equinoctial elements [sma (km), f, g, h, k, mean longitude (rad)], where f, g, h and k are the modified equinoctial
+/// elements of `Orbit::equinoctial_f` (and siblings), and the mean longitude is λ = M + ω + Ω.
+/// Reference: Broucke and Cefola, "On the equinoctial orbit elements", Celestial Mechanics 5, 1972.
+#[derive(Copy, Clone, Debug)]
+pub struct Equinoctial {
+    pub dt: Epoch,
+    pub frame: Frame,
+    pub elements: Vector6<f64>,
+}
+
+impl Equinoctial {
+    /// Computes the equinoctial elements of this orbit
+    pub fn from_orbit(orbit: &Orbit) -> Self {
+        let gm = orbit.frame.gm();
+        let r = orbit.radius();
+        let v = orbit.velocity();
+        let rmag = r.norm();
+        let sma = 1.0 / (2.0 / rmag - v.norm_squared() / gm);
+        let hvec = r.cross(&v);
+        let w = hvec / hvec.norm();
+        let h = -w[1] / (1.0 + w[2]);
+        let k = w[0] / (1.0 + w[2]);
+        let (f_hat, g_hat) = Self::basis(h, k);
+        let ecc_vec = v.cross(&hvec) / gm - r / rmag;
+        let f = ecc_vec.dot(&f_hat);
+        let g = ecc_vec.dot(&g_hat);
+        let x = r.dot(&f_hat);
+        let y = r.dot(&g_hat);
+        let beta = (1.0 - f.powi(2) - g.powi(2)).sqrt();
+        let b = 1.0 / (1.0 + beta);
+        // Eccentric longitude
+        let sin_ecc_long = g + ((1.0 - g.powi(2) * b) * y - f * g * b * x) / (sma * beta);
+        let cos_ecc_long = f + ((1.0 - f.powi(2) * b) * x - f * g * b * y) / (sma * beta);
+        let ecc_long = sin_ecc_long.atan2(cos_ecc_long);
+        let lambda = (ecc_long + g * ecc_long.cos() - f * ecc_long.sin()).rem_euclid(2.0 * PI);
+        Self {
+            dt: orbit.dt,
+            frame: orbit.frame,
+            elements: Vector6::new(sma, f, g, h, k, lambda),
+        }
+    }
+
+    /// Returns the cartesian orbit of these elements
+    pub fn to_orbit(&self) -> Orbit {
+        let (sma, f, g, h, k, lambda) = (
+            self.elements[0],
+            self.elements[1],
+            self.elements[2],
+            self.elements[3],
+            self.elements[4],
+            self.elements[5],
+        );
+        // Solve the equinoctial Kepler equation for the eccentric longitude
+        let mut ecc_long = lambda;
+        for _ in 0..50 {
+            let err = ecc_long + g * ecc_long.cos() - f * ecc_long.sin() - lambda;
+            ecc_long -= err / (1.0 - g * ecc_long.sin() - f * ecc_long.cos());
+            if err.abs() < 1e-15 {
+                break;
+            }
+        }
+        let (sin_ecc_long, cos_ecc_long) = ecc_long.sin_cos();
+        let beta = (1.0 - f.powi(2) - g.powi(2)).sqrt();
+        let b = 1.0 / (1.0 + beta);
+        let x = sma * ((1.0 - g.powi(2) * b) * cos_ecc_long + f * g * b * sin_ecc_long - f);
+        let y = sma * ((1.0 - f.powi(2) * b) * sin_ecc_long + f * g * b * cos_ecc_long - g);
+        let n = (self.frame.gm() / sma.powi(3)).sqrt();
+        let rmag = sma * (1.0 - f * cos_ecc_long - g * sin_ecc_long);
+        let x_dot = sma.powi(2) * n / rmag
+            * (f * g * b * cos_ecc_long - (1.0 - g.powi(2) * b) * sin_ecc_long);
+        let y_dot = sma.powi(2) * n / rmag
+            * ((1.0 - f.powi(2) * b) * cos_ecc_long - f * g * b * sin_ecc_long);
+        let (f_hat, g_hat) = Self::basis(h, k);
+        let r = x * f_hat + y * g_hat;
+        let v = x_dot * f_hat + y_dot * g_hat;
+        Orbit::cartesian(r[0], r[1], r[2], v[0], v[1], v[2], self.dt, self.frame)
+    }
+
+    /// Returns the unit vectors f and g of the equinoctial frame
+    fn basis(h: f64, k: f64) -> (Vector3<f64>, Vector3<f64>) {
+        let s2 = 1.0 + h.powi(2) + k.powi(2);
+        (
+            Vector3::new(1.0 + h.powi(2) - k.powi(2), 2.0 * h * k, -2.0 * k) / s2,
+            Vector3::new(2.0 * h * k, 1.0 - h.powi(2) + k.powi(2), 2.0 * h) / s2,
+        )
+    }
+
+    /// Returns the true longitude (rad) of the provided orbit, which must be that of these elements
+    fn true_longitude(&self, orbit: &Orbit) -> f64 {
+        let (f_hat, g_hat) = Self::basis(self.elements[3], self.elements[4]);
+        orbit.radius().dot(&g_hat).atan2(orbit.radius().dot(&f_hat))
+    }
+
+    pub fn sma(&self) -> f64 {
+        self.elements[0]
+    }
+
+    pub fn ecc(&self) -> f64 {
+        self.elements[1].hypot(self.elements[2])
+    }
+
+    /// Returns the inclination in degrees
+    pub fn inc(&self) -> f64 {
+        2.0 * self.elements[3].hypot(self.elements[4]).atan().to_degrees()
+    }
+
+    /// Returns the Keplerian mean motion (rad/s)
+    pub fn mean_motion(&self) -> f64 {
+        (self.frame.gm() / self.sma().powi(3)).sqrt()
+    }
+}
+
+impl fmt::Display for Equinoctial {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "[{}] {}\tsma = {:.6} km\tf = {:.6e}\tg = {:.6e}\th = {:.6e}\tk = {:.6e}\tλ = {:.6} deg",
+            self.frame,
+            self.dt.as_gregorian_utc_str(),
+            self.elements[0],
+            self.elements[1],
+            self.elements[2],
+            self.elements[3],
+            self.elements[4],
+            self.elements[5].to_degrees()
+        )
+    }
+}
+
+/// A semi-analytic propagator, in the spirit of the Draper Semi-analytic Satellite Theory (DSST).
+///
+/// The mean equinoctial elements are integrated with a fixed step RK4 of typically a day, using the perturbations of the
+/// orbital dynamics and the force models of the spacecraft (the controller, if any, is ignored). The mean rates are
+/// the variations of the elements due to these perturbations (Gauss equations) averaged over one revolution, with the
+/// epoch held fixed, e.g. the third bodies do not move during the averaging. The resonant models are instead sampled at
+/// the epoch when the spacecraft reaches each point of the revolution: these should be the tesseral harmonics of
+/// geosynchronous orbits (and not also be in the dynamics), otherwise their effect on the drift is averaged out.
+/// The samples are taken on the osculating path, so the main second order terms (e.g. J2²) are included.
+/// The short periodic terms used to rebuild the osculating orbit are the first order terms computed from the Fourier
+/// series of the same samples.
+///
+/// Reference: Danielson et al., "Semianalytic Satellite Theory", NPS-MA-95-002, 1995.
+#[derive(Clone)]
+pub struct SemiAnalytic<'a> {
+    pub dynamics: Arc<Spacecraft<'a>>,
+    /// Acceleration models averaged at the epochs of the spacecraft along its revolution
+    pub resonant: Vec<Arc<dyn AccelModel + Sync + 'a>>,
+    /// Integration step of the mean elements
+    pub step: Duration,
+    /// Number of samples over a revolution, the short periodic terms are of order strictly below half of it
+    pub num_nodes: usize,
+    pub max_iter: usize,
+}
+
+impl<'a> SemiAnalytic<'a> {
+    /// Initializes a semi-analytic propagator with 32 samples per revolution
+    pub fn new(dynamics: Arc<Spacecraft<'a>>, step: Duration) -> Self {
+        Self {
+            dynamics,
+            resonant: Vec::new(),
+            step,
+            num_nodes: 32,
+            max_iter: 20,
+        }
+    }
+
+    /// Returns the perturbing acceleration (km/s^2), i.e. all but the two body acceleration, with the resonant models
+    /// evaluated at the provided epoch
+    fn perturbation(
+        &self,
+        sc: &SpacecraftState,
+        resonant_dt: Epoch,
+    ) -> Result<Vector3<f64>, NyxError> {
+        let mut accel = Vector3::zeros();
+        for model in &self.dynamics.orbital_dyn.accel_models {
+            accel += model.eom(&sc.orbit)?;
+        }
+        let mass = sc.dry_mass_kg + sc.fuel_mass_kg;
+        for model in &self.dynamics.force_models {
+            accel += model.eom(sc)? / mass;
+        }
+        let mut resonant_orbit = sc.orbit;
+        resonant_orbit.dt = resonant_dt;
+        for model in &self.resonant {
+            accel += model.eom(&resonant_orbit)?;
+        }
+        Ok(accel)
+    }
+
+    /// Returns the rates of the equinoctial elements of this osculating state due to the perturbations, from the
+    /// Gauss variational equations.
+    fn osculating_rates(
+        &self,
+        sc: &SpacecraftState,
+        resonant_dt: Epoch,
+    ) -> Result<Vector6<f64>, NyxError> {
+        let accel = self.perturbation(sc, resonant_dt)?;
+        let accel_rcn = sc.orbit.dcm_to_inertial(Frame::RCN).transpose() * accel;
+        let eq = Equinoctial::from_orbit(&sc.orbit);
+        let elements = [
+            eq.elements[0],
+            eq.elements[1],
+            eq.elements[2],
+            eq.elements[3],
+            eq.elements[4],
+        ];
+        let true_long = eq.true_longitude(&sc.orbit);
+        let gm = sc.orbit.frame.gm();
+        Ok(Vector6::from_iterator((0..6).map(|element| {
+            equinoctial_gauss_rate(gm, &elements, true_long, element).dot(&accel_rcn)
+        })))
+    }
+
+    /// Returns the offsets in mean longitude of the samples, centered on the current mean longitude
+    fn offsets(&self) -> Vec<f64> {
+        (0..self.num_nodes)
+            .map(|j| -PI + 2.0 * PI * (j as f64) / (self.num_nodes as f64))
+            .collect()
+    }
+
+    /// Samples the osculating rates over one revolution of these mean elements, optionally shifted by the short
+    /// periodic terms of each sample.
+    fn sample(
+        &self,
+        mean: &Equinoctial,
+        sc: &SpacecraftState,
+        shifts: Option<&[Vector6<f64>]>,
+    ) -> Result<Vec<Vector6<f64>>, NyxError> {
+        let n = mean.mean_motion();
+        self.offsets()
+            .iter()
+            .enumerate()
+            .map(|(j, phi)| {
+                let mut node = *mean;
+                node.elements[5] += phi;
+                if let Some(shifts) = shifts {
+                    node.elements += shifts[j];
+                }
+                let mut node_sc = *sc;
+                node_sc.orbit = node.to_orbit();
+                // Epoch when the spacecraft reaches this mean longitude
+                self.osculating_rates(&node_sc, mean.dt + (phi / n) * TimeUnit::Second)
+            })
+            .collect()
+    }
+
+    /// Returns the short periodic terms at each of the provided offsets in mean longitude, from the Fourier series of
+    /// the samples. The short periodic mean longitude includes the effect of the short periodic semi-major axis.
+    fn short_periodics_at(
+        &self,
+        mean: &Equinoctial,
+        samples: &[Vector6<f64>],
+        offsets: &[f64],
+    ) -> Vec<Vector6<f64>> {
+        let n = mean.mean_motion();
+        let num = self.num_nodes as f64;
+        let nodes = self.offsets();
+        let mut terms = vec![Vector6::zeros(); offsets.len()];
+        for m in 1..(self.num_nodes + 1) / 2 {
+            let mf = m as f64;
+            let mut cos_coeffs = Vector6::zeros();
+            let mut sin_coeffs = Vector6::zeros();
+            for (phi, rates) in nodes.iter().zip(samples) {
+                cos_coeffs += rates * (2.0 / num) * (mf * phi).cos();
+                sin_coeffs += rates * (2.0 / num) * (mf * phi).sin();
+            }
+            // The short periodic semi-major axis is (C sin(mφ) - S cos(mφ)) / (m n), which changes the mean motion
+            cos_coeffs[5] += 1.5 * sin_coeffs[0] / (mf * mean.sma());
+            sin_coeffs[5] -= 1.5 * cos_coeffs[0] / (mf * mean.sma());
+            for (term, phi) in terms.iter_mut().zip(offsets) {
+                *term += (cos_coeffs * (mf * phi).sin() - sin_coeffs * (mf * phi).cos()) / (mf * n);
+            }
+        }
+        terms
+    }
+
+    /// Returns the mean rates of the equinoctial elements, including the mean motion
+    pub fn mean_rates(
+        &self,
+        mean: &Equinoctial,
+        sc: &SpacecraftState,
+    ) -> Result<Vector6<f64>, NyxError> {
+        let samples = self.sample(mean, sc, None)?;
+        let shifts = self.short_periodics_at(mean, &samples, &self.offsets());
+        let osc_samples = self.sample(mean, sc, Some(&shifts))?;
+        let num = self.num_nodes as f64;
+        let mut rates = osc_samples.iter().sum::<Vector6<f64>>() / num;
+        // Average the mean motion on the osculating path too
+        rates[5] += shifts
+            .iter()
+            .map(|shift| (mean.frame.gm() / (mean.sma() + shift[0]).powi(3)).sqrt())
+            .sum::<f64>()
+            / num;
+        Ok(rates)
+    }
+
+    /// Returns the short periodic terms of these mean elements, i.e. the osculating minus the mean elements
+    pub fn short_periodics(
+        &self,
+        mean: &Equinoctial,
+        sc: &SpacecraftState,
+    ) -> Result<Vector6<f64>, NyxError> {
+        let samples = self.sample(mean, sc, None)?;
+        Ok(self.short_periodics_at(mean, &samples, &[0.0])[0])
+    }
+
+    /// Returns the mean elements of this osculating state, by iterating on the short periodic terms
+    pub fn to_mean(&self, sc: &SpacecraftState) -> Result<Equinoctial, NyxError> {
+        let osc = Equinoctial::from_orbit(&sc.orbit);
+        let mut mean = osc;
+        for _ in 0..self.max_iter {
+            let next = osc.elements - self.short_periodics(&mean, sc)?;
+            let converged = (next - mean.elements).abs().max() < 1e-12 * mean.sma();
+            mean.elements = next;
+            if converged {
+                return Ok(mean);
+            }
+        }
+        Err(NyxError::MaxIterReached(self.max_iter))
+    }
+
+    /// Returns the osculating state of these mean elements, with the masses of the provided state
+    pub fn to_osculating(
+        &self,
+        mean: &Equinoctial,
+        sc: &SpacecraftState,
+    ) -> Result<SpacecraftState, NyxError> {
+        let mut osc = *mean;
+        osc.elements += self.short_periodics(mean, sc)?;
+        osc.elements[5] = osc.elements[5].rem_euclid(2.0 * PI);
+        let mut osc_sc = *sc;
+        osc_sc.orbit = osc.to_orbit();
+        Ok(osc_sc)
+    }
+
+    /// Integrates the mean elements for one step with an RK4
+    fn rk4(
+        &self,
+        mean: &Equinoctial,
+        sc: &SpacecraftState,
+        step_s: f64,
+    ) -> Result<Equinoctial, NyxError> {
+        let stage = |delta: Vector6<f64>, dt: f64| -> Equinoctial {
+            let mut state = *mean;
+            state.elements += delta;
+            state.dt = mean.dt + dt * TimeUnit::Second;
+            state
+        };
+        let k1 = self.mean_rates(mean, sc)?;
+        let k2 = self.mean_rates(&stage(k1 * step_s / 2.0, step_s / 2.0), sc)?;
+        let k3 = self.mean_rates(&stage(k2 * step_s / 2.0, step_s / 2.0), sc)?;
+        let k4 = self.mean_rates(&stage(k3 * step_s, step_s), sc)?;
+        let mut next = stage((k1 + 2.0 * k2 + 2.0 * k3 + k4) * step_s / 6.0, step_s);
+        next.elements[5] = next.elements[5].rem_euclid(2.0 * PI);
+        Ok(next)
+    }
+
+    /// Propagates these mean elements for the provided duration, with the masses of the provided state
+    pub fn propagate_mean(
+        &self,
+        mean: &Equinoctial,
+        sc: &SpacecraftState,
+        duration: Duration,
+    ) -> Result<Equinoctial, NyxError> {
+        let step_s = self.step.in_seconds().abs();
+        let total_s = duration.in_seconds();
+        let mut mean = *mean;
+        let mut elapsed_s = 0.0;
+        while elapsed_s < total_s.abs() {
+            let this_step = step_s.min(total_s.abs() - elapsed_s);
+            mean = self.rk4(&mean, sc, this_step * total_s.signum())?;
+            elapsed_s += this_step;
+        }
+        Ok(mean)
+    }
+
+    /// Propagates the mean elements of this osculating state for the provided duration, and returns the osculating
+    /// state at each step (including the initial and final states).
+    pub fn history(
+        &self,
+        sc: &SpacecraftState,
+        duration: Duration,
+    ) -> Result<Vec<SpacecraftState>, NyxError> {
+        let step_s = self.step.in_seconds().abs();
+        let total_s = duration.in_seconds();
+        let mut mean = self.to_mean(sc)?;
+        let mut states = vec![*sc];
+        let mut elapsed_s = 0.0;
+        while elapsed_s < total_s.abs() {
+            let this_step = step_s.min(total_s.abs() - elapsed_s);
+            mean = self.rk4(&mean, sc, this_step * total_s.signum())?;
+            elapsed_s += this_step;
+            states.push(self.to_osculating(&mean, sc)?);
+        }
+        debug!(
+            "semi-analytic propagation of {} steps, final mean elements {}",
+            states.len() - 1,
+            mean
+        );
+        Ok(states)
+    }
+
+    /// Propagates the mean elements of this osculating state for the provided duration, and returns the final
+    /// osculating state.
+    pub fn propagate(
+        &self,
+        sc: &SpacecraftState,
+        duration: Duration,
+    ) -> Result<SpacecraftState, NyxError> {
+        Ok(*self.history(sc, duration)?.last().unwrap())
+    }
+}
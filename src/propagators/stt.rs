@@ -0,0 +1,199 @@
+use super::{RK, RK89};
+use crate::celestia::Orbit;
+use crate::dimensions::{DVector, Matrix6, Vector6, U3};
+use crate::dynamics::orbital::OrbitalDynamics;
+use crate::dynamics::Dynamics;
+use crate::errors::NyxError;
+use crate::time::{Duration, TimeUnit};
+use std::sync::Arc;
+
+/// Size of the propagated vector: the state, the STM and the second order STT
+const STT_VEC_SIZE: usize = 6 + 36 + 216;
+
+/// The first and second order state transition tensors of an orbit propagation
+#[derive(Copy, Clone, Debug)]
+pub struct StateTransitionTensor {
+    /// Propagated orbit, whose STM is the first order state transition tensor
+    pub orbit: Orbit,
+    /// Second order state transition tensor, where `stt[i][(a, b)]` is the second partial of the i-th component of
+    /// the propagated state with respect to the a-th and b-th components of the initial state
+    pub stt: [Matrix6<f64>; 6],
+}
+
+impl StateTransitionTensor {
+    /// Returns the first order state transition tensor
+    pub fn stm(&self) -> Matrix6<f64> {
+        self.orbit.stm.unwrap()
+    }
+
+    /// Returns the deviation of the propagated state caused by the provided deviation of the initial state, to the
+    /// second order.
+    pub fn deviation(&self, init_deviation: &Vector6<f64>) -> Vector6<f64> {
+        let mut deviation = self.stm() * init_deviation;
+        for (i, stt_i) in self.stt.iter().enumerate() {
+            deviation[i] += 0.5 * init_deviation.dot(&(stt_i * init_deviation));
+        }
+        deviation
+    }
+
+    /// Returns the shift of the mean of the propagated state caused by an initial state of zero mean deviation and
+    /// of the provided covariance. This shift is zero with the STM only, i.e. with a linear propagation.
+    pub fn mean_shift(&self, init_covar: &Matrix6<f64>) -> Vector6<f64> {
+        Vector6::from_iterator(
+            self.stt
+                .iter()
+                .map(|stt_i| 0.5 * stt_i.component_mul(init_covar).sum()),
+        )
+    }
+}
+
+/// A fixed step RK89 propagator of the state transition tensors of orbital dynamics, up to the second order.
+///
+/// The second order tensor is needed for nonlinear uncertainty propagation and second order differential corrections.
+/// Its derivative requires the second partials of the acceleration models, see `AccelModel::hessian`. This propagates
+/// 258 components (the state, the STM and the second order tensor), so prefer the STM if the deviations are small.
+///
+/// Reference: Park and Scheeres, "Nonlinear mapping of Gaussian statistics: theory and applications to spacecraft
+/// trajectory design", Journal of Guidance, Control, and Dynamics 29, 2006.
+#[derive(Clone)]
+pub struct SttPropagator<'a> {
+    pub dynamics: Arc<OrbitalDynamics<'a>>,
+    /// Integration step, the last one is shortened to end at the requested epoch
+    pub step: Duration,
+}
+
+impl<'a> SttPropagator<'a> {
+    pub fn new(dynamics: Arc<OrbitalDynamics<'a>>, step: Duration) -> Self {
+        Self { dynamics, step }
+    }
+
+    /// Returns the derivative of the propagated vector, at the provided duration in seconds after the initial orbit
+    fn derivative(
+        &self,
+        init: &Orbit,
+        delta_t_s: f64,
+        vec: &DVector<f64>,
+    ) -> Result<DVector<f64>, NyxError> {
+        let mut osc = *init;
+        osc.x = vec[0];
+        osc.y = vec[1];
+        osc.z = vec[2];
+        osc.vx = vec[3];
+        osc.vy = vec[4];
+        osc.vz = vec[5];
+        osc.dt = init.dt + delta_t_s * TimeUnit::Second;
+        osc.stm = None;
+
+        let state = Vector6::from_iterator(vec.iter().take(6).cloned());
+        let (deriv, grad) = self.dynamics.eom_grad(0.0, &state, &osc)?;
+        let hessian = self.dynamics.hessian(&osc)?;
+        let stm = Matrix6::from_row_slice(&vec.as_slice()[6..42]);
+        let stm_pos = stm.fixed_rows::<U3>(0);
+
+        let mut d_vec = DVector::zeros(STT_VEC_SIZE);
+        for i in 0..6 {
+            d_vec[i] = deriv[i];
+        }
+        let stm_dt = grad * stm;
+        for i in 0..6 {
+            for j in 0..6 {
+                d_vec[6 + 6 * i + j] = stm_dt[(i, j)];
+            }
+        }
+        let stt: Vec<Matrix6<f64>> = (0..6)
+            .map(|i| Matrix6::from_row_slice(&vec.as_slice()[42 + 36 * i..78 + 36 * i]))
+            .collect();
+        for i in 0..6 {
+            let mut stt_dt = Matrix6::zeros();
+            for (alpha, stt_alpha) in stt.iter().enumerate() {
+                stt_dt += grad[(i, alpha)] * stt_alpha;
+            }
+            if i >= 3 {
+                stt_dt += stm_pos.transpose() * hessian[i - 3] * stm_pos;
+            }
+            for a in 0..6 {
+                for b in 0..6 {
+                    d_vec[42 + 36 * i + 6 * a + b] = stt_dt[(a, b)];
+                }
+            }
+        }
+        Ok(d_vec)
+    }
+
+    /// Takes an RK89 step of the provided duration in seconds
+    fn rk_step(
+        &self,
+        init: &Orbit,
+        delta_t_s: f64,
+        vec: &DVector<f64>,
+        step_s: f64,
+    ) -> Result<DVector<f64>, NyxError> {
+        let mut k = Vec::with_capacity(RK89::stages());
+        k.push(self.derivative(init, delta_t_s, vec)?);
+        let mut a_idx: usize = 0;
+        for i in 0..(RK89::stages() - 1) {
+            let mut ci = 0.0;
+            let mut wi = DVector::zeros(STT_VEC_SIZE);
+            for kj in &k[0..i + 1] {
+                let a_ij = RK89::a_coeffs()[a_idx];
+                ci += a_ij;
+                wi += a_ij * kj;
+                a_idx += 1;
+            }
+            k.push(self.derivative(init, delta_t_s + ci * step_s, &(vec + step_s * wi))?);
+        }
+        let mut next = vec.clone();
+        for (b_i, ki) in RK89::b_coeffs().iter().zip(&k) {
+            next += step_s * b_i * ki;
+        }
+        Ok(next)
+    }
+
+    /// Propagates this orbit for the provided duration, and returns its state transition tensors from the initial
+    /// epoch (the STM of the provided orbit is ignored).
+    pub fn propagate(
+        &self,
+        orbit: &Orbit,
+        duration: Duration,
+    ) -> Result<StateTransitionTensor, NyxError> {
+        let mut vec = DVector::zeros(STT_VEC_SIZE);
+        for (i, val) in [orbit.x, orbit.y, orbit.z, orbit.vx, orbit.vy, orbit.vz]
+            .iter()
+            .enumerate()
+        {
+            vec[i] = *val;
+        }
+        for i in 0..6 {
+            vec[6 + 7 * i] = 1.0;
+        }
+
+        let step_s = self.step.in_seconds().abs();
+        let total_s = duration.in_seconds();
+        let mut elapsed_s = 0.0;
+        while elapsed_s < total_s.abs() {
+            let this_step = step_s.min(total_s.abs() - elapsed_s);
+            vec = self.rk_step(
+                orbit,
+                elapsed_s * total_s.signum(),
+                &vec,
+                this_step * total_s.signum(),
+            )?;
+            elapsed_s += this_step;
+        }
+
+        let mut prop = *orbit;
+        prop.x = vec[0];
+        prop.y = vec[1];
+        prop.z = vec[2];
+        prop.vx = vec[3];
+        prop.vy = vec[4];
+        prop.vz = vec[5];
+        prop.dt = orbit.dt + duration;
+        prop.stm = Some(Matrix6::from_row_slice(&vec.as_slice()[6..42]));
+        let mut stt = [Matrix6::zeros(); 6];
+        for (i, stt_i) in stt.iter_mut().enumerate() {
+            *stt_i = Matrix6::from_row_slice(&vec.as_slice()[42 + 36 * i..78 + 36 * i]);
+        }
+        Ok(StateTransitionTensor { orbit: prop, stt })
+    }
+}
@@ -0,0 +1,296 @@
+use crate::celestia::Orbit;
+use crate::dimensions::Vector3;
+use crate::dynamics::orbital::OrbitalDynamics;
+use crate::errors::NyxError;
+use crate::time::{Duration, TimeUnit};
+use std::fmt;
+use std::sync::Arc;
+
+/// Splitting of the Hamiltonian of the orbital dynamics into two integrable parts, whose flows are the drift and the kick.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Splitting {
+    /// The drift is the free motion (kinetic energy) and the kick is the full gravity field, including two body.
+    DriftKick,
+    /// The drift is the two body motion (solved with a Kepler propagation) and the kick only includes the acceleration
+    /// models, i.e. the perturbations. The error is then proportional to the size of the perturbations, which allows
+    /// for much larger steps than the drift-kick splitting.
+    WisdomHolman,
+}
+
+/// Composition of the second order leapfrog (Störmer-Verlet) step into higher order symmetric steps.
+///
+/// Reference: Yoshida, "Construction of higher order symplectic integrators", Physics Letters A 150, 1990.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Composition {
+    Leapfrog,
+    Yoshida4,
+    Yoshida6,
+    Yoshida8,
+}
+
+impl Composition {
+    /// Returns the order of this composition
+    pub fn order(self) -> u8 {
+        match self {
+            Composition::Leapfrog => 2,
+            Composition::Yoshida4 => 4,
+            Composition::Yoshida6 => 6,
+            Composition::Yoshida8 => 8,
+        }
+    }
+
+    /// Returns the weights of the successive leapfrog steps, which sum to one
+    pub fn weights(self) -> Vec<f64> {
+        // Solution A of Yoshida for the sixth order and solution D for the eighth order
+        let half: Vec<f64> = match self {
+            Composition::Leapfrog => return vec![1.0],
+            Composition::Yoshida4 => {
+                let cbrt2 = 2.0_f64.cbrt();
+                return vec![
+                    1.0 / (2.0 - cbrt2),
+                    -cbrt2 / (2.0 - cbrt2),
+                    1.0 / (2.0 - cbrt2),
+                ];
+            }
+            Composition::Yoshida6 => vec![
+                -1.177_679_984_178_87,
+                0.235_573_213_359_357,
+                0.784_513_610_477_560,
+            ],
+            Composition::Yoshida8 => vec![
+                0.102_799_849_391_985,
+                -1.960_610_232_975_49,
+                1.938_139_137_622_76,
+                -0.158_240_635_368_243,
+                -1.444_852_236_860_48,
+                0.253_693_336_566_229,
+                0.914_844_246_229_740,
+            ],
+        };
+        let w0 = 1.0 - 2.0 * half.iter().sum::<f64>();
+        let mut weights: Vec<f64> = half.iter().rev().cloned().collect();
+        weights.push(w0);
+        weights.extend(half.iter());
+        weights
+    }
+}
+
+/// A fixed step symplectic integrator of orbital dynamics whose acceleration models are conservative, e.g. point
+/// masses and spherical harmonics. Contrary to Runge Kutta methods, the energy error of symplectic integrators remains
+/// bounded (instead of drifting) over arbitrarily long propagations, which makes them suited for long term stability
+/// and resonance studies.
+///
+/// The acceleration models are evaluated at the epoch reached by the drifts, so time dependent models are supported,
+/// although their energy is then not conserved. The STM is not propagated.
+///
+/// Reference: Wisdom and Holman, "Symplectic maps for the n-body problem", The Astronomical Journal 102, 1991.
+#[derive(Clone)]
+pub struct Symplectic<'a> {
+    pub dynamics: Arc<OrbitalDynamics<'a>>,
+    pub splitting: Splitting,
+    pub composition: Composition,
+    /// Integration step, the last one is shortened to end at the requested epoch
+    pub step: Duration,
+}
+
+impl<'a> Symplectic<'a> {
+    pub fn new(
+        dynamics: Arc<OrbitalDynamics<'a>>,
+        splitting: Splitting,
+        composition: Composition,
+        step: Duration,
+    ) -> Self {
+        Self {
+            dynamics,
+            splitting,
+            composition,
+            step,
+        }
+    }
+
+    /// Initializes a fourth order drift-kick integrator
+    pub fn yoshida4(dynamics: Arc<OrbitalDynamics<'a>>, step: Duration) -> Self {
+        Self::new(dynamics, Splitting::DriftKick, Composition::Yoshida4, step)
+    }
+
+    /// Initializes a sixth order drift-kick integrator
+    pub fn yoshida6(dynamics: Arc<OrbitalDynamics<'a>>, step: Duration) -> Self {
+        Self::new(dynamics, Splitting::DriftKick, Composition::Yoshida6, step)
+    }
+
+    /// Initializes an eighth order drift-kick integrator
+    pub fn yoshida8(dynamics: Arc<OrbitalDynamics<'a>>, step: Duration) -> Self {
+        Self::new(dynamics, Splitting::DriftKick, Composition::Yoshida8, step)
+    }
+
+    /// Initializes the (second order) Wisdom-Holman mapping
+    pub fn wisdom_holman(dynamics: Arc<OrbitalDynamics<'a>>, step: Duration) -> Self {
+        Self::new(
+            dynamics,
+            Splitting::WisdomHolman,
+            Composition::Leapfrog,
+            step,
+        )
+    }
+
+    /// Advances the position (and the epoch) for the provided duration in seconds
+    fn drift(&self, orbit: &Orbit, dt: f64) -> Result<Orbit, NyxError> {
+        match self.splitting {
+            Splitting::DriftKick => {
+                let mut next = *orbit;
+                next.x += dt * orbit.vx;
+                next.y += dt * orbit.vy;
+                next.z += dt * orbit.vz;
+                next.dt = orbit.dt + dt * TimeUnit::Second;
+                Ok(next)
+            }
+            Splitting::WisdomHolman => orbit.at_epoch(orbit.dt + dt * TimeUnit::Second),
+        }
+    }
+
+    /// Changes the velocity by the acceleration applied for the provided duration in seconds
+    fn kick(&self, orbit: &Orbit, dt: f64) -> Result<Orbit, NyxError> {
+        let mut accel = match self.splitting {
+            Splitting::DriftKick => -orbit.frame.gm() / orbit.rmag().powi(3) * orbit.radius(),
+            Splitting::WisdomHolman => Vector3::zeros(),
+        };
+        for model in &self.dynamics.accel_models {
+            accel += model.eom(orbit)?;
+        }
+        let mut next = *orbit;
+        next.vx += dt * accel[0];
+        next.vy += dt * accel[1];
+        next.vz += dt * accel[2];
+        Ok(next)
+    }
+
+    /// Performs one step, where the half drifts of consecutive leapfrog steps are merged
+    fn step(&self, orbit: &Orbit, step_s: f64, weights: &[f64]) -> Result<Orbit, NyxError> {
+        let mut next = *orbit;
+        let mut drift_weight = weights[0] / 2.0;
+        for (i, weight) in weights.iter().enumerate() {
+            next = self.drift(&next, drift_weight * step_s)?;
+            next = self.kick(&next, weight * step_s)?;
+            drift_weight = (weight + weights.get(i + 1).unwrap_or(&0.0)) / 2.0;
+        }
+        next = self.drift(&next, drift_weight * step_s)?;
+        // Avoid accumulating the round off errors of the drifts in the epoch
+        next.dt = orbit.dt + step_s * TimeUnit::Second;
+        Ok(next)
+    }
+
+    /// Propagates this orbit for the provided duration, and returns the state at each step (including the initial
+    /// and final states).
+    pub fn history(&self, orbit: &Orbit, duration: Duration) -> Result<Vec<Orbit>, NyxError> {
+        let weights = self.composition.weights();
+        let step_s = self.step.in_seconds().abs();
+        let total_s = duration.in_seconds();
+        let mut state = *orbit;
+        state.stm = None;
+        let mut states = vec![state];
+        let mut elapsed_s = 0.0;
+        while elapsed_s < total_s.abs() {
+            let this_step = step_s.min(total_s.abs() - elapsed_s);
+            state = self.step(&state, this_step * total_s.signum(), &weights)?;
+            elapsed_s += this_step;
+            if elapsed_s >= total_s.abs() {
+                // Ends exactly at the requested epoch, regardless of the round off of the steps
+                state.dt = orbit.dt + duration;
+            }
+            states.push(state);
+        }
+        debug!(
+            "{:?} {:?} propagation of {} steps",
+            self.splitting,
+            self.composition,
+            states.len() - 1
+        );
+        Ok(states)
+    }
+
+    /// Propagates this orbit for the provided duration, and returns the final state.
+    pub fn propagate(&self, orbit: &Orbit, duration: Duration) -> Result<Orbit, NyxError> {
+        Ok(*self.history(orbit, duration)?.last().unwrap())
+    }
+}
+
+/// Diagnostics of the conservation of the energy (and optionally of the Jacobi integral) along a propagation, where
+/// the drifts are relative to the initial values.
+#[derive(Copy, Clone, Debug)]
+pub struct ConservationDrift {
+    /// Initial energy (km^2/s^2)
+    pub energy: f64,
+    pub max_energy_drift: f64,
+    pub final_energy_drift: f64,
+    /// Initial Jacobi integral (km^2/s^2), if an angular velocity of the rotating frame was provided
+    pub jacobi: Option<f64>,
+    pub max_jacobi_drift: Option<f64>,
+    pub final_jacobi_drift: Option<f64>,
+}
+
+impl ConservationDrift {
+    /// Computes the drifts of the energy of these states in the provided dynamics, and of the Jacobi integral if the
+    /// angular velocity (rad/s) of the rotating frame is provided. This works with the states of any propagator.
+    pub fn from_states(
+        dynamics: &OrbitalDynamics,
+        states: &[Orbit],
+        angular_velocity: Option<Vector3<f64>>,
+    ) -> Result<Self, NyxError> {
+        if states.is_empty() {
+            return Err(NyxError::CustomError(
+                "no states to compute the drifts from".to_string(),
+            ));
+        }
+        let mut energies = Vec::with_capacity(states.len());
+        let mut jacobis = Vec::with_capacity(states.len());
+        for state in states {
+            let energy = dynamics.energy(state)?;
+            energies.push(energy);
+            if let Some(omega) = angular_velocity {
+                jacobis.push(energy - omega.dot(&state.hvec()));
+            }
+        }
+        let drifts = |values: &[f64]| -> (f64, f64) {
+            let rel = |value: &f64| ((value - values[0]) / values[0]).abs();
+            (
+                values.iter().map(rel).fold(0.0, f64::max),
+                rel(&values[values.len() - 1]),
+            )
+        };
+        let (max_energy_drift, final_energy_drift) = drifts(&energies);
+        let (jacobi, max_jacobi_drift, final_jacobi_drift) = if jacobis.is_empty() {
+            (None, None, None)
+        } else {
+            let (max_drift, final_drift) = drifts(&jacobis);
+            (Some(jacobis[0]), Some(max_drift), Some(final_drift))
+        };
+        Ok(Self {
+            energy: energies[0],
+            max_energy_drift,
+            final_energy_drift,
+            jacobi,
+            max_jacobi_drift,
+            final_jacobi_drift,
+        })
+    }
+}
+
+impl fmt::Display for ConservationDrift {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "energy {:.9} km^2/s^2: max drift {:.3e}\tfinal drift {:.3e}",
+            self.energy, self.max_energy_drift, self.final_energy_drift
+        )?;
+        if let (Some(jacobi), Some(max_drift), Some(final_drift)) =
+            (self.jacobi, self.max_jacobi_drift, self.final_jacobi_drift)
+        {
+            write!(
+                f,
+                "\tJacobi {:.9} km^2/s^2: max drift {:.3e}\tfinal drift {:.3e}",
+                jacobi, max_drift, final_drift
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -1,8 +1,14 @@
-use crate::celestia::{Frame, GuidanceMode, Orbit, SpacecraftState};
+use crate::celestia::{
+    EnckeState, FormationState, Frame, GuidanceMode, KsState, Orbit, SpacecraftState,
+    FORMATION_MEMBER_SIZE, MAX_FORMATION_SIZE,
+};
 use crate::dimensions::allocator::Allocator;
 use crate::dimensions::{
-    DefaultAllocator, DimName, Matrix6, MatrixN, Vector1, VectorN, U42, U43, U6, U7,
+    DefaultAllocator, DimName, Matrix6, MatrixN, Vector6, VectorN, U10, U42, U46, U56, U6, U7,
 };
+use crate::dynamics::deltavctrl::Impulse;
+use crate::dynamics::propulsion::{MAX_TANKS, MAX_THRUSTERS};
+use crate::dynamics::spacecraft::rocket_fuel_usage;
 use crate::errors::NyxError;
 use crate::time::{Duration, Epoch};
 use std::fmt;
@@ -66,6 +72,25 @@ where
     }
 
     fn add(self, other: VectorN<f64, Self::Size>) -> Self;
+
+    /// Returns the pairs of indexes of the propagated vector where the second component is the time derivative of the
+    /// first one, e.g. the position and the velocity. These are integrated as second order equations by the
+    /// Gauss-Jackson integrator, and there are none by default.
+    fn second_order_pairs(&self) -> Vec<(usize, usize)> {
+        Vec::new()
+    }
+
+    /// Applies an impulsive maneuver to this state.
+    fn apply_impulse(&mut self, _impulse: &Impulse) -> Result<(), NyxError> {
+        Err(NyxError::CustomError(
+            "impulsive maneuvers are not supported for this state".to_string(),
+        ))
+    }
+
+    /// Number of states interpolated together in each segment of a trajectory built from published states
+    fn interp_items_per_segment() -> usize {
+        32
+    }
 }
 
 /// Implementation of Orbit as a State for orbital dynamics with STM
@@ -154,9 +179,22 @@ impl State for Orbit {
         }
     }
 
+    fn second_order_pairs(&self) -> Vec<(usize, usize)> {
+        (0..3).map(|i| (i, i + 3)).collect()
+    }
+
     fn add(self, other: VectorN<f64, Self::Size>) -> Self {
         self + other
     }
+
+    /// Applies the change in velocity of the impulse (the isp is ignored).
+    fn apply_impulse(&mut self, impulse: &Impulse) -> Result<(), NyxError> {
+        let dv = impulse.dv_inertial(self);
+        self.vx += dv[0];
+        self.vy += dv[1];
+        self.vz += dv[2];
+        Ok(())
+    }
 }
 
 impl Add<VectorN<f64, U6>> for Orbit {
@@ -186,9 +224,11 @@ impl TimeTagged for SpacecraftState {
     }
 }
 
+/// Implementation of a spacecraft as a State, where the propagated vector is the orbit (with its STM)
+/// followed by the fuel mass of each tank. If the spacecraft has no tanks, its fuel mass is the first of these.
 impl State for SpacecraftState {
     type Size = U7;
-    type PropVecSize = U43;
+    type PropVecSize = U46;
     fn zeros() -> Self {
         Self {
             orbit: Orbit::zeros(),
@@ -196,24 +236,44 @@ impl State for SpacecraftState {
             fuel_mass_kg: 0.0,
             thruster: None,
             mode: GuidanceMode::Coast,
+            tanks: [None; MAX_TANKS],
+            thrusters: [None; MAX_THRUSTERS],
         }
     }
 
-    fn as_vector(&self) -> Result<VectorN<f64, U43>, NyxError> {
+    fn as_vector(&self) -> Result<VectorN<f64, U46>, NyxError> {
         let orb_vec: VectorN<f64, U42> = self.orbit.as_vector()?;
-        Ok(VectorN::<f64, U43>::from_iterator(
-            orb_vec
-                .iter()
-                .chain(Vector1::new(self.fuel_mass_kg).iter())
-                .cloned(),
-        ))
+        let mut as_vec = VectorN::<f64, U46>::zeros();
+        for (i, val) in orb_vec.iter().enumerate() {
+            as_vec[i] = *val;
+        }
+        if self.has_tanks() {
+            for (i, tank) in self.tanks.iter().enumerate() {
+                if let Some(tank) = tank {
+                    as_vec[U42::dim() + i] = tank.fuel_mass_kg;
+                }
+            }
+        } else {
+            as_vec[U42::dim()] = self.fuel_mass_kg;
+        }
+        Ok(as_vec)
     }
 
-    fn set(&mut self, epoch: Epoch, vector: &VectorN<f64, U43>) -> Result<(), NyxError> {
+    fn set(&mut self, epoch: Epoch, vector: &VectorN<f64, U46>) -> Result<(), NyxError> {
         self.set_epoch(epoch);
         let orbit_vec = vector.fixed_rows::<U42>(0).into_owned();
         self.orbit.set(epoch, &orbit_vec)?;
-        self.fuel_mass_kg = vector[U43::dim() - 1];
+        if self.has_tanks() {
+            self.fuel_mass_kg = 0.0;
+            for (i, tank) in self.tanks.iter_mut().enumerate() {
+                if let Some(tank) = tank {
+                    tank.fuel_mass_kg = vector[U42::dim() + i];
+                    self.fuel_mass_kg += tank.fuel_mass_kg;
+                }
+            }
+        } else {
+            self.fuel_mass_kg = vector[U42::dim()];
+        }
         Ok(())
     }
 
@@ -234,9 +294,61 @@ impl State for SpacecraftState {
         }
     }
 
+    fn second_order_pairs(&self) -> Vec<(usize, usize)> {
+        (0..3).map(|i| (i, i + 3)).collect()
+    }
+
     fn add(self, other: VectorN<f64, Self::Size>) -> Self {
         self + other
     }
+
+    /// Applies the change in velocity of the impulse, and decrements the fuel mass using the rocket equation.
+    /// If the spacecraft has several tanks, the fuel is taken from the tank feeding its first thruster.
+    fn apply_impulse(&mut self, impulse: &Impulse) -> Result<(), NyxError> {
+        let isp = match impulse.isp {
+            Some(isp) => isp,
+            None => match (self.thrusters[0], self.thruster) {
+                (Some(mounted), _) => mounted.thruster.isp,
+                (None, Some(thruster)) => thruster.isp,
+                (None, None) => return Err(NyxError::CtrlExistsButNoThrusterAvail),
+            },
+        };
+        let dv = impulse.dv_inertial(&self.orbit);
+        let fuel_usage = rocket_fuel_usage(self.dry_mass_kg + self.fuel_mass_kg, dv.norm(), isp);
+
+        // Check that there is enough fuel before changing the state
+        let feeding_tank = match self.thrusters[0] {
+            Some(mounted) => self.tanks[mounted.tank].map(|tank| (mounted.tank, tank)),
+            None => None,
+        };
+        if let Some((idx, tank)) = feeding_tank {
+            if tank.fuel_mass_kg < fuel_usage {
+                error!("negative fuel mass in tank #{} at {}", idx, self.epoch());
+                return Err(NyxError::TankExhausted(idx));
+            }
+        }
+        if self.fuel_mass_kg < fuel_usage {
+            error!("negative fuel mass at {}", self.epoch());
+            return Err(NyxError::FuelExhausted);
+        }
+
+        if let Some((idx, _)) = feeding_tank {
+            if let Some(tank) = self.tanks[idx].as_mut() {
+                tank.fuel_mass_kg -= fuel_usage;
+            }
+        }
+        self.fuel_mass_kg -= fuel_usage;
+        self.orbit.vx += dv[0];
+        self.orbit.vy += dv[1];
+        self.orbit.vz += dv[2];
+        Ok(())
+    }
+
+    // Bug? With a spacecraft, we need more interpolation windows than just an orbit.
+    // I've spent 12h trying to understand why, but I can't, so screw it for it.
+    fn interp_items_per_segment() -> usize {
+        16
+    }
 }
 
 impl Add<VectorN<f64, U7>> for SpacecraftState {
@@ -274,6 +386,207 @@ impl Add<VectorN<f64, U6>> for SpacecraftState {
     }
 }
 
+impl TimeTagged for FormationState {
+    fn epoch(&self) -> Epoch {
+        self.members[0].epoch()
+    }
+
+    fn set_epoch(&mut self, epoch: Epoch) {
+        for sc in self.members.iter_mut() {
+            sc.set_epoch(epoch);
+        }
+    }
+}
+
+/// Implementation of a formation as a State, where each member is stored as [x, y, z, vx, vy, vz, fuel mass].
+impl State for FormationState {
+    type Size = U56;
+    type PropVecSize = U56;
+
+    fn zeros() -> Self {
+        let mut sc = SpacecraftState::zeros();
+        sc.orbit.stm = None;
+        Self {
+            members: [sc; MAX_FORMATION_SIZE],
+            count: 0,
+        }
+    }
+
+    fn as_vector(&self) -> Result<VectorN<f64, U56>, NyxError> {
+        let mut as_vec = VectorN::<f64, U56>::zeros();
+        for (i, sc) in self.members.iter().take(self.count).enumerate() {
+            let offset = i * FORMATION_MEMBER_SIZE;
+            as_vec[offset] = sc.orbit.x;
+            as_vec[offset + 1] = sc.orbit.y;
+            as_vec[offset + 2] = sc.orbit.z;
+            as_vec[offset + 3] = sc.orbit.vx;
+            as_vec[offset + 4] = sc.orbit.vy;
+            as_vec[offset + 5] = sc.orbit.vz;
+            as_vec[offset + 6] = sc.fuel_mass_kg;
+        }
+        Ok(as_vec)
+    }
+
+    fn set(&mut self, epoch: Epoch, vector: &VectorN<f64, U56>) -> Result<(), NyxError> {
+        self.set_epoch(epoch);
+        for (i, sc) in self.members.iter_mut().take(self.count).enumerate() {
+            let offset = i * FORMATION_MEMBER_SIZE;
+            sc.orbit.x = vector[offset];
+            sc.orbit.y = vector[offset + 1];
+            sc.orbit.z = vector[offset + 2];
+            sc.orbit.vx = vector[offset + 3];
+            sc.orbit.vy = vector[offset + 4];
+            sc.orbit.vz = vector[offset + 5];
+            sc.fuel_mass_kg = vector[offset + 6];
+        }
+        Ok(())
+    }
+
+    /// The STM is not computed for formations.
+    fn stm(&self) -> Result<MatrixN<f64, U56>, NyxError> {
+        Err(NyxError::StateTransitionMatrixUnset)
+    }
+
+    fn second_order_pairs(&self) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::with_capacity(3 * self.count);
+        for member in 0..self.count {
+            let offset = member * FORMATION_MEMBER_SIZE;
+            pairs.extend((0..3).map(|i| (offset + i, offset + i + 3)));
+        }
+        pairs
+    }
+
+    fn add(self, other: VectorN<f64, Self::Size>) -> Self {
+        let mut me = self;
+        for (i, sc) in me.members.iter_mut().take(self.count).enumerate() {
+            let offset = i * FORMATION_MEMBER_SIZE;
+            *sc = *sc
+                + VectorN::<f64, U7>::from_iterator(
+                    other
+                        .iter()
+                        .skip(offset)
+                        .take(FORMATION_MEMBER_SIZE)
+                        .cloned(),
+                );
+        }
+        me
+    }
+}
+
+impl TimeTagged for EnckeState {
+    fn epoch(&self) -> Epoch {
+        self.dt
+    }
+
+    fn set_epoch(&mut self, epoch: Epoch) {
+        self.dt = epoch
+    }
+}
+
+/// Implementation of an Encke state as a State, where the propagated vector is the deviation from the reference orbit.
+impl State for EnckeState {
+    type Size = U6;
+    type PropVecSize = U6;
+
+    fn zeros() -> Self {
+        let mut orbit = Orbit::zeros();
+        orbit.stm = None;
+        Self::new(&orbit)
+    }
+
+    fn as_vector(&self) -> Result<Vector6<f64>, NyxError> {
+        Ok(self.deviation)
+    }
+
+    fn set(&mut self, epoch: Epoch, vector: &Vector6<f64>) -> Result<(), NyxError> {
+        self.set_epoch(epoch);
+        self.deviation = *vector;
+        Ok(())
+    }
+
+    /// The STM is not computed with Encke's method.
+    fn stm(&self) -> Result<Matrix6<f64>, NyxError> {
+        Err(NyxError::StateTransitionMatrixUnset)
+    }
+
+    fn second_order_pairs(&self) -> Vec<(usize, usize)> {
+        (0..3).map(|i| (i, i + 3)).collect()
+    }
+
+    fn add(self, other: Vector6<f64>) -> Self {
+        let mut me = self;
+        me.deviation += other;
+        me
+    }
+
+    /// Applies the change in velocity of the impulse to the deviation (the isp is ignored).
+    fn apply_impulse(&mut self, impulse: &Impulse) -> Result<(), NyxError> {
+        let dv = impulse.dv_inertial(&self.orbit()?);
+        for i in 0..3 {
+            self.deviation[i + 3] += dv[i];
+        }
+        Ok(())
+    }
+}
+
+impl TimeTagged for KsState {
+    /// Returns the fictitious clock of this state, cf. `physical_epoch` for the physical epoch.
+    fn epoch(&self) -> Epoch {
+        self.clock
+    }
+
+    fn set_epoch(&mut self, epoch: Epoch) {
+        self.clock = epoch
+    }
+}
+
+/// Implementation of a state in Kustaanheimo-Stiefel coordinates as a State, where the propagated vector is the KS
+/// position, its derivative, the negative of the Keplerian energy and the physical time elapsed.
+impl State for KsState {
+    type Size = U10;
+    type PropVecSize = U10;
+
+    fn zeros() -> Self {
+        let mut orbit = Orbit::zeros();
+        orbit.x = 1.0;
+        orbit.stm = None;
+        Self::from_orbit(&orbit)
+    }
+
+    fn as_vector(&self) -> Result<VectorN<f64, U10>, NyxError> {
+        Ok(self.elements)
+    }
+
+    fn set(&mut self, epoch: Epoch, vector: &VectorN<f64, U10>) -> Result<(), NyxError> {
+        self.set_epoch(epoch);
+        self.elements = *vector;
+        Ok(())
+    }
+
+    /// The STM is not computed in KS coordinates.
+    fn stm(&self) -> Result<MatrixN<f64, U10>, NyxError> {
+        Err(NyxError::StateTransitionMatrixUnset)
+    }
+
+    fn add(self, other: VectorN<f64, U10>) -> Self {
+        let mut me = self;
+        me.elements += other;
+        me
+    }
+
+    /// Applies the change in velocity of the impulse (the isp is ignored), and converts the new orbit back to KS
+    /// coordinates, keeping the fictitious clock.
+    fn apply_impulse(&mut self, impulse: &Impulse) -> Result<(), NyxError> {
+        let mut orbit = self.orbit();
+        orbit.apply_impulse(impulse)?;
+        let (clock, scale) = (self.clock, self.scale);
+        *self = Self::from_orbit(&orbit);
+        self.clock = clock;
+        self.scale = scale;
+        Ok(())
+    }
+}
+
 #[test]
 fn test_set_state() {
     let delta_t_s: f64 = 0.0;
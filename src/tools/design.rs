@@ -0,0 +1,551 @@
+use crate::celestia::{Bodies, Cosm, Frame, LTCorr, Orbit, EARTH_ROTATION_RATE};
+use crate::dynamics::sph_harmonics::Harmonics;
+use crate::dynamics::OrbitalDynamics;
+use crate::errors::NyxError;
+use crate::io::gravity::GravityPotentialStor;
+use crate::propagators::{PropOpts, Propagator};
+use crate::time::{Duration, Epoch, TimeUnit, SECONDS_PER_DAY};
+use crate::utils::{between_0_360, between_pm_180};
+use std::f64::consts::PI;
+use std::fmt;
+use std::sync::Arc;
+
+/// Mean motion of the Earth around the Sun over a tropical year (rad/s)
+pub const EARTH_SUN_RATE: f64 = 2.0 * PI / (365.242_189_7 * SECONDS_PER_DAY);
+
+/// Number of samples per revolution used to compute the mean elements in the verification report
+const SAMPLES_PER_REV: usize = 36;
+
+/// The mean elements of a designed orbit, in the inertial frame of the designer (km and degrees)
+#[derive(Copy, Clone, Debug)]
+pub struct OrbitDesign {
+    pub sma: f64,
+    pub ecc: f64,
+    pub inc: f64,
+    pub raan: f64,
+    pub aop: f64,
+    /// Number of revolutions and of nodal days of the repeat cycle of the ground track, if any
+    pub repeat: Option<(u32, u32)>,
+    pub sun_synchronous: bool,
+    pub frozen: bool,
+}
+
+impl OrbitDesign {
+    /// Returns the same design with the provided right ascension of the ascending node (degrees)
+    pub fn with_raan(self, raan: f64) -> Self {
+        let mut me = self;
+        me.raan = between_0_360(raan);
+        me
+    }
+}
+
+impl fmt::Display for OrbitDesign {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "sma = {:.6} km\tecc = {:.6}\tinc = {:.6} deg\traan = {:.6} deg\taop = {:.6} deg",
+            self.sma, self.ecc, self.inc, self.raan, self.aop
+        )?;
+        if self.sun_synchronous {
+            write!(f, "\tsun-synchronous")?;
+        }
+        if self.frozen {
+            write!(f, "\tfrozen")?;
+        }
+        if let Some((revs, days)) = self.repeat {
+            write!(f, "\trepeats after {} revs in {} days", revs, days)?;
+        }
+        Ok(())
+    }
+}
+
+/// The verification of a design, from the propagation of the orbit with the zonal harmonics of the designer
+#[derive(Clone, Debug)]
+pub struct DesignReport {
+    /// Number of nodal revolutions propagated
+    pub num_revs: usize,
+    pub duration: Duration,
+    /// Drift rate of the RAAN required by the design (deg/day)
+    pub expected_raan_rate_deg_day: f64,
+    /// Drift rate of the RAAN fitted on the mean RAAN of each revolution (deg/day)
+    pub raan_rate_deg_day: f64,
+    /// Mean semi-major axis over the first and last revolutions (km)
+    pub sma_km: (f64, f64),
+    /// Mean eccentricity over the first and last revolutions
+    pub ecc: (f64, f64),
+    /// Mean argument of periapsis over the first and last revolutions (deg)
+    pub aop_deg: (f64, f64),
+    /// Difference between the longitude of the last ascending node crossing and the ground track grid of the design
+    /// (deg), only for repeat ground track designs
+    pub ground_track_drift_deg: Option<f64>,
+}
+
+impl fmt::Display for DesignReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Verification over {} revs ({:.3} days)",
+            self.num_revs,
+            self.duration.in_seconds() / SECONDS_PER_DAY
+        )?;
+        writeln!(
+            f,
+            "\tRAAN rate: {:.6} deg/day (expected {:.6} deg/day)",
+            self.raan_rate_deg_day, self.expected_raan_rate_deg_day
+        )?;
+        writeln!(
+            f,
+            "\tmean sma: {:.6} -> {:.6} km",
+            self.sma_km.0, self.sma_km.1
+        )?;
+        writeln!(f, "\tmean ecc: {:.6e} -> {:.6e}", self.ecc.0, self.ecc.1)?;
+        write!(
+            f,
+            "\tmean aop: {:.6} -> {:.6} deg",
+            self.aop_deg.0, self.aop_deg.1
+        )?;
+        if let Some(drift) = self.ground_track_drift_deg {
+            write!(f, "\n\tground track drift: {:.6} deg", drift)?;
+        }
+        Ok(())
+    }
+}
+
+/// Designs sun-synchronous, frozen and repeat ground track orbits around a geoid from its J2 and J3 zonal harmonics.
+///
+/// The design relies on the first order secular rates of J2 and on the J3 frozen eccentricity. The rotation rate and
+/// the mean motion around the Sun default to those of the Earth: update them to design orbits around other bodies.
+#[derive(Clone)]
+pub struct OrbitDesigner<S: GravityPotentialStor + Send> {
+    pub cosm: Arc<Cosm>,
+    /// Inertial frame of the designed orbits
+    pub frame: Frame,
+    /// Body fixed frame of the geoid, used for the harmonics and the ground track
+    pub fixed_frame: Frame,
+    pub stor: S,
+    /// Unnormalized J2 and J3
+    pub j2: f64,
+    pub j3: f64,
+    /// Rotation rate of the geoid (rad/s)
+    pub rotation_rate: f64,
+    /// Mean motion of the geoid around the Sun (rad/s), i.e. the precession rate of sun-synchronous orbits
+    pub sun_rate: f64,
+    pub max_iter: usize,
+}
+
+impl<S: GravityPotentialStor + Send> OrbitDesigner<S> {
+    /// Initializes the designer from the normalized zonal coefficients of the storage. As in the `Harmonics` dynamics,
+    /// only the degrees strictly below the maximum degree of the storage are used.
+    pub fn new(
+        stor: S,
+        frame: Frame,
+        fixed_frame: Frame,
+        cosm: Arc<Cosm>,
+    ) -> Result<Self, NyxError> {
+        if !frame.is_geoid() || !fixed_frame.is_geoid() {
+            return Err(NyxError::InvalidOrbitDesign(
+                "orbit design requires geoid frames".to_string(),
+            ));
+        }
+        let unnormalized = |degree: usize| {
+            if degree < stor.max_degree_n() {
+                -stor.cs_nm(degree, 0).0 * ((2 * degree + 1) as f64).sqrt()
+            } else {
+                0.0
+            }
+        };
+        let j2 = unnormalized(2);
+        let j3 = unnormalized(3);
+        if j2.abs() < std::f64::EPSILON {
+            return Err(NyxError::InvalidOrbitDesign(
+                "gravity potential has no J2".to_string(),
+            ));
+        }
+        Ok(Self {
+            cosm,
+            frame,
+            fixed_frame,
+            stor,
+            j2,
+            j3,
+            rotation_rate: EARTH_ROTATION_RATE,
+            sun_rate: EARTH_SUN_RATE,
+            max_iter: 50,
+        })
+    }
+
+    /// Returns the secular rates of the RAAN, the argument of periapsis and the mean anomaly due to J2 (rad/s)
+    pub fn secular_rates(&self, sma: f64, ecc: f64, inc: f64) -> (f64, f64, f64) {
+        let n = (self.frame.gm() / sma.powi(3)).sqrt();
+        let k = self.j2 * (self.frame.equatorial_radius() / (sma * (1.0 - ecc.powi(2)))).powi(2);
+        let cos_i = inc.to_radians().cos();
+        (
+            -1.5 * n * k * cos_i,
+            0.75 * n * k * (5.0 * cos_i.powi(2) - 1.0),
+            n * (1.0 + 0.75 * k * (1.0 - ecc.powi(2)).sqrt() * (3.0 * cos_i.powi(2) - 1.0)),
+        )
+    }
+
+    /// Returns the inclination (deg) whose RAAN precesses at the mean motion around the Sun
+    pub fn sso_inclination(&self, sma: f64, ecc: f64) -> Result<f64, NyxError> {
+        let n = (self.frame.gm() / sma.powi(3)).sqrt();
+        let k = self.j2 * (self.frame.equatorial_radius() / (sma * (1.0 - ecc.powi(2)))).powi(2);
+        let cos_i = -self.sun_rate / (1.5 * n * k);
+        if cos_i.abs() > 1.0 {
+            Err(NyxError::InvalidOrbitDesign(format!(
+                "no sun-synchronous inclination for sma = {} km",
+                sma
+            )))
+        } else {
+            Ok(cos_i.acos().to_degrees())
+        }
+    }
+
+    /// Returns the eccentricity and argument of periapsis (deg) which cancel the drift of the eccentricity vector
+    /// due to J2 and J3
+    pub fn frozen_eccentricity(&self, sma: f64, inc: f64) -> (f64, f64) {
+        let e_sin_aop = -self.j3 * self.frame.equatorial_radius() * inc.to_radians().sin()
+            / (2.0 * self.j2 * sma);
+        if e_sin_aop >= 0.0 {
+            (e_sin_aop, 90.0)
+        } else {
+            (-e_sin_aop, 270.0)
+        }
+    }
+
+    /// Designs a sun-synchronous orbit at the provided altitude above the equatorial radius, circular or frozen.
+    pub fn sun_synchronous(&self, altitude_km: f64, frozen: bool) -> Result<OrbitDesign, NyxError> {
+        let sma = self.frame.equatorial_radius() + altitude_km;
+        let mut ecc = 0.0;
+        let mut aop = 0.0;
+        let mut inc = self.sso_inclination(sma, ecc)?;
+        if frozen {
+            // The inclination barely depends on the eccentricity, so a couple of passes are enough
+            for _ in 0..2 {
+                let (frozen_ecc, frozen_aop) = self.frozen_eccentricity(sma, inc);
+                ecc = frozen_ecc;
+                aop = frozen_aop;
+                inc = self.sso_inclination(sma, ecc)?;
+            }
+        }
+        Ok(OrbitDesign {
+            sma,
+            ecc,
+            inc,
+            raan: 0.0,
+            aop,
+            repeat: None,
+            sun_synchronous: true,
+            frozen,
+        })
+    }
+
+    /// Designs an orbit whose ground track repeats after `revs` revolutions in `days` nodal days (i.e. rotations of
+    /// the geoid with respect to the ascending node). The inclination is the sun-synchronous one if none is provided.
+    pub fn repeat_ground_track(
+        &self,
+        revs: u32,
+        days: u32,
+        inc: Option<f64>,
+        frozen: bool,
+    ) -> Result<OrbitDesign, NyxError> {
+        if revs == 0 || days == 0 {
+            return Err(NyxError::InvalidOrbitDesign(
+                "repeat cycle must have revolutions and days".to_string(),
+            ));
+        }
+        let gm = self.frame.gm();
+        // Initial guess from the Keplerian period
+        let period = f64::from(days) * 2.0 * PI / (self.rotation_rate * f64::from(revs));
+        let mut sma = (gm * (period / (2.0 * PI)).powi(2)).cbrt();
+        let mut ecc = 0.0;
+        let mut aop = 0.0;
+        for _ in 0..self.max_iter {
+            let cur_inc = match inc {
+                Some(inc) => inc,
+                None => self.sso_inclination(sma, ecc)?,
+            };
+            if frozen {
+                let (frozen_ecc, frozen_aop) = self.frozen_eccentricity(sma, cur_inc);
+                ecc = frozen_ecc;
+                aop = frozen_aop;
+            }
+            let (raan_dot, aop_dot, ma_dot) = self.secular_rates(sma, ecc, cur_inc);
+            // The nodal rate must be revs/days times the rotation rate with respect to the node
+            let nodal_rate = f64::from(revs) * (self.rotation_rate - raan_dot) / f64::from(days);
+            let n = (gm / sma.powi(3)).sqrt();
+            let n_req = nodal_rate * n / (aop_dot + ma_dot);
+            let next_sma = (gm / n_req.powi(2)).cbrt();
+            if (next_sma - sma).abs() < 1e-9 {
+                if next_sma * (1.0 - ecc) < self.frame.equatorial_radius() {
+                    return Err(NyxError::InvalidOrbitDesign(format!(
+                        "repeat ground track of {} revs in {} days is below the surface",
+                        revs, days
+                    )));
+                }
+                return Ok(OrbitDesign {
+                    sma: next_sma,
+                    ecc,
+                    inc: cur_inc,
+                    raan: 0.0,
+                    aop,
+                    repeat: Some((revs, days)),
+                    sun_synchronous: inc.is_none(),
+                    frozen,
+                });
+            }
+            sma = next_sma;
+        }
+        Err(NyxError::MaxIterReached(self.max_iter))
+    }
+
+    /// Returns the RAAN (deg) for the provided local time of the ascending node (hours) at this epoch, from the
+    /// right ascension of the Sun.
+    pub fn raan_from_ltan(&self, ltan_hours: f64, epoch: Epoch) -> Result<f64, NyxError> {
+        let sun = self.cosm.try_celestial_state(
+            Bodies::Sun.ephem_path(),
+            epoch,
+            self.frame,
+            LTCorr::None,
+        )?;
+        let sun_ra = sun.y.atan2(sun.x).to_degrees();
+        Ok(between_0_360(sun_ra + 15.0 * (ltan_hours - 12.0)))
+    }
+
+    /// Returns the osculating orbit of the design at its ascending node at the provided epoch, by adding the first
+    /// order J2 short period terms of the semi-major axis and of the eccentricity vector to the mean elements.
+    pub fn orbit(&self, design: &OrbitDesign, epoch: Epoch) -> Orbit {
+        let (sma, ecc, aop) = self.osculating(design, 0.0);
+        Orbit::keplerian(
+            sma,
+            ecc,
+            design.inc,
+            design.raan,
+            aop,
+            between_0_360(-aop),
+            epoch,
+            self.frame,
+        )
+    }
+
+    /// Returns the osculating semi-major axis, eccentricity and argument of periapsis at this argument of latitude
+    /// (deg). The eccentricity vector terms are those of near circular orbits.
+    fn osculating(&self, design: &OrbitDesign, aol: f64) -> (f64, f64, f64) {
+        let sma = design.sma;
+        let ecc = design.ecc;
+        let u = aol.to_radians();
+        let ta = u - design.aop.to_radians();
+        let gamma = self.j2 * (self.frame.equatorial_radius() / sma).powi(2);
+        let sin2_i = design.inc.to_radians().sin().powi(2);
+        let a_r3 = ((1.0 + ecc * ta.cos()) / (1.0 - ecc.powi(2))).powi(3);
+        let osc_sma = sma
+            + gamma
+                * sma
+                * ((1.0 - 1.5 * sin2_i) * (a_r3 - (1.0 - ecc.powi(2)).powf(-1.5))
+                    + 1.5 * sin2_i * a_r3 * (2.0 * u).cos());
+        let ex = ecc * design.aop.to_radians().cos()
+            + 1.5
+                * gamma
+                * ((1.0 - 1.25 * sin2_i) * u.cos() + 7.0 / 12.0 * sin2_i * (3.0 * u).cos());
+        let ey = ecc * design.aop.to_radians().sin()
+            + 1.5
+                * gamma
+                * ((1.0 - 1.75 * sin2_i) * u.sin() + 7.0 / 12.0 * sin2_i * (3.0 * u).sin());
+        (
+            osc_sma,
+            (ex.powi(2) + ey.powi(2)).sqrt(),
+            between_0_360(ey.atan2(ex).to_degrees()),
+        )
+    }
+
+    /// Returns the longitude (deg) of the nearest ascending node crossing in the body fixed frame, and the time (s)
+    /// elapsed since that crossing (negative if the crossing is ahead)
+    fn last_node_crossing(&self, orbit: &Orbit, raan_dot: f64, nodal_rate: f64) -> (f64, f64) {
+        let node_long = self.cosm.frame_chg(orbit, self.fixed_frame).raan();
+        let since_node = between_pm_180(orbit.aol()).to_radians() / nodal_rate;
+        (
+            node_long + (since_node * (self.rotation_rate - raan_dot)).to_degrees(),
+            since_node,
+        )
+    }
+
+    /// Propagates the orbit of the design from this epoch with the J2 and J3 harmonics for the whole revolutions of
+    /// the provided duration, and compares its mean elements and ground track to those of the design.
+    pub fn verify(
+        &self,
+        design: &OrbitDesign,
+        epoch: Epoch,
+        duration: Duration,
+    ) -> Result<DesignReport, NyxError> {
+        let (raan_dot, aop_dot, ma_dot) = self.secular_rates(design.sma, design.ecc, design.inc);
+        let nodal_rate = aop_dot + ma_dot;
+        let nodal_period = 2.0 * PI / nodal_rate;
+        let num_revs = (duration.in_seconds() / nodal_period).floor() as usize;
+        if num_revs < 2 {
+            return Err(NyxError::InvalidOrbitDesign(
+                "verification requires at least two revolutions".to_string(),
+            ));
+        }
+        let step = (nodal_period / SAMPLES_PER_REV as f64) * TimeUnit::Second;
+
+        let init = self.orbit(design, epoch);
+        let (init_long, init_since_node) = self.last_node_crossing(&init, raan_dot, nodal_rate);
+
+        let dynamics = OrbitalDynamics::with_model(Harmonics::from_stor(
+            self.fixed_frame,
+            self.stor.clone(),
+            self.cosm.clone(),
+        ));
+        let setup = Propagator::rk89(dynamics, PropOpts::with_tolerance(1e-10));
+        let mut prop = setup.with(init);
+
+        // Mean elements of each revolution: time since epoch, unwrapped RAAN, sma, and eccentricity vector
+        let mut means = Vec::with_capacity(num_revs);
+        let mut raan = design.raan;
+        let mut orbit = init;
+        for _ in 0..num_revs {
+            let mut sums = [0.0; 5];
+            for _ in 0..SAMPLES_PER_REV {
+                orbit = prop.for_duration(step)?;
+                raan += between_pm_180(orbit.raan() - raan);
+                let aop = orbit.aop().to_radians();
+                sums[0] += (orbit.dt - epoch).in_seconds();
+                sums[1] += raan;
+                sums[2] += orbit.sma();
+                sums[3] += orbit.ecc() * aop.cos();
+                sums[4] += orbit.ecc() * aop.sin();
+            }
+            means.push(
+                sums.iter()
+                    .map(|s| s / SAMPLES_PER_REV as f64)
+                    .collect::<Vec<f64>>(),
+            );
+        }
+
+        // Least squares fit of the RAAN drift rate
+        let num = means.len() as f64;
+        let t_avg = means.iter().map(|m| m[0]).sum::<f64>() / num;
+        let raan_avg = means.iter().map(|m| m[1]).sum::<f64>() / num;
+        let (cov, var) = means.iter().fold((0.0, 0.0), |(cov, var), m| {
+            (
+                cov + (m[0] - t_avg) * (m[1] - raan_avg),
+                var + (m[0] - t_avg).powi(2),
+            )
+        });
+
+        let ground_track_drift_deg = match design.repeat {
+            Some((revs, days)) => {
+                let (long, since_node) = self.last_node_crossing(&orbit, raan_dot, nodal_rate);
+                let crossing_no = (((orbit.dt - epoch).in_seconds() - since_node
+                    + init_since_node)
+                    / nodal_period)
+                    .round();
+                let spacing = 360.0 * f64::from(days) / f64::from(revs);
+                Some(between_pm_180(long - (init_long - crossing_no * spacing)))
+            }
+            None => None,
+        };
+
+        let first = &means[0];
+        let last = &means[means.len() - 1];
+        Ok(DesignReport {
+            num_revs,
+            duration: orbit.dt - epoch,
+            expected_raan_rate_deg_day: raan_dot.to_degrees() * SECONDS_PER_DAY,
+            raan_rate_deg_day: cov / var * SECONDS_PER_DAY,
+            sma_km: (first[2], last[2]),
+            ecc: (first[3].hypot(first[4]), last[3].hypot(last[4])),
+            aop_deg: (
+                between_0_360(first[4].atan2(first[3]).to_degrees()),
+                between_0_360(last[4].atan2(last[3]).to_degrees()),
+            ),
+            ground_track_drift_deg,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::gravity::HarmonicsMem;
+
+    fn designer() -> OrbitDesigner<HarmonicsMem> {
+        let cosm = Cosm::de438();
+        let eme2k = cosm.frame("EME2000");
+        let iau_earth = cosm.frame("IAU Earth");
+        // Degree 4 in storage means that the dynamics use J2 and J3
+        let stor = HarmonicsMem::from_cof("data/JGM3.cof.gz", 4, 0, true).unwrap();
+        OrbitDesigner::new(stor, eme2k, iau_earth, cosm).unwrap()
+    }
+
+    #[test]
+    fn design_sun_synchronous() {
+        let designer = designer();
+        assert!((designer.j2 - 1.082_6e-3).abs() < 1e-6);
+        assert!((designer.j3 + 2.532e-6).abs() < 1e-8);
+
+        let epoch = Epoch::from_gregorian_utc_at_midnight(2021, 3, 20);
+        let ltan = designer.raan_from_ltan(10.5, epoch).unwrap();
+        let design = designer
+            .sun_synchronous(700.0, false)
+            .unwrap()
+            .with_raan(ltan);
+        println!("{}", design);
+        assert!((design.inc - 98.19).abs() < 0.02);
+        // Close to the March equinox, the Sun is near the vernal point, so the RAAN is near 10:30 - 12:00 = -22.5 deg
+        assert!((design.raan - 337.5).abs() < 1.0);
+
+        let orbit = designer.orbit(&design, epoch);
+        assert!((orbit.raan() - design.raan).abs() < 1e-6);
+        assert!(orbit.aol() < 1e-6 || orbit.aol() > 360.0 - 1e-6);
+
+        let report = designer.verify(&design, epoch, 2 * TimeUnit::Day).unwrap();
+        println!("{}", report);
+        assert!((report.expected_raan_rate_deg_day - 0.985_6).abs() < 1e-4);
+        assert!((report.raan_rate_deg_day / report.expected_raan_rate_deg_day - 1.0).abs() < 0.01);
+        // The mean semi-major axis is the design one thanks to the short period terms
+        assert!((report.sma_km.0 - design.sma).abs() < 0.05);
+        assert!(report.ground_track_drift_deg.is_none());
+
+        // There is no sun-synchronous orbit that high
+        assert!(designer.sun_synchronous(10_000.0, false).is_err());
+    }
+
+    #[test]
+    fn design_frozen_repeat_ground_track() {
+        let designer = designer();
+        let epoch = Epoch::from_gregorian_utc_at_midnight(2021, 6, 1);
+        // Sentinel-2 repeats its ground track after 143 revolutions in 10 days
+        let design = designer
+            .repeat_ground_track(143, 10, None, true)
+            .unwrap()
+            .with_raan(designer.raan_from_ltan(10.5, epoch).unwrap());
+        println!("{}", design);
+        assert!((design.sma - designer.frame.equatorial_radius() - 786.1).abs() < 0.5);
+        assert!((design.inc - 98.54).abs() < 0.02);
+        assert!((design.ecc - 1.03e-3).abs() < 2e-5);
+        assert!((design.aop - 90.0).abs() < 1e-9);
+
+        let report = designer
+            .verify(&design, epoch, 10.05 * TimeUnit::Day)
+            .unwrap();
+        println!("{}", report);
+        assert_eq!(report.num_revs, 143);
+        // The mean eccentricity vector stays put
+        assert!((report.ecc.0 - design.ecc).abs() < 2e-5);
+        assert!((report.ecc.1 - design.ecc).abs() < 2e-5);
+        assert!((report.aop_deg.0 - 90.0).abs() < 1.0);
+        assert!((report.aop_deg.1 - 90.0).abs() < 1.0);
+        assert!(report.ground_track_drift_deg.unwrap().abs() < 0.1);
+
+        // A repeat ground track at a fixed inclination is not sun-synchronous
+        let design = designer
+            .repeat_ground_track(15, 1, Some(51.6), false)
+            .unwrap();
+        assert!(!design.sun_synchronous);
+        assert!((design.inc - 51.6).abs() < 1e-12);
+        let (raan_dot, aop_dot, ma_dot) = designer.secular_rates(design.sma, 0.0, 51.6);
+        let nodal_day = 2.0 * PI / (designer.rotation_rate - raan_dot);
+        assert!((15.0 * 2.0 * PI / (aop_dot + ma_dot) - nodal_day).abs() < 1e-6);
+    }
+}
@@ -25,7 +25,7 @@ pub struct LambertSolution {
 /// Solves the Lambert boundary problem using a standard secant method.
 /// Given the initial and final radii, a time of flight, and a gravitational parameters, it returns the needed initial and final velocities
 /// along with φ which is the square of the difference in eccentric anomaly. Note that the direction of motion
-/// is computed directly in this function to simplify the generation of Pork chop plots.
+/// is computed directly in this function to simplify the generation of Pork chop plots (cf. `tools::porkchop`).
 pub fn standard(
     r_init: Vector3<f64>,
     r_final: Vector3<f64>,
@@ -147,6 +147,313 @@ pub fn standard(
     })
 }
 
+/// Tolerance on the x variable of Izzo's algorithm
+const IZZO_EPSILON: f64 = 1e-11;
+/// Maximum number of iterations of the Householder and Halley methods in Izzo's algorithm
+const IZZO_MAX_ITER: usize = 15;
+/// Maximum number of terms of the hypergeometric series of Battin's formulation, which converges for |z| < 1
+const HYPERGEOMETRIC_MAX_ITER: usize = 1000;
+
+/// Branch of a multi-revolution Lambert solution: for a given number of revolutions, there are two solutions
+/// for the same time of flight, on either side of the minimum time of flight.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LambertBranch {
+    /// Solution whose x variable (in Izzo's algorithm) is less than that of the minimum time of flight
+    Left,
+    /// Solution whose x variable (in Izzo's algorithm) is greater than that of the minimum time of flight
+    Right,
+}
+
+/// A solution of the Lambert problem found by Izzo's algorithm
+#[derive(Debug)]
+pub struct MultiRevSolution {
+    pub v_init: Vector3<f64>,
+    pub v_final: Vector3<f64>,
+    /// Number of complete revolutions of the transfer
+    pub revs: u8,
+    /// Branch of the solution, None for the zero revolution solution
+    pub branch: Option<LambertBranch>,
+    /// Number of iterations of the Householder method
+    pub iterations: usize,
+}
+
+/// Solves the Lambert boundary problem using Izzo's algorithm (Izzo, 2015, "Revisiting Lambert's problem").
+/// Given the initial and final radii, a time of flight, and a gravitational parameter, it returns every solution for
+/// this time of flight: first the zero revolution solution, and then the left and right branches of each number of
+/// revolutions which is feasible in this time of flight.
+///
+/// The transfer kind defines the direction of motion: `ShortWay` (resp. `LongWay`) uses a transfer angle less
+/// (resp. more) than 180 degrees, `Auto` uses prograde motion (about the Z axis), and `NRevs(n)` uses prograde motion
+/// with at most n revolutions. If the radii are exactly opposite, the transfer plane is ambiguous: the plane which
+/// is the closest to the XY plane is used.
+pub fn izzo(
+    r_init: Vector3<f64>,
+    r_final: Vector3<f64>,
+    tof: f64,
+    gm: f64,
+    kind: TransferKind,
+) -> Result<Vec<MultiRevSolution>, NyxError> {
+    if tof <= 0.0 {
+        return Err(NyxError::LambertNotReasonablePhi);
+    }
+    let r_init_norm = r_init.norm();
+    let r_final_norm = r_final.norm();
+    let chord = r_final - r_init;
+    let c = chord.norm();
+    if c < LAMBERT_EPSILON {
+        return Err(NyxError::TargetsTooClose);
+    }
+    // Semi-perimeter of the triangle
+    let s = (c + r_init_norm + r_final_norm) / 2.0;
+    let ir1 = r_init / r_init_norm;
+    let ir2 = r_final / r_final_norm;
+    let cross = ir1.cross(&ir2);
+    let ih = if cross.norm() > LAMBERT_EPSILON_RAD {
+        cross / cross.norm()
+    } else {
+        // Opposite radii: the normal of the plane is the closest to the Z axis, or to the X axis if the radii are along Z
+        let axis = if ir1[2].abs() < 1.0 - LAMBERT_EPSILON {
+            Vector3::new(0.0, 0.0, 1.0)
+        } else {
+            Vector3::new(1.0, 0.0, 0.0)
+        };
+        let normal = axis - axis.dot(&ir1) * ir1;
+        normal / normal.norm()
+    };
+
+    let lambda2 = 1.0 - c / s;
+    let mut lambda = lambda2.sqrt();
+    // The transfer angle is less than 180 degrees in the direction of ih
+    let long_way = match kind {
+        TransferKind::ShortWay => false,
+        TransferKind::LongWay => true,
+        TransferKind::Auto | TransferKind::NRevs(_) => ih[2] < 0.0,
+    };
+    let (it1, it2) = if long_way {
+        lambda = -lambda;
+        (ir1.cross(&ih), ir2.cross(&ih))
+    } else {
+        (ih.cross(&ir1), ih.cross(&ir2))
+    };
+
+    // Non-dimensional time of flight
+    let t = (2.0 * gm / s.powi(3)).sqrt() * tof;
+    let solver = IzzoSolver { lambda };
+
+    // Maximum number of revolutions
+    let t00 = lambda.acos() + lambda * (1.0 - lambda2).sqrt();
+    let mut n_max = (t / PI).floor() as u32;
+    let t0 = t00 + f64::from(n_max) * PI;
+    if n_max > 0 && t < t0 {
+        // Find the minimum time of flight of n_max revolutions with Halley's method
+        let mut x_old = 0.0;
+        let mut t_min = t0;
+        for _ in 0..IZZO_MAX_ITER {
+            let (dt, ddt, dddt) = solver.derivatives(x_old, t_min);
+            let x_new = if dt.abs() > 0.0 {
+                x_old - dt * ddt / (ddt.powi(2) - dt * dddt / 2.0)
+            } else {
+                x_old
+            };
+            let converged = (x_old - x_new).abs() < IZZO_EPSILON;
+            t_min = solver.tof(x_new, n_max)?;
+            x_old = x_new;
+            if converged {
+                break;
+            }
+        }
+        if t_min > t {
+            n_max -= 1;
+        }
+    }
+    if let TransferKind::NRevs(revs) = kind {
+        n_max = n_max.min(u32::from(revs));
+    }
+
+    let gamma = (gm * s / 2.0).sqrt();
+    let rho = (r_init_norm - r_final_norm) / c;
+    let sigma = (1.0 - rho.powi(2)).sqrt();
+    let velocities = |x: f64| {
+        let y = (1.0 - lambda2 + lambda2 * x.powi(2)).sqrt();
+        let vr1 = gamma * ((lambda * y - x) - rho * (lambda * y + x)) / r_init_norm;
+        let vr2 = -gamma * ((lambda * y - x) + rho * (lambda * y + x)) / r_final_norm;
+        let vt = gamma * sigma * (y + lambda * x);
+        (
+            vr1 * ir1 + vt / r_init_norm * it1,
+            vr2 * ir2 + vt / r_final_norm * it2,
+        )
+    };
+
+    let mut solutions = Vec::with_capacity(2 * n_max as usize + 1);
+
+    // Zero revolution solution
+    let t1 = 2.0 / 3.0 * (1.0 - lambda.powi(3));
+    let x0 = if t >= t00 {
+        -(t - t00) / (t - t00 + 4.0)
+    } else if t <= t1 {
+        t1 * (t1 - t) / (2.0 / 5.0 * (1.0 - lambda2 * lambda.powi(3)) * t) + 1.0
+    } else {
+        (t / t00).powf(2.0_f64.ln() / (t1 / t00).ln()) - 1.0
+    };
+    let (x, iterations) = solver.householder(t, x0, 0)?;
+    let (v_init, v_final) = velocities(x);
+    solutions.push(MultiRevSolution {
+        v_init,
+        v_final,
+        revs: 0,
+        branch: None,
+        iterations,
+    });
+
+    // Multi-revolution solutions
+    for revs in 1..=n_max {
+        let nrevs_pi = f64::from(revs) * PI;
+        let tmp = ((nrevs_pi + PI) / (8.0 * t)).powf(2.0 / 3.0);
+        let x_left = (tmp - 1.0) / (tmp + 1.0);
+        let tmp = ((8.0 * t) / nrevs_pi).powf(2.0 / 3.0);
+        let x_right = (tmp - 1.0) / (tmp + 1.0);
+        for (branch, x0) in &[
+            (LambertBranch::Left, x_left),
+            (LambertBranch::Right, x_right),
+        ] {
+            let (x, iterations) = solver.householder(t, *x0, revs)?;
+            let (v_init, v_final) = velocities(x);
+            solutions.push(MultiRevSolution {
+                v_init,
+                v_final,
+                revs: revs as u8,
+                branch: Some(*branch),
+                iterations,
+            });
+        }
+    }
+
+    Ok(solutions)
+}
+
+/// Non-dimensional time of flight equations of Izzo's algorithm, for a given lambda parameter
+struct IzzoSolver {
+    lambda: f64,
+}
+
+impl IzzoSolver {
+    /// Returns the non-dimensional time of flight of x for this number of revolutions
+    fn tof(&self, x: f64, revs: u32) -> Result<f64, NyxError> {
+        let battin = 0.01;
+        let lagrange = 0.2;
+        let dist = (x - 1.0).abs();
+        if dist < lagrange && dist > battin {
+            return Ok(self.tof_lagrange(x, revs));
+        }
+        let k = self.lambda.powi(2);
+        let e = x.powi(2) - 1.0;
+        let rho = e.abs();
+        let z = (1.0 + k * e).sqrt();
+        if dist < battin {
+            // Battin series
+            let eta = z - self.lambda * x;
+            let s1 = 0.5 * (1.0 - self.lambda - x * eta);
+            let q = 4.0 / 3.0 * hypergeometric(s1)?;
+            Ok((eta.powi(3) * q + 4.0 * self.lambda * eta) / 2.0
+                + f64::from(revs) * PI / rho.powf(1.5))
+        } else {
+            // Lancaster's formulation
+            let y = rho.sqrt();
+            let g = x * z - self.lambda * e;
+            let d = if e < 0.0 {
+                f64::from(revs) * PI + g.acos()
+            } else {
+                let f = y * (z - self.lambda * x);
+                (f + g).ln()
+            };
+            Ok((x - self.lambda * z - d / y) / e)
+        }
+    }
+
+    /// Lagrange's formulation of the time of flight, used close to the parabola
+    fn tof_lagrange(&self, x: f64, revs: u32) -> f64 {
+        let a = 1.0 / (1.0 - x.powi(2));
+        if a > 0.0 {
+            let alpha = 2.0 * x.acos();
+            let mut beta = 2.0 * (self.lambda.powi(2) / a).sqrt().asin();
+            if self.lambda < 0.0 {
+                beta = -beta;
+            }
+            a * a.sqrt()
+                * ((alpha - alpha.sin()) - (beta - beta.sin()) + 2.0 * PI * f64::from(revs))
+                / 2.0
+        } else {
+            let alpha = 2.0 * x.acosh();
+            let mut beta = 2.0 * (-self.lambda.powi(2) / a).sqrt().asinh();
+            if self.lambda < 0.0 {
+                beta = -beta;
+            }
+            -a * (-a).sqrt() * ((beta - beta.sinh()) - (alpha - alpha.sinh())) / 2.0
+        }
+    }
+
+    /// Returns the first three derivatives of the time of flight with respect to x
+    fn derivatives(&self, x: f64, t: f64) -> (f64, f64, f64) {
+        let l2 = self.lambda.powi(2);
+        let l3 = l2 * self.lambda;
+        let umx2 = 1.0 - x.powi(2);
+        let y = (1.0 - l2 * umx2).sqrt();
+        let y2 = y.powi(2);
+        let y3 = y2 * y;
+        let dt = (3.0 * t * x - 2.0 + 2.0 * l3 * x / y) / umx2;
+        let ddt = (3.0 * t + 5.0 * x * dt + 2.0 * (1.0 - l2) * l3 / y3) / umx2;
+        let dddt = (7.0 * x * ddt + 8.0 * dt - 6.0 * (1.0 - l2) * l2 * l3 * x / y3 / y2) / umx2;
+        (dt, ddt, dddt)
+    }
+
+    /// Solves for x such that the time of flight is t with Householder's method, starting from x0
+    fn householder(&self, t: f64, x0: f64, revs: u32) -> Result<(f64, usize), NyxError> {
+        let mut x = x0;
+        for iter in 1..=IZZO_MAX_ITER {
+            let tof = self.tof(x, revs)?;
+            let (dt, ddt, dddt) = self.derivatives(x, tof);
+            let delta = tof - t;
+            let dt2 = dt.powi(2);
+            let x_new = x - delta * (dt2 - delta * ddt / 2.0)
+                / (dt * (dt2 - delta * ddt) + dddt * delta.powi(2) / 6.0);
+            let err = (x - x_new).abs();
+            x = x_new;
+            if err < IZZO_EPSILON {
+                return Ok((x, iter));
+            }
+        }
+        Err(NyxError::MaxIterReached(IZZO_MAX_ITER))
+    }
+}
+
+/// Gauss hypergeometric function 2F1(3, 1, 5/2, z) used in Battin's series, which returns an error if the series
+/// does not converge within `HYPERGEOMETRIC_MAX_ITER` terms
+fn hypergeometric(z: f64) -> Result<f64, NyxError> {
+    let mut sj = 1.0;
+    let mut cj = 1.0;
+    for j in 0..HYPERGEOMETRIC_MAX_ITER {
+        let j = j as f64;
+        cj *= (3.0 + j) * (1.0 + j) / (2.5 + j) * z / (j + 1.0);
+        sj += cj;
+        if cj.abs() < 1e-11 {
+            return Ok(sj);
+        }
+    }
+    Err(NyxError::MaxIterReached(HYPERGEOMETRIC_MAX_ITER))
+}
+
+#[test]
+fn test_hypergeometric() {
+    // 2F1(3, 1, 5/2, 0) = 1
+    assert!((hypergeometric(0.0).unwrap() - 1.0).abs() < std::f64::EPSILON);
+    // The series diverges outside of the unit disk
+    assert_eq!(
+        hypergeometric(1.5),
+        Err(NyxError::MaxIterReached(HYPERGEOMETRIC_MAX_ITER))
+    );
+    assert!(hypergeometric(std::f64::NAN).is_err());
+}
+
 #[test]
 fn test_lambert_vallado_shortway() {
     let ri = Vector3::new(15945.34, 0.0, 0.0);
@@ -178,3 +485,94 @@ fn test_lambert_vallado_lonway() {
     assert!((sol.v_init - exp_vi).norm() < 1e-6);
     assert!((sol.v_final - exp_vf).norm() < 1e-6);
 }
+
+#[test]
+fn test_lambert_izzo_vallado() {
+    let ri = Vector3::new(15945.34, 0.0, 0.0);
+    let rf = Vector3::new(12214.83899, 10249.46731, 0.0);
+    let tof_s = 76.0 * 60.0;
+    let gm = 3.98600433e5;
+
+    let sols = izzo(ri, rf, tof_s, gm, TransferKind::ShortWay).unwrap();
+    assert_eq!(sols.len(), 1);
+    assert!((sols[0].v_init - Vector3::new(2.058913, 2.915965, 0.0)).norm() < 1e-6);
+    assert!((sols[0].v_final - Vector3::new(-3.451565, 0.910315, 0.0)).norm() < 1e-6);
+
+    let sols = izzo(ri, rf, tof_s, gm, TransferKind::LongWay).unwrap();
+    assert_eq!(sols.len(), 1);
+    assert!((sols[0].v_init - Vector3::new(-3.811158, -2.003854, 0.0)).norm() < 1e-6);
+    assert!((sols[0].v_final - Vector3::new(4.207569, 0.914724, 0.0)).norm() < 1e-6);
+
+    // Auto is the prograde transfer, i.e. the short way here
+    let sols = izzo(ri, rf, tof_s, gm, TransferKind::Auto).unwrap();
+    assert!((sols[0].v_init - Vector3::new(2.058913, 2.915965, 0.0)).norm() < 1e-6);
+}
+
+#[test]
+fn test_lambert_izzo_multirev() {
+    use crate::celestia::{Cosm, Orbit};
+    use crate::dynamics::orbital::OrbitalDynamics;
+    use crate::propagators::{PropOpts, Propagator};
+    use crate::time::{Epoch, TimeUnit};
+
+    let cosm = Cosm::de438();
+    let eme2k = cosm.frame("EME2000");
+    let dt = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+    let gm = eme2k.gm();
+
+    let ri = Vector3::new(7000.0, 0.0, 0.0);
+    let rf = Vector3::new(0.0, 8000.0, 1000.0);
+    let tof_s = 6.0 * 3600.0;
+
+    let sols = izzo(ri, rf, tof_s, gm, TransferKind::Auto).unwrap();
+    // About 3.5 revolutions of a LEO fit in six hours
+    assert_eq!(sols.len(), 7);
+    let setup = Propagator::rk89(OrbitalDynamics::two_body(), PropOpts::with_tolerance(1e-12));
+    for (i, sol) in sols.iter().enumerate() {
+        assert_eq!(sol.revs as usize, (i + 1) / 2);
+        let orbit = Orbit::cartesian(
+            ri[0],
+            ri[1],
+            ri[2],
+            sol.v_init[0],
+            sol.v_init[1],
+            sol.v_init[2],
+            dt,
+            eme2k,
+        );
+        let arrival = setup
+            .with(orbit)
+            .for_duration(tof_s * TimeUnit::Second)
+            .unwrap();
+        println!(
+            "{} revs ({:?}): {}\t{:.3e} km",
+            sol.revs,
+            sol.branch,
+            orbit,
+            (arrival.radius() - rf).norm()
+        );
+        assert!((arrival.radius() - rf).norm() < 1e-3);
+        assert!((arrival.velocity() - sol.v_final).norm() < 1e-6);
+        assert_eq!(sol.branch.is_none(), sol.revs == 0);
+    }
+
+    // Limit the number of revolutions
+    let sols = izzo(ri, rf, tof_s, gm, TransferKind::NRevs(1)).unwrap();
+    assert_eq!(sols.len(), 3);
+}
+
+#[test]
+fn test_lambert_izzo_180deg() {
+    let gm = 3.98600433e5;
+    let ri = Vector3::new(7000.0, 0.0, 0.0);
+    let rf = Vector3::new(-8000.0, 0.0, 0.0);
+    // Half the period of the Hohmann transfer
+    let sma: f64 = 7500.0;
+    let tof_s = PI * (sma.powi(3) / gm).sqrt();
+
+    let sols = izzo(ri, rf, tof_s, gm, TransferKind::Auto).unwrap();
+    // The transfer is the Hohmann transfer, in the XY plane
+    let v_peri = (gm * (2.0 / 7000.0 - 1.0 / sma)).sqrt();
+    assert!((sols[0].v_init - Vector3::new(0.0, v_peri, 0.0)).norm() < 1e-6);
+    assert!(sols[0].v_final[2].abs() < 1e-9);
+}
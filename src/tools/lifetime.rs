@@ -0,0 +1,446 @@
+extern crate csv;
+extern crate rand;
+extern crate rand_distr;
+extern crate rayon;
+
+use self::rand::rngs::StdRng;
+use self::rand::SeedableRng;
+use self::rand_distr::{Distribution, Normal};
+use self::rayon::prelude::*;
+use crate::celestia::{Orbit, SpacecraftState};
+use crate::dynamics::drag::{AtmDensity, Drag};
+use crate::dynamics::orbital::OrbitalDynamics;
+use crate::dynamics::spacecraft::Spacecraft;
+use crate::errors::NyxError;
+use crate::propagators::semianalytic::{Equinoctial, SemiAnalytic};
+use crate::propagators::{PropOpts, Propagator};
+use crate::time::{Duration, Epoch, TimeUnit, SECONDS_PER_DAY};
+use std::fmt;
+use std::sync::Arc;
+
+/// Duration between two checks of the geodetic altitude during the final decay (s)
+const REENTRY_CHECK_S: f64 = 60.0;
+
+/// A point of the decay profile: the elements are mean elements during the semi-analytic phase and osculating
+/// elements during the final decay. The altitudes are above the equatorial radius.
+#[derive(Copy, Clone, Debug)]
+pub struct DecayPoint {
+    pub epoch: Epoch,
+    pub sma_km: f64,
+    pub ecc: f64,
+    pub perigee_alt_km: f64,
+    pub apogee_alt_km: f64,
+}
+
+impl DecayPoint {
+    fn new(epoch: Epoch, sma_km: f64, ecc: f64, eq_radius: f64) -> Self {
+        Self {
+            epoch,
+            sma_km,
+            ecc,
+            perigee_alt_km: sma_km * (1.0 - ecc) - eq_radius,
+            apogee_alt_km: sma_km * (1.0 + ecc) - eq_radius,
+        }
+    }
+
+    fn from_mean(mean: &Equinoctial) -> Self {
+        Self::new(
+            mean.dt,
+            mean.sma(),
+            mean.ecc(),
+            mean.frame.equatorial_radius(),
+        )
+    }
+
+    fn from_orbit(orbit: &Orbit) -> Self {
+        Self::new(
+            orbit.dt,
+            orbit.sma(),
+            orbit.ecc(),
+            orbit.frame.equatorial_radius(),
+        )
+    }
+}
+
+/// The decay profile of a spacecraft and its reentry epoch, if it reentered before the maximum duration
+#[derive(Clone, Debug)]
+pub struct LifetimeReport {
+    pub start: Epoch,
+    pub profile: Vec<DecayPoint>,
+    pub reentry: Option<Epoch>,
+}
+
+impl LifetimeReport {
+    /// Returns the orbit lifetime in days, if the spacecraft reentered
+    pub fn lifetime_days(&self) -> Option<f64> {
+        self.reentry
+            .map(|reentry| (reentry - self.start).in_seconds() / SECONDS_PER_DAY)
+    }
+
+    /// Writes the decay profile to a CSV file, with one row per point (as Gregorian UTC)
+    pub fn to_csv(&self, path: &str) -> Result<(), csv::Error> {
+        let mut wtr = csv::Writer::from_path(path)?;
+        wtr.write_record(&["epoch", "sma_km", "ecc", "perigee_alt_km", "apogee_alt_km"])?;
+        for point in &self.profile {
+            wtr.write_record(&[
+                point.epoch.as_gregorian_utc_str(),
+                format!("{}", point.sma_km),
+                format!("{}", point.ecc),
+                format!("{}", point.perigee_alt_km),
+                format!("{}", point.apogee_alt_km),
+            ])?;
+        }
+        wtr.flush()?;
+        info!("Decay profile saved to {}", path);
+        Ok(())
+    }
+}
+
+impl fmt::Display for LifetimeReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.reentry {
+            Some(reentry) => write!(
+                f,
+                "reentry on {} after {:.3} days",
+                reentry.as_gregorian_utc_str(),
+                self.lifetime_days().unwrap()
+            ),
+            None => write!(
+                f,
+                "no reentry within {:.3} days",
+                (self.profile.last().unwrap().epoch - self.start).in_seconds() / SECONDS_PER_DAY
+            ),
+        }
+    }
+}
+
+/// A Monte Carlo run of the orbit lifetime
+#[derive(Copy, Clone, Debug)]
+pub struct LifetimeSample {
+    pub cd: f64,
+    pub f107: f64,
+    pub lifetime_days: Option<f64>,
+}
+
+/// The spread of the orbit lifetime over the dispersions of the drag coefficient and of the solar flux
+#[derive(Clone, Debug)]
+pub struct LifetimeSpread {
+    pub samples: Vec<LifetimeSample>,
+}
+
+impl LifetimeSpread {
+    /// Returns the sorted lifetimes (days) of the runs which reentered
+    pub fn lifetimes_days(&self) -> Vec<f64> {
+        let mut lifetimes: Vec<f64> = self
+            .samples
+            .iter()
+            .filter_map(|sample| sample.lifetime_days)
+            .collect();
+        lifetimes.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        lifetimes
+    }
+
+    /// Returns the number of runs which reentered
+    pub fn num_reentered(&self) -> usize {
+        self.lifetimes_days().len()
+    }
+
+    /// Returns the mean lifetime (days) of the runs which reentered, if any
+    pub fn mean_days(&self) -> Option<f64> {
+        let lifetimes = self.lifetimes_days();
+        if lifetimes.is_empty() {
+            None
+        } else {
+            Some(lifetimes.iter().sum::<f64>() / lifetimes.len() as f64)
+        }
+    }
+
+    /// Returns the standard deviation of the lifetime (days) of the runs which reentered, if any
+    pub fn std_days(&self) -> Option<f64> {
+        let lifetimes = self.lifetimes_days();
+        let mean = self.mean_days()?;
+        Some(
+            (lifetimes.iter().map(|l| (l - mean).powi(2)).sum::<f64>()
+                / (lifetimes.len() as f64 - 1.0).max(1.0))
+            .sqrt(),
+        )
+    }
+
+    /// Returns the percentile (between 0 and 100) of the lifetime (days) of the runs which reentered, by linear
+    /// interpolation between the closest ranks, if any run reentered
+    pub fn percentile_days(&self, percentile: f64) -> Option<f64> {
+        let lifetimes = self.lifetimes_days();
+        if lifetimes.is_empty() {
+            return None;
+        }
+        let rank = (percentile / 100.0).max(0.0).min(1.0) * (lifetimes.len() as f64 - 1.0);
+        let lo = rank.floor() as usize;
+        let hi = rank.ceil() as usize;
+        Some(lifetimes[lo] + (rank - lo as f64) * (lifetimes[hi] - lifetimes[lo]))
+    }
+}
+
+impl fmt::Display for LifetimeSpread {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (
+            self.mean_days(),
+            self.std_days(),
+            self.percentile_days(5.0),
+            self.percentile_days(50.0),
+            self.percentile_days(95.0),
+        ) {
+            (Some(mean), Some(std), Some(p5), Some(p50), Some(p95)) => write!(
+                f,
+                "{} of {} runs reentered: lifetime {:.3} ± {:.3} days (5%: {:.3}, median: {:.3}, 95%: {:.3})",
+                self.num_reentered(),
+                self.samples.len(),
+                mean,
+                std,
+                p5,
+                p50,
+                p95
+            ),
+            _ => write!(f, "no reentry in any of {} runs", self.samples.len()),
+        }
+    }
+}
+
+/// Orbit lifetime and reentry prediction.
+///
+/// The mean elements are propagated with the semi-analytic propagator until the mean perigee altitude drops below
+/// the handover altitude, then the osculating state is propagated with an RK89 until the geodetic altitude drops below
+/// the reentry altitude.
+#[derive(Clone)]
+pub struct Lifetime<'a> {
+    pub orbital_dyn: Arc<OrbitalDynamics<'a>>,
+    /// Drag model, whose solar flux is dispersed in the Monte Carlo if its density is `AtmDensity::SolarFlux`
+    pub drag: Drag,
+    /// Geodetic altitude of the reentry (km)
+    pub reentry_altitude_km: f64,
+    /// Mean perigee altitude below which the decay is propagated with the osculating dynamics (km)
+    pub handover_altitude_km: f64,
+    /// Step of the semi-analytic propagation and of the decay profile
+    pub step: Duration,
+    pub max_duration: Duration,
+}
+
+impl<'a> Lifetime<'a> {
+    /// Initializes a lifetime prediction with a reentry at 120 km, a handover at 200 km, a daily step, and a maximum
+    /// duration of 25 years
+    pub fn new(orbital_dyn: Arc<OrbitalDynamics<'a>>, drag: Drag) -> Self {
+        Self {
+            orbital_dyn,
+            drag,
+            reentry_altitude_km: 120.0,
+            handover_altitude_km: 200.0,
+            step: 1.0 * TimeUnit::Day,
+            max_duration: (25.0 * 365.25) * TimeUnit::Day,
+        }
+    }
+
+    fn perigee_alt(mean: &Equinoctial) -> f64 {
+        mean.sma() * (1.0 - mean.ecc()) - mean.frame.equatorial_radius()
+    }
+
+    /// Predicts the decay and the reentry epoch of this spacecraft
+    pub fn predict(&self, sc: &SpacecraftState) -> Result<LifetimeReport, NyxError> {
+        let dynamics =
+            Spacecraft::with_models(self.orbital_dyn.clone(), vec![Arc::new(self.drag.clone())]);
+        let start = sc.orbit.dt;
+        let end = start + self.max_duration;
+        let semi_analytic = SemiAnalytic::new(dynamics.clone(), self.step);
+
+        // Semi-analytic decay
+        let mut mean = semi_analytic.to_mean(sc)?;
+        let mut profile = vec![DecayPoint::from_mean(&mean)];
+        while Self::perigee_alt(&mean) > self.handover_altitude_km && mean.dt < end {
+            let next = semi_analytic.propagate_mean(&mean, sc, self.step)?;
+            if Self::perigee_alt(&next) < self.reentry_altitude_km {
+                // The final decay is too fast for this step
+                break;
+            }
+            mean = next;
+            profile.push(DecayPoint::from_mean(&mean));
+        }
+
+        // Osculating final decay
+        let setup = Propagator::rk89(dynamics, PropOpts::with_tolerance(1e-9));
+        let mut prop = setup.with(semi_analytic.to_osculating(&mean, sc)?);
+        let mut prev_dt = mean.dt;
+        let mut prev_alt = self
+            .drag
+            .cosm
+            .frame_chg(&prop.state.orbit, self.drag.drag_frame)
+            .geodetic_height();
+        let mut next_point = mean.dt + self.step;
+        let mut reentry = None;
+        while prev_dt < end {
+            let state = prop.for_duration(REENTRY_CHECK_S * TimeUnit::Second)?;
+            let alt = self
+                .drag
+                .cosm
+                .frame_chg(&state.orbit, self.drag.drag_frame)
+                .geodetic_height();
+            if alt < self.reentry_altitude_km {
+                // Linear interpolation of the crossing of the reentry altitude
+                let frac = (prev_alt - self.reentry_altitude_km) / (prev_alt - alt);
+                reentry = Some(prev_dt + (frac * REENTRY_CHECK_S) * TimeUnit::Second);
+                profile.push(DecayPoint::from_orbit(&state.orbit));
+                break;
+            }
+            if state.orbit.dt >= next_point {
+                profile.push(DecayPoint::from_orbit(&state.orbit));
+                next_point = next_point + self.step;
+            }
+            prev_dt = state.orbit.dt;
+            prev_alt = alt;
+        }
+
+        let report = LifetimeReport {
+            start,
+            profile,
+            reentry,
+        };
+        info!("{}", report);
+        Ok(report)
+    }
+
+    /// Predicts the lifetime of this spacecraft for normally distributed drag coefficients (with a standard deviation
+    /// relative to the drag coefficient, i.e. of the ballistic coefficient) and solar fluxes (with a standard deviation
+    /// in sfu). The runs are seeded for reproducibility and predicted in parallel.
+    pub fn monte_carlo(
+        &self,
+        sc: &SpacecraftState,
+        cd_rel_sigma: f64,
+        f107_sigma: f64,
+        runs: usize,
+        seed: u64,
+    ) -> Result<LifetimeSpread, NyxError> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let unit = Normal::new(0.0, 1.0).unwrap();
+        let f107 = match self.drag.density {
+            AtmDensity::SolarFlux { f107, .. } => Some(f107),
+            _ => None,
+        };
+        let params: Vec<(f64, Option<f64>)> = (0..runs)
+            .map(|_| {
+                let cd = self.drag.cd * (1.0 + cd_rel_sigma * unit.sample(&mut rng)).max(0.0);
+                // The flux model is not valid below the minimum of the solar cycle
+                let f107 = f107.map(|f107| (f107 + f107_sigma * unit.sample(&mut rng)).max(65.0));
+                (cd, f107)
+            })
+            .collect();
+
+        let samples = params
+            .par_iter()
+            .map(|(cd, f107)| -> Result<LifetimeSample, NyxError> {
+                let mut run = self.clone();
+                run.drag.cd = *cd;
+                if let AtmDensity::SolarFlux {
+                    f107: ref mut flux, ..
+                } = run.drag.density
+                {
+                    *flux = f107.unwrap();
+                }
+                Ok(LifetimeSample {
+                    cd: *cd,
+                    f107: f107.unwrap_or(std::f64::NAN),
+                    lifetime_days: run.predict(sc)?.lifetime_days(),
+                })
+            })
+            .collect::<Result<Vec<LifetimeSample>, NyxError>>()?;
+
+        let spread = LifetimeSpread { samples };
+        info!("{}", spread);
+        Ok(spread)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::celestia::Cosm;
+    use crate::dynamics::sph_harmonics::Harmonics;
+    use crate::io::gravity::HarmonicsMem;
+
+    fn leo_decay(alt_km: f64) -> (Lifetime<'static>, SpacecraftState) {
+        let cosm = Cosm::de438();
+        let eme2k = cosm.frame("EME2000");
+        let iau_earth = cosm.frame("IAU Earth");
+        let orbital_dyn = OrbitalDynamics::new(vec![Harmonics::from_stor(
+            iau_earth,
+            HarmonicsMem::j2_jgm3(),
+            cosm.clone(),
+        )]);
+        let drag = Drag::earth_solar_flux(1.0, 2.2, 150.0, 15.0, cosm);
+        let dt = Epoch::from_gregorian_tai_at_midnight(2021, 3, 1);
+        let orbit = Orbit::keplerian(
+            eme2k.equatorial_radius() + alt_km,
+            1e-4,
+            51.6,
+            30.0,
+            90.0,
+            0.0,
+            dt,
+            eme2k,
+        );
+        (
+            Lifetime::new(orbital_dyn, (*drag).clone()),
+            SpacecraftState::new(orbit, 100.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn lifetime_leo_reentry() {
+        let (lifetime, sc) = leo_decay(300.0);
+        let report = lifetime.predict(&sc).unwrap();
+        println!("{}", report);
+        for point in &report.profile {
+            println!("{:?}", point);
+        }
+        // A simple circular decay model with the same density and ballistic coefficient reenters after 14.3 days
+        let days = report.lifetime_days().unwrap();
+        assert!(days > 11.0 && days < 22.0, "lifetime of {} days", days);
+        // The profile mixes mean and osculating elements, so only its extremities are compared
+        for pair in report.profile.windows(2) {
+            assert!(pair[1].epoch > pair[0].epoch);
+        }
+        let last = report.profile.last().unwrap();
+        assert!(last.sma_km < report.profile[0].sma_km - 100.0);
+        assert!(last.perigee_alt_km < lifetime.handover_altitude_km);
+    }
+
+    #[test]
+    fn lifetime_monte_carlo() {
+        let (lifetime, sc) = leo_decay(250.0);
+        let nominal = lifetime.predict(&sc).unwrap().lifetime_days().unwrap();
+        let spread = lifetime.monte_carlo(&sc, 0.1, 25.0, 6, 0).unwrap();
+        println!("nominal {:.3} days, {}", nominal, spread);
+        assert_eq!(spread.num_reentered(), 6);
+        assert!(spread.std_days().unwrap() > 0.0);
+        assert!(spread.percentile_days(5.0).unwrap() <= spread.percentile_days(50.0).unwrap());
+        assert!(spread.percentile_days(50.0).unwrap() <= spread.percentile_days(95.0).unwrap());
+        assert!((spread.mean_days().unwrap() - nominal).abs() < 0.5 * nominal);
+        // A higher flux heats up and expands the atmosphere, so the lifetime is shorter
+        for a in &spread.samples {
+            for b in &spread.samples {
+                if a.cd >= b.cd && a.f107 >= b.f107 {
+                    assert!(a.lifetime_days.unwrap() <= b.lifetime_days.unwrap() + 0.05);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn lifetime_monte_carlo_no_reentry() {
+        let (mut lifetime, sc) = leo_decay(300.0);
+        lifetime.max_duration = 2.0 * TimeUnit::Day;
+        let spread = lifetime.monte_carlo(&sc, 0.1, 25.0, 3, 0).unwrap();
+        println!("{}", spread);
+        assert_eq!(spread.num_reentered(), 0);
+        assert!(spread.mean_days().is_none());
+        assert!(spread.std_days().is_none());
+        assert!(spread.percentile_days(50.0).is_none());
+        assert_eq!(format!("{}", spread), "no reentry in any of 3 runs");
+    }
+}
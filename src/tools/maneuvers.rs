@@ -0,0 +1,556 @@
+use crate::celestia::{Frame, Orbit, SpacecraftState};
+use crate::dimensions::Vector3;
+use crate::dynamics::deltavctrl::{Impulse, InstantBurns, Mnvr};
+use crate::dynamics::spacecraft::rocket_fuel_usage;
+use crate::errors::NyxError;
+use crate::propagators::impulses::ScheduledImpulse;
+use crate::time::{Epoch, TimeUnit};
+use crate::utils::rotv;
+use std::f64::consts::PI;
+use std::fmt;
+
+/// An impulsive burn of a maneuver plan
+#[derive(Copy, Clone, Debug)]
+pub struct PlannedBurn {
+    /// Orbit just before the burn
+    pub orbit: Orbit,
+    /// Change in velocity in the frame of the orbit (km/s)
+    pub dv: Vector3<f64>,
+}
+
+impl PlannedBurn {
+    /// Computes the burn which changes the velocity of this orbit to the provided one
+    fn reaching(orbit: Orbit, velocity: Vector3<f64>) -> Self {
+        Self {
+            orbit,
+            dv: velocity - orbit.velocity(),
+        }
+    }
+
+    pub fn epoch(&self) -> Epoch {
+        self.orbit.dt
+    }
+
+    /// Returns the change in velocity in the VNC frame of the orbit before the burn (km/s)
+    pub fn dv_vnc(&self) -> Vector3<f64> {
+        self.orbit.dcm_to_inertial(Frame::VNC).transpose() * self.dv
+    }
+
+    /// Returns the orbit just after the burn
+    pub fn after(&self) -> Orbit {
+        let mut orbit = self.orbit;
+        orbit.vx += self.dv[0];
+        orbit.vy += self.dv[1];
+        orbit.vz += self.dv[2];
+        orbit
+    }
+}
+
+/// A sequence of impulsive burns computed with two body dynamics
+#[derive(Clone, Debug)]
+pub struct ManeuverPlan {
+    pub burns: Vec<PlannedBurn>,
+}
+
+impl ManeuverPlan {
+    /// Returns the total deltaV of the plan (km/s)
+    pub fn total_dv_km_s(&self) -> f64 {
+        self.burns.iter().map(|burn| burn.dv.norm()).sum()
+    }
+
+    /// Returns the orbit just after the last burn
+    pub fn final_orbit(&self) -> Orbit {
+        self.burns[self.burns.len() - 1].after()
+    }
+
+    /// Returns the instantaneous maneuvers of this plan, whose vectors are the deltaV in the frame of the orbit
+    pub fn mnvrs(&self) -> Vec<Mnvr> {
+        self.burns
+            .iter()
+            .map(|burn| Mnvr::instantaneous(burn.epoch(), burn.dv))
+            .collect()
+    }
+
+    /// Returns the instantaneous burns of this plan, whose vectors are the deltaV in the VNC frame as expected by
+    /// `InstantBurns`
+    pub fn instant_burns(&self) -> InstantBurns {
+        InstantBurns::from_mnvrs(
+            self.burns
+                .iter()
+                .map(|burn| Mnvr::instantaneous(burn.epoch(), burn.dv_vnc()))
+                .collect(),
+        )
+    }
+
+    /// Returns the impulses of this plan, in the frame of the orbit, scheduled at the epoch of each burn
+    pub fn impulses<S: Copy>(&self) -> Vec<ScheduledImpulse<S>> {
+        self.burns
+            .iter()
+            .map(|burn| {
+                ScheduledImpulse::at_epoch(burn.epoch(), Impulse::new(burn.dv, Frame::Inertial))
+            })
+            .collect()
+    }
+
+    /// Converts each burn of this plan into an equivalent finite burn at full thrust of this spacecraft, centered on
+    /// the epoch of the burn and whose vector is in the frame of the orbit, e.g. for `FiniteBurns` in the inertial
+    /// frame. The fuel mass is decremented after each burn with the rocket equation.
+    pub fn finite_burns(&self, sc: &SpacecraftState) -> Result<Vec<Mnvr>, NyxError> {
+        let isp = match (sc.thrusters[0], sc.thruster) {
+            (Some(mounted), _) => mounted.thruster.isp,
+            (None, Some(thruster)) => thruster.isp,
+            (None, None) => return Err(NyxError::CtrlExistsButNoThrusterAvail),
+        };
+        let mut state = *sc;
+        let mut mnvrs = Vec::with_capacity(self.burns.len());
+        for burn in &self.burns {
+            state.orbit = burn.orbit;
+            mnvrs.push(Mnvr::from_impulse(
+                &state,
+                &Impulse::new(burn.dv, Frame::Inertial),
+                Frame::Inertial,
+            )?);
+            let mass = state.dry_mass_kg + state.fuel_mass_kg;
+            state.fuel_mass_kg -= rocket_fuel_usage(mass, burn.dv.norm(), isp);
+        }
+        Ok(mnvrs)
+    }
+}
+
+impl fmt::Display for ManeuverPlan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Total deltaV: {:.6} km/s", self.total_dv_km_s())?;
+        for burn in &self.burns {
+            let dv_vnc = burn.dv_vnc();
+            writeln!(
+                f,
+                "\t{}\t{:.6} km/s\tVNC = [{:.6}, {:.6}, {:.6}] km/s",
+                burn.epoch(),
+                burn.dv.norm(),
+                dv_vnc[0],
+                dv_vnc[1],
+                dv_vnc[2]
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the GM of the frame of the orbit, or an error if the orbit is not elliptical
+fn elliptical_gm(orbit: &Orbit) -> Result<f64, NyxError> {
+    if !(orbit.frame.is_celestial() || orbit.frame.is_geoid()) {
+        return Err(NyxError::InvalidTargetingProblem(format!(
+            "{} has no gravitational parameter",
+            orbit.frame
+        )));
+    }
+    if orbit.ecc() >= 1.0 {
+        return Err(NyxError::InvalidTargetingProblem(format!(
+            "orbit is not elliptical (ecc = {})",
+            orbit.ecc()
+        )));
+    }
+    Ok(orbit.frame.gm())
+}
+
+/// Returns the unit orbital momentum of the plane of these inclination and RAAN, in degrees
+fn plane_normal(inc_deg: f64, raan_deg: f64) -> Vector3<f64> {
+    let (inc, raan) = (inc_deg.to_radians(), raan_deg.to_radians());
+    Vector3::new(inc.sin() * raan.sin(), -inc.sin() * raan.cos(), inc.cos())
+}
+
+/// Returns the time in seconds for the orbit to travel this angle, in radians, from its current position
+fn time_to_angle(orbit: &Orbit, gm: f64, angle: f64) -> f64 {
+    let mean_motion = (gm / orbit.sma().powi(3)).sqrt();
+    let ecc = orbit.ecc();
+    if ecc < 1e-8 {
+        return angle / mean_motion;
+    }
+    let e_hat = orbit.evec() / ecc;
+    let r_hat = orbit.radius() / orbit.rmag();
+    let h_hat = orbit.hvec() / orbit.hmag();
+    let ta = e_hat.cross(&r_hat).dot(&h_hat).atan2(e_hat.dot(&r_hat));
+    let mean_anomaly = |ta: f64| {
+        let ea = 2.0
+            * ((1.0 - ecc).sqrt() * (0.5 * ta).sin()).atan2((1.0 + ecc).sqrt() * (0.5 * ta).cos());
+        (ea - ecc * ea.sin()).rem_euclid(2.0 * PI)
+    };
+    (mean_anomaly(ta + angle) - mean_anomaly(ta)).rem_euclid(2.0 * PI) / mean_motion
+}
+
+/// Returns the orbit at the next crossing of the line of nodes between its plane and the target plane, or the orbit
+/// itself if they are the same plane
+fn at_next_node(orbit: &Orbit, gm: f64, target_h: &Vector3<f64>) -> Result<Orbit, NyxError> {
+    let h_hat = orbit.hvec() / orbit.hmag();
+    let node = h_hat.cross(target_h);
+    if node.norm() < 1e-12 {
+        return Ok(*orbit);
+    }
+    let r_hat = orbit.radius() / orbit.rmag();
+    let to_node = r_hat
+        .cross(&node)
+        .dot(&h_hat)
+        .atan2(r_hat.dot(&node))
+        .rem_euclid(2.0 * PI);
+    // Both ends of the line of nodes are suitable
+    let angle = to_node.min((to_node + PI).rem_euclid(2.0 * PI));
+    orbit.at_epoch(orbit.dt + time_to_angle(orbit, gm, angle) * TimeUnit::Second)
+}
+
+/// Returns the prograde horizontal direction at this position for an orbit of this orbital momentum
+fn horizontal(h_hat: &Vector3<f64>, radius: &Vector3<f64>) -> Vector3<f64> {
+    let dir = h_hat.cross(radius);
+    dir / dir.norm()
+}
+
+/// Computes a Hohmann transfer from the current position of the orbit to a circular orbit of the target radius, in
+/// the plane of the orbit. The first burn is applied immediately and the second one half a transfer orbit later.
+/// If the orbit is not circular, the first burn also cancels its radial velocity.
+pub fn hohmann(orbit: &Orbit, target_radius_km: f64) -> Result<ManeuverPlan, NyxError> {
+    let h_hat = orbit.hvec() / orbit.hmag();
+    transfer(orbit, target_radius_km, h_hat, false)
+}
+
+/// Computes the combined Hohmann transfer and plane change to a circular orbit of the target radius, inclination
+/// and RAAN (in degrees). The first burn happens at the next crossing of the line of nodes, and the plane change is
+/// split between both burns to minimize the total deltaV.
+pub fn combined_transfer(
+    orbit: &Orbit,
+    target_radius_km: f64,
+    target_inc_deg: f64,
+    target_raan_deg: f64,
+) -> Result<ManeuverPlan, NyxError> {
+    transfer(
+        orbit,
+        target_radius_km,
+        plane_normal(target_inc_deg, target_raan_deg),
+        true,
+    )
+}
+
+/// Computes the combined Hohmann transfer and plane change to the circular orbit of the radius and plane of the
+/// target orbit. The phasing with the target is not considered.
+pub fn transfer_to(orbit: &Orbit, target: &Orbit) -> Result<ManeuverPlan, NyxError> {
+    transfer(orbit, target.sma(), target.hvec() / target.hmag(), true)
+}
+
+fn transfer(
+    orbit: &Orbit,
+    target_radius_km: f64,
+    target_h: Vector3<f64>,
+    wait_for_node: bool,
+) -> Result<ManeuverPlan, NyxError> {
+    let gm = elliptical_gm(orbit)?;
+    if target_radius_km <= 0.0 {
+        return Err(NyxError::InvalidTargetingProblem(format!(
+            "target radius of {} km is not positive",
+            target_radius_km
+        )));
+    }
+    let first = if wait_for_node {
+        at_next_node(orbit, gm, &target_h)?
+    } else {
+        *orbit
+    };
+    let h_hat = first.hvec() / first.hmag();
+    let r1 = first.rmag();
+    let r2 = target_radius_km;
+    let transfer_sma = 0.5 * (r1 + r2);
+    let vt1 = (gm * (2.0 / r1 - 1.0 / transfer_sma)).sqrt();
+    let vt2 = (gm * (2.0 / r2 - 1.0 / transfer_sma)).sqrt();
+    let vc2 = (gm / r2).sqrt();
+
+    // Orbital momentum of the transfer after rotating the plane by this fraction of the plane change
+    let axis = h_hat.cross(&target_h);
+    let plane_angle = axis.norm().atan2(h_hat.dot(&target_h));
+    let transfer_h = |fraction: f64| {
+        if plane_angle.abs() < 1e-12 {
+            h_hat
+        } else {
+            rotv(&h_hat, &axis, fraction * plane_angle)
+        }
+    };
+    // The second burn is opposite to the first one, where the horizontal directions are reversed
+    let total_dv = |fraction: f64| {
+        let h_tf = transfer_h(fraction);
+        let radius = first.radius();
+        (vt1 * horizontal(&h_tf, &radius) - first.velocity()).norm()
+            + (vc2 * horizontal(&target_h, &radius) - vt2 * horizontal(&h_tf, &radius)).norm()
+    };
+
+    // Golden section search of the fraction of the plane change done by the first burn
+    let ratio = 0.5 * (5.0_f64.sqrt() - 1.0);
+    let (mut lo, mut hi) = (0.0, 1.0);
+    if plane_angle.abs() >= 1e-12 {
+        while hi - lo > 1e-10 {
+            let left = hi - ratio * (hi - lo);
+            let right = lo + ratio * (hi - lo);
+            if total_dv(left) < total_dv(right) {
+                hi = right;
+            } else {
+                lo = left;
+            }
+        }
+    }
+    let h_tf = transfer_h(0.5 * (lo + hi));
+
+    let burn1 = PlannedBurn::reaching(first, vt1 * horizontal(&h_tf, &first.radius()));
+    let half_period = PI * (transfer_sma.powi(3) / gm).sqrt();
+    let second = burn1
+        .after()
+        .at_epoch(first.dt + half_period * TimeUnit::Second)?;
+    let burn2 = PlannedBurn::reaching(second, vc2 * horizontal(&target_h, &second.radius()));
+    Ok(ManeuverPlan {
+        burns: vec![burn1, burn2],
+    })
+}
+
+/// Computes a bi-elliptic transfer from the current position of the orbit to a circular orbit of the target radius,
+/// in the plane of the orbit, through the intermediate radius which must be greater than both the initial and
+/// target radii. For large ratios of the target to initial radii, this is cheaper than a Hohmann transfer.
+pub fn bi_elliptic(
+    orbit: &Orbit,
+    intermediate_radius_km: f64,
+    target_radius_km: f64,
+) -> Result<ManeuverPlan, NyxError> {
+    let gm = elliptical_gm(orbit)?;
+    let h_hat = orbit.hvec() / orbit.hmag();
+    let r1 = orbit.rmag();
+    let rb = intermediate_radius_km;
+    let r2 = target_radius_km;
+    if rb < r1 || rb < r2 {
+        return Err(NyxError::InvalidTargetingProblem(format!(
+            "intermediate radius of {} km must be greater than {} km and {} km",
+            rb, r1, r2
+        )));
+    }
+    let sma1 = 0.5 * (r1 + rb);
+    let sma2 = 0.5 * (rb + r2);
+
+    let burn1 = PlannedBurn::reaching(
+        *orbit,
+        (gm * (2.0 / r1 - 1.0 / sma1)).sqrt() * horizontal(&h_hat, &orbit.radius()),
+    );
+    let second = burn1
+        .after()
+        .at_epoch(orbit.dt + PI * (sma1.powi(3) / gm).sqrt() * TimeUnit::Second)?;
+    let burn2 = PlannedBurn::reaching(
+        second,
+        (gm * (2.0 / rb - 1.0 / sma2)).sqrt() * horizontal(&h_hat, &second.radius()),
+    );
+    let third = burn2
+        .after()
+        .at_epoch(second.dt + PI * (sma2.powi(3) / gm).sqrt() * TimeUnit::Second)?;
+    let burn3 = PlannedBurn::reaching(
+        third,
+        (gm / r2).sqrt() * horizontal(&h_hat, &third.radius()),
+    );
+    Ok(ManeuverPlan {
+        burns: vec![burn1, burn2, burn3],
+    })
+}
+
+/// Computes the plane change to the target inclination and RAAN (in degrees) at the next crossing of the line of
+/// nodes. The burn rotates the horizontal velocity and preserves the radial velocity, so the shape of the orbit is
+/// unchanged.
+pub fn plane_change(
+    orbit: &Orbit,
+    target_inc_deg: f64,
+    target_raan_deg: f64,
+) -> Result<ManeuverPlan, NyxError> {
+    let gm = elliptical_gm(orbit)?;
+    let target_h = plane_normal(target_inc_deg, target_raan_deg);
+    let at_node = at_next_node(orbit, gm, &target_h)?;
+    let radius = at_node.radius();
+    let r_hat = radius / radius.norm();
+    let radial_vel = at_node.velocity().dot(&r_hat);
+    let horizontal_vel = (at_node.velocity() - radial_vel * r_hat).norm();
+    let velocity = horizontal_vel * horizontal(&target_h, &radius) + radial_vel * r_hat;
+    Ok(ManeuverPlan {
+        burns: vec![PlannedBurn::reaching(at_node, velocity)],
+    })
+}
+
+/// Computes the phasing maneuver which moves the spacecraft ahead on its orbit by this phase angle (in degrees, in
+/// mean anomaly, negative to move behind) after the provided number of revolutions on the phasing orbit. The first
+/// burn is applied immediately along the velocity and the second one restores the initial velocity at the same
+/// position. The periapsis of the phasing orbit is not checked against the radius of the central body.
+pub fn phasing(orbit: &Orbit, phase_deg: f64, num_revs: u32) -> Result<ManeuverPlan, NyxError> {
+    let gm = elliptical_gm(orbit)?;
+    if num_revs == 0 {
+        return Err(NyxError::InvalidTargetingProblem(
+            "phasing requires at least one revolution".to_string(),
+        ));
+    }
+    let revs = f64::from(num_revs);
+    let period = 2.0 * PI * (orbit.sma().powi(3) / gm).sqrt();
+    let phasing_period = period * (revs - phase_deg / 360.0) / revs;
+    if phasing_period <= 0.0 {
+        return Err(NyxError::InvalidTargetingProblem(format!(
+            "cannot phase by {} degrees in {} revolutions",
+            phase_deg, num_revs
+        )));
+    }
+    let phasing_sma = (gm * (phasing_period / (2.0 * PI)).powi(2)).cbrt();
+    let speed = gm * (2.0 / orbit.rmag() - 1.0 / phasing_sma);
+    if speed <= 0.0 {
+        return Err(NyxError::InvalidTargetingProblem(format!(
+            "phasing orbit of SMA {} km does not reach the current radius",
+            phasing_sma
+        )));
+    }
+    let burn1 = PlannedBurn::reaching(*orbit, speed.sqrt() * orbit.velocity() / orbit.vmag());
+    let second = burn1
+        .after()
+        .at_epoch(orbit.dt + revs * phasing_period * TimeUnit::Second)?;
+    let burn2 = PlannedBurn::reaching(second, orbit.velocity());
+    Ok(ManeuverPlan {
+        burns: vec![burn1, burn2],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::celestia::{Cosm, GuidanceMode};
+    use crate::dynamics::orbital::OrbitalDynamics;
+    use crate::dynamics::spacecraft::Spacecraft;
+    use crate::dynamics::thrustctrl::Thruster;
+    use crate::propagators::{PropOpts, Propagator};
+
+    fn leo(inc_deg: f64) -> Orbit {
+        let cosm = Cosm::de438();
+        let eme2k = cosm.frame("EME2000");
+        let dt = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+        Orbit::keplerian(7000.0, 0.0, inc_deg, 20.0, 0.0, 45.0, dt, eme2k)
+    }
+
+    #[test]
+    fn maneuvers_hohmann() {
+        let start = leo(28.5);
+        let plan = hohmann(&start, 42_164.0).unwrap();
+        println!("{}", plan);
+        assert_eq!(plan.burns.len(), 2);
+        assert_eq!(plan.burns[0].epoch(), start.dt);
+
+        // Textbook values
+        let gm = start.frame.gm();
+        let (r1, r2) = (7000.0, 42_164.0);
+        let dv1 = (gm / r1).sqrt() * ((2.0 * r2 / (r1 + r2)).sqrt() - 1.0);
+        let dv2 = (gm / r2).sqrt() * (1.0 - (2.0 * r1 / (r1 + r2)).sqrt());
+        assert!((plan.burns[0].dv.norm() - dv1).abs() < 1e-9);
+        assert!((plan.burns[1].dv.norm() - dv2).abs() < 1e-6);
+        // Both burns are along the velocity
+        let vnc = plan.burns[0].dv_vnc();
+        assert!((vnc[0] - dv1).abs() < 1e-9 && vnc[1].abs() < 1e-9 && vnc[2].abs() < 1e-9);
+
+        let target = plan.final_orbit();
+        assert!((target.sma() - 42_164.0).abs() < 1e-3);
+        assert!(target.ecc() < 1e-6);
+        assert!((target.inc() - 28.5).abs() < 1e-6);
+
+        // Replay the plan with impulses in a two body propagation
+        let sc = SpacecraftState::with_thruster(
+            start,
+            1000.0,
+            3000.0,
+            Thruster {
+                thrust: 500.0,
+                isp: 320.0,
+            },
+            GuidanceMode::Coast,
+        );
+        let prop = Propagator::rk89(
+            Spacecraft::new(OrbitalDynamics::two_body()),
+            PropOpts::with_tolerance(1e-12),
+        );
+        let end_time = plan.burns[1].epoch() + 1 * TimeUnit::Hour;
+        let replayed = prop
+            .with(sc)
+            .with_impulses(plan.impulses())
+            .for_duration(end_time - start.dt)
+            .unwrap();
+        let expected = target.at_epoch(end_time).unwrap();
+        assert!((replayed.orbit.radius() - expected.radius()).norm() < 1e-2);
+
+        let finite = plan.finite_burns(&sc).unwrap();
+        assert_eq!(finite.len(), 2);
+        assert!(finite[1].duration() < finite[0].duration());
+        assert_eq!(plan.mnvrs().len(), 2);
+        assert_eq!(plan.instant_burns().mnvrs.len(), 2);
+    }
+
+    #[test]
+    fn maneuvers_bi_elliptic() {
+        let start = leo(10.0);
+        let target_radius = 20.0 * 7000.0;
+        let hohmann_dv = hohmann(&start, target_radius).unwrap().total_dv_km_s();
+        let plan = bi_elliptic(&start, 2.0 * target_radius, target_radius).unwrap();
+        println!("{}", plan);
+        assert_eq!(plan.burns.len(), 3);
+        assert!(plan.total_dv_km_s() < hohmann_dv);
+        let target = plan.final_orbit();
+        assert!((target.sma() - target_radius).abs() < 1e-2);
+        assert!(target.ecc() < 1e-6);
+
+        assert!(bi_elliptic(&start, 0.5 * target_radius, target_radius).is_err());
+    }
+
+    #[test]
+    fn maneuvers_plane_change() {
+        let start = leo(28.5);
+        let plan = plane_change(&start, 51.6, 40.0).unwrap();
+        println!("{}", plan);
+        assert_eq!(plan.burns.len(), 1);
+        assert!(plan.burns[0].epoch() >= start.dt);
+        let target = plan.final_orbit();
+        assert!((target.inc() - 51.6).abs() < 1e-6);
+        assert!((target.raan() - 40.0).abs() < 1e-6);
+        assert!((target.sma() - 7000.0).abs() < 1e-6);
+        // Rotation of the velocity by the angle between both planes
+        let angle = plane_normal(28.5, 20.0).angle(&plane_normal(51.6, 40.0));
+        let vel = start.vmag();
+        assert!((plan.total_dv_km_s() - 2.0 * vel * (0.5 * angle).sin()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn maneuvers_combined() {
+        let start = leo(28.5);
+        let plan = combined_transfer(&start, 42_164.0, 0.0, 0.0).unwrap();
+        println!("{}", plan);
+        let target = plan.final_orbit();
+        assert!((target.sma() - 42_164.0).abs() < 1e-3);
+        assert!(target.ecc() < 1e-6);
+        assert!(target.inc() < 1e-6);
+
+        // Cheaper than a Hohmann transfer followed by a plane change in GEO
+        let hohmann_plan = hohmann(&start, 42_164.0).unwrap();
+        let plane_plan = plane_change(&hohmann_plan.final_orbit(), 0.0, 0.0).unwrap();
+        let split_dv = hohmann_plan.total_dv_km_s() + plane_plan.total_dv_km_s();
+        assert!(plan.total_dv_km_s() < split_dv);
+        assert!(plan.total_dv_km_s() > 4.0 && plan.total_dv_km_s() < 4.4);
+
+        // Same transfer from a target orbit
+        let geo = Orbit::keplerian(42_164.0, 0.0, 0.0, 0.0, 0.0, 0.0, start.dt, start.frame);
+        let to_geo = transfer_to(&start, &geo).unwrap();
+        assert!((to_geo.total_dv_km_s() - plan.total_dv_km_s()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn maneuvers_phasing() {
+        let start = leo(28.5);
+        let plan = phasing(&start, 60.0, 2).unwrap();
+        println!("{}", plan);
+        let end = plan.final_orbit();
+        // The spacecraft is where the initial orbit would be a sixth of a period later
+        let period_s = start.period().in_seconds();
+        let expected = start
+            .at_epoch(end.dt + period_s / 6.0 * TimeUnit::Second)
+            .unwrap();
+        assert!((end.radius() - expected.radius()).norm() < 1e-3);
+        assert!((end.velocity() - expected.velocity()).norm() < 1e-6);
+        assert!((plan.burns[0].dv + plan.burns[1].dv).norm() < 1e-9);
+
+        assert!(phasing(&start, 60.0, 0).is_err());
+    }
+}
@@ -0,0 +1,593 @@
+extern crate rand;
+extern crate rayon;
+
+use self::rand::rngs::StdRng;
+use self::rand::{Rng, SeedableRng};
+use self::rayon::prelude::*;
+use super::lambert::{izzo, TransferKind};
+use crate::celestia::{Bodies, Cosm, LTCorr, Orbit};
+use crate::dimensions::Vector3;
+use crate::errors::NyxError;
+use crate::time::{Epoch, TimeUnit, SECONDS_PER_DAY};
+use std::fmt;
+use std::sync::Arc;
+
+/// Periapsis radius beyond which a flyby no longer turns the v-infinity, in km
+const MAX_PERIAPSIS_KM: f64 = 1e12;
+
+/// Hyperbolic flyby of a body between two legs of a patched conic trajectory
+#[derive(Copy, Clone, Debug)]
+pub struct Flyby {
+    pub body: Bodies,
+    pub epoch: Epoch,
+    /// Incoming v-infinity relative to the body, in the heliocentric J2000 frame (km/s)
+    pub vinf_in: Vector3<f64>,
+    /// Outgoing v-infinity relative to the body, in the heliocentric J2000 frame (km/s)
+    pub vinf_out: Vector3<f64>,
+    /// Angle between the incoming and the outgoing v-infinity (deg)
+    pub turn_angle_deg: f64,
+    /// Largest turn angle at the minimum periapsis (deg)
+    pub max_turn_angle_deg: f64,
+    /// Periapsis radius of the flyby, bounded by the minimum periapsis (km)
+    pub periapsis_km: f64,
+    /// DeltaV of a powered flyby, applied at periapsis, including a penalty if the turn angle exceeds the largest
+    /// turn angle (km/s)
+    pub dv_km_s: f64,
+}
+
+impl Flyby {
+    /// Computes the flyby of the body of this GM between the incoming and the outgoing v-infinity, where the
+    /// periapsis radius may not be less than the provided one.
+    ///
+    /// The periapsis radius is that of the hyperbolas of the incoming and outgoing v-infinity which achieve the turn
+    /// angle, and the deltaV is the difference of the periapsis velocities of these hyperbolas. If the turn angle
+    /// cannot be achieved above the minimum periapsis, the deltaV includes the impulse needed to rotate the outgoing
+    /// v-infinity by the remaining angle.
+    pub fn new(
+        body: Bodies,
+        epoch: Epoch,
+        vinf_in: Vector3<f64>,
+        vinf_out: Vector3<f64>,
+        gm: f64,
+        min_periapsis_km: f64,
+    ) -> Self {
+        let vin = vinf_in.norm();
+        let vout = vinf_out.norm();
+        let turn = (vinf_in.dot(&vinf_out) / (vin * vout))
+            .max(-1.0)
+            .min(1.0)
+            .acos();
+        let a_in = gm / vin.powi(2);
+        let a_out = gm / vout.powi(2);
+        // Turn angle of the flyby for the provided periapsis radius, which decreases with the radius
+        let turn_at = |rp: f64| (a_in / (a_in + rp)).asin() + (a_out / (a_out + rp)).asin();
+
+        let max_turn = turn_at(min_periapsis_km);
+        let (periapsis_km, penalty) = if turn >= max_turn {
+            (
+                min_periapsis_km,
+                2.0 * vout * (0.5 * (turn - max_turn)).sin(),
+            )
+        } else if turn <= turn_at(MAX_PERIAPSIS_KM) {
+            (MAX_PERIAPSIS_KM, 0.0)
+        } else {
+            // Bisection on the logarithm of the periapsis radius
+            let mut lo = min_periapsis_km.max(1.0).ln();
+            let mut hi = MAX_PERIAPSIS_KM.ln();
+            for _ in 0..100 {
+                let mid = 0.5 * (lo + hi);
+                if turn_at(mid.exp()) > turn {
+                    lo = mid;
+                } else {
+                    hi = mid;
+                }
+                if hi - lo < 1e-12 {
+                    break;
+                }
+            }
+            ((0.5 * (lo + hi)).exp(), 0.0)
+        };
+
+        let vp_in = (vin.powi(2) + 2.0 * gm / periapsis_km).sqrt();
+        let vp_out = (vout.powi(2) + 2.0 * gm / periapsis_km).sqrt();
+
+        Self {
+            body,
+            epoch,
+            vinf_in,
+            vinf_out,
+            turn_angle_deg: turn.to_degrees(),
+            max_turn_angle_deg: max_turn.to_degrees(),
+            periapsis_km,
+            dv_km_s: (vp_out - vp_in).abs() + penalty,
+        }
+    }
+
+    /// Returns the penalty of this flyby if it must be unpowered: the mismatch of the v-infinity magnitudes, plus the
+    /// impulse needed to rotate the outgoing v-infinity if the turn angle exceeds the largest turn angle (km/s)
+    pub fn unpowered_penalty_km_s(&self) -> f64 {
+        let vout = self.vinf_out.norm();
+        let excess = (self.turn_angle_deg - self.max_turn_angle_deg)
+            .max(0.0)
+            .to_radians();
+        (vout - self.vinf_in.norm()).abs() + 2.0 * vout * (0.5 * excess).sin()
+    }
+
+    /// Returns whether this flyby is feasible without deltaV, given the tolerance on the v-infinity magnitudes
+    pub fn is_unpowered(&self, tol_km_s: f64) -> bool {
+        (self.vinf_out.norm() - self.vinf_in.norm()).abs() <= tol_km_s
+            && self.turn_angle_deg <= self.max_turn_angle_deg
+    }
+}
+
+impl fmt::Display for Flyby {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} flyby on {}: v-inf {:.3} -> {:.3} km/s, turn {:.3} deg (max {:.3} deg), periapsis {:.1} km, dv {:.3} km/s",
+            self.body.name(),
+            self.epoch.as_gregorian_utc_str(),
+            self.vinf_in.norm(),
+            self.vinf_out.norm(),
+            self.turn_angle_deg,
+            self.max_turn_angle_deg,
+            self.periapsis_km,
+            self.dv_km_s
+        )
+    }
+}
+
+/// A patched conic trajectory through the sequence of the planner
+#[derive(Clone, Debug)]
+pub struct MgaTrajectory {
+    /// Epoch of the departure, of each flyby and of the arrival
+    pub epochs: Vec<Epoch>,
+    /// Departure v-infinity in the heliocentric J2000 frame (km/s)
+    pub vinf_dep: Vector3<f64>,
+    /// Arrival v-infinity in the heliocentric J2000 frame (km/s)
+    pub vinf_arr: Vector3<f64>,
+    pub flybys: Vec<Flyby>,
+    /// Cost of the trajectory: the departure v-infinity, the deltaV (or penalty) of each flyby and, if requested by
+    /// the planner, the arrival v-infinity (km/s)
+    pub cost_km_s: f64,
+}
+
+impl MgaTrajectory {
+    /// Returns the characteristic energy at departure (km^2/s^2)
+    pub fn c3(&self) -> f64 {
+        self.vinf_dep.norm_squared()
+    }
+
+    /// Returns the time of flight of each leg in days
+    pub fn tofs_days(&self) -> Vec<f64> {
+        self.epochs
+            .windows(2)
+            .map(|dts| (dts[1] - dts[0]).in_seconds() / SECONDS_PER_DAY)
+            .collect()
+    }
+}
+
+impl fmt::Display for MgaTrajectory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Departure on {} with C3 = {:.3} km^2/s^2, arrival on {} with v-inf = {:.3} km/s, cost = {:.3} km/s",
+            self.epochs[0].as_gregorian_utc_str(),
+            self.c3(),
+            self.epochs[self.epochs.len() - 1].as_gregorian_utc_str(),
+            self.vinf_arr.norm(),
+            self.cost_km_s
+        )?;
+        for flyby in &self.flybys {
+            writeln!(f, "\t{}", flyby)?;
+        }
+        Ok(())
+    }
+}
+
+/// Bounds of the departure epoch and of the time of flight of each leg for the evolutionary search
+#[derive(Clone, Debug)]
+pub struct MgaBounds {
+    pub departure: (Epoch, Epoch),
+    /// Bounds of the time of flight of each leg in days
+    pub tofs_days: Vec<(f64, f64)>,
+}
+
+/// Options of the differential evolution (DE/rand/1/bin)
+#[derive(Copy, Clone, Debug)]
+pub struct EvolutionOptions {
+    pub population: usize,
+    pub generations: usize,
+    /// Differential weight, usually between 0.5 and 1.0
+    pub weight: f64,
+    /// Crossover probability
+    pub crossover: f64,
+    /// Seed of the random number generator, for reproducible searches
+    pub seed: u64,
+}
+
+impl Default for EvolutionOptions {
+    fn default() -> Self {
+        Self {
+            population: 40,
+            generations: 200,
+            weight: 0.8,
+            crossover: 0.9,
+            seed: 0,
+        }
+    }
+}
+
+/// Patched conic planner of multiple gravity assist (MGA) trajectories, e.g. Earth-Venus-Earth-Jupiter.
+///
+/// Each leg is the zero revolution Lambert transfer between the bodies in the heliocentric J2000 frame, and the legs
+/// are chained with flybys. The minimum periapsis of each flyby is the equatorial radius of the body, if known by
+/// the Cosm, plus the minimum altitude.
+pub struct MgaPlanner {
+    pub cosm: Arc<Cosm>,
+    /// Departure body, flyby bodies and arrival body
+    pub sequence: Vec<Bodies>,
+    /// Minimum altitude of the flybys above the equatorial radius of the body (km)
+    pub min_altitude_km: f64,
+    /// Whether the flybys may be powered, otherwise their deltaV is replaced with their unpowered penalty
+    pub powered: bool,
+    /// Whether the arrival v-infinity is included in the cost, e.g. for an orbit insertion
+    pub include_arrival: bool,
+}
+
+impl MgaPlanner {
+    /// Initializes a planner of powered flybys, with a minimum flyby altitude of 200 km
+    pub fn new(cosm: Arc<Cosm>, sequence: Vec<Bodies>) -> Result<Self, NyxError> {
+        if sequence.len() < 2 {
+            return Err(NyxError::InvalidTargetingProblem(
+                "a sequence requires at least a departure and an arrival body".to_string(),
+            ));
+        }
+        Ok(Self {
+            cosm,
+            sequence,
+            min_altitude_km: 200.0,
+            powered: true,
+            include_arrival: false,
+        })
+    }
+
+    /// Computes the trajectory which departs at the provided epoch with these times of flight (one per leg)
+    pub fn evaluate(&self, departure: Epoch, tofs_days: &[f64]) -> Result<MgaTrajectory, NyxError> {
+        if tofs_days.len() != self.sequence.len() - 1 {
+            return Err(NyxError::InvalidTargetingProblem(format!(
+                "{} times of flight provided for {} legs",
+                tofs_days.len(),
+                self.sequence.len() - 1
+            )));
+        }
+        let sun2k = self.cosm.try_frame("Sun J2000")?;
+        let gm = sun2k.gm();
+
+        let mut epochs = vec![departure];
+        for tof in tofs_days {
+            if *tof <= 0.0 {
+                return Err(NyxError::InvalidTargetingProblem(format!(
+                    "time of flight of {} days is not positive",
+                    tof
+                )));
+            }
+            epochs.push(epochs[epochs.len() - 1] + *tof * TimeUnit::Day);
+        }
+        let states = self
+            .sequence
+            .iter()
+            .zip(&epochs)
+            .map(|(body, dt)| {
+                self.cosm
+                    .try_celestial_state(body.ephem_path(), *dt, sun2k, LTCorr::None)
+            })
+            .collect::<Result<Vec<Orbit>, NyxError>>()?;
+
+        let mut vinf_dep = Vector3::zeros();
+        let mut vinf_arr = Vector3::zeros();
+        let mut flybys = Vec::with_capacity(self.sequence.len() - 2);
+        let mut cost_km_s = 0.0;
+        for (leg, bodies) in states.windows(2).enumerate() {
+            let sols = izzo(
+                bodies[0].radius(),
+                bodies[1].radius(),
+                (bodies[1].dt - bodies[0].dt).in_seconds(),
+                gm,
+                TransferKind::NRevs(0),
+            )?;
+            let v_out = sols[0].v_init - bodies[0].velocity();
+            if leg == 0 {
+                vinf_dep = v_out;
+                cost_km_s += v_out.norm();
+            } else {
+                let flyby = self.flyby(leg, bodies[0].dt, vinf_arr, v_out);
+                cost_km_s += if self.powered {
+                    flyby.dv_km_s
+                } else {
+                    flyby.unpowered_penalty_km_s()
+                };
+                flybys.push(flyby);
+            }
+            // Incoming v-infinity of the next body
+            vinf_arr = sols[0].v_final - bodies[1].velocity();
+        }
+        if self.include_arrival {
+            cost_km_s += vinf_arr.norm();
+        }
+
+        Ok(MgaTrajectory {
+            epochs,
+            vinf_dep,
+            vinf_arr,
+            flybys,
+            cost_km_s,
+        })
+    }
+
+    /// Computes the flyby of the i-th body of the sequence
+    fn flyby(
+        &self,
+        i: usize,
+        epoch: Epoch,
+        vinf_in: Vector3<f64>,
+        vinf_out: Vector3<f64>,
+    ) -> Flyby {
+        let body = self.sequence[i];
+        let frame = self.cosm.frame_from_ephem_path(body.ephem_path());
+        let radius = if frame.is_geoid() {
+            frame.equatorial_radius()
+        } else {
+            0.0
+        };
+        Flyby::new(
+            body,
+            epoch,
+            vinf_in,
+            vinf_out,
+            frame.gm(),
+            radius + self.min_altitude_km,
+        )
+    }
+
+    /// Evaluates all of the combinations of the departure epochs and of the times of flight of each leg, in
+    /// parallel, and returns the valid trajectories sorted by increasing cost.
+    pub fn grid(&self, departures: &[Epoch], tofs_days: &[Vec<f64>]) -> Vec<MgaTrajectory> {
+        let mut combinations: Vec<(Epoch, Vec<f64>)> =
+            departures.iter().map(|dt| (*dt, Vec::new())).collect();
+        for leg_tofs in tofs_days {
+            combinations = combinations
+                .into_iter()
+                .flat_map(|(dt, tofs)| {
+                    leg_tofs.iter().map(move |tof| {
+                        let mut tofs = tofs.clone();
+                        tofs.push(*tof);
+                        (dt, tofs)
+                    })
+                })
+                .collect();
+        }
+
+        let mut trajectories: Vec<MgaTrajectory> = combinations
+            .par_iter()
+            .filter_map(|(dt, tofs)| match self.evaluate(*dt, tofs) {
+                Ok(traj) if traj.cost_km_s.is_finite() => Some(traj),
+                Ok(_) => None,
+                Err(e) => {
+                    debug!("no trajectory departing on {} with {:?}: {}", dt, tofs, e);
+                    None
+                }
+            })
+            .collect();
+        trajectories.sort_by(|a, b| a.cost_km_s.partial_cmp(&b.cost_km_s).unwrap());
+        trajectories
+    }
+
+    /// Searches for the trajectory of minimum cost within the bounds with a differential evolution, where the
+    /// population is evaluated in parallel at each generation.
+    pub fn evolve(
+        &self,
+        bounds: &MgaBounds,
+        opts: EvolutionOptions,
+    ) -> Result<MgaTrajectory, NyxError> {
+        if bounds.tofs_days.len() != self.sequence.len() - 1 {
+            return Err(NyxError::InvalidTargetingProblem(format!(
+                "{} bounds of time of flight provided for {} legs",
+                bounds.tofs_days.len(),
+                self.sequence.len() - 1
+            )));
+        }
+        if opts.population < 4 {
+            return Err(NyxError::InvalidTargetingProblem(
+                "differential evolution requires a population of at least four".to_string(),
+            ));
+        }
+        // The decision vector is the departure in days after the lower bound, and the times of flight
+        let mut lower = vec![0.0];
+        let mut upper =
+            vec![(bounds.departure.1 - bounds.departure.0).in_seconds() / SECONDS_PER_DAY];
+        for (lo, hi) in &bounds.tofs_days {
+            lower.push(*lo);
+            upper.push(*hi);
+        }
+        let dim = lower.len();
+
+        let cost = |x: &[f64]| -> f64 {
+            match self.evaluate(bounds.departure.0 + x[0] * TimeUnit::Day, &x[1..]) {
+                Ok(traj) if traj.cost_km_s.is_finite() => traj.cost_km_s,
+                _ => std::f64::INFINITY,
+            }
+        };
+
+        let mut rng = StdRng::seed_from_u64(opts.seed);
+        let mut population: Vec<Vec<f64>> = (0..opts.population)
+            .map(|_| {
+                (0..dim)
+                    .map(|k| rng.gen_range(lower[k]..=upper[k]))
+                    .collect()
+            })
+            .collect();
+        let mut costs: Vec<f64> = population.par_iter().map(|x| cost(x)).collect();
+
+        for gen in 0..opts.generations {
+            let trials: Vec<Vec<f64>> = (0..opts.population)
+                .map(|i| {
+                    // Three distinct members, different from the current one
+                    let mut picks = Vec::with_capacity(3);
+                    while picks.len() < 3 {
+                        let pick = rng.gen_range(0..opts.population);
+                        if pick != i && !picks.contains(&pick) {
+                            picks.push(pick);
+                        }
+                    }
+                    let forced = rng.gen_range(0..dim);
+                    (0..dim)
+                        .map(|k| {
+                            if k == forced || rng.gen::<f64>() < opts.crossover {
+                                let value = population[picks[0]][k]
+                                    + opts.weight
+                                        * (population[picks[1]][k] - population[picks[2]][k]);
+                                value.max(lower[k]).min(upper[k])
+                            } else {
+                                population[i][k]
+                            }
+                        })
+                        .collect()
+                })
+                .collect();
+            let trial_costs: Vec<f64> = trials.par_iter().map(|x| cost(x)).collect();
+            for (i, (trial, trial_cost)) in trials.into_iter().zip(trial_costs).enumerate() {
+                if trial_cost <= costs[i] {
+                    population[i] = trial;
+                    costs[i] = trial_cost;
+                }
+            }
+            debug!(
+                "generation #{}: best cost = {:.6} km/s",
+                gen,
+                costs.iter().cloned().fold(std::f64::INFINITY, f64::min)
+            );
+        }
+
+        let (best, best_cost) = population
+            .iter()
+            .zip(&costs)
+            .min_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+            .unwrap();
+        if !best_cost.is_finite() {
+            return Err(NyxError::InvalidTargetingProblem(
+                "no valid trajectory within the bounds".to_string(),
+            ));
+        }
+        self.evaluate(bounds.departure.0 + best[0] * TimeUnit::Day, &best[1..])
+    }
+}
+
+#[test]
+fn mga_flyby() {
+    let gm = 398_600.441_5;
+    let min_periapsis_km = 6_578.0;
+    let vinf = 5.0;
+    // Turn angle of an unpowered flyby with a periapsis radius of 10,000 km
+    let ecc: f64 = 1.0 + 10_000.0 * vinf * vinf / gm;
+    let turn = 2.0 * (1.0 / ecc).asin();
+    let vinf_in = Vector3::new(vinf, 0.0, 0.0);
+    let vinf_out = vinf * Vector3::new(turn.cos(), turn.sin(), 0.0);
+    let epoch = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+
+    let flyby = Flyby::new(
+        Bodies::Earth,
+        epoch,
+        vinf_in,
+        vinf_out,
+        gm,
+        min_periapsis_km,
+    );
+    println!("{}", flyby);
+    assert!((flyby.periapsis_km - 10_000.0).abs() < 1e-3);
+    assert!((flyby.turn_angle_deg - turn.to_degrees()).abs() < 1e-9);
+    assert!(flyby.dv_km_s < 1e-9);
+    assert!(flyby.unpowered_penalty_km_s() < 1e-9);
+    assert!(flyby.is_unpowered(1e-9));
+
+    // Increasing the outgoing v-infinity requires a periapsis burn
+    let powered = Flyby::new(
+        Bodies::Earth,
+        epoch,
+        vinf_in,
+        1.1 * vinf_out,
+        gm,
+        min_periapsis_km,
+    );
+    assert!(!powered.is_unpowered(1e-3));
+    assert!(powered.dv_km_s > 0.0 && powered.dv_km_s < 0.5);
+    assert!((powered.unpowered_penalty_km_s() - 0.5).abs() < 1e-9);
+
+    // This turn angle requires a periapsis below the minimum
+    let turn = 170.0_f64.to_radians();
+    let too_tight = Flyby::new(
+        Bodies::Earth,
+        epoch,
+        vinf_in,
+        vinf * Vector3::new(turn.cos(), turn.sin(), 0.0),
+        gm,
+        min_periapsis_km,
+    );
+    assert!((too_tight.periapsis_km - min_periapsis_km).abs() < 1e-12);
+    assert!(too_tight.max_turn_angle_deg < 170.0);
+    assert!(too_tight.dv_km_s > 0.0);
+    assert!(!too_tight.is_unpowered(1e-3));
+}
+
+#[test]
+fn mga_earth_venus_earth() {
+    let cosm = Cosm::de438();
+    let mut planner = MgaPlanner::new(
+        cosm,
+        vec![
+            Bodies::EarthBarycenter,
+            Bodies::VenusBarycenter,
+            Bodies::EarthBarycenter,
+        ],
+    )
+    .unwrap();
+    planner.include_arrival = true;
+
+    let start = Epoch::from_gregorian_utc_at_midnight(2021, 1, 1);
+    let departures: Vec<Epoch> = (0..6)
+        .map(|i: u8| start + 30.0 * f64::from(i) * TimeUnit::Day)
+        .collect();
+    let tofs_days = vec![vec![100.0, 150.0, 200.0], vec![200.0, 300.0, 400.0]];
+    let grid = planner.grid(&departures, &tofs_days);
+    assert!(!grid.is_empty() && grid.len() <= 6 * 3 * 3);
+    for pair in grid.windows(2) {
+        assert!(pair[0].cost_km_s <= pair[1].cost_km_s);
+    }
+    println!("Best of the grid: {}", grid[0]);
+    assert_eq!(grid[0].flybys.len(), 1);
+    assert_eq!(grid[0].tofs_days().len(), 2);
+
+    let bounds = MgaBounds {
+        departure: (start, start + 150 * TimeUnit::Day),
+        tofs_days: vec![(100.0, 200.0), (200.0, 400.0)],
+    };
+    let opts = EvolutionOptions {
+        population: 20,
+        generations: 50,
+        ..Default::default()
+    };
+    let best = planner.evolve(&bounds, opts).unwrap();
+    println!("Best of the evolution: {}", best);
+    assert!(best.epochs[0] >= bounds.departure.0 && best.epochs[0] <= bounds.departure.1);
+    for (tof, (lo, hi)) in best.tofs_days().iter().zip(&bounds.tofs_days) {
+        assert!(*tof >= lo - 1e-6 && *tof <= hi + 1e-6);
+    }
+    // The evolution searches a continuous domain which includes the grid
+    assert!(best.cost_km_s <= grid[0].cost_km_s + 1.0);
+
+    // Same seed, same result
+    let again = planner.evolve(&bounds, opts).unwrap();
+    assert!((again.cost_km_s - best.cost_km_s).abs() < 1e-12);
+
+    assert!(planner.evaluate(start, &[100.0]).is_err());
+}
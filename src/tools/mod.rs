@@ -1 +1,7 @@
+pub mod design;
 pub mod lambert;
+pub mod lifetime;
+pub mod maneuvers;
+pub mod mga;
+pub mod porkchop;
+pub mod relative;
@@ -0,0 +1,246 @@
+extern crate csv;
+extern crate rayon;
+
+use self::rayon::prelude::*;
+use super::lambert::{izzo, TransferKind};
+use crate::celestia::{Bodies, Cosm, LTCorr, Orbit};
+use crate::errors::NyxError;
+use crate::time::{Duration, Epoch, SECONDS_PER_DAY};
+use std::sync::Arc;
+
+/// The zero revolution Lambert transfer between a departure and an arrival epoch
+#[derive(Copy, Clone, Debug)]
+pub struct PorkchopPoint {
+    pub departure: Epoch,
+    pub arrival: Epoch,
+    /// Time of flight in days
+    pub tof_days: f64,
+    /// Characteristic energy at departure, i.e. the square of the departure v-infinity (km^2/s^2)
+    pub c3: f64,
+    /// Declination of the departure v-infinity in the J2000 frame (deg)
+    pub dla_deg: f64,
+    /// Right ascension of the departure v-infinity in the J2000 frame (deg)
+    pub rla_deg: f64,
+    /// Norm of the arrival v-infinity (km/s)
+    pub vinf_arr: f64,
+}
+
+/// A porkchop plot: the grid of the Lambert transfers between two bodies, in the heliocentric J2000 frame, for each
+/// pair of departure and arrival epochs.
+#[derive(Clone, Debug)]
+pub struct Porkchop {
+    pub departure_body: Bodies,
+    pub arrival_body: Bodies,
+    pub departures: Vec<Epoch>,
+    pub arrivals: Vec<Epoch>,
+    /// Transfer of each departure (row) and arrival (column), None if the arrival is not after the departure or
+    /// if the Lambert problem has no solution.
+    pub grid: Vec<Vec<Option<PorkchopPoint>>>,
+}
+
+impl Porkchop {
+    /// Solves the prograde Lambert problem for each pair of departure and arrival epochs, in parallel.
+    /// The states of the bodies are computed from the ephemerides of the Cosm, without light time correction.
+    pub fn new(
+        cosm: Arc<Cosm>,
+        departure_body: Bodies,
+        arrival_body: Bodies,
+        departures: Vec<Epoch>,
+        arrivals: Vec<Epoch>,
+    ) -> Result<Self, NyxError> {
+        let sun2k = cosm.try_frame("Sun J2000")?;
+        let gm = sun2k.gm();
+
+        let arrival_states = arrivals
+            .par_iter()
+            .map(|dt| cosm.try_celestial_state(arrival_body.ephem_path(), *dt, sun2k, LTCorr::None))
+            .collect::<Result<Vec<Orbit>, NyxError>>()?;
+
+        let grid = departures
+            .par_iter()
+            .map(|dt| {
+                let dep_state = cosm.try_celestial_state(
+                    departure_body.ephem_path(),
+                    *dt,
+                    sun2k,
+                    LTCorr::None,
+                )?;
+                Ok(arrival_states
+                    .iter()
+                    .map(|arr_state| Self::transfer(&dep_state, arr_state, gm))
+                    .collect())
+            })
+            .collect::<Result<Vec<Vec<Option<PorkchopPoint>>>, NyxError>>()?;
+
+        Ok(Self {
+            departure_body,
+            arrival_body,
+            departures,
+            arrivals,
+            grid,
+        })
+    }
+
+    /// Returns the transfer between these states of the departure and arrival bodies, if any
+    fn transfer(dep_state: &Orbit, arr_state: &Orbit, gm: f64) -> Option<PorkchopPoint> {
+        if arr_state.dt <= dep_state.dt {
+            return None;
+        }
+        let tof_s = (arr_state.dt - dep_state.dt).in_seconds();
+        match izzo(
+            dep_state.radius(),
+            arr_state.radius(),
+            tof_s,
+            gm,
+            TransferKind::NRevs(0),
+        ) {
+            Ok(sols) => {
+                let vinf_dep = sols[0].v_init - dep_state.velocity();
+                let vinf_arr = sols[0].v_final - arr_state.velocity();
+                let vinf_dep_norm = vinf_dep.norm();
+                if !vinf_dep_norm.is_finite() || !vinf_arr.norm().is_finite() {
+                    debug!(
+                        "no finite transfer from {} to {}",
+                        dep_state.dt, arr_state.dt
+                    );
+                    return None;
+                }
+                Some(PorkchopPoint {
+                    departure: dep_state.dt,
+                    arrival: arr_state.dt,
+                    tof_days: tof_s / SECONDS_PER_DAY,
+                    c3: vinf_dep_norm.powi(2),
+                    dla_deg: (vinf_dep[2] / vinf_dep_norm).asin().to_degrees(),
+                    rla_deg: vinf_dep[1]
+                        .atan2(vinf_dep[0])
+                        .to_degrees()
+                        .rem_euclid(360.0),
+                    vinf_arr: vinf_arr.norm(),
+                })
+            }
+            Err(e) => {
+                debug!(
+                    "no transfer from {} to {}: {}",
+                    dep_state.dt, arr_state.dt, e
+                );
+                None
+            }
+        }
+    }
+
+    /// Returns the transfer of minimum C3, if any
+    pub fn min_c3(&self) -> Option<PorkchopPoint> {
+        self.points()
+            .filter(|pt| pt.c3.is_finite())
+            .min_by(|a, b| a.c3.partial_cmp(&b.c3).unwrap())
+            .copied()
+    }
+
+    /// Returns the transfer of minimum total v-infinity (departure and arrival), if any
+    pub fn min_vinf(&self) -> Option<PorkchopPoint> {
+        self.points()
+            .filter(|pt| (pt.c3.sqrt() + pt.vinf_arr).is_finite())
+            .min_by(|a, b| {
+                (a.c3.sqrt() + a.vinf_arr)
+                    .partial_cmp(&(b.c3.sqrt() + b.vinf_arr))
+                    .unwrap()
+            })
+            .copied()
+    }
+
+    /// Iterates through all of the transfers of the grid
+    pub fn points(&self) -> impl Iterator<Item = &PorkchopPoint> {
+        self.grid.iter().flatten().flatten()
+    }
+
+    /// Writes the grid to a CSV file, with one row per pair of departure and arrival epochs (as Gregorian UTC).
+    /// The quantities are empty if there is no transfer for this pair.
+    pub fn to_csv(&self, path: &str) -> Result<(), csv::Error> {
+        let mut wtr = csv::Writer::from_path(path)?;
+        wtr.write_record(&[
+            "departure",
+            "arrival",
+            "tof_days",
+            "c3_km2_s2",
+            "dla_deg",
+            "rla_deg",
+            "vinf_arr_km_s",
+        ])?;
+        for (dep, row) in self.departures.iter().zip(&self.grid) {
+            for (arr, point) in self.arrivals.iter().zip(row) {
+                let quantities = match point {
+                    Some(point) => vec![
+                        format!("{}", point.tof_days),
+                        format!("{}", point.c3),
+                        format!("{}", point.dla_deg),
+                        format!("{}", point.rla_deg),
+                        format!("{}", point.vinf_arr),
+                    ],
+                    None => vec![String::new(); 5],
+                };
+                let mut record = vec![dep.as_gregorian_utc_str(), arr.as_gregorian_utc_str()];
+                record.extend(quantities);
+                wtr.write_record(&record)?;
+            }
+        }
+        wtr.flush()?;
+        info!("Porkchop plot saved to {}", path);
+        Ok(())
+    }
+}
+
+/// Returns the epochs from the start to the end (included) with the provided step, e.g. to build the departure and
+/// arrival windows of a porkchop plot.
+pub fn epoch_grid(start: Epoch, end: Epoch, step: Duration) -> Vec<Epoch> {
+    let mut epochs = Vec::new();
+    let mut epoch = start;
+    while epoch <= end {
+        epochs.push(epoch);
+        epoch = epoch + step;
+    }
+    epochs
+}
+
+#[test]
+fn porkchop_earth_mars_2020() {
+    use crate::time::TimeUnit;
+    let cosm = Cosm::de438();
+
+    // Mars 2020 launched on 2020-07-30 and landed on 2021-02-18
+    let departures = epoch_grid(
+        Epoch::from_gregorian_utc_at_midnight(2020, 7, 1),
+        Epoch::from_gregorian_utc_at_midnight(2020, 9, 1),
+        5 * TimeUnit::Day,
+    );
+    let arrivals = epoch_grid(
+        Epoch::from_gregorian_utc_at_midnight(2021, 1, 1),
+        Epoch::from_gregorian_utc_at_midnight(2021, 4, 1),
+        5 * TimeUnit::Day,
+    );
+    assert_eq!(departures.len(), 13);
+    assert_eq!(arrivals.len(), 19);
+
+    let pc = Porkchop::new(
+        cosm,
+        Bodies::EarthBarycenter,
+        Bodies::MarsBarycenter,
+        departures,
+        arrivals,
+    )
+    .unwrap();
+    assert_eq!(pc.grid.len(), 13);
+    assert_eq!(pc.points().count(), 13 * 19);
+
+    let best = pc.min_c3().unwrap();
+    println!("{:?}", best);
+    // The C3 of the 2020 opportunity is around 15 km^2/s^2
+    assert!(best.c3 > 10.0 && best.c3 < 20.0);
+    assert!(best.tof_days > 120.0 && best.tof_days < 270.0);
+    assert!(best.dla_deg.abs() < 60.0);
+    assert!(best.vinf_arr > 2.0 && best.vinf_arr < 8.0);
+
+    let path = std::env::temp_dir().join("porkchop_earth_mars_2020.csv");
+    pc.to_csv(path.to_str().unwrap()).unwrap();
+    let contents = std::fs::read_to_string(path).unwrap();
+    assert_eq!(contents.lines().count(), 1 + 13 * 19);
+}
@@ -0,0 +1,411 @@
+use crate::celestia::{Frame, Orbit};
+use crate::dimensions::{Matrix3, Matrix4, Matrix6, Vector3, Vector6, U3};
+use crate::errors::NyxError;
+
+const KEPLER_TOL: f64 = 1e-14;
+const KEPLER_MAX_ITER: usize = 50;
+
+/// Linearized models of the relative motion of a deputy around a chief orbit.
+/// All of the relative states are expressed in the rotating RIC frame of the chief (radial, in-track, cross-track),
+/// and the relative velocities are the derivatives of the relative position as seen from that rotating frame.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RelativeMotion {
+    /// Clohessy-Wiltshire (or Hill's) equations, only valid if the chief is on a (near) circular orbit
+    ClohessyWiltshire,
+    /// Yamanaka-Ankersen solution of the Tschauner-Hempel equations, valid for any elliptical chief orbit
+    YamanakaAnkersen,
+}
+
+impl RelativeMotion {
+    /// Returns the state transition matrix of the relative RIC state from the epoch of the chief to `tof` seconds later
+    pub fn stm(self, chief: &Orbit, tof: f64) -> Result<Matrix6<f64>, NyxError> {
+        match self {
+            RelativeMotion::ClohessyWiltshire => {
+                let mean_motion = (chief.frame.gm() / chief.sma().powi(3)).sqrt();
+                Ok(cw_stm(mean_motion, tof))
+            }
+            RelativeMotion::YamanakaAnkersen => ya_stm(chief, tof),
+        }
+    }
+}
+
+/// Stores the two impulses of a rendezvous
+#[derive(Debug)]
+pub struct RendezvousSolution {
+    /// Impulse at the start of the transfer in the RIC frame of the chief, in km/s
+    pub dv_init_ric: Vector3<f64>,
+    /// Impulse at the end of the transfer in the RIC frame of the chief, in km/s
+    pub dv_final_ric: Vector3<f64>,
+    /// Impulse at the start of the transfer in the inertial frame of the chief, in km/s
+    pub dv_init: Vector3<f64>,
+    /// Impulse at the end of the transfer in the inertial frame of the chief, in km/s
+    pub dv_final: Vector3<f64>,
+}
+
+impl RendezvousSolution {
+    /// Returns the total Δv of this rendezvous in km/s
+    pub fn dv_total(&self) -> f64 {
+        self.dv_init.norm() + self.dv_final.norm()
+    }
+}
+
+/// Returns the Clohessy-Wiltshire state transition matrix for the provided mean motion (in rad/s) of the chief
+/// and a time of flight in seconds.
+pub fn cw_stm(mean_motion: f64, tof: f64) -> Matrix6<f64> {
+    let n = mean_motion;
+    let nt = n * tof;
+    let (s, c) = nt.sin_cos();
+
+    let mut stm = Matrix6::zeros();
+    // Radial
+    stm[(0, 0)] = 4.0 - 3.0 * c;
+    stm[(0, 3)] = s / n;
+    stm[(0, 4)] = 2.0 * (1.0 - c) / n;
+    // In-track
+    stm[(1, 0)] = 6.0 * (s - nt);
+    stm[(1, 1)] = 1.0;
+    stm[(1, 3)] = 2.0 * (c - 1.0) / n;
+    stm[(1, 4)] = (4.0 * s - 3.0 * nt) / n;
+    // Cross-track
+    stm[(2, 2)] = c;
+    stm[(2, 5)] = s / n;
+    // Radial velocity
+    stm[(3, 0)] = 3.0 * n * s;
+    stm[(3, 3)] = c;
+    stm[(3, 4)] = 2.0 * s;
+    // In-track velocity
+    stm[(4, 0)] = 6.0 * n * (c - 1.0);
+    stm[(4, 3)] = -2.0 * s;
+    stm[(4, 4)] = 4.0 * c - 3.0;
+    // Cross-track velocity
+    stm[(5, 2)] = -n * s;
+    stm[(5, 5)] = c;
+    stm
+}
+
+/// Returns the Yamanaka-Ankersen state transition matrix of the relative RIC state from the epoch of the chief
+/// to `tof` seconds later.
+/// Source: Yamanaka, K. and Ankersen, F., "New State Transition Matrix for Relative Motion on an Arbitrary Elliptical Orbit", JGCD 2002.
+pub fn ya_stm(chief: &Orbit, tof: f64) -> Result<Matrix6<f64>, NyxError> {
+    let ecc = chief.ecc();
+    if ecc >= 1.0 {
+        return Err(NyxError::CustomError(format!(
+            "Yamanaka-Ankersen requires an elliptical chief orbit (e = {})",
+            ecc
+        )));
+    }
+
+    let k2 = chief.hmag() / chief.semi_parameter().powi(2);
+
+    let (ea_init, delta_ea) = delta_ea(chief, tof)?;
+    let ta_init = ta_from_ea(ea_init, ecc);
+    let ta_final = ta_from_ea(ea_init + delta_ea, ecc);
+
+    // In-plane motion, on (x, z, x', z') of the transformed variables
+    let phi_init_inv = match ya_in_plane(ta_init, ecc, 0.0).try_inverse() {
+        Some(inv) => inv,
+        None => return Err(NyxError::SingularStateTransitionMatrix),
+    };
+    let phi_in_plane = ya_in_plane(ta_final, ecc, k2 * tof) * phi_init_inv;
+
+    // Assemble the full STM in the transformed variables (x, y, z, x', y', z')
+    let mut phi = Matrix6::zeros();
+    let in_plane_idx = [0, 2, 3, 5];
+    for (i, row) in in_plane_idx.iter().enumerate() {
+        for (j, col) in in_plane_idx.iter().enumerate() {
+            phi[(*row, *col)] = phi_in_plane[(i, j)];
+        }
+    }
+    // The out-of-plane motion is harmonic in the true anomaly
+    let (s_dta, c_dta) = (ta_final - ta_init).sin_cos();
+    phi[(1, 1)] = c_dta;
+    phi[(1, 4)] = s_dta;
+    phi[(4, 1)] = -s_dta;
+    phi[(4, 4)] = c_dta;
+
+    Ok(ya_to_ric(ta_final, ecc, k2) * phi * ric_to_ya(ta_init, ecc, k2))
+}
+
+/// Returns the RIC state of the deputy relative to the chief, cf. `Orbit::ric_difference`.
+pub fn ric_state(chief: &Orbit, deputy: &Orbit) -> Vector6<f64> {
+    chief.ric_difference(deputy)
+}
+
+/// Builds the deputy orbit from its RIC state relative to the chief. This is the inverse of `ric_state`.
+pub fn deputy_from_ric(chief: &Orbit, ric: &Vector6<f64>) -> Orbit {
+    let dcm = chief.dcm_to_inertial(Frame::RCN);
+    let omega = Vector3::new(0.0, 0.0, chief.hmag() / chief.rmag().powi(2));
+    let rho = Vector3::new(ric[0], ric[1], ric[2]);
+    let rho_dot = Vector3::new(ric[3], ric[4], ric[5]);
+    let r = chief.radius() + dcm * rho;
+    let v = chief.velocity() + dcm * (rho_dot + omega.cross(&rho));
+    Orbit::cartesian(r[0], r[1], r[2], v[0], v[1], v[2], chief.dt, chief.frame)
+}
+
+/// Propagates the deputy for `tof` seconds using the linearized relative motion around the chief.
+/// The chief itself is propagated with two body dynamics. Returns the propagated chief and deputy.
+pub fn propagate(
+    chief: &Orbit,
+    deputy: &Orbit,
+    tof: f64,
+    model: RelativeMotion,
+) -> Result<(Orbit, Orbit), NyxError> {
+    let ric = model.stm(chief, tof)? * ric_state(chief, deputy);
+    let chief_final = two_body(chief, tof)?;
+    let deputy_final = deputy_from_ric(&chief_final, &ric);
+    Ok((chief_final, deputy_final))
+}
+
+/// Solves the two impulse rendezvous of the deputy onto the chief in `tof` seconds using the linearized relative motion.
+/// The first impulse sets the deputy on a trajectory which reaches the chief, and the second one nulls the relative velocity.
+pub fn rendezvous(
+    chief: &Orbit,
+    deputy: &Orbit,
+    tof: f64,
+    model: RelativeMotion,
+) -> Result<RendezvousSolution, NyxError> {
+    if tof.abs() < std::f64::EPSILON {
+        return Err(NyxError::TargetsTooClose);
+    }
+    let stm = model.stm(chief, tof)?;
+    let ric = ric_state(chief, deputy);
+    let rho = Vector3::new(ric[0], ric[1], ric[2]);
+    let rho_dot = Vector3::new(ric[3], ric[4], ric[5]);
+
+    let phi_rr: Matrix3<f64> = stm.fixed_slice::<U3, U3>(0, 0).into_owned();
+    let phi_rv: Matrix3<f64> = stm.fixed_slice::<U3, U3>(0, 3).into_owned();
+    let phi_vr: Matrix3<f64> = stm.fixed_slice::<U3, U3>(3, 0).into_owned();
+    let phi_vv: Matrix3<f64> = stm.fixed_slice::<U3, U3>(3, 3).into_owned();
+
+    // The position to velocity block is singular for transfers of full revolutions
+    let phi_rv_inv = match phi_rv.try_inverse() {
+        Some(inv) => inv,
+        None => return Err(NyxError::SingularStateTransitionMatrix),
+    };
+
+    let dv_init_ric = -phi_rv_inv * phi_rr * rho - rho_dot;
+    let dv_final_ric = -(phi_vr * rho + phi_vv * (rho_dot + dv_init_ric));
+
+    let chief_final = two_body(chief, tof)?;
+
+    // Impulses do not change the position, so only the rotation of the frame matters
+    Ok(RendezvousSolution {
+        dv_init: chief.dcm_to_inertial(Frame::RCN) * dv_init_ric,
+        dv_final: chief_final.dcm_to_inertial(Frame::RCN) * dv_final_ric,
+        dv_init_ric,
+        dv_final_ric,
+    })
+}
+
+/// Propagates an orbit for `tof` seconds with two body dynamics, cf. `Orbit::at_epoch`.
+pub fn two_body(orbit: &Orbit, tof: f64) -> Result<Orbit, NyxError> {
+    orbit.at_epoch(orbit.dt + tof)
+}
+
+/// Returns the initial eccentric anomaly and its change after `tof` seconds, both in radians.
+/// This formulation does not rely on the argument of periapsis, so it is valid for circular orbits.
+fn delta_ea(orbit: &Orbit, tof: f64) -> Result<(f64, f64), NyxError> {
+    let gm = orbit.frame.gm();
+    let sma = orbit.sma();
+    if sma <= 0.0 {
+        return Err(NyxError::CustomError(
+            "relative motion requires an elliptical orbit".to_string(),
+        ));
+    }
+    let e_cos_ea = 1.0 - orbit.rmag() / sma;
+    let e_sin_ea = orbit.radius().dot(&orbit.velocity()) / (gm * sma).sqrt();
+    let ea_init = e_sin_ea.atan2(e_cos_ea);
+
+    let delta_ma = (gm / sma.powi(3)).sqrt() * tof;
+    let mut delta_ea = delta_ma;
+    for _ in 0..KEPLER_MAX_ITER {
+        let (sin_dea, cos_dea) = delta_ea.sin_cos();
+        let err = delta_ea - e_cos_ea * sin_dea + e_sin_ea * (1.0 - cos_dea) - delta_ma;
+        let deriv = 1.0 - e_cos_ea * cos_dea + e_sin_ea * sin_dea;
+        let step = err / deriv;
+        delta_ea -= step;
+        if step.abs() < KEPLER_TOL {
+            return Ok((ea_init, delta_ea));
+        }
+    }
+    Err(NyxError::MaxIterReached(KEPLER_MAX_ITER))
+}
+
+/// Converts the eccentric anomaly to the true anomaly (both in radians)
+fn ta_from_ea(ea: f64, ecc: f64) -> f64 {
+    let (sin_half, cos_half) = (ea / 2.0).sin_cos();
+    2.0 * ((1.0 + ecc).sqrt() * sin_half).atan2((1.0 - ecc).sqrt() * cos_half)
+}
+
+/// Yamanaka-Ankersen fundamental matrix of the in-plane motion, where `j` is k²(t - t0)
+fn ya_in_plane(ta: f64, ecc: f64, j: f64) -> Matrix4<f64> {
+    let (sin_ta, cos_ta) = ta.sin_cos();
+    let rho = 1.0 + ecc * cos_ta;
+    let s = rho * sin_ta;
+    let c = rho * cos_ta;
+    let s_prime = cos_ta + ecc * (2.0 * ta).cos();
+    let c_prime = -(sin_ta + ecc * (2.0 * ta).sin());
+
+    let mut phi = Matrix4::zeros();
+    phi[(0, 0)] = 1.0;
+    phi[(0, 1)] = -c * (1.0 + 1.0 / rho);
+    phi[(0, 2)] = s * (1.0 + 1.0 / rho);
+    phi[(0, 3)] = 3.0 * rho.powi(2) * j;
+    phi[(1, 1)] = s;
+    phi[(1, 2)] = c;
+    phi[(1, 3)] = 2.0 - 3.0 * ecc * s * j;
+    phi[(2, 1)] = 2.0 * s;
+    phi[(2, 2)] = 2.0 * c - ecc;
+    phi[(2, 3)] = 3.0 * (1.0 - 2.0 * ecc * s * j);
+    phi[(3, 1)] = s_prime;
+    phi[(3, 2)] = c_prime;
+    phi[(3, 3)] = -3.0 * ecc * (s_prime * j + s / rho.powi(2));
+    phi
+}
+
+/// Rotation from the RIC frame to the frame used by Yamanaka-Ankersen (in-track, anti-normal, anti-radial)
+fn ric_to_lvlh() -> Matrix6<f64> {
+    let mut rot = Matrix6::zeros();
+    for offset in [0, 3].iter() {
+        rot[(*offset, offset + 1)] = 1.0;
+        rot[(offset + 1, offset + 2)] = -1.0;
+        rot[(offset + 2, *offset)] = -1.0;
+    }
+    rot
+}
+
+/// Maps a RIC state to the transformed variables of Yamanaka-Ankersen, whose derivatives are with respect to the true anomaly
+fn ric_to_ya(ta: f64, ecc: f64, k2: f64) -> Matrix6<f64> {
+    let (sin_ta, cos_ta) = ta.sin_cos();
+    let rho = 1.0 + ecc * cos_ta;
+    let mut transform = Matrix6::zeros();
+    for i in 0..3 {
+        transform[(i, i)] = rho;
+        transform[(i + 3, i)] = -ecc * sin_ta;
+        transform[(i + 3, i + 3)] = 1.0 / (k2 * rho);
+    }
+    transform * ric_to_lvlh()
+}
+
+/// Maps the transformed variables of Yamanaka-Ankersen back to a RIC state
+fn ya_to_ric(ta: f64, ecc: f64, k2: f64) -> Matrix6<f64> {
+    let (sin_ta, cos_ta) = ta.sin_cos();
+    let rho = 1.0 + ecc * cos_ta;
+    let mut transform = Matrix6::zeros();
+    for i in 0..3 {
+        transform[(i, i)] = 1.0 / rho;
+        transform[(i + 3, i)] = k2 * ecc * sin_ta;
+        transform[(i + 3, i + 3)] = k2 * rho;
+    }
+    ric_to_lvlh().transpose() * transform
+}
+
+#[test]
+fn test_ric_roundtrip() {
+    use crate::celestia::Cosm;
+    use crate::time::Epoch;
+    let cosm = Cosm::de438();
+    let eme2k = cosm.frame("EME2000");
+    let dt = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+
+    let chief = Orbit::keplerian(7000.0, 0.05, 30.0, 45.0, 60.0, 75.0, dt, eme2k);
+    let deputy = Orbit::keplerian(7001.0, 0.0501, 30.01, 45.0, 60.0, 74.99, dt, eme2k);
+
+    let ric = ric_state(&chief, &deputy);
+    let deputy_rebuilt = deputy_from_ric(&chief, &ric);
+    assert!((deputy_rebuilt.radius() - deputy.radius()).norm() < 1e-9);
+    assert!((deputy_rebuilt.velocity() - deputy.velocity()).norm() < 1e-12);
+}
+
+#[test]
+fn test_two_body_period() {
+    use crate::celestia::Cosm;
+    use crate::time::Epoch;
+    let cosm = Cosm::de438();
+    let eme2k = cosm.frame("EME2000");
+    let dt = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+
+    let orbit = Orbit::keplerian(8000.0, 0.2, 30.0, 45.0, 60.0, 75.0, dt, eme2k);
+    let after = two_body(&orbit, orbit.period().in_seconds()).unwrap();
+    assert!((after.radius() - orbit.radius()).norm() < 1e-6);
+    assert!((after.velocity() - orbit.velocity()).norm() < 1e-9);
+    let half = two_body(&orbit, orbit.period().in_seconds() / 2.0).unwrap();
+    assert!((half.sma() - orbit.sma()).abs() < 1e-6);
+    assert!((half.ecc() - orbit.ecc()).abs() < 1e-9);
+}
+
+#[test]
+fn test_ya_matches_cw_circular() {
+    use crate::celestia::Cosm;
+    use crate::time::Epoch;
+    let cosm = Cosm::de438();
+    let eme2k = cosm.frame("EME2000");
+    let dt = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+
+    let chief = Orbit::keplerian(7000.0, 0.0, 30.0, 45.0, 0.0, 20.0, dt, eme2k);
+    let tof = 2500.0;
+    let cw = RelativeMotion::ClohessyWiltshire.stm(&chief, tof).unwrap();
+    let ya = RelativeMotion::YamanakaAnkersen.stm(&chief, tof).unwrap();
+    println!("CW{}YA{}", cw, ya);
+    assert!((cw - ya).norm() < 1e-6);
+}
+
+#[test]
+fn test_ya_eccentric() {
+    use crate::celestia::Cosm;
+    use crate::time::Epoch;
+    let cosm = Cosm::de438();
+    let eme2k = cosm.frame("EME2000");
+    let dt = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+
+    let chief = Orbit::keplerian(8000.0, 0.2, 30.0, 45.0, 60.0, 75.0, dt, eme2k);
+    let ric = Vector6::new(0.1, -0.2, 0.05, 1e-4, -2e-4, 5e-5);
+    let deputy = deputy_from_ric(&chief, &ric);
+
+    let tof = 0.75 * chief.period().in_seconds();
+    let (chief_final, deputy_ya) =
+        propagate(&chief, &deputy, tof, RelativeMotion::YamanakaAnkersen).unwrap();
+    let (_, deputy_cw) =
+        propagate(&chief, &deputy, tof, RelativeMotion::ClohessyWiltshire).unwrap();
+    let deputy_truth = two_body(&deputy, tof).unwrap();
+
+    let ya_err = ric_state(&chief_final, &deputy_ya) - ric_state(&chief_final, &deputy_truth);
+    let cw_err = ric_state(&chief_final, &deputy_cw) - ric_state(&chief_final, &deputy_truth);
+    println!("YA err: {}\nCW err: {}", ya_err, cw_err);
+    // Linearization errors are on the order of the squared separation over the radius
+    assert!(ya_err.fixed_rows::<U3>(0).norm() < 1e-3);
+    assert!(ya_err.fixed_rows::<U3>(0).norm() < cw_err.fixed_rows::<U3>(0).norm());
+}
+
+#[test]
+fn test_rendezvous() {
+    use crate::celestia::Cosm;
+    use crate::time::Epoch;
+    let cosm = Cosm::de438();
+    let eme2k = cosm.frame("EME2000");
+    let dt = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+
+    let chief = Orbit::keplerian(6778.0, 0.01, 51.6, 45.0, 60.0, 75.0, dt, eme2k);
+    // Deputy is 10 km behind and 1 km below the chief
+    let deputy = deputy_from_ric(&chief, &Vector6::new(-1.0, -10.0, 0.0, 0.0, 0.0, 0.0));
+    let tof = 0.4 * chief.period().in_seconds();
+
+    let sol = rendezvous(&chief, &deputy, tof, RelativeMotion::YamanakaAnkersen).unwrap();
+    println!("{:?}\ntotal: {} m/s", sol, sol.dv_total() * 1e3);
+
+    let mut deputy_burnt = deputy;
+    deputy_burnt.vx += sol.dv_init[0];
+    deputy_burnt.vy += sol.dv_init[1];
+    deputy_burnt.vz += sol.dv_init[2];
+    let chief_final = two_body(&chief, tof).unwrap();
+    let mut deputy_final = two_body(&deputy_burnt, tof).unwrap();
+    deputy_final.vx += sol.dv_final[0];
+    deputy_final.vy += sol.dv_final[1];
+    deputy_final.vz += sol.dv_final[2];
+
+    let miss = ric_state(&chief_final, &deputy_final);
+    println!("miss: {}", miss);
+    // The remaining errors are due to the linearization
+    assert!(miss.fixed_rows::<U3>(0).norm() < 0.1);
+    assert!(miss.fixed_rows::<U3>(3).norm() < 1e-4);
+}
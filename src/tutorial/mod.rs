@@ -436,7 +436,7 @@ In many cases, you'll want to propagate until a given condition is met instead o
 
 Nyx allows you to propagate until a condition is reached once or more times. We'll look at two such examples.
 
-The condition stopper uses a Brent root solver. Documentation on StopCondition is available [here](../propagators/events/struct.StopCondition.html).
+The condition stopper locates the event with a Brent root solver on the continuous extension of the integration step during which it happens. Documentation on StopCondition is available [here](../propagators/events/struct.StopCondition.html).
 
 ```
 extern crate nyx_space as nyx;
@@ -966,7 +966,7 @@ use nyx::dimensions::Vector3;
 use nyx::dynamics::orbital::OrbitalDynamics;
 use nyx::dynamics::propulsion::{Propulsion, Thruster};
 use nyx::dynamics::spacecraft::Spacecraft;
-use nyx::dynamics::thrustctrl::{FiniteBurns, Mnvr};
+use nyx::dynamics::thrustctrl::{FiniteBurns, Mnvr, Steering};
 use nyx::dynamics::Dynamics;
 use nyx::propagators::{PropOpts, Propagator};
 use nyx::time::Epoch;
@@ -999,6 +999,7 @@ let mnvr = Mnvr {
     end: start_time + prop_time,
     thrust_lvl: 1.0, // Full thrust
     vector: Vector3::new(1.0, 0.0, 0.0),
+    steering: Steering::Constant,
 };
 
 // Now, let's define a schedule, which expects a vector of maneuvers.
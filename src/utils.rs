@@ -122,6 +122,32 @@ pub fn projv(a: &Vector3<f64>, b: &Vector3<f64>) -> Vector3<f64> {
     b * a.dot(&b) / b.dot(&b)
 }
 
+/// Returns the c2 Stumpff function of the universal variable formulation, i.e. (1 - cos(sqrt(psi))) / psi
+pub fn stumpff_c2(psi: f64) -> f64 {
+    if psi > 1e-6 {
+        (1.0 - psi.sqrt().cos()) / psi
+    } else if psi < -1e-6 {
+        ((-psi).sqrt().cosh() - 1.0) / -psi
+    } else {
+        // Series expansion near zero
+        1.0 / 2.0 - psi / 24.0 + psi.powi(2) / 720.0
+    }
+}
+
+/// Returns the c3 Stumpff function of the universal variable formulation, i.e. (sqrt(psi) - sin(sqrt(psi))) / sqrt(psi)^3
+pub fn stumpff_c3(psi: f64) -> f64 {
+    if psi > 1e-6 {
+        let sqrt_psi = psi.sqrt();
+        (sqrt_psi - sqrt_psi.sin()) / psi.powi(3).sqrt()
+    } else if psi < -1e-6 {
+        let sqrt_psi = (-psi).sqrt();
+        (sqrt_psi.sinh() - sqrt_psi) / (-psi).powi(3).sqrt()
+    } else {
+        // Series expansion near zero
+        1.0 / 6.0 - psi / 120.0 + psi.powi(2) / 5040.0
+    }
+}
+
 /// Computes the RSS state errors in position and in velocity of two state vectors [P V]
 pub fn rss_errors(prop_err: &Vector6<f64>, cur_state: &Vector6<f64>) -> (f64, f64) {
     let err_radius = (prop_err.fixed_rows::<U3>(0) - cur_state.fixed_rows::<U3>(0)).norm();
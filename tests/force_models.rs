@@ -126,16 +126,12 @@ fn std_atm_drag_earth() {
     println!("{}", final_state.orbit);
 
     /*
-    Test: compared with exponential drag model, and the final states are similar:
-
-    exp_drag_earth
-    [Earth J2000] 2000-01-25T00:00:00 TAI   sma = 24394.167595 km   ecc = 0.000019  inc = 0.000001 deg      raan = 299.937993 deg   aop = 264.180317 deg    ta = 42.152440 deg      300 kg
-    [Earth J2000] 2000-01-25T00:00:00 TAI   position = [-9816.442834, -22331.499423, -0.000477] km  velocity = [3.700562, -1.626744, 0.000000] km/s
-
-    std_atm_drag_earth
-    [Earth J2000] 2000-01-25T00:00:00 TAI   sma = 24396.000574 km   ecc = 0.000020  inc = 0.000001 deg      raan = 299.400845 deg   aop = 264.478503 deg    ta = 41.265314 deg      300 kg
-    [Earth J2000] 2000-01-25T00:00:00 TAI   position = [-10254.183112, -22135.911958, -0.000484] km velocity = [3.667742, -1.699095, 0.000000] km/s
+    Test: compared with exponential drag model, and the final states are similar.
 
+    The final states previously listed here were computed when the drag of the exponential and standard atmosphere
+    models lacked the 1e3 factor from (kg/m^3)*(m^2)*(km/s)^2 to kg*km/s^2, and used the difference between the
+    EME2000 and IAU Earth velocities (i.e. only the rotation of the Earth) instead of the velocity relative to the
+    atmosphere. The drag is now larger by several orders of magnitude, so those states are no longer representative.
     */
 }
 
@@ -183,15 +179,11 @@ fn std_atm_drag_earth_low() {
     println!("{}", final_state.orbit);
 
     /*
-    Test: compared with exponential drag model, and the final states are similar:
-
-    exp_drag_earth
-    [Earth J2000] 2000-01-25T00:00:00 TAI   sma = 24394.167595 km   ecc = 0.000019  inc = 0.000001 deg      raan = 299.937993 deg   aop = 264.180317 deg    ta = 42.152440 deg      300 kg
-    [Earth J2000] 2000-01-25T00:00:00 TAI   position = [-9816.442834, -22331.499423, -0.000477] km  velocity = [3.700562, -1.626744, 0.000000] km/s
-
-    std_atm_drag_earth
-    [Earth J2000] 2000-01-25T00:00:00 TAI   sma = 24396.000574 km   ecc = 0.000020  inc = 0.000001 deg      raan = 299.400845 deg   aop = 264.478503 deg    ta = 41.265314 deg      300 kg
-    [Earth J2000] 2000-01-25T00:00:00 TAI   position = [-10254.183112, -22135.911958, -0.000484] km velocity = [3.667742, -1.699095, 0.000000] km/s
+    Test: compared with exponential drag model, and the final states are similar.
 
+    The final states previously listed here were computed when the drag of the exponential and standard atmosphere
+    models lacked the 1e3 factor from (kg/m^3)*(m^2)*(km/s)^2 to kg*km/s^2, and used the difference between the
+    EME2000 and IAU Earth velocities (i.e. only the rotation of the Earth) instead of the velocity relative to the
+    atmosphere. The drag is now larger by several orders of magnitude, so those states are no longer representative.
     */
 }
@@ -0,0 +1,116 @@
+extern crate nyx_space as nyx;
+
+use nyx::celestia::{Cosm, FormationState, Orbit, SpacecraftState, MAX_FORMATION_SIZE};
+use nyx::dynamics::{Formation, OrbitalDynamics, Spacecraft};
+use nyx::propagators::error_ctrl::RSSState;
+use nyx::propagators::events::{FormationEvent, StopCondition};
+use nyx::propagators::{PropOpts, Propagator};
+use nyx::time::{Epoch, TimeUnit};
+use nyx::utils::rss_errors;
+
+#[test]
+fn formation_matches_individual_props() {
+    let cosm = Cosm::de438();
+    let eme2k = cosm.frame("EME2000");
+
+    let dt = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+    let chief = Orbit::keplerian(7000.0, 0.001, 45.0, 10.0, 20.0, 30.0, dt, eme2k);
+    let deputy = Orbit::keplerian(7010.0, 0.002, 45.1, 10.0, 20.0, 29.9, dt, eme2k);
+
+    let sc_chief = SpacecraftState::new(chief, 100.0, 0.0);
+    let sc_deputy = SpacecraftState::new(deputy, 150.0, 0.0);
+
+    let sc_dyn = Spacecraft::new(OrbitalDynamics::two_body());
+    let prop_time = 2 * TimeUnit::Hour;
+
+    // Propagate both spacecraft in the same integration
+    let formation = FormationState::new(&[sc_chief, sc_deputy]).unwrap();
+    let setup = Propagator::rk89(
+        Formation::identical(sc_dyn.clone(), 2),
+        PropOpts::with_fixed_step_s(10.0),
+    );
+    let final_formation = setup.with(formation).for_duration(prop_time).unwrap();
+
+    // And each of them separately
+    let setup = Propagator::rk89(sc_dyn, PropOpts::with_fixed_step_s(10.0));
+    for (i, sc) in [sc_chief, sc_deputy].iter().enumerate() {
+        let final_sc = setup.with(*sc).for_duration(prop_time).unwrap();
+        let (err_r, err_v) = rss_errors(
+            &final_formation.member(i).orbit.to_cartesian_vec(),
+            &final_sc.orbit.to_cartesian_vec(),
+        );
+        println!(
+            "member #{}: RSS errors:\tpos = {:.5e} km\tvel = {:.5e} km/s",
+            i, err_r, err_v
+        );
+        assert!(err_r < 1e-9, "member #{} position differs", i);
+        assert!(err_v < 1e-12, "member #{} velocity differs", i);
+        assert_eq!(final_formation.member(i).orbit.dt, final_sc.orbit.dt);
+    }
+
+    // Check that the relative state is consistent with the separation
+    let ric = final_formation.relative_ric(0, 1);
+    let ric_dist = (ric[0].powi(2) + ric[1].powi(2) + ric[2].powi(2)).sqrt();
+    assert!((ric_dist - final_formation.separation(0, 1)).abs() < 1e-9);
+}
+
+#[test]
+fn formation_separation_event() {
+    let cosm = Cosm::de438();
+    let eme2k = cosm.frame("EME2000");
+
+    let dt = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+    // The deputy is on a slightly higher orbit, so it will drift behind the chief
+    let chief = Orbit::keplerian(7000.0, 0.001, 45.0, 10.0, 20.0, 30.0, dt, eme2k);
+    let deputy = Orbit::keplerian(7001.0, 0.001, 45.0, 10.0, 20.0, 30.0, dt, eme2k);
+
+    let formation = FormationState::new(&[
+        SpacecraftState::new(chief, 100.0, 0.0),
+        SpacecraftState::new(deputy, 100.0, 0.0),
+    ])
+    .unwrap();
+    println!("{}", formation);
+
+    let init_sep = formation.separation(0, 1);
+    assert!(init_sep < 10.0);
+
+    let setup = Propagator::rk89(
+        Formation::identical(Spacecraft::new(OrbitalDynamics::two_body()), 2),
+        PropOpts::with_adaptive_step_s(1.0, 60.0, 1e-12, RSSState {}),
+    );
+
+    let condition = StopCondition::new(
+        FormationEvent::separation(0, 1, 10.0),
+        1 * TimeUnit::Day,
+        1e-3,
+    );
+
+    let found = setup.with(formation).until_event(condition).unwrap();
+    println!("{}\n{:?}", found, found.relative_ric(0, 1));
+    assert!(
+        (found.separation(0, 1) - 10.0).abs() < 1e-2,
+        "separation event not converged"
+    );
+}
+
+#[test]
+fn formation_size_limits() {
+    let cosm = Cosm::de438();
+    let eme2k = cosm.frame("EME2000");
+
+    let dt = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+    let sc = SpacecraftState::new(
+        Orbit::keplerian(7000.0, 0.001, 45.0, 10.0, 20.0, 30.0, dt, eme2k),
+        100.0,
+        0.0,
+    );
+
+    assert!(FormationState::new(&[]).is_err());
+    assert!(FormationState::new(&[sc; MAX_FORMATION_SIZE]).is_ok());
+    assert!(FormationState::new(&[sc; MAX_FORMATION_SIZE + 1]).is_err());
+
+    // All members must be defined at the same epoch
+    let mut late = sc;
+    late.orbit.dt = dt + 1 * TimeUnit::Second;
+    assert!(FormationState::new(&[sc, late]).is_err());
+}
@@ -1000,3 +1000,163 @@ fn hf_prop() {
 
     println!("{}\n{:o}", rslt, rslt);
 }
+
+#[test]
+fn potential_gradient() {
+    use nyx::dynamics::{AccelModel, Harmonics};
+    use nyx::io::gravity::HarmonicsMem;
+    use std::sync::Arc;
+
+    let cosm = Cosm::de438_gmat();
+    let eme2k = cosm.frame("EME2000");
+    let iau_earth = cosm.frame("IAU Earth");
+
+    let dt = Epoch::from_mjd_tai(J2000_OFFSET);
+    let state = Orbit::cartesian(
+        -2436.45, -2436.45, 6891.037, 5.088_611, -5.088_611, 0.0, dt, eme2k,
+    );
+
+    let stor = HarmonicsMem::from_cof("data/JGM3.cof.gz", 12, 12, true).unwrap();
+    let models: [(&str, Arc<dyn AccelModel>); 2] = [
+        (
+            "harmonics",
+            Harmonics::from_stor(iau_earth, stor, cosm.clone()),
+        ),
+        (
+            "point masses",
+            PointMasses::new(eme2k, &[Bodies::Luna, Bodies::Sun], cosm),
+        ),
+    ];
+
+    // The acceleration of the conservative models is the gradient of their potential
+    let step_km = 1e-2;
+    for (name, model) in &models {
+        let accel = model.eom(&state).unwrap();
+        let mut gradient = accel;
+        for i in 0..3 {
+            let mut plus = state;
+            let mut minus = state;
+            match i {
+                0 => {
+                    plus.x += step_km;
+                    minus.x -= step_km;
+                }
+                1 => {
+                    plus.y += step_km;
+                    minus.y -= step_km;
+                }
+                _ => {
+                    plus.z += step_km;
+                    minus.z -= step_km;
+                }
+            }
+            gradient[i] = (model.potential(&plus).unwrap() - model.potential(&minus).unwrap())
+                / (2.0 * step_km);
+        }
+        let err = (gradient - accel).norm() / accel.norm();
+        println!(
+            "{}: {} vs {} (rel. err. {:.3e})",
+            name, accel, gradient, err
+        );
+        assert!(
+            err < 1e-6,
+            "{} potential inconsistent with its acceleration",
+            name
+        );
+    }
+
+    // Without acceleration models, the energy is the two body energy
+    assert_eq!(
+        OrbitalDynamics::two_body().energy(&state).unwrap(),
+        state.energy()
+    );
+}
+
+#[test]
+fn second_order_partials() {
+    use nyx::dimensions::{Matrix3, Vector3};
+    use nyx::dynamics::{AccelModel, Harmonics};
+    use nyx::io::gravity::HarmonicsMem;
+    use nyx::State;
+
+    let cosm = Cosm::de438_gmat();
+    let eme2k = cosm.frame("EME2000");
+    let iau_earth = cosm.frame("IAU Earth");
+
+    let dt = Epoch::from_mjd_tai(J2000_OFFSET);
+    let state = Orbit::cartesian(
+        -2436.45, -2436.45, 6891.037, 5.088_611, -5.088_611, 0.0, dt, eme2k,
+    );
+
+    let stor = HarmonicsMem::from_cof("data/JGM3.cof.gz", 12, 12, true).unwrap();
+    let harmonics = Harmonics::from_stor(iau_earth, stor, cosm.clone());
+    let point_masses = PointMasses::new(eme2k, &[Bodies::Luna, Bodies::Sun], cosm);
+    let mut dynamics = OrbitalDynamics::new_raw(vec![]);
+    dynamics.add_model(harmonics);
+    dynamics.add_model(point_masses.clone());
+
+    // The second partials match the second differences of the acceleration (the point masses have exact second
+    // partials, and the ones of the harmonics are the differences of their partials)
+    let full_accel = |orbit: &Orbit| -> Vector3<f64> {
+        dynamics
+            .eom(0.0, &orbit.as_vector().unwrap(), orbit)
+            .unwrap()
+            .fixed_rows::<U3>(3)
+            .into_owned()
+    };
+    let third_body_accel = |orbit: &Orbit| -> Vector3<f64> { point_masses.eom(orbit).unwrap() };
+    // The third bodies are far, so their accelerations are differenced with a larger step
+    let cases: [(
+        &str,
+        &dyn Fn(&Orbit) -> Vector3<f64>,
+        [Matrix3<f64>; 3],
+        f64,
+    ); 2] = [
+        (
+            "full dynamics",
+            &full_accel,
+            dynamics.hessian(&state).unwrap(),
+            1.0,
+        ),
+        (
+            "point masses",
+            &third_body_accel,
+            point_masses.hessian(&state).unwrap(),
+            100.0,
+        ),
+    ];
+    let perturbed = |j: usize, dj: f64, k: usize, dk: f64| -> Orbit {
+        let mut radius = state.radius();
+        radius[j] += dj;
+        radius[k] += dk;
+        let mut orbit = state;
+        orbit.x = radius[0];
+        orbit.y = radius[1];
+        orbit.z = radius[2];
+        orbit
+    };
+    for (name, accel, hessian, step_km) in &cases {
+        let step_km = *step_km;
+        let mut err = 0.0;
+        let mut norm = 0.0;
+        for j in 0..3 {
+            for k in 0..3 {
+                let diff = (accel(&perturbed(j, step_km, k, step_km))
+                    - accel(&perturbed(j, step_km, k, -step_km))
+                    - accel(&perturbed(j, -step_km, k, step_km))
+                    + accel(&perturbed(j, -step_km, k, -step_km)))
+                    / (4.0 * step_km * step_km);
+                for i in 0..3 {
+                    err += (hessian[i][(j, k)] - diff[i]).powi(2);
+                    norm += diff[i].powi(2);
+                }
+            }
+        }
+        let rel_err = (err / norm).sqrt();
+        println!("{}: rel. err. {:.3e}", name, rel_err);
+        assert!(rel_err < 1e-5, "{} second partials are incorrect", name);
+        for hess_i in hessian {
+            assert!((hess_i - hess_i.transpose()).norm() <= 1e-12 * hess_i.norm());
+        }
+    }
+}
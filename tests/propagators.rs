@@ -1,6 +1,10 @@
 extern crate nyx_space as nyx;
+use hyperdual::Hyperdual;
 use nyx::celestia::{assert_orbit_eq_or_abs, assert_orbit_eq_or_rel, Cosm, Orbit};
+use nyx::dimensions::{Matrix3, Vector3, U7};
 use nyx::dynamics::orbital::OrbitalDynamics;
+use nyx::dynamics::AccelModel;
+use nyx::errors::NyxError;
 use nyx::propagators::error_ctrl::RSSStatePV;
 use nyx::propagators::*;
 use nyx::time::{Epoch, TimeUnit, J2000_OFFSET};
@@ -464,3 +468,542 @@ fn gmat_val_leo_day_fixed() {
         println!();
     }
 }
+
+#[allow(clippy::identity_op)]
+#[test]
+fn semianalytic_leo_j2() {
+    use nyx::celestia::SpacecraftState;
+    use nyx::dynamics::sph_harmonics::Harmonics;
+    use nyx::dynamics::Spacecraft;
+    use nyx::io::gravity::HarmonicsMem;
+    use nyx::propagators::semianalytic::{Equinoctial, SemiAnalytic};
+
+    let cosm = Cosm::de438_gmat();
+    let eme2k = cosm.frame("EME2000");
+    let iau_earth = cosm.frame("IAU Earth");
+
+    let dt = Epoch::from_gregorian_tai_at_midnight(2021, 1, 1);
+    let init = Orbit::keplerian(7000.0, 0.001, 51.6, 28.6, 17.2, 11.5, dt, eme2k);
+
+    // The equinoctial elements are a one to one mapping of the cartesian state
+    let eq = Equinoctial::from_orbit(&init);
+    assert!((eq.sma() - init.sma()).abs() < 1e-9);
+    assert!((eq.ecc() - init.ecc()).abs() < 1e-12);
+    assert!((eq.inc() - init.inc()).abs() < 1e-10);
+    let (err_r, err_v) = rss_state_errors(&eq.to_orbit(), &init);
+    assert!(err_r < 1e-8 && err_v < 1e-11);
+
+    let harmonics = Harmonics::from_stor(iau_earth, HarmonicsMem::j2_jgm3(), cosm);
+    let orbital_dyn = OrbitalDynamics::new(vec![harmonics]);
+
+    let prop_time = 2 * TimeUnit::Day;
+    let setup = Propagator::rk89(orbital_dyn.clone(), PropOpts::with_tolerance(1e-12));
+    let truth = setup.with(init).for_duration(prop_time).unwrap();
+
+    let sa = SemiAnalytic::new(Spacecraft::new(orbital_dyn), 1 * TimeUnit::Day);
+    let sc = SpacecraftState::new(init, 100.0, 0.0);
+
+    // The osculating state is rebuilt from the mean elements
+    let mean = sa.to_mean(&sc).unwrap();
+    println!("{}", mean);
+    // J2 makes the mean semi-major axis of a LEO orbit several kilometers away from the osculating one
+    assert!((mean.sma() - init.sma()).abs() > 1.0);
+    let (err_r, err_v) = rss_state_errors(&sa.to_osculating(&mean, &sc).unwrap().orbit, &init);
+    assert!(err_r < 1e-6 && err_v < 1e-9);
+
+    let states = sa.history(&sc, prop_time).unwrap();
+    assert_eq!(states.len(), 3);
+    let rslt = states[2].orbit;
+    assert_eq!(rslt.dt, truth.dt);
+    let (err_r, err_v) = rss_state_errors(&rslt, &truth);
+    println!(
+        "semi-analytic vs Cowell after {}: {:.3e} km\t{:.3e} km/s",
+        prop_time, err_r, err_v
+    );
+    assert!(err_r < 1.0, "semi-analytic position error too large");
+    assert!(err_v < 1e-3, "semi-analytic velocity error too large");
+}
+
+#[allow(clippy::identity_op)]
+#[test]
+fn semianalytic_geo_third_body_resonance() {
+    use nyx::celestia::{Bodies, SpacecraftState};
+    use nyx::dynamics::sph_harmonics::Harmonics;
+    use nyx::dynamics::{PointMasses, Spacecraft};
+    use nyx::io::gravity::HarmonicsMem;
+    use nyx::propagators::semianalytic::SemiAnalytic;
+
+    let cosm = Cosm::de438_gmat();
+    let eme2k = cosm.frame("EME2000");
+    let iau_earth = cosm.frame("IAU Earth");
+
+    let dt = Epoch::from_gregorian_tai_at_midnight(2021, 1, 1);
+    let init = Orbit::keplerian(42_164.0, 1e-4, 0.05, 17.2, 10.0, 45.0, dt, eme2k);
+
+    let third_bodies = PointMasses::new(eme2k, &[Bodies::Luna, Bodies::Sun], cosm.clone());
+    // The tesseral harmonics make the longitude of a geostationary orbit drift, which is resonant
+    let stor = HarmonicsMem::from_cof("data/JGM3.cof.gz", 4, 4, true).unwrap();
+    let harmonics = Harmonics::from_stor(iau_earth, stor, cosm);
+
+    let prop_time = 30 * TimeUnit::Day;
+    let setup = Propagator::rk89(
+        OrbitalDynamics::new(vec![third_bodies.clone(), harmonics.clone()]),
+        PropOpts::with_tolerance(1e-12),
+    );
+    let truth = setup.with(init).for_duration(prop_time).unwrap();
+
+    let mut sa = SemiAnalytic::new(
+        Spacecraft::new(OrbitalDynamics::new(vec![third_bodies])),
+        1 * TimeUnit::Day,
+    );
+    sa.resonant.push(harmonics);
+    let rslt = sa
+        .propagate(&SpacecraftState::new(init, 1000.0, 0.0), prop_time)
+        .unwrap()
+        .orbit;
+    let (err_r, err_v) = rss_state_errors(&rslt, &truth);
+    println!(
+        "semi-analytic vs Cowell after {}: {:.3e} km\t{:.3e} km/s\tinc {:.6} vs {:.6} deg",
+        prop_time,
+        err_r,
+        err_v,
+        rslt.inc(),
+        truth.inc()
+    );
+    // The lunisolar perturbations increase the inclination
+    assert!(truth.inc() > init.inc() + 0.03);
+    assert!((rslt.inc() - truth.inc()).abs() < 1e-3);
+    assert!(err_r < 20.0, "semi-analytic position error too large");
+}
+
+#[allow(clippy::identity_op)]
+#[test]
+fn multistep_two_body() {
+    let cosm = Cosm::de438();
+    let eme2k = cosm.frame("EME2000");
+
+    let dt = Epoch::from_mjd_tai(J2000_OFFSET);
+    let init = Orbit::cartesian(
+        -2436.45, -2436.45, 6891.037, 5.088_611, -5.088_611, 0.0, dt, eme2k,
+    );
+    let prop_time = 1 * TimeUnit::Day;
+    let expected = init.at_epoch(dt + prop_time).unwrap();
+
+    let dynamics = OrbitalDynamics::two_body();
+    for method in &[Multistep::AdamsBashforthMoulton, Multistep::GaussJackson] {
+        let fixed = Propagator::multistep(
+            dynamics.clone(),
+            *method,
+            PropOpts::with_fixed_step(30 * TimeUnit::Second),
+        );
+        let rslt = fixed.with(init).for_duration(prop_time).unwrap();
+        let (err_r, err_v) = rss_state_errors(&rslt, &expected);
+        println!(
+            "{:?} fixed step: {:.3e} km\t{:.3e} km/s",
+            method, err_r, err_v
+        );
+        assert!(err_r < 1e-4, "{:?} fixed step position error", method);
+
+        // Adaptive step, propagated in chunks which do not end on the steps of the integrator
+        let adaptive = Propagator::multistep(dynamics.clone(), *method, PropOpts::default());
+        let mut prop = adaptive.with(init);
+        for _ in 0..24 {
+            prop.for_duration(1 * TimeUnit::Hour).unwrap();
+        }
+        let (err_r, err_v) = rss_state_errors(&prop.state, &expected);
+        println!(
+            "{:?} adaptive step: {:.3e} km\t{:.3e} km/s (last step of {})",
+            method,
+            err_r,
+            err_v,
+            prop.latest_details().step
+        );
+        assert!((prop.state.dt - expected.dt).in_seconds().abs() < 1e-6);
+        assert!(err_r < 1e-3, "{:?} adaptive step position error", method);
+    }
+}
+
+#[test]
+fn multistep_event_and_stm() {
+    use nyx::propagators::events::{EventKind, OrbitalEvent, StopCondition};
+
+    let cosm = Cosm::de438();
+    let eme2k = cosm.frame("EME2000");
+
+    let dt = Epoch::from_mjd_tai(J2000_OFFSET);
+    let init = Orbit::cartesian(
+        -2436.45, -2436.45, 6891.037, 5.088_611, -5.088_611, 0.0, dt, eme2k,
+    );
+    let period = init.period();
+    let dynamics = OrbitalDynamics::two_body();
+
+    // The third periapse matches the one found with an RK89 (within the one second convergence of the search)
+    let condition = || {
+        StopCondition::after_hits(
+            OrbitalEvent::new(EventKind::Periapse),
+            3,
+            4.0 * period,
+            1e-6,
+        )
+    };
+    let rk89 = Propagator::default(dynamics.clone());
+    let expected = rk89.with(init).until_event(condition()).unwrap();
+    let gj = Propagator::gauss_jackson(dynamics.clone(), PropOpts::default());
+    let rslt = gj.with(init).until_event(condition()).unwrap();
+    println!("periapse: {} (RK89: {})", rslt.dt, expected.dt);
+    assert!((rslt.dt - expected.dt).in_seconds().abs() < 1.0);
+    assert!(rslt.ta().abs() < 1e-1 || (360.0 - rslt.ta().abs() < 1e-1));
+
+    // The STM of the last step matches the one of an RK89 with the same steps
+    let init = Orbit::cartesian_stm(
+        -2436.45, -2436.45, 6891.037, 5.088_611, -5.088_611, 0.0, dt, eme2k,
+    );
+    let opts = PropOpts::with_fixed_step_s(10.0);
+    let prop_time = period;
+    let rk89 = Propagator::rk89(dynamics.clone(), opts);
+    let expected = rk89.with(init).for_duration(prop_time).unwrap();
+    for method in &[Multistep::AdamsBashforthMoulton, Multistep::GaussJackson] {
+        let setup = Propagator::multistep(dynamics.clone(), *method, opts);
+        let rslt = setup.with(init).for_duration(prop_time).unwrap();
+        let stm_err = (rslt.stm.unwrap() - expected.stm.unwrap()).norm();
+        let (err_r, _) = rss_state_errors(&rslt, &expected);
+        println!("{:?}: STM error {:.3e}\t{:.3e} km", method, stm_err, err_r);
+        assert!(stm_err < 1e-6, "{:?} STM differs from RK89", method);
+        assert!(err_r < 1e-5, "{:?} state differs from RK89", method);
+    }
+}
+
+#[allow(clippy::identity_op)]
+#[test]
+fn symplectic_two_body() {
+    use nyx::propagators::symplectic::{Composition, ConservationDrift, Symplectic};
+
+    let cosm = Cosm::de438();
+    let eme2k = cosm.frame("EME2000");
+
+    let dt = Epoch::from_mjd_tai(J2000_OFFSET);
+    let init = Orbit::cartesian(
+        -2436.45, -2436.45, 6891.037, 5.088_611, -5.088_611, 0.0, dt, eme2k,
+    );
+    let period = init.period();
+    let dynamics = OrbitalDynamics::two_body();
+
+    // The Yoshida compositions have the expected order
+    let step = |div: f64| (period.in_seconds() / div) * TimeUnit::Second;
+    let expected = init.at_epoch(dt + period).unwrap();
+    let (err_coarse, _) = rss_state_errors(
+        &Symplectic::yoshida4(dynamics.clone(), step(100.0))
+            .propagate(&init, period)
+            .unwrap(),
+        &expected,
+    );
+    let (err_fine, _) = rss_state_errors(
+        &Symplectic::yoshida4(dynamics.clone(), step(200.0))
+            .propagate(&init, period)
+            .unwrap(),
+        &expected,
+    );
+    println!(
+        "Yoshida4 over one orbit: {:.3e} km (halved step: {:.3e} km)",
+        err_coarse, err_fine
+    );
+    assert!(err_coarse / err_fine > 12.0 && err_coarse / err_fine < 20.0);
+
+    // Over fifty orbits, the energy error remains bounded
+    let prop_time = 50 * period;
+    let expected = init.at_epoch(dt + prop_time).unwrap();
+    for (method, max_err, max_drift) in &[
+        (Composition::Yoshida4, 5.0, 1e-8),
+        (Composition::Yoshida6, 1e-3, 1e-11),
+        (Composition::Yoshida8, 1e-4, 1e-11),
+    ] {
+        let mut setup = Symplectic::yoshida4(dynamics.clone(), step(200.0));
+        setup.composition = *method;
+        let states = setup.history(&init, prop_time).unwrap();
+        let rslt = states[states.len() - 1];
+        assert_eq!(rslt.dt, expected.dt);
+        let (err_r, err_v) = rss_state_errors(&rslt, &expected);
+        let drift = ConservationDrift::from_states(&dynamics, &states, None).unwrap();
+        println!(
+            "{:?}: {:.3e} km\t{:.3e} km/s\t{}",
+            method, err_r, err_v, drift
+        );
+        assert!(err_r < *max_err, "{:?} position error", method);
+        assert!(
+            drift.max_energy_drift < *max_drift,
+            "{:?} energy drift",
+            method
+        );
+        assert!(drift.jacobi.is_none());
+    }
+}
+
+#[allow(clippy::identity_op)]
+#[test]
+fn symplectic_wisdom_holman_j2() {
+    use nyx::dynamics::sph_harmonics::Harmonics;
+    use nyx::io::gravity::HarmonicsMem;
+    use nyx::propagators::symplectic::{Composition, ConservationDrift, Splitting, Symplectic};
+
+    let cosm = Cosm::de438_gmat();
+    let eme2k = cosm.frame("EME2000");
+    let iau_earth = cosm.frame("IAU Earth");
+
+    let dt = Epoch::from_gregorian_tai_at_midnight(2021, 1, 1);
+    let init = Orbit::keplerian(7000.0, 0.001, 51.6, 28.6, 17.2, 11.5, dt, eme2k);
+
+    // J2 is axially symmetric, so the Jacobi integral in the frame rotating with the Earth is also conserved
+    let dcm = cosm
+        .try_frame_chg_dcm_from_to(&iau_earth, &eme2k, dt)
+        .unwrap();
+    let earth_rotation = Some(dcm * Vector3::new(0.0, 0.0, 7.292_115e-5));
+
+    let harmonics = Harmonics::from_stor(iau_earth, HarmonicsMem::j2_jgm3(), cosm);
+    let dynamics = OrbitalDynamics::new(vec![harmonics]);
+
+    let prop_time = 1 * TimeUnit::Day;
+    let setup = Propagator::rk89(dynamics.clone(), PropOpts::with_tolerance(1e-12));
+    let truth = setup.with(init).for_duration(prop_time).unwrap();
+
+    // The Kepler drift allows for large steps, since the error is driven by the perturbations only
+    for (composition, step_s, max_err, max_drift) in &[
+        (Composition::Leapfrog, 30.0, 1.0, 1e-6),
+        (Composition::Yoshida4, 60.0, 1e-2, 1e-9),
+    ] {
+        let wh = Symplectic::new(
+            dynamics.clone(),
+            Splitting::WisdomHolman,
+            *composition,
+            *step_s * TimeUnit::Second,
+        );
+        let states = wh.history(&init, prop_time).unwrap();
+        let rslt = states[states.len() - 1];
+        let (err_r, err_v) = rss_state_errors(&rslt, &truth);
+        let drift = ConservationDrift::from_states(&dynamics, &states, earth_rotation).unwrap();
+        println!(
+            "Wisdom-Holman {:?} ({} s) vs RK89: {:.3e} km\t{:.3e} km/s\t{}",
+            composition, step_s, err_r, err_v, drift
+        );
+        assert_eq!(rslt.dt, truth.dt);
+        assert!(err_r < *max_err, "{:?} position error", composition);
+        assert!(drift.max_energy_drift < *max_drift);
+        assert!(drift.max_jacobi_drift.unwrap() < *max_drift);
+    }
+
+    // The classic Wisdom-Holman mapping is much more accurate than a drift-kick leapfrog of the same step
+    let leapfrog = Symplectic::new(
+        dynamics.clone(),
+        Splitting::DriftKick,
+        Composition::Leapfrog,
+        30 * TimeUnit::Second,
+    );
+    let (err_leapfrog, _) =
+        rss_state_errors(&leapfrog.propagate(&init, prop_time).unwrap(), &truth);
+    let wh = Symplectic::wisdom_holman(dynamics, 30 * TimeUnit::Second);
+    let (err_wh, _) = rss_state_errors(&wh.propagate(&init, prop_time).unwrap(), &truth);
+    println!(
+        "leapfrog: {:.3e} km\tWisdom-Holman: {:.3e} km",
+        err_leapfrog, err_wh
+    );
+    assert!(err_wh * 100.0 < err_leapfrog);
+}
+
+/// Drag in an exponential atmosphere whose scale height is very small, which makes the dynamics stiff.
+struct SteepDrag;
+
+impl AccelModel for SteepDrag {
+    fn eom(&self, osc: &Orbit) -> Result<Vector3<f64>, NyxError> {
+        // Ballistic coefficient times the density (1/km), with a 6 km scale height from 80 km altitude
+        let k = 1000.0 * (-(osc.rmag() - 6378.1363 - 80.0) / 6.0).exp();
+        Ok(-k * osc.vmag() * osc.velocity())
+    }
+
+    fn dual_eom(
+        &self,
+        _radius: &Vector3<Hyperdual<f64, U7>>,
+        _osc: &Orbit,
+    ) -> Result<(Vector3<f64>, Matrix3<f64>), NyxError> {
+        Err(NyxError::PartialsUndefined)
+    }
+}
+
+#[allow(clippy::identity_op)]
+#[test]
+fn radau_stiff_drag() {
+    use std::sync::mpsc::channel;
+    use std::sync::Arc;
+
+    let cosm = Cosm::de438();
+    let eme2k = cosm.frame("EME2000");
+
+    // Falls from rest at 80 km of altitude, and quickly reaches its terminal velocity
+    let dt = Epoch::from_mjd_tai(J2000_OFFSET);
+    let init = Orbit::cartesian(6378.1363 + 80.0, 0.0, 0.0, 0.0, 0.0, 0.0, dt, eme2k);
+    let prop_time = 10 * TimeUnit::Minute;
+    let dynamics = OrbitalDynamics::with_model(Arc::new(SteepDrag));
+
+    // The partials of the drag are not defined, so the Jacobian is computed by finite differences
+    let (tx, rx) = channel();
+    let radau = Propagator::radau(dynamics.clone(), PropOpts::with_tolerance(1e-9));
+    let rslt = radau
+        .with(init)
+        .with_tx(tx)
+        .for_duration(prop_time)
+        .unwrap();
+    let radau_steps = rx.try_iter().count();
+
+    let (tx, rx) = channel();
+    let rk89 = Propagator::rk89(dynamics, PropOpts::with_tolerance(1e-11));
+    let expected = rk89.with(init).with_tx(tx).for_duration(prop_time).unwrap();
+    let rk89_steps = rx.try_iter().count();
+
+    let (err_r, err_v) = rss_state_errors(&rslt, &expected);
+    println!(
+        "Radau: {} steps\tRK89: {} steps\t{:.3e} km\t{:.3e} km/s",
+        radau_steps, rk89_steps, err_r, err_v
+    );
+    assert_eq!(rslt.dt, expected.dt);
+    assert!(err_r < 1e-6, "Radau differs from RK89");
+    assert!(
+        radau_steps * 4 < rk89_steps,
+        "Radau should take much larger steps on stiff dynamics"
+    );
+}
+
+#[allow(clippy::identity_op)]
+#[test]
+fn radau_two_body() {
+    let cosm = Cosm::de438();
+    let eme2k = cosm.frame("EME2000");
+
+    let dt = Epoch::from_mjd_tai(J2000_OFFSET);
+    let init = Orbit::cartesian(
+        -2436.45, -2436.45, 6891.037, 5.088_611, -5.088_611, 0.0, dt, eme2k,
+    );
+    let period = init.period();
+    let expected = init.at_epoch(dt + period).unwrap();
+    let dynamics = OrbitalDynamics::two_body();
+
+    let fixed = Propagator::radau(
+        dynamics.clone(),
+        PropOpts::with_fixed_step(30 * TimeUnit::Second),
+    );
+    let rslt = fixed.with(init).for_duration(period).unwrap();
+    let (err_r, err_v) = rss_state_errors(&rslt, &expected);
+    println!("Radau fixed step: {:.3e} km\t{:.3e} km/s", err_r, err_v);
+    assert!(err_r < 1e-5, "fixed step position error");
+
+    let adaptive = Propagator::radau(dynamics.clone(), PropOpts::with_tolerance(1e-9));
+    let mut prop = adaptive.with(init);
+    let rslt = prop.for_duration(period).unwrap();
+    let (err_r, err_v) = rss_state_errors(&rslt, &expected);
+    println!(
+        "Radau adaptive step: {:.3e} km\t{:.3e} km/s (last step of {})",
+        err_r,
+        err_v,
+        prop.latest_details().step
+    );
+    assert!((rslt.dt - expected.dt).in_seconds().abs() < 1e-6);
+    assert!(err_r < 1e-5, "adaptive step position error");
+
+    // The STM of the last step matches the one of an RK89 with the same steps
+    let init = Orbit::cartesian_stm(
+        -2436.45, -2436.45, 6891.037, 5.088_611, -5.088_611, 0.0, dt, eme2k,
+    );
+    let opts = PropOpts::with_fixed_step_s(10.0);
+    let expected = Propagator::rk89(dynamics.clone(), opts)
+        .with(init)
+        .for_duration(period)
+        .unwrap();
+    let rslt = Propagator::radau(dynamics, opts)
+        .with(init)
+        .for_duration(period)
+        .unwrap();
+    let stm_err = (rslt.stm.unwrap() - expected.stm.unwrap()).norm();
+    let (err_r, _) = rss_state_errors(&rslt, &expected);
+    println!("Radau: STM error {:.3e}\t{:.3e} km", stm_err, err_r);
+    assert!(stm_err < 1e-6, "STM differs from RK89");
+    assert!(err_r < 1e-5, "state differs from RK89");
+}
+
+#[test]
+fn second_order_stt_two_body() {
+    use nyx::dimensions::{Matrix6, Vector6, U3};
+    use nyx::propagators::stt::SttPropagator;
+
+    let cosm = Cosm::de438();
+    let eme2k = cosm.frame("EME2000");
+
+    let dt = Epoch::from_mjd_tai(J2000_OFFSET);
+    let init = Orbit::cartesian(
+        -2436.45, -2436.45, 6891.037, 5.088_611, -5.088_611, 0.0, dt, eme2k,
+    );
+    let period = init.period();
+    let step = (period.in_seconds() / 600.0) * TimeUnit::Second;
+    let rslt = SttPropagator::new(OrbitalDynamics::two_body(), step)
+        .propagate(&init, period)
+        .unwrap();
+    let expected = init.at_epoch(dt + period).unwrap();
+    let (err_r, err_v) = rss_state_errors(&rslt.orbit, &expected);
+    println!("state error: {:.3e} km\t{:.3e} km/s", err_r, err_v);
+    assert_eq!(rslt.orbit.dt, expected.dt);
+    assert!(err_r < 1e-6);
+
+    let perturbed = |dev: &Vector6<f64>| -> Vector6<f64> {
+        let orbit = Orbit::cartesian(
+            init.x + dev[0],
+            init.y + dev[1],
+            init.z + dev[2],
+            init.vx + dev[3],
+            init.vy + dev[4],
+            init.vz + dev[5],
+            dt,
+            eme2k,
+        )
+        .at_epoch(dt + period)
+        .unwrap();
+        Vector6::new(
+            orbit.x - expected.x,
+            orbit.y - expected.y,
+            orbit.z - expected.z,
+            orbit.vx - expected.vx,
+            orbit.vy - expected.vy,
+            orbit.vz - expected.vz,
+        )
+    };
+
+    // The STM matches the central differences of the two body propagation
+    let steps = [1e-1, 1e-1, 1e-1, 1e-4, 1e-4, 1e-4];
+    let mut stm_diff = Matrix6::zeros();
+    for (j, step_j) in steps.iter().enumerate() {
+        let mut dev = Vector6::zeros();
+        dev[j] = *step_j;
+        stm_diff.set_column(j, &((perturbed(&dev) - perturbed(&-dev)) / (2.0 * step_j)));
+    }
+    let stm_err = (rslt.stm() - stm_diff).norm() / stm_diff.norm();
+    println!("STM rel. err. {:.3e}", stm_err);
+    assert!(stm_err < 1e-5);
+
+    // The second order tensor is symmetric, and captures most of the nonlinearity of a large deviation
+    for stt_i in &rslt.stt {
+        assert!((stt_i - stt_i.transpose()).norm() <= 1e-9 * stt_i.norm());
+    }
+    let dev = Vector6::new(1.0, -1.0, 0.5, 1e-3, 0.0, -1e-3);
+    let truth = perturbed(&dev);
+    let lin_err = (rslt.stm() * dev - truth).fixed_rows::<U3>(0).norm();
+    let quad_err = (rslt.deviation(&dev) - truth).fixed_rows::<U3>(0).norm();
+    println!(
+        "deviation of {:.3} km: linear error {:.3e} km\tsecond order error {:.3e} km",
+        truth.fixed_rows::<U3>(0).norm(),
+        lin_err,
+        quad_err
+    );
+    assert!(quad_err * 100.0 < lin_err);
+
+    // The mean shift is the expectation of the second order term
+    let covar = Matrix6::from_diagonal(&Vector6::new(1.0, 1.0, 1.0, 1e-6, 1e-6, 1e-6));
+    let shift = rslt.mean_shift(&covar);
+    for i in 0..6 {
+        assert!((shift[i] - 0.5 * (rslt.stt[i] * covar).trace()).abs() <= 1e-9 * shift.norm());
+    }
+}
@@ -0,0 +1,189 @@
+extern crate nyx_space as nyx;
+
+use self::nyx::celestia::{Cosm, GuidanceMode, Orbit, SpacecraftState};
+use self::nyx::dynamics::thrustctrl::{Achieve, QLaw, QLawOptions, Thruster};
+use self::nyx::dynamics::{OrbitalDynamics, Spacecraft};
+use self::nyx::propagators::{PropOpts, Propagator, RK4Fixed};
+use self::nyx::time::{Epoch, TimeUnit};
+
+/// NOTE: These are the same cases as the `qlaw_as_ruggiero` tests, the Q-law should use less fuel than Ruggiero.
+
+#[test]
+fn qlaw_case_a() {
+    // Source: AAS-2004-5089
+    let mut cosm = Cosm::de438_raw();
+    cosm.frame_mut_gm("EME2000", 398_600.433);
+    let eme2k = cosm.frame("EME2000");
+
+    let start_time = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+
+    let orbit = Orbit::keplerian(7000.0, 0.01, 0.05, 0.0, 0.0, 1.0, start_time, eme2k);
+
+    let prop_time = 39.91 * TimeUnit::Day;
+
+    // Define the thruster
+    let lowt = Thruster {
+        thrust: 1.0,
+        isp: 3100.0,
+    };
+
+    // Define the objectives
+    let objectives = vec![
+        Achieve::Sma {
+            target: 42000.0,
+            tol: 1.0,
+        },
+        Achieve::Ecc {
+            target: 0.01,
+            tol: 5e-5,
+        },
+    ];
+
+    let dry_mass = 1.0;
+    let fuel_mass = 299.0;
+
+    let sc_state =
+        SpacecraftState::with_thruster(orbit, dry_mass, fuel_mass, lowt, GuidanceMode::Thrust);
+
+    let sc = Spacecraft::with_ctrl(OrbitalDynamics::two_body(), QLaw::new(objectives).unwrap());
+    println!("[qlaw_case_a] {:o}", orbit);
+
+    let final_state = Propagator::new::<RK4Fixed>(
+        sc.clone(),
+        PropOpts::with_fixed_step(10.0 * TimeUnit::Second),
+    )
+    .with(sc_state)
+    .for_duration(prop_time)
+    .unwrap();
+
+    let fuel_usage = fuel_mass - final_state.fuel_mass_kg;
+    println!("[qlaw_case_a] {:o}", final_state.orbit);
+    println!("[qlaw_case_a] fuel usage: {:.3} kg", fuel_usage);
+
+    assert!(
+        sc.ctrl_achieved(&final_state).unwrap(),
+        "objective not achieved"
+    );
+
+    // Ruggiero uses 93.449 kg for the same transfer
+    assert!(fuel_usage < 93.449);
+}
+
+#[test]
+fn qlaw_case_b() {
+    // Source: AAS-2004-5089
+    let cosm = Cosm::de438();
+    let eme2k = cosm.frame("EME2000");
+
+    let start_time = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+
+    let orbit = Orbit::keplerian(24505.9, 0.725, 7.05, 0.0, 0.0, 0.0, start_time, eme2k);
+
+    let prop_time = 160.0 * TimeUnit::Day;
+
+    // Define the thruster
+    let lowt = Thruster {
+        thrust: 0.350,
+        isp: 2000.0,
+    };
+
+    // Define the objectives
+    let objectives = vec![
+        Achieve::Sma {
+            target: 42165.0,
+            tol: 20.0,
+        },
+        Achieve::Ecc {
+            target: 0.001,
+            tol: 5e-5,
+        },
+        Achieve::Inc {
+            target: 0.05,
+            tol: 5e-3,
+        },
+    ];
+
+    let fuel_mass = 1999.9;
+    let dry_mass = 0.1;
+
+    let sc_state =
+        SpacecraftState::with_thruster(orbit, dry_mass, fuel_mass, lowt, GuidanceMode::Thrust);
+
+    let sc = Spacecraft::with_ctrl(OrbitalDynamics::two_body(), QLaw::new(objectives).unwrap());
+    println!("[qlaw_case_b] {:o}", orbit);
+
+    let final_state = Propagator::new::<RK4Fixed>(
+        sc.clone(),
+        PropOpts::with_fixed_step(10.0 * TimeUnit::Second),
+    )
+    .with(sc_state)
+    .for_duration(prop_time)
+    .unwrap();
+
+    let fuel_usage = fuel_mass - final_state.fuel_mass_kg;
+    println!("[qlaw_case_b] {:o}", final_state.orbit);
+    println!("[qlaw_case_b] fuel usage: {:.3} kg", fuel_usage);
+
+    assert!(
+        sc.ctrl_achieved(&final_state).unwrap(),
+        "objective not achieved"
+    );
+
+    // Ruggiero uses 223.515 kg for the same transfer
+    assert!(fuel_usage < 223.515);
+}
+
+#[test]
+fn qlaw_effectivity_coasting() {
+    let cosm = Cosm::de438();
+    let eme2k = cosm.frame("EME2000");
+
+    let start_time = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+
+    // Raise the sma from a GTO, with and without coasting when the thrust is not effective
+    let orbit = Orbit::keplerian(24505.9, 0.725, 7.05, 0.0, 0.0, 0.0, start_time, eme2k);
+    let objective = Achieve::Sma {
+        target: 42165.0,
+        tol: 20.0,
+    };
+
+    let lowt = Thruster {
+        thrust: 0.350,
+        isp: 2000.0,
+    };
+    let fuel_mass = 1999.9;
+    let sc_state =
+        SpacecraftState::with_thruster(orbit, 0.1, fuel_mass, lowt, GuidanceMode::Thrust);
+
+    let prop_time = 10.0 * TimeUnit::Day;
+
+    let mut sma_per_kg = Vec::with_capacity(2);
+    for eta_rel in &[0.0, 0.5] {
+        let ctrl = QLaw::with_options(
+            vec![(objective, 1.0)],
+            QLawOptions {
+                eta_rel: *eta_rel,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let sc = Spacecraft::with_ctrl(OrbitalDynamics::two_body(), ctrl);
+        let final_state =
+            Propagator::new::<RK4Fixed>(sc, PropOpts::with_fixed_step(10.0 * TimeUnit::Second))
+                .with(sc_state)
+                .for_duration(prop_time)
+                .unwrap();
+
+        let fuel_usage = fuel_mass - final_state.fuel_mass_kg;
+        let sma_gain = final_state.orbit.sma() - orbit.sma();
+        println!(
+            "[qlaw_effectivity_coasting] eta_rel = {}\tfuel usage: {:.3} kg\tsma gain: {:.3} km",
+            eta_rel, fuel_usage, sma_gain
+        );
+        assert!(sma_gain > 0.0);
+        sma_per_kg.push(sma_gain / fuel_usage);
+    }
+
+    // Coasting when the thrust is not effective uses the fuel more efficiently
+    assert!(sma_per_kg[1] > sma_per_kg[0]);
+}
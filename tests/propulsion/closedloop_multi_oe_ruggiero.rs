@@ -515,3 +515,79 @@ fn ruggiero_iepc_2011_102() {
     // WARNING: Paper claims this can be done with only 49kg of fuel.
     assert!((fuel_usage - 49.0).abs() < 1.0);
 }
+
+#[test]
+fn rugg_raise_perigee_hold_apogee() {
+    let cosm = Cosm::de438();
+    let eme2k = cosm.frame("EME2000");
+
+    let start_time = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+
+    // Perigee altitude of 300 km and apogee altitude of 2000 km
+    let rp = eme2k.equatorial_radius() + 300.0;
+    let ra = eme2k.equatorial_radius() + 2000.0;
+    let sma = 0.5 * (rp + ra);
+    let orbit = Orbit::keplerian(
+        sma,
+        (ra - rp) / (ra + rp),
+        28.5,
+        0.0,
+        0.0,
+        0.0,
+        start_time,
+        eme2k,
+    );
+
+    let prop_time = 2.0 * TimeUnit::Day;
+
+    // Define the thruster
+    let lowt = Thruster {
+        thrust: 0.5,
+        isp: 1500.0,
+    };
+
+    // Raise the perigee to 400 km while holding the apogee
+    let objectives = vec![
+        Achieve::Periapsis {
+            target: rp + 100.0,
+            tol: 1.0,
+        },
+        Achieve::Apoapsis {
+            target: ra,
+            tol: 5.0,
+        },
+    ];
+
+    let ruggiero_ctrl = Ruggiero::new(objectives, orbit);
+
+    let fuel_mass = 10.0;
+    let dry_mass = 90.0;
+
+    let sc_state =
+        SpacecraftState::with_thruster(orbit, dry_mass, fuel_mass, lowt, GuidanceMode::Thrust);
+
+    let sc = Spacecraft::with_ctrl(OrbitalDynamics::two_body(), ruggiero_ctrl);
+    println!("[rugg_raise_perigee_hold_apogee] {:o}", orbit);
+
+    let final_state = Propagator::new::<RK4Fixed>(
+        sc.clone(),
+        PropOpts::with_fixed_step(10.0 * TimeUnit::Second),
+    )
+    .with(sc_state)
+    .for_duration(prop_time)
+    .unwrap();
+
+    let fuel_usage = fuel_mass - final_state.fuel_mass_kg;
+    println!("[rugg_raise_perigee_hold_apogee] {:o}", final_state.orbit);
+    println!(
+        "[rugg_raise_perigee_hold_apogee] fuel usage: {:.3} kg",
+        fuel_usage
+    );
+
+    assert!(
+        sc.ctrl_achieved(&final_state).unwrap(),
+        "objective not achieved"
+    );
+    assert!((final_state.orbit.periapsis() - rp - 100.0).abs() < 1.0);
+    assert!((final_state.orbit.apoapsis() - ra).abs() < 5.0);
+}
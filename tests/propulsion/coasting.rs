@@ -0,0 +1,133 @@
+extern crate nyx_space as nyx;
+
+use self::nyx::celestia::eclipse::{EclipseLocator, EclipseState};
+use self::nyx::celestia::{Cosm, GuidanceMode, LTCorr, Orbit, SpacecraftState};
+use self::nyx::dynamics::thrustctrl::{Achieve, DutyCycle, EclipseCoasting, Ruggiero, Thruster};
+use self::nyx::dynamics::{OrbitalDynamics, Spacecraft};
+use self::nyx::propagators::{PropOpts, Propagator, RK4Fixed};
+use self::nyx::time::{Epoch, TimeUnit};
+use std::sync::mpsc::channel;
+
+#[test]
+fn ruggiero_eclipse_coasting() {
+    let cosm = Cosm::de438();
+    let eme2k = cosm.frame("EME2000");
+
+    let start_time = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+
+    // A LEO in the ecliptic plane is in eclipse for about a third of each orbit
+    let orbit = Orbit::keplerian(7000.0, 0.001, 23.4, 0.0, 0.0, 0.0, start_time, eme2k);
+
+    let lowt = Thruster {
+        thrust: 0.1,
+        isp: 3100.0,
+    };
+    let fuel_mass = 100.0;
+    let sc_state =
+        SpacecraftState::with_thruster(orbit, 500.0, fuel_mass, lowt, GuidanceMode::Thrust);
+
+    let objectives = vec![Achieve::Sma {
+        target: 42164.0,
+        tol: 1.0,
+    }];
+
+    let e_loc = EclipseLocator {
+        light_source: cosm.frame("Sun J2000"),
+        shadow_bodies: vec![eme2k],
+        cosm: cosm.clone(),
+        correction: LTCorr::None,
+    };
+
+    let ctrl = EclipseCoasting::new(Ruggiero::new(objectives, orbit), e_loc.clone());
+    let sc = Spacecraft::with_ctrl(OrbitalDynamics::two_body(), ctrl.clone());
+
+    let (tx, rx) = channel();
+    let final_state = Propagator::new::<RK4Fixed>(sc, PropOpts::with_fixed_step_s(10.0))
+        .with(sc_state)
+        .with_tx(tx)
+        .for_duration(1 * TimeUnit::Day)
+        .unwrap();
+
+    let states: Vec<SpacecraftState> = rx.try_iter().collect();
+    let duty_cycle = DutyCycle::from_states(ctrl.as_ref(), &states);
+    println!("{}\n{}", final_state, duty_cycle);
+
+    // No fuel is used while in umbra
+    let mut umbra_cnt = 0;
+    for pair in states.windows(2) {
+        if e_loc.compute(&pair[0].orbit) == EclipseState::Umbra {
+            umbra_cnt += 1;
+            assert_eq!(pair[0].mode, GuidanceMode::Coast, "thrusting in umbra");
+        }
+        if pair[0].mode == GuidanceMode::Coast
+            && e_loc.compute(&pair[1].orbit) == EclipseState::Umbra
+        {
+            assert!((pair[0].fuel_mass_kg - pair[1].fuel_mass_kg).abs() < std::f64::EPSILON);
+        }
+    }
+    assert!(umbra_cnt > 0, "never in umbra");
+
+    assert!(final_state.orbit.sma() > orbit.sma());
+    assert!(final_state.fuel_mass_kg < fuel_mass);
+    // The control coasts in eclipse and resumes in sunlight
+    assert!(
+        duty_cycle.ratio() > 0.5 && duty_cycle.ratio() < 0.8,
+        "unexpected duty cycle: {}",
+        duty_cycle
+    );
+    assert!((duty_cycle.elapsed_s - 86_400.0).abs() < 20.0);
+}
+
+#[test]
+fn ruggiero_efficiency_coasting() {
+    let cosm = Cosm::de438();
+    let eme2k = cosm.frame("EME2000");
+
+    let start_time = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+
+    // Raise the sma from a GTO, with and without coasting when the thrust is not efficient
+    let orbit = Orbit::keplerian(24505.9, 0.725, 7.05, 0.0, 0.0, 0.0, start_time, eme2k);
+    let objectives = vec![Achieve::Sma {
+        target: 42165.0,
+        tol: 20.0,
+    }];
+
+    let lowt = Thruster {
+        thrust: 0.350,
+        isp: 2000.0,
+    };
+    let fuel_mass = 1999.9;
+    let sc_state =
+        SpacecraftState::with_thruster(orbit, 0.1, fuel_mass, lowt, GuidanceMode::Thrust);
+
+    let mut sma_per_kg = Vec::with_capacity(2);
+    let mut duty_cycles = Vec::with_capacity(2);
+    for eta in &[0.0, 0.8] {
+        let ctrl = Ruggiero::with_eta(objectives.clone(), orbit, *eta).unwrap();
+        let sc = Spacecraft::with_ctrl(OrbitalDynamics::two_body(), ctrl.clone());
+        let (tx, rx) = channel();
+        let final_state = Propagator::new::<RK4Fixed>(sc, PropOpts::with_fixed_step_s(10.0))
+            .with(sc_state)
+            .with_tx(tx)
+            .for_duration(5 * TimeUnit::Day)
+            .unwrap();
+
+        let states: Vec<SpacecraftState> = rx.try_iter().collect();
+        let duty_cycle = DutyCycle::from_states(ctrl.as_ref(), &states);
+        let fuel_usage = fuel_mass - final_state.fuel_mass_kg;
+        let sma_gain = final_state.orbit.sma() - orbit.sma();
+        println!(
+            "[ruggiero_efficiency_coasting] eta = {}\tfuel usage: {:.3} kg\tsma gain: {:.3} km\t{}",
+            eta, fuel_usage, sma_gain, duty_cycle
+        );
+        assert!(sma_gain > 0.0);
+        sma_per_kg.push(sma_gain / fuel_usage);
+        duty_cycles.push(duty_cycle.ratio());
+    }
+
+    // Without a threshold, the control always thrusts
+    assert!((duty_cycles[0] - 1.0).abs() < 1e-9);
+    // And coasting when the thrust is not efficient uses the fuel more efficiently
+    assert!(duty_cycles[1] < 1.0);
+    assert!(sma_per_kg[1] > sma_per_kg[0]);
+}
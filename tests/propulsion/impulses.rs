@@ -0,0 +1,170 @@
+extern crate nyx_space as nyx;
+
+use self::nyx::celestia::{Cosm, Frame, GuidanceMode, Orbit, SpacecraftState};
+use self::nyx::dimensions::Vector3;
+use self::nyx::dynamics::deltavctrl::Impulse;
+use self::nyx::dynamics::thrustctrl::Thruster;
+use self::nyx::dynamics::{OrbitalDynamics, Spacecraft};
+use self::nyx::errors::NyxError;
+use self::nyx::propagators::error_ctrl::RSSStepPV;
+use self::nyx::propagators::events::{EventKind, OrbitalEvent, SCEvent};
+use self::nyx::propagators::impulses::ScheduledImpulse;
+use self::nyx::propagators::{PropOpts, Propagator};
+use self::nyx::time::{Epoch, TimeUnit};
+use self::nyx::utils::rss_errors;
+use self::nyx::State;
+use std::sync::mpsc::channel;
+
+const STD_GRAVITY: f64 = 9.80665;
+
+#[test]
+fn impulse_at_epoch() {
+    let cosm = Cosm::de438();
+    let eme2k = cosm.frame("EME2000");
+
+    let start_time = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+    let orbit = Orbit::keplerian(7000.0, 0.01, 28.5, 10.0, 20.0, 30.0, start_time, eme2k);
+    // Not a multiple of the step size, so the propagator must stop exactly at the impulse
+    let burn_time = start_time + 1803.5 * TimeUnit::Second;
+    let impulse = Impulse::new(Vector3::new(0.1, 0.0, 0.0), Frame::VNC);
+
+    let setup = Propagator::rk89(
+        OrbitalDynamics::two_body(),
+        PropOpts::with_fixed_step_s(10.0),
+    );
+
+    let (tx, rx) = channel();
+    let final_state = setup
+        .with(orbit)
+        .with_impulses(vec![ScheduledImpulse::at_epoch(burn_time, impulse)])
+        .with_tx(tx)
+        .for_duration(1 * TimeUnit::Hour)
+        .unwrap();
+
+    // The post-burn state is published exactly at the burn time
+    let post_burn = rx.try_iter().find(|state| state.dt == burn_time).unwrap();
+
+    // Compare with propagating to the burn, applying it manually, and propagating until the end
+    let mut pre_burn = setup
+        .with(orbit)
+        .for_duration(burn_time - start_time)
+        .unwrap();
+    assert_eq!(pre_burn.dt, burn_time);
+    let dv = impulse.dv_inertial(&pre_burn);
+    pre_burn.vx += dv[0];
+    pre_burn.vy += dv[1];
+    pre_burn.vz += dv[2];
+
+    let (err_r, err_v) = rss_errors(&post_burn.to_cartesian_vec(), &pre_burn.to_cartesian_vec());
+    assert!(err_r < 1e-12 && err_v < 1e-12, "post-burn state differs");
+    assert!(post_burn.sma() > orbit.sma());
+
+    let expected = setup
+        .with(pre_burn)
+        .for_duration(final_state.dt - burn_time)
+        .unwrap();
+    let (err_r, err_v) = rss_errors(
+        &final_state.to_cartesian_vec(),
+        &expected.to_cartesian_vec(),
+    );
+    println!(
+        "{}\n{}\nRSS errors:\tpos = {:.5e} km\tvel = {:.5e} km/s",
+        final_state, expected, err_r, err_v
+    );
+    assert!(err_r < 1e-9, "final position differs");
+    assert!(err_v < 1e-12, "final velocity differs");
+}
+
+#[test]
+fn impulse_at_apoapse() {
+    let cosm = Cosm::de438();
+    let eme2k = cosm.frame("EME2000");
+
+    let start_time = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+    let orbit = Orbit::keplerian(8000.0, 0.1, 28.5, 10.0, 20.0, 30.0, start_time, eme2k);
+
+    let isp = 300.0;
+    let init_sc = SpacecraftState::with_thruster(
+        orbit,
+        1000.0,
+        100.0,
+        Thruster { thrust: 10.0, isp },
+        GuidanceMode::Coast,
+    );
+
+    // Raise the perigee at the next apoapse
+    let dv = 0.05;
+    let apoapse = SCEvent::orbital(OrbitalEvent::new(EventKind::Apoapse));
+    let impulse = Impulse::new(Vector3::new(dv, 0.0, 0.0), Frame::VNC);
+
+    let setup = Propagator::rk89(
+        Spacecraft::new(OrbitalDynamics::two_body()),
+        PropOpts::with_adaptive_step_s(1.0, 60.0, 1e-12, RSSStepPV {}),
+    );
+
+    let (tx, rx) = channel();
+    let final_state = setup
+        .with(init_sc)
+        .with_impulses(vec![ScheduledImpulse::at_event(apoapse, impulse)])
+        .with_tx(tx)
+        .for_duration(orbit.period())
+        .unwrap();
+
+    // The first state with less fuel is the post-burn state
+    let post_burn = rx
+        .try_iter()
+        .find(|state| state.fuel_mass_kg < init_sc.fuel_mass_kg)
+        .unwrap();
+    println!("{}\n{}", post_burn, final_state);
+
+    assert!(
+        (post_burn.orbit.ta() - 180.0).abs() < 1e-2,
+        "impulse not applied at apoapse"
+    );
+    assert!(post_burn.orbit.sma() > orbit.sma());
+    assert!(post_burn.orbit.ecc() < orbit.ecc());
+
+    // Check the fuel usage with the rocket equation
+    let init_mass = init_sc.dry_mass_kg + init_sc.fuel_mass_kg;
+    let fuel_usage = init_mass * (1.0 - (-dv * 1e3 / (isp * STD_GRAVITY)).exp());
+    assert!((init_sc.fuel_mass_kg - post_burn.fuel_mass_kg - fuel_usage).abs() < 1e-9);
+    assert!((final_state.fuel_mass_kg - post_burn.fuel_mass_kg).abs() < std::f64::EPSILON);
+}
+
+#[test]
+fn impulse_errors() {
+    let cosm = Cosm::de438();
+    let eme2k = cosm.frame("EME2000");
+
+    let start_time = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+    let orbit = Orbit::keplerian(7000.0, 0.01, 28.5, 10.0, 20.0, 30.0, start_time, eme2k);
+    let impulse = Impulse::new(Vector3::new(0.1, 0.0, 0.0), Frame::VNC);
+
+    // An impulse scheduled before the start of the propagation is not silently applied
+    let rslt = Propagator::rk89(
+        OrbitalDynamics::two_body(),
+        PropOpts::with_fixed_step_s(10.0),
+    )
+    .with(orbit)
+    .with_impulses(vec![ScheduledImpulse::at_epoch(
+        start_time - 1 * TimeUnit::Minute,
+        impulse,
+    )])
+    .for_duration(1 * TimeUnit::Hour);
+    assert!(rslt.is_err());
+
+    // Without enough fuel, the impulse fails and the state is unchanged
+    let sc = SpacecraftState::with_thruster(
+        orbit,
+        1000.0,
+        1.0,
+        Thruster {
+            thrust: 10.0,
+            isp: 300.0,
+        },
+        GuidanceMode::Coast,
+    );
+    let mut after = sc;
+    assert_eq!(after.apply_impulse(&impulse), Err(NyxError::FuelExhausted));
+    assert_eq!(after, sc);
+}
@@ -1,3 +1,10 @@
+mod closedloop_multi_oe_qlaw;
 mod closedloop_multi_oe_ruggiero;
 mod closedloop_single_oe_ruggiero;
+mod coasting;
+mod impulses;
 mod schedule;
+mod sep;
+mod steering;
+mod tanks;
+mod targeter;
@@ -2,7 +2,7 @@ extern crate nyx_space as nyx;
 
 use self::nyx::celestia::{Bodies, Cosm, Frame, GuidanceMode, Orbit, SpacecraftState};
 use self::nyx::dimensions::Vector3;
-use self::nyx::dynamics::thrustctrl::{FiniteBurns, Mnvr, Thruster};
+use self::nyx::dynamics::thrustctrl::{FiniteBurns, Mnvr, Steering, Thruster};
 use self::nyx::dynamics::{OrbitalDynamics, Spacecraft};
 use self::nyx::propagators::{PropOpts, Propagator};
 use self::nyx::time::{Epoch, TimeUnit};
@@ -55,6 +55,7 @@ fn val_transfer_schedule_no_depl() {
         end: end_time,
         thrust_lvl: 1.0, // Full thrust
         vector: Vector3::new(1.0, 0.0, 0.0),
+        steering: Steering::Constant,
     };
 
     let schedule = FiniteBurns::from_mnvrs(vec![mnvr0], Frame::VNC);
@@ -155,6 +156,7 @@ fn val_transfer_schedule_depl() {
         end: end_time,
         thrust_lvl: 1.0, // Full thrust
         vector: Vector3::new(1.0, 0.0, 0.0),
+        steering: Steering::Constant,
     };
 
     let schedule = FiniteBurns::from_mnvrs(vec![mnvr0], Frame::VNC);
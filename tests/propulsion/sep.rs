@@ -0,0 +1,75 @@
+extern crate nyx_space as nyx;
+
+use self::nyx::celestia::{Cosm, GuidanceMode, Orbit, SpacecraftState};
+use self::nyx::dynamics::thrustctrl::{Achieve, Ruggiero, ThrusterModel};
+use self::nyx::dynamics::{OrbitalDynamics, SEPThruster, SolarArray, Spacecraft, ThrottleTable};
+use self::nyx::propagators::{PropOpts, Propagator, RK4Fixed};
+use self::nyx::time::{Epoch, TimeUnit};
+
+#[test]
+fn sep_nstar_ruggiero_sma() {
+    let cosm = Cosm::de438();
+    let eme2k = cosm.frame("EME2000");
+
+    let start_time = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+
+    let orbit = Orbit::keplerian(24396.0, 0.0, 0.0, 0.0, 0.0, 0.0, start_time, eme2k);
+
+    let prop_time = 5 * TimeUnit::Day;
+
+    // Define the SEP system: at 1 AU and in sunlight, the NSTAR thruster is at full power
+    let array = SolarArray::new(2.6, 0.3, start_time, vec![eme2k], cosm.clone());
+    let sep = SEPThruster::new(array, ThrottleTable::nstar());
+
+    let sc_state = SpacecraftState::new(orbit, 300.0, 67.0);
+    let thruster = sep.thruster(&sc_state).unwrap();
+    println!("[sep_nstar_ruggiero_sma] {:?}", thruster);
+    assert!((thruster.thrust - 0.092).abs() < 1e-3);
+
+    // Define the objectives (which won't be reached in this time)
+    let objectives = vec![Achieve::Sma {
+        target: 42164.0,
+        tol: 1.0,
+    }];
+
+    // Propagate with the SEP thruster
+    let sc_sep = Spacecraft::with_ctrl_and_thruster(
+        OrbitalDynamics::two_body(),
+        Ruggiero::new(objectives.clone(), orbit),
+        sep,
+    );
+
+    let final_sep =
+        Propagator::new::<RK4Fixed>(sc_sep, PropOpts::with_fixed_step(10.0 * TimeUnit::Second))
+            .with(sc_state)
+            .for_duration(prop_time)
+            .unwrap();
+
+    // And with a constant thruster with the same performance, i.e. without eclipses
+    let sc_cst_state =
+        SpacecraftState::with_thruster(orbit, 300.0, 67.0, thruster, GuidanceMode::Thrust);
+    let sc_cst = Spacecraft::with_ctrl(
+        OrbitalDynamics::two_body(),
+        Ruggiero::new(objectives, orbit),
+    );
+
+    let final_cst =
+        Propagator::new::<RK4Fixed>(sc_cst, PropOpts::with_fixed_step(10.0 * TimeUnit::Second))
+            .with(sc_cst_state)
+            .for_duration(prop_time)
+            .unwrap();
+
+    let fuel_sep = 67.0 - final_sep.fuel_mass_kg;
+    let fuel_cst = 67.0 - final_cst.fuel_mass_kg;
+    println!("[sep_nstar_ruggiero_sma] {:o}", final_sep.orbit);
+    println!(
+        "[sep_nstar_ruggiero_sma] fuel usage: {:.3} kg (SEP) vs {:.3} kg (constant)",
+        fuel_sep, fuel_cst
+    );
+
+    // The eclipses prevent thrusting for part of each orbit
+    assert!(fuel_sep > 0.0);
+    assert!(fuel_sep < fuel_cst);
+    assert!(final_sep.orbit.sma() < final_cst.orbit.sma());
+    assert!(final_sep.orbit.sma() > orbit.sma());
+}
@@ -0,0 +1,230 @@
+extern crate nyx_space as nyx;
+
+use self::nyx::celestia::{Cosm, Frame, GuidanceMode, Orbit, SpacecraftState};
+use self::nyx::dimensions::Vector3;
+use self::nyx::dynamics::thrustctrl::{
+    FiniteBurns, Mnvr, Steering, ThrustControl, ThrustProfile, Thruster,
+};
+use self::nyx::dynamics::{OrbitalDynamics, Spacecraft};
+use self::nyx::propagators::{PropOpts, Propagator};
+use self::nyx::time::{Epoch, TimeUnit};
+use self::nyx::utils::rss_errors;
+use std::io::Write;
+use std::sync::Arc;
+
+/// Returns a spacecraft on a near circular LEO
+fn leo_sc(start_time: Epoch) -> SpacecraftState {
+    let cosm = Cosm::de438();
+    let eme2k = cosm.frame("EME2000");
+    let orbit = Orbit::keplerian(7000.0, 1e-4, 28.5, 10.0, 20.0, 30.0, start_time, eme2k);
+    SpacecraftState::with_thruster(
+        orbit,
+        500.0,
+        100.0,
+        Thruster {
+            thrust: 10.0,
+            isp: 300.0,
+        },
+        GuidanceMode::Custom(0),
+    )
+}
+
+/// Propagates the spacecraft with the provided control until the end epoch
+fn propagate(
+    sc_state: SpacecraftState,
+    ctrl: Arc<dyn ThrustControl>,
+    end_time: Epoch,
+) -> SpacecraftState {
+    let sc = Spacecraft::with_ctrl(OrbitalDynamics::two_body(), ctrl);
+    Propagator::rk89(sc, PropOpts::with_fixed_step_s(10.0))
+        .with(sc_state)
+        .for_duration(end_time - sc_state.orbit.dt)
+        .unwrap()
+}
+
+#[test]
+fn steering_laws() {
+    let start_time = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+    let end_time = start_time + 10 * TimeUnit::Minute;
+    let sc_state = leo_sc(start_time);
+    let setup_for = |mnvr: Mnvr, frame: Frame| {
+        propagate(
+            sc_state,
+            FiniteBurns::from_mnvrs(vec![mnvr], frame),
+            end_time,
+        )
+    };
+
+    let constant = setup_for(
+        Mnvr {
+            start: start_time,
+            end: end_time,
+            thrust_lvl: 1.0,
+            vector: Vector3::new(1.0, 0.0, 0.0),
+            steering: Steering::Constant,
+        },
+        Frame::VNC,
+    );
+
+    // A linear steering law without any rate is a constant direction
+    let linear = setup_for(
+        Mnvr::with_steering(
+            start_time,
+            end_time,
+            1.0,
+            Steering::Linear {
+                alpha: [0.0, 0.0],
+                beta: [0.0, 0.0],
+            },
+        ),
+        Frame::VNC,
+    );
+    let (err_r, err_v) = rss_errors(
+        &constant.orbit.to_cartesian_vec(),
+        &linear.orbit.to_cartesian_vec(),
+    );
+    assert!(err_r < 1e-12 && err_v < 1e-12, "linear steering differs");
+
+    // On a near circular orbit, the in-track direction of the RIC frame is along the velocity
+    let ric = setup_for(
+        Mnvr {
+            start: start_time,
+            end: end_time,
+            thrust_lvl: 1.0,
+            vector: Vector3::new(0.0, 1.0, 0.0),
+            steering: Steering::Constant,
+        },
+        Frame::RIC,
+    );
+    let (err_r, err_v) = rss_errors(
+        &constant.orbit.to_cartesian_vec(),
+        &ric.orbit.to_cartesian_vec(),
+    );
+    println!(
+        "RIC vs VNC:\tpos = {:.5e} km\tvel = {:.5e} km/s",
+        err_r, err_v
+    );
+    assert!(
+        err_r < 1e-3 && err_v < 1e-6,
+        "RIC burn differs from VNC burn"
+    );
+
+    // Steering out of plane reduces the in-plane effect of the burn and changes the inclination
+    let quadratic = setup_for(
+        Mnvr::with_steering(
+            start_time,
+            end_time,
+            1.0,
+            Steering::Quadratic {
+                alpha: [0.0, 0.0, 0.0],
+                beta: [0.0, 1e-3, 1e-6],
+            },
+        ),
+        Frame::VNC,
+    );
+    assert!(quadratic.orbit.sma() < constant.orbit.sma());
+    assert!(quadratic.orbit.sma() > sc_state.orbit.sma());
+    assert!((quadratic.orbit.inc() - sc_state.orbit.inc()).abs() > 1e-3);
+    // The fuel usage only depends on the throttle
+    assert!((quadratic.fuel_mass_kg - constant.fuel_mass_kg).abs() < 1e-9);
+}
+
+#[test]
+fn thrust_profile_replay() {
+    let start_time = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+    let end_time = start_time + 10 * TimeUnit::Minute;
+    let sc_state = leo_sc(start_time);
+
+    let steering = Steering::Linear {
+        alpha: [0.1, 1e-3],
+        beta: [0.0, -2e-4],
+    };
+
+    // Tabulate the steering law every ten seconds, with a throttle of 50%, and stop at the end of the maneuver
+    let mut entries = Vec::new();
+    for step in 0..=60 {
+        let delta_t_s = 10.0 * f64::from(step);
+        let throttle = if step < 60 { 0.5 } else { 0.0 };
+        entries.push((
+            start_time + delta_t_s * TimeUnit::Second,
+            steering.direction(delta_t_s).unwrap(),
+            throttle,
+        ));
+    }
+
+    // Write the same profile to a CSV file
+    let path = std::env::temp_dir().join("nyx_thrust_profile.csv");
+    {
+        let mut file = std::fs::File::create(&path).unwrap();
+        writeln!(file, "epoch,x,y,z,throttle").unwrap();
+        for (epoch, dir, throttle) in &entries {
+            // Scale the direction to check that it gets normalized
+            writeln!(
+                file,
+                "{}, {:.17e}, {:.17e}, {:.17e}, {}",
+                epoch,
+                2.0 * dir[0],
+                2.0 * dir[1],
+                2.0 * dir[2],
+                throttle
+            )
+            .unwrap();
+        }
+    }
+
+    let profile = ThrustProfile::new(entries, Frame::VNC).unwrap();
+    let from_csv = ThrustProfile::from_csv(path.to_str().unwrap(), Frame::VNC).unwrap();
+    assert_eq!(from_csv.epochs.len(), 61);
+
+    let mnvr = Mnvr::with_steering(start_time, end_time, 0.5, steering);
+    let law = propagate(
+        sc_state,
+        FiniteBurns::from_mnvrs(vec![mnvr], Frame::VNC),
+        end_time,
+    );
+    let table = propagate(sc_state, profile, end_time);
+    let replayed = propagate(sc_state, from_csv, end_time);
+
+    let (err_r, err_v) = rss_errors(
+        &table.orbit.to_cartesian_vec(),
+        &replayed.orbit.to_cartesian_vec(),
+    );
+    println!(
+        "table vs CSV:\tpos = {:.5e} km\tvel = {:.5e} km/s",
+        err_r, err_v
+    );
+    assert!(err_r < 1e-9 && err_v < 1e-12, "CSV profile differs");
+
+    // The interpolated profile is close to the steering law
+    let (err_r, err_v) = rss_errors(
+        &table.orbit.to_cartesian_vec(),
+        &law.orbit.to_cartesian_vec(),
+    );
+    println!(
+        "table vs law:\tpos = {:.5e} km\tvel = {:.5e} km/s",
+        err_r, err_v
+    );
+    assert!(err_r < 1e-3 && err_v < 1e-6, "tabulated profile differs");
+    assert!((table.fuel_mass_kg - law.fuel_mass_kg).abs() < 1e-6);
+}
+
+#[test]
+fn thrust_profile_last_entry() {
+    let start_time = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+    let sc_state = leo_sc(start_time);
+
+    // A single entry is held indefinitely
+    let profile = ThrustProfile::new(vec![(start_time, Vector3::x(), 0.5)], Frame::VNC).unwrap();
+    let mut later = sc_state;
+    later.orbit.dt = start_time + 1 * TimeUnit::Hour;
+    assert!((profile.throttle(&later) - 0.5).abs() < std::f64::EPSILON);
+    assert!((profile.direction(&later).norm() - 1.0).abs() < 1e-12);
+    // But the spacecraft coasts before it
+    later.orbit.dt = start_time - 1 * TimeUnit::Hour;
+    assert!(profile.throttle(&later).abs() < std::f64::EPSILON);
+
+    // Profiles must be defined in the inertial frame or in a local frame
+    assert!(
+        ThrustProfile::new(vec![(start_time, Vector3::x(), 0.5)], sc_state.orbit.frame).is_err()
+    );
+}
@@ -0,0 +1,244 @@
+extern crate nyx_space as nyx;
+
+use self::nyx::celestia::{Cosm, Frame, GuidanceMode, Orbit, SpacecraftState};
+use self::nyx::dimensions::Vector3;
+use self::nyx::dynamics::thrustctrl::{FiniteBurns, Mnvr, Steering, Thruster};
+use self::nyx::dynamics::{MountedThruster, OrbitalDynamics, Spacecraft, Tank};
+use self::nyx::errors::NyxError;
+use self::nyx::propagators::{PropOpts, Propagator};
+use self::nyx::time::{Epoch, TimeUnit};
+use self::nyx::utils::rss_errors;
+use std::sync::Arc;
+
+const STD_GRAVITY: f64 = 9.80665;
+
+/// Returns a LEO and a maneuver along the velocity vector, which must be propagated for exactly its duration
+fn leo_burn(start_time: Epoch, duration_s: f64) -> (Orbit, Mnvr) {
+    let cosm = Cosm::de438();
+    let eme2k = cosm.frame("EME2000");
+    let orbit = Orbit::keplerian(7000.0, 0.01, 28.5, 10.0, 20.0, 30.0, start_time, eme2k);
+    let mnvr = Mnvr {
+        start: start_time,
+        end: start_time + duration_s * TimeUnit::Second,
+        thrust_lvl: 1.0,
+        vector: Vector3::new(1.0, 0.0, 0.0),
+        steering: Steering::Constant,
+    };
+    (orbit, mnvr)
+}
+
+#[test]
+fn single_tank_matches_thruster() {
+    let start_time = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+    let (orbit, mnvr) = leo_burn(start_time, 600.0);
+
+    let monoprop = Thruster {
+        thrust: 10.0,
+        isp: 300.0,
+    };
+
+    // A single axial thruster fed by a regulated tank is the same as the spacecraft thruster
+    let sc_thruster =
+        SpacecraftState::with_thruster(orbit, 1000.0, 100.0, monoprop, GuidanceMode::Custom(0));
+    let sc_tanks = SpacecraftState::with_propulsion(
+        orbit,
+        1000.0,
+        &[Tank::regulated(100.0)],
+        &[MountedThruster::new(monoprop, Vector3::x(), 0)],
+        GuidanceMode::Custom(0),
+    );
+
+    let sc = Spacecraft::with_ctrl(
+        OrbitalDynamics::two_body(),
+        FiniteBurns::from_mnvrs(vec![mnvr], Frame::VNC),
+    );
+    let setup = Propagator::rk89(sc, PropOpts::with_fixed_step(10.0 * TimeUnit::Second));
+
+    let final_thruster = setup
+        .with(sc_thruster)
+        .for_duration(10 * TimeUnit::Minute)
+        .unwrap();
+    let final_tanks = setup
+        .with(sc_tanks)
+        .for_duration(10 * TimeUnit::Minute)
+        .unwrap();
+
+    let (err_r, err_v) = rss_errors(
+        &final_thruster.orbit.to_cartesian_vec(),
+        &final_tanks.orbit.to_cartesian_vec(),
+    );
+    println!(
+        "RSS errors:\tpos = {:.5e} km\tvel = {:.5e} km/s",
+        err_r, err_v
+    );
+    assert!(err_r < 1e-9);
+    assert!(err_v < 1e-12);
+
+    let expected_usage = 600.0 * monoprop.thrust / (monoprop.isp * STD_GRAVITY);
+    assert!((100.0 - final_tanks.fuel_mass_kg - expected_usage).abs() < 1e-9);
+    assert!((final_thruster.fuel_mass_kg - final_tanks.fuel_mass_kg).abs() < 1e-9);
+    assert!(
+        (final_tanks.tanks[0].unwrap().fuel_mass_kg - final_tanks.fuel_mass_kg).abs()
+            < std::f64::EPSILON
+    );
+}
+
+#[test]
+fn off_axis_thruster_with_model() {
+    let start_time = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+    let (orbit, mnvr) = leo_burn(start_time, 600.0);
+
+    let monoprop = Thruster {
+        thrust: 10.0,
+        isp: 300.0,
+    };
+    // The thruster model sets the performance of the mounted thrusters, e.g. from the available power
+    let derated = Thruster {
+        thrust: 5.0,
+        isp: 250.0,
+    };
+
+    // The spacecraft is oriented such that its only thruster, mounted along the body +Y axis, thrusts along the control
+    let sc_thruster =
+        SpacecraftState::with_thruster(orbit, 1000.0, 100.0, derated, GuidanceMode::Custom(0));
+    let sc_tanks = SpacecraftState::with_propulsion(
+        orbit,
+        1000.0,
+        &[Tank::regulated(100.0)],
+        &[MountedThruster::new(monoprop, Vector3::y(), 0)],
+        GuidanceMode::Custom(0),
+    );
+
+    let final_thruster = Propagator::rk89(
+        Spacecraft::with_ctrl(
+            OrbitalDynamics::two_body(),
+            FiniteBurns::from_mnvrs(vec![mnvr], Frame::VNC),
+        ),
+        PropOpts::with_fixed_step(10.0 * TimeUnit::Second),
+    )
+    .with(sc_thruster)
+    .for_duration(10 * TimeUnit::Minute)
+    .unwrap();
+    let final_tanks = Propagator::rk89(
+        Spacecraft::with_ctrl_and_thruster(
+            OrbitalDynamics::two_body(),
+            FiniteBurns::from_mnvrs(vec![mnvr], Frame::VNC),
+            Arc::new(derated),
+        ),
+        PropOpts::with_fixed_step(10.0 * TimeUnit::Second),
+    )
+    .with(sc_tanks)
+    .for_duration(10 * TimeUnit::Minute)
+    .unwrap();
+
+    let (err_r, err_v) = rss_errors(
+        &final_thruster.orbit.to_cartesian_vec(),
+        &final_tanks.orbit.to_cartesian_vec(),
+    );
+    println!(
+        "RSS errors:\tpos = {:.5e} km\tvel = {:.5e} km/s",
+        err_r, err_v
+    );
+    assert!(err_r < 1e-9);
+    assert!(err_v < 1e-12);
+    assert!((final_thruster.fuel_mass_kg - final_tanks.fuel_mass_kg).abs() < 1e-9);
+}
+
+#[test]
+fn multi_tank_depletion() {
+    let start_time = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+    let burn_s = 1800.0;
+    let (orbit, mnvr) = leo_burn(start_time, burn_s);
+
+    let biprop = Thruster {
+        thrust: 1.0,
+        isp: 300.0,
+    };
+    let monoprop = Thruster {
+        thrust: 0.5,
+        isp: 230.0,
+    };
+    let cant = 30.0_f64.to_radians();
+
+    // Two canted thrusters on the regulated tank, and one axial thruster on the blowdown tank
+    let sc_state = SpacecraftState::with_propulsion(
+        orbit,
+        500.0,
+        &[
+            Tank::regulated(20.0),
+            Tank::blowdown(5.0, 2400.0, 0.002, 1004.0),
+        ],
+        &[
+            MountedThruster::new(biprop, Vector3::new(cant.cos(), cant.sin(), 0.0), 0),
+            MountedThruster::new(biprop, Vector3::new(cant.cos(), -cant.sin(), 0.0), 0),
+            MountedThruster::new(monoprop, Vector3::x(), 1),
+        ],
+        GuidanceMode::Custom(0),
+    );
+    assert!((sc_state.fuel_mass_kg - 25.0).abs() < std::f64::EPSILON);
+
+    let sc = Spacecraft::with_ctrl(
+        OrbitalDynamics::two_body(),
+        FiniteBurns::from_mnvrs(vec![mnvr], Frame::VNC),
+    );
+    let final_state = Propagator::rk89(sc, PropOpts::with_fixed_step(10.0 * TimeUnit::Second))
+        .with(sc_state)
+        .for_duration(30 * TimeUnit::Minute)
+        .unwrap();
+
+    let tank0 = final_state.tanks[0].unwrap();
+    let tank1 = final_state.tanks[1].unwrap();
+    println!(
+        "{}\ntank #0: {:.6} kg\ttank #1: {:.6} kg @ {:.1} kPa",
+        final_state,
+        tank0.fuel_mass_kg,
+        tank1.fuel_mass_kg,
+        tank1.pressure_kpa()
+    );
+
+    // The regulated tank is depleted at a constant rate
+    let usage0 = 20.0 - tank0.fuel_mass_kg;
+    let expected0 = burn_s * 2.0 * biprop.thrust / (biprop.isp * STD_GRAVITY);
+    assert!((usage0 - expected0).abs() < 1e-9);
+
+    // The blowdown tank loses pressure, so it is depleted slower than at full pressure
+    let usage1 = 5.0 - tank1.fuel_mass_kg;
+    let max_usage1 = burn_s * monoprop.thrust / (monoprop.isp * STD_GRAVITY);
+    assert!(usage1 > 0.0 && usage1 < max_usage1);
+    assert!(tank1.pressure_kpa() < 2400.0);
+
+    assert!((final_state.fuel_mass_kg - tank0.fuel_mass_kg - tank1.fuel_mass_kg).abs() < 1e-12);
+}
+
+#[test]
+fn tank_exhausted() {
+    let start_time = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+    let (orbit, mnvr) = leo_burn(start_time, 3600.0);
+
+    let monoprop = Thruster {
+        thrust: 10.0,
+        isp: 230.0,
+    };
+
+    // The second tank only has enough fuel for a few minutes
+    let sc_state = SpacecraftState::with_propulsion(
+        orbit,
+        500.0,
+        &[Tank::regulated(50.0), Tank::regulated(1.0)],
+        &[
+            MountedThruster::new(monoprop, Vector3::x(), 0),
+            MountedThruster::new(monoprop, Vector3::x(), 1),
+        ],
+        GuidanceMode::Custom(0),
+    );
+
+    let sc = Spacecraft::with_ctrl(
+        OrbitalDynamics::two_body(),
+        FiniteBurns::from_mnvrs(vec![mnvr], Frame::VNC),
+    );
+    let rslt = Propagator::rk89(sc, PropOpts::with_fixed_step(10.0 * TimeUnit::Second))
+        .with(sc_state)
+        .for_duration(1 * TimeUnit::Hour);
+
+    assert_eq!(rslt, Err(NyxError::TankExhausted(1)));
+}
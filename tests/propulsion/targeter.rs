@@ -0,0 +1,247 @@
+extern crate nyx_space as nyx;
+
+use self::nyx::celestia::{Cosm, Frame, GuidanceMode, Orbit, SpacecraftState};
+use self::nyx::dimensions::Vector3;
+use self::nyx::dynamics::deltavctrl::Impulse;
+use self::nyx::dynamics::thrustctrl::{FiniteBurns, Mnvr, Steering, Thruster};
+use self::nyx::dynamics::{OrbitalDynamics, Spacecraft};
+use self::nyx::io::formatter::StateHeader;
+use self::nyx::md::targeter::{FiniteBurnTargeter, Objective, Vary};
+use self::nyx::propagators::error_ctrl::RSSStepPV;
+use self::nyx::propagators::impulses::ScheduledImpulse;
+use self::nyx::propagators::{PropOpts, Propagator};
+use self::nyx::time::{Epoch, TimeUnit};
+
+const STD_GRAVITY: f64 = 9.80665;
+
+/// Returns a spacecraft on a near circular LEO with a chemical thruster
+fn leo_sc(start_time: Epoch) -> SpacecraftState {
+    let cosm = Cosm::de438();
+    let eme2k = cosm.frame("EME2000");
+    let orbit = Orbit::keplerian(7000.0, 1e-3, 28.5, 10.0, 20.0, 30.0, start_time, eme2k);
+    SpacecraftState::with_thruster(
+        orbit,
+        900.0,
+        100.0,
+        Thruster {
+            thrust: 500.0,
+            isp: 300.0,
+        },
+        GuidanceMode::Coast,
+    )
+}
+
+#[test]
+fn targeter_sma_inc() {
+    let cosm = Cosm::de438();
+    let start_time = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+    let target_epoch = start_time + 1 * TimeUnit::Hour;
+    let sc_state = leo_sc(start_time);
+
+    let burn_start = start_time + 10 * TimeUnit::Minute;
+    let guess = Mnvr {
+        start: burn_start,
+        end: burn_start + 60 * TimeUnit::Second,
+        thrust_lvl: 1.0,
+        vector: Vector3::new(1.0, 0.0, 0.0),
+        steering: Steering::Constant,
+    };
+
+    let objectives = vec![
+        Objective::equal(StateHeader::SMA { frame: None }, 7050.0, 1e-3),
+        Objective::equal(StateHeader::INC { frame: None }, 28.6, 1e-4),
+        Objective::less_than(StateHeader::ECC { frame: None }, 0.01, 0.0),
+    ];
+
+    let targeter = FiniteBurnTargeter::new(
+        Spacecraft::new(OrbitalDynamics::two_body()),
+        PropOpts::with_adaptive_step_s(1.0, 60.0, 1e-12, RSSStepPV {}),
+        objectives,
+        vec![Vary::Duration, Vary::InPlaneAngle, Vary::OutOfPlaneAngle],
+        Frame::VNC,
+        cosm,
+    )
+    .unwrap();
+
+    let sol = targeter.try_achieve(sc_state, guess, target_epoch).unwrap();
+    println!("{}", sol);
+
+    assert_eq!(sol.mnvr.start, burn_start, "start epoch was not varied");
+    assert!((sol.achieved.orbit.sma() - 7050.0).abs() < 1e-3);
+    assert!((sol.achieved.orbit.inc() - 28.6).abs() < 1e-4);
+    assert!(sol.achieved.orbit.ecc() < 0.01);
+    assert_eq!(sol.achieved.orbit.dt, target_epoch);
+
+    // Replay the solution in one go, which coasts after the burn
+    let sc = Spacecraft::with_ctrl(
+        OrbitalDynamics::two_body(),
+        FiniteBurns::from_mnvrs(vec![sol.mnvr], Frame::VNC),
+    );
+    let setup = Propagator::rk89(sc, PropOpts::with_fixed_step_s(1.0));
+    let mut prop = setup.with(sc_state);
+    let after_burn = prop
+        .for_duration(sol.mnvr.end + 1 * TimeUnit::Minute - start_time)
+        .unwrap();
+    let replayed = prop
+        .for_duration(target_epoch - after_burn.orbit.dt)
+        .unwrap();
+    assert_eq!(
+        replayed.fuel_mass_kg, after_burn.fuel_mass_kg,
+        "thrusting after the end of the burn"
+    );
+    assert_eq!(replayed.mode, GuidanceMode::Coast);
+    // The fixed step propagator does not stop exactly at the start and at the end of the burn
+    assert!((replayed.orbit.sma() - 7050.0).abs() < 1.0);
+    assert!((replayed.fuel_mass_kg - sol.achieved.fuel_mass_kg).abs() < 0.2);
+}
+
+#[test]
+fn targeter_from_impulse() {
+    let cosm = Cosm::de438();
+    let start_time = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+    let target_epoch = start_time + 1 * TimeUnit::Hour;
+    let sc_state = leo_sc(start_time);
+
+    // Impulsive solution
+    let impulse_epoch = start_time + 20 * TimeUnit::Minute;
+    let impulse = Impulse::new(Vector3::new(0.03, 0.0, 0.005), Frame::VNC);
+    let opts = PropOpts::with_adaptive_step_s(1.0, 60.0, 1e-12, RSSStepPV {});
+    let setup = Propagator::rk89(Spacecraft::new(OrbitalDynamics::two_body()), opts);
+    let impulsive = setup
+        .with(sc_state)
+        .with_impulses(vec![ScheduledImpulse::at_epoch(impulse_epoch, impulse)])
+        .for_duration(target_epoch - start_time)
+        .unwrap();
+
+    // The equivalent finite burn uses the same fuel as the impulse
+    let at_impulse = setup
+        .with(sc_state)
+        .for_duration(impulse_epoch - start_time)
+        .unwrap();
+    let guess = Mnvr::from_impulse(&at_impulse, &impulse, Frame::VNC).unwrap();
+    let fuel_usage = sc_state.fuel_mass_kg - impulsive.fuel_mass_kg;
+    let expected_s = fuel_usage * 300.0 * STD_GRAVITY / 500.0;
+    assert!((guess.duration().in_seconds() - expected_s).abs() < 1e-3);
+    assert!(
+        ((guess.start - impulse_epoch).in_seconds() + 0.5 * guess.duration().in_seconds()).abs()
+            < 1e-3
+    );
+    assert!((guess.vector.norm() - 1.0).abs() < 1e-12);
+
+    // Achieve the same orbit as the impulsive solution
+    let objectives = vec![
+        Objective::equal(
+            StateHeader::SMA { frame: None },
+            impulsive.orbit.sma(),
+            1e-3,
+        ),
+        Objective::equal(
+            StateHeader::ECC { frame: None },
+            impulsive.orbit.ecc(),
+            1e-6,
+        ),
+        Objective::equal(
+            StateHeader::INC { frame: None },
+            impulsive.orbit.inc(),
+            1e-4,
+        ),
+    ];
+    let targeter = FiniteBurnTargeter::new(
+        Spacecraft::new(OrbitalDynamics::two_body()),
+        opts,
+        objectives,
+        vec![
+            Vary::StartEpoch,
+            Vary::Duration,
+            Vary::InPlaneAngle,
+            Vary::OutOfPlaneAngle,
+        ],
+        Frame::VNC,
+        cosm,
+    )
+    .unwrap();
+
+    let sol = targeter
+        .try_achieve_from_impulse(sc_state, impulse_epoch, impulse, target_epoch)
+        .unwrap();
+    println!("{}", sol);
+
+    assert!((sol.achieved.orbit.sma() - impulsive.orbit.sma()).abs() < 1e-3);
+    assert!((sol.achieved.orbit.ecc() - impulsive.orbit.ecc()).abs() < 1e-6);
+    assert!((sol.achieved.orbit.inc() - impulsive.orbit.inc()).abs() < 1e-4);
+    // Finite burn losses are small for such a short burn
+    let finite_fuel = sc_state.fuel_mass_kg - sol.achieved.fuel_mass_kg;
+    assert!(
+        (finite_fuel - fuel_usage).abs() / fuel_usage < 0.05,
+        "finite burn uses {} kg instead of {} kg",
+        finite_fuel,
+        fuel_usage
+    );
+}
+
+#[test]
+fn targeter_invalid() {
+    let cosm = Cosm::de438();
+    let start_time = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+    let sc_state = leo_sc(start_time);
+    let guess = Mnvr {
+        start: start_time + 10 * TimeUnit::Minute,
+        end: start_time + 11 * TimeUnit::Minute,
+        thrust_lvl: 1.0,
+        vector: Vector3::new(1.0, 0.0, 0.0),
+        steering: Steering::Constant,
+    };
+
+    // Vector quantities cannot be targeted
+    let targeter = FiniteBurnTargeter::new(
+        Spacecraft::new(OrbitalDynamics::two_body()),
+        PropOpts::with_fixed_step_s(10.0),
+        vec![Objective::equal(
+            StateHeader::radius { frame: None },
+            7000.0,
+            1e-3,
+        )],
+        vec![Vary::Duration],
+        Frame::VNC,
+        cosm,
+    )
+    .unwrap();
+    assert!(targeter
+        .try_achieve(sc_state, guess, start_time + 1 * TimeUnit::Hour)
+        .is_err());
+
+    // The target must be after the burn
+    assert!(targeter
+        .try_achieve(sc_state, guess, start_time + 5 * TimeUnit::Minute)
+        .is_err());
+}
+
+#[test]
+fn targeter_invalid_inputs() {
+    let cosm = Cosm::de438();
+    let start_time = Epoch::from_gregorian_tai_at_midnight(2020, 1, 1);
+    let mut sc_state = leo_sc(start_time);
+
+    // Targeted burns must be in the inertial frame or in a local frame
+    assert!(FiniteBurnTargeter::new(
+        Spacecraft::new(OrbitalDynamics::two_body()),
+        PropOpts::with_adaptive_step_s(1.0, 60.0, 1e-12, RSSStepPV {}),
+        vec![Objective::equal(
+            StateHeader::SMA { frame: None },
+            7050.0,
+            1e-3
+        )],
+        vec![Vary::Duration],
+        sc_state.orbit.frame,
+        cosm,
+    )
+    .is_err());
+
+    // An impulse cannot be converted into a finite burn without any thrust
+    sc_state.thruster = Some(Thruster {
+        thrust: 0.0,
+        isp: 300.0,
+    });
+    let impulse = Impulse::new(Vector3::new(0.01, 0.0, 0.0), Frame::VNC);
+    assert!(Mnvr::from_impulse(&sc_state, &impulse, Frame::VNC).is_err());
+}
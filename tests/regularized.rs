@@ -0,0 +1,137 @@
+extern crate nyx_space as nyx;
+
+use nyx::celestia::{Cosm, EnckeState, KsState, Orbit};
+use nyx::dynamics::sph_harmonics::Harmonics;
+use nyx::dynamics::{Encke, KustaanheimoStiefel, OrbitalDynamics};
+use nyx::io::gravity::HarmonicsMem;
+use nyx::propagators::error_ctrl::LargestError;
+use nyx::propagators::{PropOpts, Propagator};
+use nyx::time::{Epoch, TimeUnit};
+use nyx::utils::rss_errors;
+use std::f64::consts::PI;
+
+/// A 300 km by 40,000 km orbit
+fn heo(dt: Epoch, cosm: &Cosm) -> Orbit {
+    let eme2k = cosm.frame("EME2000");
+    let rp = eme2k.equatorial_radius() + 300.0;
+    let ra = eme2k.equatorial_radius() + 40_000.0;
+    Orbit::keplerian(
+        0.5 * (rp + ra),
+        (ra - rp) / (ra + rp),
+        63.4,
+        45.0,
+        270.0,
+        0.0,
+        dt,
+        eme2k,
+    )
+}
+
+fn period_s(orbit: &Orbit) -> f64 {
+    2.0 * PI * (orbit.sma().powi(3) / orbit.frame.gm()).sqrt()
+}
+
+#[test]
+fn regularized_two_body() {
+    let cosm = Cosm::de438();
+    let eme2k = cosm.frame("EME2000");
+    let dt = Epoch::from_gregorian_tai_at_midnight(2021, 1, 1);
+    let heo = heo(dt, &cosm);
+    // A hyperbolic flyby at 1,000 km of altitude, starting before periapsis
+    let flyby = Orbit::keplerian(-20_000.0, 1.4, 30.0, 10.0, 40.0, -100.0, dt, eme2k);
+
+    let encke = Propagator::rk89(
+        Encke::new(OrbitalDynamics::two_body()),
+        PropOpts::with_tolerance(1e-12),
+    );
+    let ks = Propagator::rk89(
+        KustaanheimoStiefel::new(OrbitalDynamics::two_body()),
+        PropOpts::with_adaptive_step_s(1e-3, 2700.0, 1e-12, LargestError),
+    );
+
+    for (orbit, duration_s) in &[
+        (heo, period_s(&heo)),
+        (heo, 2.3 * period_s(&heo)),
+        (flyby, 7200.0),
+    ] {
+        let epoch = dt + *duration_s * TimeUnit::Second;
+        let expected = orbit.at_epoch(epoch).unwrap();
+
+        let encke_orbit = encke
+            .with(EnckeState::new(orbit))
+            .for_duration(*duration_s * TimeUnit::Second)
+            .unwrap()
+            .orbit()
+            .unwrap();
+        let (err_r, err_v) = rss_errors(
+            &encke_orbit.to_cartesian_vec(),
+            &expected.to_cartesian_vec(),
+        );
+        println!("Encke: {:.3e} km\t{:.3e} km/s", err_r, err_v);
+        assert!(err_r < 1e-6, "Encke two body position error");
+
+        let mut prop = ks.with(KsState::from_orbit(orbit));
+        let ks_state = prop.until_physical_epoch(epoch).unwrap();
+        let ks_orbit = ks_state.orbit();
+        assert!((ks_orbit.dt - epoch).in_seconds().abs() < 1e-4);
+        let (err_r, err_v) = rss_errors(
+            &ks_orbit.at_epoch(epoch).unwrap().to_cartesian_vec(),
+            &expected.to_cartesian_vec(),
+        );
+        println!(
+            "KS: {:.3e} km\t{:.3e} km/s (fictitious clock at {})",
+            err_r, err_v, ks_state.clock
+        );
+        assert!(err_r < 1e-4, "KS two body position error");
+        assert!(err_v < 1e-7, "KS two body velocity error");
+    }
+}
+
+#[test]
+fn regularized_j2_matches_cowell() {
+    let cosm = Cosm::de438();
+    let iau_earth = cosm.frame("IAU Earth");
+    let dt = Epoch::from_gregorian_tai_at_midnight(2021, 1, 1);
+    let heo = heo(dt, &cosm);
+    let prop_time = 2.0 * period_s(&heo) * TimeUnit::Second;
+
+    let orbital_dyn = OrbitalDynamics::new(vec![Harmonics::from_stor(
+        iau_earth,
+        HarmonicsMem::j2_jgm3(),
+        cosm.clone(),
+    )]);
+
+    let cowell = Propagator::rk89(orbital_dyn.clone(), PropOpts::with_tolerance(1e-13));
+    let expected = cowell.with(heo).for_duration(prop_time).unwrap();
+
+    let encke = Propagator::rk89(
+        Encke::new(orbital_dyn.clone()),
+        PropOpts::with_tolerance(1e-12),
+    );
+    let encke_orbit = encke
+        .with(EnckeState::new(&heo))
+        .for_duration(prop_time)
+        .unwrap()
+        .orbit()
+        .unwrap();
+    let (err_r, err_v) = rss_errors(
+        &encke_orbit.to_cartesian_vec(),
+        &expected.to_cartesian_vec(),
+    );
+    println!("Encke: {:.3e} km\t{:.3e} km/s", err_r, err_v);
+    assert!(err_r < 1e-3, "Encke differs from Cowell");
+
+    let ks = Propagator::rk89(
+        KustaanheimoStiefel::new(orbital_dyn),
+        PropOpts::with_adaptive_step_s(1e-3, 2700.0, 1e-12, LargestError),
+    );
+    let mut prop = ks.with(KsState::from_orbit(&heo));
+    let ks_orbit = prop.until_physical_epoch(dt + prop_time).unwrap().orbit();
+    // Compare at exactly the same epoch
+    let (err_r, err_v) = rss_errors(
+        &ks_orbit.at_epoch(expected.dt).unwrap().to_cartesian_vec(),
+        &expected.to_cartesian_vec(),
+    );
+    println!("KS: {:.3e} km\t{:.3e} km/s", err_r, err_v);
+    assert!(err_r < 1e-3, "KS differs from Cowell");
+}
@@ -1,7 +1,7 @@
 extern crate nyx_space as nyx;
 extern crate pretty_env_logger as pel;
 
-use nyx::celestia::{Cosm, Orbit};
+use nyx::celestia::{Cosm, Frame, Orbit};
 use nyx::time::{Epoch, TimeUnit};
 
 macro_rules! f64_eq {
@@ -375,3 +375,44 @@ fn geodetic_vallado() {
     f64_eq!(r.geodetic_longitude(), long, "longitude (λ)");
     f64_eq!(r.geodetic_height(), height_val, "height");
 }
+
+#[test]
+fn state_kepler_propagation() {
+    let cosm = Cosm::de438();
+    let eme2k = cosm.frame("EME2000");
+    let dt = Epoch::from_mjd_tai(51_545.0);
+
+    // Test case from Vallado, 4th Ed., page 96, Example 2-4
+    let start = Orbit::cartesian(
+        1131.340, -2282.343, 6672.423, -5.64305, 4.30333, 2.42879, dt, eme2k,
+    );
+    let end = start.at_epoch(dt + 40 * TimeUnit::Minute).unwrap();
+    assert_eq!(end.dt, dt + 40 * TimeUnit::Minute);
+    assert!((end.x - -4219.7527).abs() < 1e-3);
+    assert!((end.y - 4363.0292).abs() < 1e-3);
+    assert!((end.z - -3958.7666).abs() < 1e-3);
+    assert!((end.vx - 3.689866).abs() < 1e-6);
+    assert!((end.vy - -1.916735).abs() < 1e-6);
+    assert!((end.vz - -6.112511).abs() < 1e-6);
+
+    // Several revolutions forward and backward
+    let later = start.at_epoch(dt + 3 * TimeUnit::Day).unwrap();
+    assert!((later.energy() - start.energy()).abs() < 1e-10);
+    let back = later.at_epoch(dt).unwrap();
+    assert!((back.radius() - start.radius()).norm() < 1e-6);
+    assert!((back.velocity() - start.velocity()).norm() < 1e-9);
+
+    // Hyperbolic orbit
+    let hyp = Orbit::cartesian(7000.0, 0.0, 0.0, 0.0, 11.5, 1.0, dt, eme2k);
+    assert!(hyp.ecc() > 1.0);
+    let hyp_later = hyp.at_epoch(dt + 6 * TimeUnit::Hour).unwrap();
+    assert!((hyp_later.hmag() - hyp.hmag()).abs() < 1e-6);
+    let hyp_back = hyp_later.at_epoch(dt).unwrap();
+    assert!((hyp_back.radius() - hyp.radius()).norm() < 1e-6);
+    assert!((hyp_back.velocity() - hyp.velocity()).norm() < 1e-9);
+
+    // Frames without a GM cannot be propagated
+    let mut no_gm = start;
+    no_gm.frame = Frame::Inertial;
+    assert!(no_gm.at_epoch(dt + 40 * TimeUnit::Minute).is_err());
+}
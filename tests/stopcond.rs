@@ -7,7 +7,7 @@ use nyx::dynamics::orbital::OrbitalDynamics;
 use nyx::md::Ephemeris;
 use nyx::propagators::error_ctrl::RSSStepPV;
 use nyx::propagators::events::{EventKind, OrbitalEvent, StopCondition};
-use nyx::propagators::{PropOpts, Propagator};
+use nyx::propagators::{Dormand45, PropOpts, Propagator};
 use nyx::time::{Epoch, TimeUnit, J2000_OFFSET};
 use std::sync::mpsc::channel;
 
@@ -109,6 +109,55 @@ fn stop_cond_3rd_peri() {
     );
 }
 
+#[allow(clippy::identity_op)]
+#[test]
+fn stop_cond_dense_output() {
+    // The event is located on the continuous extension of the steps, with or without a native one
+    let cosm = Cosm::de438();
+    let eme2k = cosm.frame("EME2000");
+
+    let dt = Epoch::from_mjd_tai(J2000_OFFSET);
+    let state = Orbit::cartesian(
+        -2436.45, -2436.45, 6891.037, 5.088_611, -5.088_611, 0.0, dt, eme2k,
+    );
+    let period = state.period();
+    let opts = PropOpts::with_adaptive_step_s(1.0, 60.0, 1e-9, RSSStepPV {});
+
+    for (name, setup) in &[
+        (
+            "Dormand45",
+            Propagator::new::<Dormand45>(OrbitalDynamics::two_body(), opts),
+        ),
+        ("RK89", Propagator::rk89(OrbitalDynamics::two_body(), opts)),
+    ] {
+        let mut condition =
+            StopCondition::after_hits(OrbitalEvent::new(EventKind::Apoapse), 3, 4 * period, 0.0);
+        condition.epsilon = 1 * TimeUnit::Millisecond;
+
+        let (tx, rx) = channel();
+        let mut prop = setup.with(state).with_tx(tx);
+        let orbit = prop
+            .until_event(condition)
+            .expect("condition should have been found");
+        println!("[{}] {:o}", name, orbit);
+        assert!(
+            orbit.dt - dt < 3.0 * period && orbit.dt - dt >= 2.0 * period,
+            "[{}] converged on the wrong apoapse",
+            name
+        );
+        assert!(
+            (180.0 - orbit.ta()).abs() < 1e-4,
+            "[{}] converged, yet convergence critera not met",
+            name
+        );
+        // All of the states until the event are published, and the last one is the state at the event
+        drop(prop);
+        let published: Vec<Orbit> = rx.iter().collect();
+        assert_eq!(published[published.len() - 1], orbit);
+        assert!(published.iter().all(|state| state.dt <= orbit.dt));
+    }
+}
+
 #[ignore]
 #[test]
 fn nrho_apo() {
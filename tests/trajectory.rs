@@ -266,3 +266,80 @@ fn traj_spacecraft() {
         "Maximum spacecraft fuel in interpolation is too high!"
     );
 }
+
+#[allow(clippy::identity_op)]
+#[test]
+fn traj_from_steps() {
+    // Test the trajectories built from the continuous extension of the integration steps
+    let cosm = Cosm::de438();
+    let eme2k = cosm.frame("EME2000");
+
+    let start_dt = Epoch::from_gregorian_utc_at_noon(2021, 1, 1);
+    let start_state = Orbit::cartesian(
+        -2436.45, -2436.45, 6891.037, 5.088_611, -5.088_611, 0.0, start_dt, eme2k,
+    );
+    let opts = PropOpts::with_adaptive_step_s(1.0, 60.0, 1e-9, RSSStepPV {});
+
+    for (name, setup) in &[
+        (
+            "Dormand45",
+            Propagator::new::<Dormand45>(OrbitalDynamics::two_body(), opts),
+        ),
+        ("RK89", Propagator::rk89(OrbitalDynamics::two_body(), opts)),
+    ] {
+        let mut prop = setup.with(start_state);
+        let (end_state, ephem) = prop.for_duration_with_traj(1 * TimeUnit::Day).unwrap();
+
+        assert_eq!(ephem.first(), start_state, "[{}] wrong initial state", name);
+        assert!(
+            (ephem.last().radius() - end_state.radius()).norm() < 1e-9,
+            "[{}] wrong final state",
+            name
+        );
+        assert!(
+            ephem.evaluate(end_state.dt + 1 * TimeUnit::Second).is_err(),
+            "[{}] expected to be outside of interpolation window!",
+            name
+        );
+
+        // Compare with propagations which stop at each epoch, hence with different steps
+        let mut truth = setup.with(start_state);
+        let mut max_pos_err = 0.0;
+        let mut max_vel_err = 0.0;
+        for epoch in TimeSeries::inclusive(
+            start_dt + 17 * TimeUnit::Second,
+            end_state.dt,
+            617 * TimeUnit::Second,
+        ) {
+            let prop_state = truth.for_duration(epoch - truth.state.dt).unwrap();
+            let eval_state = ephem.evaluate(epoch).unwrap();
+            let pos_err = (eval_state.radius() - prop_state.radius()).norm();
+            if pos_err > max_pos_err {
+                max_pos_err = pos_err;
+            }
+            let vel_err = (eval_state.velocity() - prop_state.velocity()).norm();
+            if vel_err > max_vel_err {
+                max_vel_err = vel_err;
+            }
+        }
+
+        println!(
+            "[traj_from_steps] {}: maximum error: pos: {:.2e} m\t\tvel: {:.2e} m/s",
+            name,
+            max_pos_err * 1e3,
+            max_vel_err * 1e3
+        );
+
+        // Allow for up to meter error, which includes the differences of the integration itself
+        assert!(
+            max_pos_err < 1e-3,
+            "[{}] maximum position error in trajectory is too high!",
+            name
+        );
+        assert!(
+            max_vel_err < 1e-6,
+            "[{}] maximum velocity error in trajectory is too high!",
+            name
+        );
+    }
+}